@@ -0,0 +1,25 @@
+# Put this in ~/.config/fish/functions/hunter.fish. Runs hunter, and when
+# it's quit with QuitWithDir (Q), cds to the directory it was in.
+#
+# In a shell started with RunSubshell, that goes back to the hunter
+# it came from instead of starting another one.
+
+function hunter
+    if set -q HUNTER_LEVEL; and test (count $argv) -eq 0
+        exit
+    end
+
+    set -l cd_file (mktemp -t hunter_cd.XXXXXX); or return 1
+
+    command hunter --cd-file $cd_file $argv
+    set -l hunter_status $status
+
+    set -l dir (cat $cd_file)
+    rm -f $cd_file
+
+    if test -n "$dir"; and test -d "$dir"; and test "$dir" != "$PWD"
+        cd -- $dir
+    end
+
+    return $hunter_status
+end
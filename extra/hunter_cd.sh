@@ -0,0 +1,26 @@
+# Source this from ~/.bashrc or ~/.zshrc. Runs hunter, and when it's quit
+# with QuitWithDir (Q), cds to the directory it was in.
+#
+# In a shell started with RunSubshell, that goes back to the hunter
+# it came from instead of starting another one.
+
+hunter() {
+    if [ -n "$HUNTER_LEVEL" ] && [ $# -eq 0 ]; then
+        exit
+    fi
+
+    local cd_file dir ret
+    cd_file="$(mktemp -t hunter_cd.XXXXXX)" || return 1
+
+    command hunter --cd-file "$cd_file" "$@"
+    ret=$?
+
+    dir="$(cat "$cd_file")"
+    rm -f "$cd_file"
+
+    if [ -n "$dir" ] && [ -d "$dir" ] && [ "$dir" != "$PWD" ]; then
+        cd -- "$dir"
+    fi
+
+    return $ret
+}
@@ -0,0 +1,84 @@
+use lazy_static;
+
+use std::time::Duration;
+
+use crate::config::Config;
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Animation {
+    SlideUp,
+}
+
+impl Animation {
+    pub fn duration(&self) -> Duration {
+        match self {
+            Animation::SlideUp => Duration::from_millis(64)
+        }
+    }
+}
+
+lazy_static! {
+    static ref SLOW_TERMINAL: bool = detect_slow_terminal();
+}
+
+// Every frame goes over the wire over ssh, and serial consoles are just
+// too slow for it to look like anything but flicker
+fn detect_slow_terminal() -> bool {
+    let ssh = ["SSH_CONNECTION", "SSH_CLIENT", "SSH_TTY"]
+        .iter()
+        .any(|var| std::env::var_os(var).is_some());
+
+    let baud = unsafe {
+        let mut termios: libc::termios = std::mem::zeroed();
+        match libc::tcgetattr(libc::STDOUT_FILENO, &mut termios) {
+            0 => Some(libc::cfgetospeed(&termios)),
+            _ => None
+        }
+    };
+
+    // pseudo terminals report 38400 and up
+    let slow_line = match baud {
+        Some(baud) => baud != 0 && baud < libc::B38400,
+        None => false
+    };
+
+    ssh || slow_line
+}
+
+pub fn motion_allowed(config: &Config) -> bool {
+    config.animate() && !(config.animation_auto_off && *SLOW_TERMINAL)
+}
+
+// Counts down the frames of an animation, waiting in between so it takes
+// about as long as it should at the configured frequency
+#[derive(Debug, Clone)]
+pub struct Tween {
+    frames: u16,
+    pause: Duration,
+}
+
+impl Tween {
+    // None if there shouldn't be any motion at all
+    pub fn new(config: &Config, animation: Animation) -> Option<Tween> {
+        if !motion_allowed(config) { return None; }
+
+        let hz = config.animation_refresh_frequency.max(1) as u64;
+        let pause = Duration::from_millis(1000 / hz);
+        let frames = animation.duration().as_millis() / pause.as_millis().max(1);
+
+        Some(Tween {
+            frames: frames as u16,
+            pause
+        })
+    }
+
+    // Frames left to go, counting down to 0
+    pub fn frames(&self) -> std::iter::Rev<std::ops::Range<u16>> {
+        (0..self.frames).rev()
+    }
+
+    pub fn wait(&self) {
+        std::thread::sleep(self.pause);
+    }
+}
@@ -0,0 +1,273 @@
+use chrono::{Local, NaiveDateTime};
+use termion::event::Key;
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::fail::{HResult, ErrorLog};
+use crate::keybind::{Acting, AuditAction, Bindings, Movement};
+use crate::listview::{Listable, ListView};
+use crate::term;
+use crate::widget::Widget;
+
+
+// Everything hunter does to files goes into one file, a line per file with
+// when, what, where from, where to and how it went. Nothing is ever taken
+// out of it, so it's still there when wondering where something went last
+// week. Fields are separated by tabs, tabs and newlines in names escaped.
+const SHOWN_MAX: usize = 10_000;
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditEntry {
+    pub time: NaiveDateTime,
+    pub op: String,
+    pub from: PathBuf,
+    pub to: Option<PathBuf>,
+    // the error, if there was one
+    pub error: Option<String>,
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            Some(c) => unescaped.push(c),
+            None => unescaped.push('\\')
+        }
+    }
+
+    unescaped
+}
+
+pub fn record<T, E>(op: &str, from: &Path, to: Option<&Path>, result: &Result<T, E>)
+where
+    E: std::fmt::Display
+{
+    let to = to.map(|to| escape(&to.to_string_lossy())).unwrap_or_default();
+    let outcome = match result {
+        Ok(_) => String::from("ok"),
+        Err(e) => escape(&e.to_string())
+    };
+
+    let line = format!("{}\t{}\t{}\t{}\t{}\n",
+                       Local::now().format(TIME_FORMAT),
+                       op,
+                       escape(&from.to_string_lossy()),
+                       to,
+                       outcome);
+
+    // one write per line, so lines from jobs running at the same time
+    // don't get mixed up
+    let written = (|| -> HResult<()> {
+        let path = crate::paths::audit_path()?;
+        std::fs::create_dir_all(path.parent()?)?;
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?
+            .write_all(line.as_bytes())?;
+        Ok(())
+    })();
+    written.log();
+}
+
+fn parse(line: &str) -> Option<AuditEntry> {
+    let fields = line.split('\t').collect::<Vec<_>>();
+    if fields.len() != 5 { return None; }
+
+    Some(AuditEntry {
+        time: NaiveDateTime::parse_from_str(fields[0], TIME_FORMAT).ok()?,
+        op: fields[1].to_string(),
+        from: PathBuf::from(unescape(fields[2])),
+        to: match fields[3] {
+            "" => None,
+            to => Some(PathBuf::from(unescape(to)))
+        },
+        error: match fields[4] {
+            "ok" => None,
+            error => Some(unescape(error))
+        }
+    })
+}
+
+// Newest first
+pub fn load() -> HResult<Vec<AuditEntry>> {
+    let text = std::fs::read_to_string(crate::paths::audit_path()?)?;
+    let entries = text.lines()
+        .rev()
+        .filter_map(parse)
+        .take(SHOWN_MAX)
+        .collect();
+
+    Ok(entries)
+}
+
+pub struct AuditLog {
+    entries: Vec<AuditEntry>,
+    filter: Option<String>,
+    picked: Option<PathBuf>,
+}
+
+impl AuditLog {
+    pub fn new(entries: Vec<AuditEntry>) -> AuditLog {
+        AuditLog {
+            entries: entries,
+            filter: None,
+            picked: None
+        }
+    }
+
+    fn shown(&self) -> Vec<&AuditEntry> {
+        let filter = self.filter.as_ref().map(|filter| filter.to_lowercase());
+
+        self.entries
+            .iter()
+            .filter(|entry| match &filter {
+                Some(filter) => {
+                    let to = entry.to.as_ref().map(|to| to.to_string_lossy().to_lowercase());
+                    entry.from.to_string_lossy().to_lowercase().contains(filter) ||
+                        to.map(|to| to.contains(filter)).unwrap_or(false) ||
+                        entry.op == *filter
+                }
+                None => true
+            })
+            .collect()
+    }
+}
+
+pub type AuditView = ListView<AuditLog>;
+
+impl AuditView {
+    pub fn picked(&self) -> Option<PathBuf> {
+        self.content.picked.clone()
+    }
+
+    // wherever the file is now, as far as the log knows
+    fn pick(&mut self) -> HResult<()> {
+        let selection = self.get_selection();
+        let entry = (*self.content.shown().get(selection)?).clone();
+
+        self.content.picked = match entry.to {
+            Some(to) if to.exists() => Some(to),
+            _ => Some(entry.from)
+        };
+        self.popup_finnished()
+    }
+
+    fn filter(&mut self) -> HResult<()> {
+        let filter = self.core.minibuffer("filter (part of a path, or an operation like move)");
+        self.content.filter = match filter {
+            Ok(filter) => Some(filter),
+            Err(_) => None
+        };
+
+        self.set_selection(0);
+        self.refresh()?;
+        self.core.clear().log();
+        Ok(())
+    }
+}
+
+impl Listable for ListView<AuditLog> {
+    type Item = ();
+
+    fn len(&self) -> usize {
+        self.content.shown().len()
+    }
+
+    fn render(&self) -> Vec<String> {
+        self.content.shown()
+            .iter()
+            .map(|entry| {
+                let op_color = match entry.error {
+                    Some(_) => term::color_red(),
+                    None => term::color_yellow()
+                };
+                let to = match &entry.to {
+                    Some(to) => format!(" -> {}", term::sanitize(&to.to_string_lossy())),
+                    None => String::new()
+                };
+                let error = match &entry.error {
+                    Some(error) => format!(" {}({})", term::color_red(), term::sanitize(error)),
+                    None => String::new()
+                };
+
+                format!("{}{} {}{:<8}{} {}{}{}",
+                        term::color_cyan(),
+                        entry.time.format("%F %R"),
+                        op_color,
+                        entry.op,
+                        term::normal_color(),
+                        term::sanitize(&entry.from.to_string_lossy()),
+                        to,
+                        error)
+            })
+            .collect()
+    }
+
+    fn render_header(&self) -> HResult<String> {
+        match &self.content.filter {
+            Some(filter) => Ok(format!("File operations matching \"{}\": {}",
+                                       filter,
+                                       self.content.shown().len())),
+            None => Ok(format!("File operations: {}", self.content.entries.len()))
+        }
+    }
+
+    fn on_key(&mut self, key: Key) -> HResult<()> {
+        self.do_key(key)
+    }
+}
+
+impl Acting for ListView<AuditLog> {
+    type Action = AuditAction;
+
+    fn search_in(&self) -> Bindings<Self::Action> {
+        self.core.config().keybinds.audit
+    }
+
+    fn movement(&mut self, movement: &Movement) -> HResult<()> {
+        use Movement::*;
+
+        match movement {
+            Up(n) => { for _ in 0..*n { self.move_up(); }; self.refresh()?; }
+            Down(n) => { for _ in 0..*n { self.move_down(); }; self.refresh()?; }
+            PageUp => self.page_up(),
+            PageDown => self.page_down(),
+            Top => self.move_top(),
+            Bottom => self.move_bottom(),
+            Left | Right => {}
+        }
+
+        Ok(())
+    }
+
+    fn do_action(&mut self, action: &Self::Action) -> HResult<()> {
+        use AuditAction::*;
+
+        match action {
+            Close => self.popup_finnished()?,
+            Pick => self.pick()?,
+            Filter => self.filter()?
+        }
+
+        Ok(())
+    }
+}
@@ -5,6 +5,7 @@ use std::collections::HashMap;
 use crate::fail::{HResult, HError, ErrorLog};
 use crate::widget::{Widget, WidgetCore};
 use crate::coordinates::Coordinates;
+use crate::keybind::{Acting, Bindings, BookmarkAction};
 use crate::term;
 
 #[derive(PartialEq, Eq, Clone, Debug)]
@@ -122,7 +123,7 @@ impl BMPopup {
         HError::terminal_resized()?
     }
 
-    pub fn render_line(&self, n: u16, key: &char, path: &str) -> String {
+    pub fn render_line(&self, n: u16, key: &str, path: &str) -> String {
         let xsize = term::xsize();
         let padding = xsize - 4;
 
@@ -170,12 +171,17 @@ impl Widget for BMPopup {
 
         if !self.add_mode {
             let cwd = self.bookmark_path.as_ref()?;
-            drawlist += &self.render_line(ypos, &'`', cwd);
+            let key = self.core.config().keybinds.bookmark
+                .keys_for(&BookmarkAction::GotoLastCwd)
+                .first()
+                .map(|key| key.to_string())
+                .unwrap_or_default();
+            drawlist += &self.render_line(ypos, &key, cwd);
         }
 
         let bm_list = self.bookmarks.mapping.iter().enumerate().map(|(i, (key, path))| {
             let line = i as u16 + ypos + 1;
-            self.render_line(line, key, path)
+            self.render_line(line, &key.to_string(), path)
         }).collect::<String>();
 
         drawlist += &bm_list;
@@ -183,31 +189,47 @@ impl Widget for BMPopup {
         Ok(drawlist)
     }
     fn on_key(&mut self, key: Key) -> HResult<()> {
-        match key {
-            Key::Ctrl('c') | Key::Esc => {
+        match self.do_key(key) {
+            Err(HError::WidgetUndefinedKeyError{..}) => Ok(()),
+            result => result
+        }
+    }
+}
+
+impl Acting for BMPopup {
+    type Action = BookmarkAction;
+
+    fn search_in(&self) -> Bindings<Self::Action> {
+        self.core.config().keybinds.bookmark
+    }
+
+    fn do_action(&mut self, action: &Self::Action) -> HResult<()> {
+        use BookmarkAction::*;
+
+        match action {
+            Close => {
                 self.bookmark_path = None;
-                return HError::popup_finnished()
-            },
-            Key::Char('`') => return HError::popup_finnished(),
-            Key::Char(key) => {
+                HError::popup_finnished()?
+            }
+            GotoLastCwd => HError::popup_finnished()?,
+            Goto(key) => {
                 if self.add_mode {
                     let path = self.bookmark_path.take()?;
-                    self.bookmarks.add(key, &path)?;
+                    self.bookmarks.add(*key, &path)?;
                     self.add_mode = false;
                     self.bookmarks.save().log();
                     return HError::popup_finnished();
                 }
-                if let Ok(path) = self.bookmarks.get(key) {
+                if let Ok(path) = self.bookmarks.get(*key) {
                     self.bookmark_path.replace(path.clone());
                     return HError::popup_finnished();
                 }
             }
-            Key::Alt(key) => {
-                self.bookmarks.mapping.remove(&key);
+            Delete(key) => {
+                self.bookmarks.mapping.remove(key);
                 self.bookmarks.save().log();
                 return HError::widget_resized();
             }
-            _ => {}
         }
         Ok(())
     }
@@ -0,0 +1,239 @@
+use termion::event::Key;
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::fail::{HResult, HError};
+use crate::keybind::{Acting, Bindings, BranchPickerAction, Movement};
+use crate::listview::{Listable, ListView};
+use crate::term;
+use crate::widget::Widget;
+
+
+// The branches and worktrees of the repository hunter is in, to hop
+// between them. Other worktrees are listed first, since going there doesn't
+// touch any files. A branch that's checked out in another worktree can't be
+// checked out here too, picking that one goes to its worktree instead.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Target {
+    Branch(String),
+    Worktree(PathBuf),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub name: String,
+    pub current: bool,
+    // where it's checked out, if it is
+    pub worktree: Option<PathBuf>,
+    pub is_worktree: bool,
+}
+
+pub struct Branches {
+    pub root: PathBuf,
+    entries: Vec<Entry>,
+    picked: Option<Target>,
+}
+
+fn git(dir: &Path, args: &[&str]) -> HResult<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .stdin(std::process::Stdio::null())
+        .output()?;
+
+    match output.status.success() {
+        true => Ok(String::from_utf8_lossy(&output.stdout).to_string()),
+        false => {
+            let error = String::from_utf8_lossy(&output.stderr);
+            let error = error.lines().next().unwrap_or("").trim();
+            Err(HError::Error(format!("git {}: {}", args[0], error)))
+        }
+    }
+}
+
+// Path and branch of each worktree, the main one first. Detached and bare
+// ones have no branch.
+fn worktrees(root: &Path) -> HResult<Vec<(PathBuf, Option<String>)>> {
+    let list = git(root, &["worktree", "list", "--porcelain"])?;
+
+    let worktrees = list.split("\n\n")
+        .filter_map(|block| {
+            let mut path = None;
+            let mut branch = None;
+
+            for line in block.lines() {
+                if line.starts_with("worktree ") {
+                    path = Some(PathBuf::from(&line["worktree ".len()..]));
+                } else if line.starts_with("branch ") {
+                    branch = Some(line["branch ".len()..]
+                                  .trim_start_matches("refs/heads/")
+                                  .to_string());
+                }
+            }
+
+            Some((path?, branch))
+        })
+        .collect();
+
+    Ok(worktrees)
+}
+
+impl Branches {
+    pub fn new(dir: &Path) -> HResult<Branches> {
+        let root = PathBuf::from(git(dir, &["rev-parse", "--show-toplevel"])?.trim());
+        let worktrees = worktrees(&root)?;
+
+        let checked_out = |branch: &str| -> Option<PathBuf> {
+            worktrees.iter()
+                .find(|(_, wt_branch)| wt_branch.as_ref().map(|b| b == branch).unwrap_or(false))
+                .map(|(path, _)| path.clone())
+        };
+
+        let mut entries = vec![];
+
+        // just the one means there's nothing to switch to
+        if worktrees.len() > 1 {
+            entries.extend(worktrees.iter().map(|(path, branch)| Entry {
+                name: branch.clone().unwrap_or_else(|| String::from("(detached)")),
+                current: *path == root,
+                worktree: Some(path.clone()),
+                is_worktree: true
+            }));
+        }
+
+        let branches = git(&root, &["branch", "--format=%(HEAD)%09%(refname:short)"])?;
+        entries.extend(branches.lines().filter_map(|line| {
+            let mut parts = line.splitn(2, '\t');
+            let current = parts.next()? == "*";
+            let name = parts.next()?.to_string();
+
+            // detached HEAD shows up as "(HEAD detached at ...)"
+            if name.starts_with('(') { return None; }
+
+            Some(Entry {
+                worktree: checked_out(&name),
+                name: name,
+                current: current,
+                is_worktree: false
+            })
+        }));
+
+        Ok(Branches {
+            root: root,
+            entries: entries,
+            picked: None
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+// Fails when there are changes in the way, with git's complaint
+pub fn switch(root: &Path, branch: &str) -> HResult<()> {
+    git(root, &["checkout", branch])?;
+    Ok(())
+}
+
+pub type BranchPicker = ListView<Branches>;
+
+impl BranchPicker {
+    pub fn picked(&self) -> Option<Target> {
+        self.content.picked.clone()
+    }
+
+    fn pick(&mut self) -> HResult<()> {
+        let entry = self.content.entries.get(self.get_selection())?.clone();
+
+        self.content.picked = match entry.worktree {
+            Some(path) if path != self.content.root => Some(Target::Worktree(path)),
+            _ if entry.is_worktree || entry.current => None,
+            _ => Some(Target::Branch(entry.name))
+        };
+        self.popup_finnished()
+    }
+}
+
+impl Listable for ListView<Branches> {
+    type Item = ();
+
+    fn len(&self) -> usize {
+        self.content.entries.len()
+    }
+
+    fn render(&self) -> Vec<String> {
+        self.content.entries
+            .iter()
+            .map(|entry| {
+                let mark = match entry.current {
+                    true => format!("{}* ", term::color_green()),
+                    false => String::from("  ")
+                };
+                let kind = match entry.is_worktree {
+                    true => format!("{}worktree ", term::color_yellow()),
+                    false => format!("{}branch   ", term::color_cyan())
+                };
+                let worktree = match &entry.worktree {
+                    Some(path) if !entry.current => format!(" {}-> {}",
+                                                            term::color_yellow(),
+                                                            term::sanitize(&path.to_string_lossy())),
+                    _ => String::new()
+                };
+
+                format!("{}{}{}{}{}",
+                        mark,
+                        kind,
+                        term::normal_color(),
+                        term::sanitize(&entry.name),
+                        worktree)
+            })
+            .collect()
+    }
+
+    fn render_header(&self) -> HResult<String> {
+        Ok(format!("Branches and worktrees of {}",
+                   self.content.root.to_string_lossy()))
+    }
+
+    fn on_key(&mut self, key: Key) -> HResult<()> {
+        self.do_key(key)
+    }
+}
+
+impl Acting for ListView<Branches> {
+    type Action = BranchPickerAction;
+
+    fn search_in(&self) -> Bindings<Self::Action> {
+        self.core.config().keybinds.branchpicker
+    }
+
+    fn movement(&mut self, movement: &Movement) -> HResult<()> {
+        use Movement::*;
+
+        match movement {
+            Up(n) => { for _ in 0..*n { self.move_up(); }; self.refresh()?; }
+            Down(n) => { for _ in 0..*n { self.move_down(); }; self.refresh()?; }
+            PageUp => self.page_up(),
+            PageDown => self.page_down(),
+            Top => self.move_top(),
+            Bottom => self.move_bottom(),
+            Left | Right => {}
+        }
+
+        Ok(())
+    }
+
+    fn do_action(&mut self, action: &Self::Action) -> HResult<()> {
+        use BranchPickerAction::*;
+
+        match action {
+            Close => self.popup_finnished()?,
+            Pick => self.pick()?
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,264 @@
+use termion::event::Key;
+
+use std::path::{Path, PathBuf};
+
+use crate::fail::{HResult, ErrorLog};
+use crate::fileops::{Job, JobKind, JobQueue};
+use crate::files::pretty_size;
+use crate::keybind::{Acting, Bindings, CleanupAction, Movement};
+use crate::listview::{Listable, ListView};
+use crate::term;
+use crate::widget::{Events, Widget};
+
+
+// Where did all the space go? The biggest things below a directory, so
+// they can be trashed, deleted or packed up right there. Sizes come from
+// the same walk RefineSize does, archiving goes through the job queue.
+const LARGEST_MAX: usize = 50;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub path: PathBuf,
+    pub size: u64,
+    pub is_dir: bool,
+}
+
+pub struct Cleanup {
+    pub root: PathBuf,
+    entries: Vec<Entry>,
+    jobs: JobQueue,
+    picked: Option<(PathBuf, bool)>,
+}
+
+impl Cleanup {
+    pub fn new(root: PathBuf,
+               one_filesystem: bool,
+               min_size: u64,
+               jobs: JobQueue) -> HResult<Cleanup> {
+        let entries = crate::dirsize::largest(&root, one_filesystem, min_size, LARGEST_MAX)?
+            .into_iter()
+            .map(|(path, size, is_dir)| Entry {
+                path: path,
+                size: size,
+                is_dir: is_dir
+            })
+            .collect();
+
+        Ok(Cleanup {
+            root: root,
+            entries: entries,
+            jobs: jobs,
+            picked: None
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // Whatever was in it is gone too and what it was in got smaller
+    fn forget(&mut self, path: &Path) {
+        let size = match self.entries.iter().find(|entry| entry.path == path) {
+            Some(entry) => entry.size,
+            None => return
+        };
+
+        self.entries.retain(|entry| !entry.path.starts_with(path));
+        for entry in self.entries.iter_mut() {
+            if path.starts_with(&entry.path) {
+                entry.size = entry.size.saturating_sub(size);
+            }
+        }
+
+        self.entries.sort_by_key(|entry| std::cmp::Reverse(entry.size));
+    }
+}
+
+pub type CleanupView = ListView<Cleanup>;
+
+impl CleanupView {
+    // the path and whether it should get its own tab
+    pub fn picked(&self) -> Option<(PathBuf, bool)> {
+        self.content.picked.clone()
+    }
+
+    fn selected_entry(&self) -> HResult<Entry> {
+        Ok(self.content.entries.get(self.get_selection())?.clone())
+    }
+
+    fn forget(&mut self, path: &Path) {
+        self.content.forget(path);
+
+        let last = self.content.entries.len().saturating_sub(1);
+        if self.get_selection() > last {
+            self.set_selection(last);
+        }
+
+        self.refresh().log();
+        self.core.clear().log();
+    }
+
+    fn trash(&mut self) -> HResult<()> {
+        let entry = self.selected_entry()?;
+        if !crate::protect::guard(&self.core, &[entry.path.clone()])? { return Ok(()); }
+
+        crate::trash::trash(&entry.path)?;
+
+        self.forget(&entry.path);
+        self.core.show_status(&format!("Moved {} ({}) to trash",
+                                       entry.path.to_string_lossy(),
+                                       pretty_size(entry.size)))
+    }
+
+    fn delete(&mut self) -> HResult<()> {
+        let entry = self.selected_entry()?;
+        if !crate::protect::guard(&self.core, &[entry.path.clone()])? { return Ok(()); }
+
+        let query = format!("Delete {} ({}) for good?",
+                            entry.path.to_string_lossy(),
+                            pretty_size(entry.size));
+
+        // same as in the file list, directories need a real yes
+        let confirmed = match entry.is_dir {
+            true => self.core.minibuffer(&format!("{} Type yes", query))? == "yes",
+            false => match self.core.minibuffer(&format!("{} (y/n)", query))?.as_str() {
+                "y" | "yes" => true,
+                _ => false
+            }
+        };
+
+        if !confirmed {
+            return self.core.show_status("Nothing deleted");
+        }
+
+        self.forget(&entry.path);
+
+        let sender = self.core.get_sender();
+        std::thread::spawn(move || -> HResult<()> {
+            let result = match entry.is_dir {
+                true => std::fs::remove_dir_all(&entry.path),
+                false => std::fs::remove_file(&entry.path)
+            };
+            crate::audit::record("delete", &entry.path, None, &result);
+
+            let msg = match result {
+                Ok(()) => format!("Deleted {} ({})",
+                                  entry.path.to_string_lossy(),
+                                  pretty_size(entry.size)),
+                Err(e) => format!("Can't delete {}: {}",
+                                  entry.path.to_string_lossy(),
+                                  e)
+            };
+            sender.send(Events::Status(msg))?;
+            Ok(())
+        });
+
+        Ok(())
+    }
+
+    fn archive(&mut self) -> HResult<()> {
+        let entry = self.selected_entry()?;
+        if !crate::protect::guard(&self.core, &[entry.path.clone()])? { return Ok(()); }
+
+        let dir = entry.path.parent()?.to_path_buf();
+
+        self.content.jobs.add(Job::new(JobKind::Archive, vec![entry.path.clone()], dir))?;
+        self.forget(&entry.path);
+
+        match self.content.jobs.pending() {
+            1 => self.core.show_status(&format!("Archiving {}",
+                                                entry.path.to_string_lossy())),
+            pending => self.core.show_status(&format!("Queued, {} jobs waiting",
+                                                      pending - 1))
+        }
+    }
+}
+
+impl Listable for ListView<Cleanup> {
+    type Item = ();
+
+    fn len(&self) -> usize {
+        self.content.entries.len()
+    }
+
+    fn render(&self) -> Vec<String> {
+        let root = &self.content.root;
+
+        self.content.entries
+            .iter()
+            .map(|entry| {
+                let path = entry.path.strip_prefix(root).unwrap_or(&entry.path);
+                let slash = if entry.is_dir { "/" } else { "" };
+
+                format!("{}{:>8}{} {}{}",
+                        term::color_cyan(),
+                        pretty_size(entry.size),
+                        term::normal_color(),
+                        term::sanitize(&path.to_string_lossy()),
+                        slash)
+            })
+            .collect()
+    }
+
+    fn render_header(&self) -> HResult<String> {
+        Ok(format!("Biggest below {}: {}",
+                   self.content.root.to_string_lossy(),
+                   self.content.entries.len()))
+    }
+
+    fn render_footer(&self) -> HResult<String> {
+        match self.content.entries.is_empty() {
+            true => Ok(String::from("All cleaned up")),
+            false => Ok(String::new())
+        }
+    }
+
+    fn on_key(&mut self, key: Key) -> HResult<()> {
+        self.do_key(key)
+    }
+}
+
+impl Acting for ListView<Cleanup> {
+    type Action = CleanupAction;
+
+    fn search_in(&self) -> Bindings<Self::Action> {
+        self.core.config().keybinds.cleanup
+    }
+
+    fn movement(&mut self, movement: &Movement) -> HResult<()> {
+        use Movement::*;
+
+        match movement {
+            Up(n) => { for _ in 0..*n { self.move_up(); }; self.refresh()?; }
+            Down(n) => { for _ in 0..*n { self.move_down(); }; self.refresh()?; }
+            PageUp => self.page_up(),
+            PageDown => self.page_down(),
+            Top => self.move_top(),
+            Bottom => self.move_bottom(),
+            Left | Right => {}
+        }
+
+        Ok(())
+    }
+
+    fn do_action(&mut self, action: &Self::Action) -> HResult<()> {
+        use CleanupAction::*;
+
+        match action {
+            Close => self.popup_finnished()?,
+            Pick => {
+                self.content.picked = Some((self.selected_entry()?.path, false));
+                self.popup_finnished()?
+            }
+            PickInTab => {
+                self.content.picked = Some((self.selected_entry()?.path, true));
+                self.popup_finnished()?
+            }
+            Trash => self.trash()?,
+            DeleteForever => self.delete()?,
+            Archive => self.archive()?
+        }
+
+        Ok(())
+    }
+}
@@ -1,12 +1,14 @@
 use lazy_static;
 use clap;
 
+use std::path::PathBuf;
 use std::sync::RwLock;
 
 use crate::paths;
 
 use crate::fail::{HError, HResult, ErrorLog};
 use crate::keybind::KeyBinds;
+use crate::theme::Theme;
 
 
 #[derive(Clone)]
@@ -16,6 +18,7 @@ struct ArgvConfig {
     show_hidden: Option<bool>,
     icons: Option<bool>,
     graphics: Option<String>,
+    cd_file: Option<PathBuf>,
 }
 
 impl ArgvConfig {
@@ -24,7 +27,8 @@ impl ArgvConfig {
             animation: None,
             show_hidden: None,
             icons: None,
-            graphics: None
+            graphics: None,
+            cd_file: None
         }
     }
 }
@@ -61,6 +65,8 @@ pub fn set_argv_config(args: clap::ArgMatches) -> HResult<()> {
         }
     }
 
+    config.cd_file = args.value_of("cd-file").map(PathBuf::from);
+
     *ARGV_CONFIG.write()? = config;
     Ok(())
 }
@@ -69,6 +75,11 @@ fn get_argv_config() -> HResult<ArgvConfig> {
         Ok(ARGV_CONFIG.try_read()?.clone())
 }
 
+// Where QuitWithDir leaves the directory for the shell to cd to
+pub fn cd_file() -> Option<PathBuf> {
+    get_argv_config().ok()?.cd_file
+}
+
 fn infuse_argv_config(mut config: Config) -> Config {
     let argv_config = get_argv_config().unwrap_or(ArgvConfig::new());
 
@@ -84,6 +95,7 @@ fn infuse_argv_config(mut config: Config) -> Config {
 pub struct Config {
     pub animation: bool,
     pub animation_refresh_frequency: usize,
+    pub animation_auto_off: bool,
     pub show_hidden: bool,
     pub select_cmd: String,
     pub cd_cmd: String,
@@ -94,7 +106,37 @@ pub struct Config {
     pub media_previewer_exists: bool,
     pub ratios: Vec::<usize>,
     pub graphics: String,
+    pub path_keep: (usize, usize),
+    pub title_update: bool,
+    pub title_format: String,
+    pub footer_format: String,
+    pub header_format: String,
+    pub one_filesystem: bool,
+    pub transfer_limit: u64,
+    pub mouse: bool,
+    pub tab_bar: bool,
+    pub dual_pane: bool,
+    pub layout_translation: bool,
+    pub dir_stats: bool,
+    pub render_markdown: bool,
+    pub process_limit: usize,
+    pub process_logs: bool,
+    pub process_notify: bool,
+    pub direnv: bool,
+    pub nice_level: i32,
+    pub cpu_quota: String,
+    pub project_detection: bool,
+    pub recent_roots: String,
+    pub cleanup_threshold: u64,
+    pub key_hints: bool,
+    pub open_rules: Vec<crate::opener::OpenRule>,
+    pub protected_paths: String,
+    pub trash_max_age: u64,
+    pub trash_max_size: u64,
+    pub preview_timeout: u64,
+    pub preview_cache_size: usize,
     pub keybinds: KeyBinds,
+    pub theme: Theme,
 }
 
 
@@ -109,6 +151,7 @@ impl Config {
         Config {
             animation: true,
             animation_refresh_frequency: 60,
+            animation_auto_off: true,
             show_hidden: false,
             select_cmd: "find -type f | fzf -m".to_string(),
             cd_cmd: "find -type d | fzf".to_string(),
@@ -119,7 +162,37 @@ impl Config {
             media_previewer_exists: false,
             ratios: vec![20,30,49],
             graphics: detect_g_mode(),
+            path_keep: (1, 2),
+            title_update: true,
+            title_format: "hunter: {dir}{activity}".to_string(),
+            footer_format: "{permissions} {owner} {mtime}( {sparse})( {target}){>}({mode} | )({macro} | )({jobs} | ){device}{space} | {count}".to_string(),
+            header_format: String::new(),
+            one_filesystem: false,
+            transfer_limit: 0,
+            mouse: false,
+            tab_bar: false,
+            dual_pane: false,
+            layout_translation: true,
+            dir_stats: true,
+            render_markdown: true,
+            process_limit: 0,
+            process_logs: true,
+            process_notify: false,
+            direnv: true,
+            nice_level: 10,
+            cpu_quota: String::new(),
+            project_detection: false,
+            recent_roots: String::from("~,projects"),
+            cleanup_threshold: 10 * 1024 * 1024,
+            key_hints: false,
+            open_rules: vec![],
+            protected_paths: String::from("~/.ssh,~/.gnupg"),
+            trash_max_age: 0,
+            trash_max_size: 0,
+            preview_timeout: 10,
+            preview_cache_size: 32 * 1024 * 1024,
             keybinds: KeyBinds::default(),
+            theme: Theme::default(),
         }
     }
 
@@ -133,9 +206,16 @@ impl Config {
         let config_string = std::fs::read_to_string(config_path)?;
 
         let config = config_string.lines().fold(Config::new(), |mut config, line| {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with(';') {
+                return config;
+            }
+
             match Config::prep_line(line) {
                 Ok(("animation", "on")) => config.animation = true,
                 Ok(("animation", "off")) => config.animation = false,
+                Ok(("animation_auto_off", "on")) => config.animation_auto_off = true,
+                Ok(("animation_auto_off", "off")) => config.animation_auto_off = false,
                 Ok(("animation_refresh_frequency", frequency)) => {
                     match frequency.parse::<usize>() {
                         Ok(parsed_freq) => config.animation_refresh_frequency = parsed_freq,
@@ -156,6 +236,9 @@ impl Config {
                 }
                 Ok(("media_autoplay", "on")) => config.media_autoplay = true,
                 Ok(("media_autoplay", "off")) => config.media_autoplay = false,
+                // what the default config has always said
+                Ok(("media_autostart", "on")) => config.media_autoplay = true,
+                Ok(("media_autostart", "off")) => config.media_autoplay = false,
                 Ok(("media_mute", "on")) => config.media_mute = true,
                 Ok(("media_mute", "off")) => config.media_mute = false,
                 Ok(("media_previewer", cmd)) => {
@@ -180,6 +263,112 @@ impl Config {
                             }
                     }
                 }
+                Ok(("title_update", "on")) => config.title_update = true,
+                Ok(("title_update", "off")) => config.title_update = false,
+                Ok(("title_format", format)) => {
+                    let format = format.to_string();
+                    config.title_format = format;
+                }
+                Ok(("footer_format", format)) => {
+                    let format = format.to_string();
+                    config.footer_format = format;
+                }
+                Ok(("header_format", format)) => {
+                    let format = format.to_string();
+                    config.header_format = format;
+                }
+                Ok(("one_filesystem", "on")) => config.one_filesystem = true,
+                Ok(("one_filesystem", "off")) => config.one_filesystem = false,
+                Ok(("transfer_limit", limit)) => {
+                    match crate::theme::parse_size(limit) {
+                        Ok(limit) => config.transfer_limit = limit,
+                        _ => HError::config_error::<Config>(line.to_string()).log()
+                    }
+                }
+                Ok(("mouse", "on")) => config.mouse = true,
+                Ok(("mouse", "off")) => config.mouse = false,
+                Ok(("tab_bar", "on")) => config.tab_bar = true,
+                Ok(("tab_bar", "off")) => config.tab_bar = false,
+                Ok(("dual_pane", "on")) => config.dual_pane = true,
+                Ok(("dual_pane", "off")) => config.dual_pane = false,
+                Ok(("layout_translation", "on")) => config.layout_translation = true,
+                Ok(("layout_translation", "off")) => config.layout_translation = false,
+                Ok(("dir_stats", "on")) => config.dir_stats = true,
+                Ok(("dir_stats", "off")) => config.dir_stats = false,
+                Ok(("render_markdown", "on")) => config.render_markdown = true,
+                Ok(("render_markdown", "off")) => config.render_markdown = false,
+                Ok(("preview_cache_size", size)) => {
+                    match crate::theme::parse_size(size) {
+                        Ok(size) => config.preview_cache_size = size as usize,
+                        _ => HError::config_error::<Config>(line.to_string()).log()
+                    }
+                }
+                Ok(("process_logs", "on")) => config.process_logs = true,
+                Ok(("process_logs", "off")) => config.process_logs = false,
+                Ok(("process_notify", "on")) => config.process_notify = true,
+                Ok(("process_notify", "off")) => config.process_notify = false,
+                Ok(("direnv", "on")) => config.direnv = true,
+                Ok(("direnv", "off")) => config.direnv = false,
+                Ok(("nice_level", level)) => {
+                    match level.parse::<i32>() {
+                        Ok(level) if level >= 0 && level <= 19 => config.nice_level = level,
+                        _ => HError::config_error::<Config>(line.to_string()).log()
+                    }
+                }
+                Ok(("cpu_quota", quota)) => config.cpu_quota = quota.to_string(),
+                Ok(("project_detection", "on")) => config.project_detection = true,
+                Ok(("project_detection", "off")) => config.project_detection = false,
+                Ok(("recent_roots", roots)) => config.recent_roots = roots.to_string(),
+                Ok(("cleanup_threshold", size)) => {
+                    match crate::theme::parse_size(size) {
+                        Ok(size) => config.cleanup_threshold = size,
+                        _ => HError::config_error::<Config>(line.to_string()).log()
+                    }
+                }
+                Ok(("open_rules", rules)) => {
+                    match crate::opener::parse(rules) {
+                        Some(rules) => config.open_rules = rules,
+                        None => HError::config_error::<Config>(line.to_string()).log()
+                    }
+                }
+                Ok(("protected_paths", paths)) => config.protected_paths = paths.to_string(),
+                Ok(("trash_max_age", days)) => {
+                    match days.parse::<u64>() {
+                        Ok(days) => config.trash_max_age = days,
+                        _ => HError::config_error::<Config>(line.to_string()).log()
+                    }
+                }
+                Ok(("trash_max_size", size)) => {
+                    match crate::theme::parse_size(size) {
+                        Ok(size) => config.trash_max_size = size,
+                        _ => HError::config_error::<Config>(line.to_string()).log()
+                    }
+                }
+                Ok(("key_hints", "on")) => config.key_hints = true,
+                Ok(("key_hints", "off")) => config.key_hints = false,
+                Ok(("process_limit", limit)) => {
+                    match limit.parse::<usize>() {
+                        Ok(limit) => config.process_limit = limit,
+                        _ => HError::config_error::<Config>(line.to_string()).log()
+                    }
+                }
+                Ok(("preview_timeout", timeout)) => {
+                    match timeout.parse::<u64>() {
+                        Ok(timeout) => config.preview_timeout = timeout,
+                        _ => HError::config_error::<Config>(line.to_string()).log()
+                    }
+                }
+                Ok(("path_truncation", keep)) => {
+                    let keep = keep.split([',', ':'].as_ref())
+                        .map(|n| n.trim().parse::<usize>())
+                        .collect::<Result<Vec<usize>, _>>();
+                    match keep {
+                        Ok(ref keep) if keep.len() == 2 => {
+                            config.path_keep = (keep[0], keep[1]);
+                        }
+                        _ => HError::config_error::<Config>(line.to_string()).log()
+                    }
+                }
                 #[cfg(feature = "sixel")]
                 Ok(("graphics",
                     "sixel")) => config.graphics = "sixel".to_string(),
@@ -187,6 +376,8 @@ impl Config {
                     "kitty")) => config.graphics = "kitty".to_string(),
                 Ok(("graphics",
                     "auto")) => config.graphics = detect_g_mode(),
+                Ok(("graphics",
+                    "unicode")) => config.graphics = "unicode".to_string(),
                 _ => { HError::config_error::<Config>(line.to_string()).log(); }
             }
 
@@ -205,6 +396,10 @@ impl Config {
             .map(|kb| config.keybinds = kb)
             .log();
 
+        Theme::load()
+            .map(|theme| config.theme = theme)
+            .log();
+
         Ok(config)
     }
 
@@ -249,3 +444,26 @@ fn has_media_previewer(name: &str) -> bool {
         false => find_bins(name).is_ok()
     }
 }
+
+// rust-ini keeps the keys of a section in a HashMap, where the order is
+// anyone's guess. For the sections where it matters this goes through the
+// lines of [name] as they're written instead.
+pub fn ini_section(text: &str, name: &str) -> Vec<(String, String)> {
+    let header = format!("[{}]", name);
+    let mut in_section = false;
+
+    text.lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with(';'))
+        .filter_map(|line| {
+            if line.starts_with('[') {
+                in_section = line == header;
+                return None;
+            }
+            if !in_section { return None; }
+
+            let pos = line.find('=')?;
+            Some((line[..pos].trim().to_string(), line[pos + 1..].trim().to_string()))
+        })
+        .collect()
+}
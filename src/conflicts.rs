@@ -0,0 +1,144 @@
+use lazy_static;
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use crate::fail::{HResult, HError};
+
+
+// While git is in the middle of a merge, rebase or cherry-pick, the files
+// it couldn't merge are marked in the listing, as long as they still have
+// conflict markers in them. That way the ones already fixed but not added
+// yet stop showing up. Nothing is asked when git isn't merging anything,
+// which is a few stats. Every git add writes the index, so what git says is
+// kept until the index changes, the markers until the file does.
+const MERGE_STATES: &[&str] = &["MERGE_HEAD",
+                                "CHERRY_PICK_HEAD",
+                                "REVERT_HEAD",
+                                "rebase-merge",
+                                "rebase-apply"];
+
+// the rest of bigger files isn't looked at
+const MARKER_SCAN_MAX: u64 = 10 * 1024 * 1024;
+
+lazy_static! {
+    static ref UNMERGED: Mutex<HashMap<PathBuf, (SystemTime, Vec<PathBuf>)>> =
+        Mutex::new(HashMap::new());
+    static ref MARKERS: Mutex<HashMap<PathBuf, (SystemTime, bool)>> =
+        Mutex::new(HashMap::new());
+}
+
+fn work_tree(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .find(|dir| dir.join(".git").exists())
+        .map(|dir| dir.to_path_buf())
+}
+
+fn is_merging(git_dir: &Path) -> bool {
+    MERGE_STATES.iter().any(|state| git_dir.join(state).exists())
+}
+
+fn load_unmerged(root: &Path) -> HResult<Vec<PathBuf>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(&["diff", "--name-only", "--diff-filter=U", "-z"])
+        .stdin(std::process::Stdio::null())
+        .output()?;
+
+    if !output.status.success() {
+        return Err(HError::Error(String::from_utf8_lossy(&output.stderr).to_string()));
+    }
+
+    let mut paths = output.stdout
+        .split(|&b| b == 0)
+        .filter(|path| !path.is_empty())
+        .map(|path| root.join(std::ffi::OsStr::from_bytes(path)))
+        .collect::<Vec<_>>();
+
+    paths.sort();
+    paths.dedup();
+    Ok(paths)
+}
+
+fn unmerged(root: &Path, git_dir: &Path) -> Vec<PathBuf> {
+    let mtime = match std::fs::metadata(git_dir.join("index")).and_then(|m| m.modified()) {
+        Ok(mtime) => mtime,
+        Err(_) => return vec![]
+    };
+
+    let mut cache = match UNMERGED.lock() {
+        Ok(cache) => cache,
+        Err(_) => return vec![]
+    };
+
+    if let Some((loaded, paths)) = cache.get(root) {
+        if *loaded == mtime { return paths.clone(); }
+    }
+
+    let paths = load_unmerged(root).unwrap_or_default();
+    cache.insert(root.to_path_buf(), (mtime, paths.clone()));
+    paths
+}
+
+fn scan_markers(path: &Path) -> bool {
+    let file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(_) => return false
+    };
+
+    BufReader::new(file)
+        .take(MARKER_SCAN_MAX)
+        .split(b'\n')
+        .filter_map(|line| line.ok())
+        .any(|line| line.starts_with(b"<<<<<<< ") || line == b"<<<<<<<")
+}
+
+fn has_markers(path: &Path) -> bool {
+    let mtime = match std::fs::metadata(path).and_then(|m| m.modified()) {
+        Ok(mtime) => mtime,
+        Err(_) => return false
+    };
+
+    if let Some((scanned, found)) = MARKERS.lock().ok().and_then(|m| m.get(path).cloned()) {
+        if scanned == mtime { return found; }
+    }
+
+    let found = scan_markers(path);
+    MARKERS.lock().map(|mut m| m.insert(path.to_path_buf(), (mtime, found))).ok();
+    found
+}
+
+// All of them in the repository dir is in, sorted by path
+pub fn conflicted(dir: &Path) -> Vec<PathBuf> {
+    let root = match work_tree(dir) {
+        Some(root) => root,
+        None => return vec![]
+    };
+    let git_dir = match crate::footer::git_dir(&root) {
+        Some(git_dir) => git_dir,
+        None => return vec![]
+    };
+
+    if !is_merging(&git_dir) { return vec![]; }
+
+    unmerged(&root, &git_dir)
+        .into_iter()
+        .filter(|path| has_markers(path))
+        .collect()
+}
+
+// The one after path, starting over at the top
+pub fn next(dir: &Path, path: &Path) -> Option<PathBuf> {
+    let conflicted = conflicted(dir);
+
+    conflicted.iter()
+        .find(|conflict| conflict.as_path() > path)
+        .or_else(|| conflicted.first())
+        .cloned()
+}
@@ -0,0 +1,140 @@
+use termion::event::Key;
+
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::fail::{HResult, HError};
+use crate::keybind::{Acting, Bindings, DevicePickerAction, Movement};
+use crate::listview::{Listable, ListView};
+use crate::term;
+use crate::widget::Widget;
+
+
+// Phones and other machines paired through KDE Connect. Everything goes
+// through kdeconnect-cli, the daemon does the actual work.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Device {
+    pub id: String,
+    pub name: String,
+    picked: bool,
+}
+
+fn kdeconnect(args: &[&str]) -> HResult<String> {
+    let output = Command::new("kdeconnect-cli")
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|_| HError::Error(String::from("Can't find kdeconnect-cli!")))?;
+
+    if !output.status.success() {
+        return Err(HError::Error(format!("kdeconnect-cli {} failed",
+                                         args.join(" "))));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+// Only the ones that are paired and reachable right now, lines look like
+// "id name with spaces"
+pub fn discover() -> HResult<Vec<Device>> {
+    // asks the daemon to look around first, otherwise the list can be stale
+    kdeconnect(&["--refresh"]).ok();
+
+    let devices = kdeconnect(&["-a", "--id-name-only"])?
+        .lines()
+        .filter_map(|line| {
+            let mut line = line.trim().splitn(2, ' ');
+            let id = line.next().filter(|id| !id.is_empty())?;
+            let name = line.next().unwrap_or(id);
+            Some(Device {
+                id: id.to_string(),
+                name: name.to_string(),
+                picked: false
+            })
+        })
+        .collect();
+
+    Ok(devices)
+}
+
+pub fn send(device: &Device, file: &Path) -> HResult<()> {
+    let file = file.to_string_lossy();
+    kdeconnect(&["-d", &device.id, "--share", &file])?;
+    Ok(())
+}
+
+pub type DevicePicker = ListView<Vec<Device>>;
+
+impl DevicePicker {
+    pub fn picked(&self) -> Option<Device> {
+        self.content.iter().find(|device| device.picked).cloned()
+    }
+}
+
+impl Listable for ListView<Vec<Device>> {
+    type Item = ();
+
+    fn len(&self) -> usize {
+        self.content.len()
+    }
+
+    fn render(&self) -> Vec<String> {
+        self.content
+            .iter()
+            .map(|device| format!("{}{} {}({}){}",
+                                  term::normal_color(),
+                                  device.name,
+                                  term::color_cyan(),
+                                  device.id,
+                                  term::normal_color()))
+            .collect()
+    }
+
+    fn render_header(&self) -> HResult<String> {
+        Ok(format!("Send to: {} devices", self.content.len()))
+    }
+
+    fn on_key(&mut self, key: Key) -> HResult<()> {
+        self.do_key(key)
+    }
+}
+
+impl Acting for ListView<Vec<Device>> {
+    type Action = DevicePickerAction;
+
+    fn search_in(&self) -> Bindings<Self::Action> {
+        self.core.config().keybinds.devicepicker
+    }
+
+    fn movement(&mut self, movement: &Movement) -> HResult<()> {
+        use Movement::*;
+
+        match movement {
+            Up(n) => { for _ in 0..*n { self.move_up(); }; self.refresh()?; }
+            Down(n) => { for _ in 0..*n { self.move_down(); }; self.refresh()?; }
+            PageUp => self.page_up(),
+            PageDown => self.page_down(),
+            Top => self.move_top(),
+            Bottom => self.move_bottom(),
+            Left | Right => {}
+        }
+
+        Ok(())
+    }
+
+    fn do_action(&mut self, action: &Self::Action) -> HResult<()> {
+        use DevicePickerAction::*;
+
+        match action {
+            Close => self.popup_finnished()?,
+            Pick => {
+                let selection = self.get_selection();
+                self.content.get_mut(selection)?.picked = true;
+                self.popup_finnished()?
+            }
+        }
+
+        Ok(())
+    }
+}
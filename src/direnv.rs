@@ -0,0 +1,92 @@
+use lazy_static;
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use crate::fail::{HResult, HError, ErrorLog};
+
+
+// Commands started in a directory with an .envrc get the environment
+// direnv would set up there, so nvm, virtualenvs and the like work just
+// like in the shell. That's `direnv exec`, which refuses an .envrc that
+// wasn't allowed with `direnv allow`, those are left alone. It's asked once
+// per .envrc and asked again when the file changes.
+type Env = Vec<(OsString, OsString)>;
+
+lazy_static! {
+    static ref ENVS: Mutex<HashMap<PathBuf, (SystemTime, Env)>> = Mutex::new(HashMap::new());
+}
+
+fn find_envrc(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|dir| dir.join(".envrc"))
+        .find(|path| path.is_file())
+}
+
+fn load(dir: &Path) -> HResult<Option<Env>> {
+    let output = Command::new("direnv")
+        .arg("exec")
+        .arg(dir)
+        .args(&["env", "-0"])
+        .stdin(std::process::Stdio::null())
+        .output();
+
+    let output = match output {
+        Ok(output) => output,
+        // no direnv, nothing to do
+        Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => Err(e)?
+    };
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        return Err(HError::Error(format!("direnv: {}", error.trim())));
+    }
+
+    let env = output.stdout
+        .split(|&b| b == 0)
+        .filter_map(|var| {
+            let pos = var.iter().position(|&b| b == b'=')?;
+            let name = std::ffi::OsStr::from_bytes(&var[..pos]).to_os_string();
+            let value = std::ffi::OsStr::from_bytes(&var[pos + 1..]).to_os_string();
+            Some((name, value))
+        })
+        .collect();
+
+    Ok(Some(env))
+}
+
+// None if there's no .envrc, or direnv didn't want to
+pub fn env(dir: &Path) -> Option<Env> {
+    let envrc = find_envrc(dir)?;
+    let mtime = std::fs::metadata(&envrc).and_then(|meta| meta.modified()).ok()?;
+
+    if let Some((loaded, env)) = ENVS.lock().ok()?.get(&envrc) {
+        if *loaded == mtime { return Some(env.clone()); }
+    }
+
+    // not kept when it failed, it might be allowed by the next time
+    let env = load(envrc.parent()?).log_and().ok()??;
+    ENVS.lock().ok()?.insert(envrc, (mtime, env.clone()));
+    Some(env)
+}
+
+pub trait DirenvExt {
+    fn direnv(&mut self, dir: &Path, enabled: bool) -> &mut Self;
+}
+
+impl DirenvExt for Command {
+    fn direnv(&mut self, dir: &Path, enabled: bool) -> &mut Self {
+        if !enabled { return self; }
+
+        match env(dir) {
+            Some(env) => self.env_clear().envs(env),
+            None => self
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use lazy_static;
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::RwLock;
+
+use crate::fail::{HResult, HError, ErrorLog};
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DirSize {
+    Exact(u64),
+    // from an older scan, the directory might have grown since
+    AtLeast(u64),
+}
+
+impl DirSize {
+    pub fn bytes(&self) -> u64 {
+        match self {
+            DirSize::Exact(size) |
+            DirSize::AtLeast(size) => *size
+        }
+    }
+
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            DirSize::Exact(_) => "",
+            DirSize::AtLeast(_) => "≥"
+        }
+    }
+}
+
+lazy_static! {
+    static ref CACHE: RwLock<Option<HashMap<PathBuf, u64>>> = RwLock::new(None);
+}
+
+fn load_cache() -> HResult<HashMap<PathBuf, u64>> {
+    let cache_path = crate::paths::dirsize_cache_path()?;
+    if !cache_path.exists() { return Ok(HashMap::new()) }
+
+    let content = std::fs::read_to_string(cache_path)?;
+    let cache = content.lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(2, ":");
+            let size = parts.next()?.parse::<u64>().ok()?;
+            let path = PathBuf::from(parts.next()?);
+            Some((path, size))
+        })
+        .collect();
+
+    Ok(cache)
+}
+
+fn save_cache(cache: &HashMap<PathBuf, u64>) -> HResult<()> {
+    let cache_path = crate::paths::dirsize_cache_path()?;
+    let content = cache.iter()
+        .map(|(path, size)| format!("{}:{}\n", size, path.to_string_lossy()))
+        .collect::<String>();
+    std::fs::write(cache_path, content)?;
+    Ok(())
+}
+
+pub fn cached_size(path: &Path) -> Option<DirSize> {
+    if CACHE.read().ok()?.is_none() {
+        let cache = load_cache().log_and().unwrap_or_default();
+        CACHE.write().ok()?.replace(cache);
+    }
+
+    CACHE.read()
+        .ok()?
+        .as_ref()?
+        .get(path)
+        .map(|&size| DirSize::AtLeast(size))
+}
+
+fn store_size(path: &Path, size: u64) -> HResult<()> {
+    let mut cache = CACHE.write()?;
+    let cache = cache.get_or_insert_with(HashMap::new);
+    cache.insert(path.to_path_buf(), size);
+    save_cache(cache)
+}
+
+// Subvolume roots on btrfs always have inode 256. If quotas are enabled
+// the referenced size of the subvolume is already known, no need to walk.
+fn qgroup_size(path: &Path) -> HResult<u64> {
+    if std::fs::symlink_metadata(path)?.ino() != 256 {
+        return Err(HError::Error("Not a subvolume".into()));
+    }
+
+    let output = Command::new("btrfs")
+        .args(&["qgroup", "show", "-f", "--raw"])
+        .arg(path)
+        .stderr(std::process::Stdio::null())
+        .output()?;
+
+    if !output.status.success() {
+        return Err(HError::Error("No quota information".into()));
+    }
+
+    // qgroupid rfer excl, after two header lines
+    let output = String::from_utf8_lossy(&output.stdout);
+    let size = output.lines()
+        .nth(2)?
+        .split_whitespace()
+        .nth(1)?
+        .parse::<u64>()
+        .map_err(|_| HError::Error("Can't parse qgroup size".into()))?;
+
+    Ok(size)
+}
+
+// Bind mounts and hard links show the same inodes in more than one place,
+// so remember what was counted already. Subvolumes and other mounts have a
+// different device, skip those if only one filesystem should be counted.
+// Everything counted is passed to found along with its size.
+fn walk_size(path: &Path,
+             root_dev: Option<u64>,
+             seen: &mut HashSet<(u64, u64)>,
+             files: &mut u64,
+             found: &mut dyn FnMut(&Path, u64, bool)) -> HResult<u64> {
+    let mut size = 0;
+
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        let meta = match entry.metadata() {
+            Ok(meta) => meta,
+            Err(_) => continue
+        };
+
+        if root_dev.is_some() && root_dev != Some(meta.dev()) { continue; }
+        if !seen.insert((meta.dev(), meta.ino())) { continue; }
+
+        if meta.is_dir() {
+            let dir_size = walk_size(&entry.path(), root_dev, seen, files, found).unwrap_or(0);
+            found(&entry.path(), dir_size, true);
+            size += dir_size;
+        } else {
+            size += meta.size();
+            *files += 1;
+            found(&entry.path(), meta.size(), false);
+        }
+    }
+
+    Ok(size)
+}
+
+// Gets the real size, either from quotas or by walking the whole tree
+pub fn refine(path: &Path, one_filesystem: bool) -> HResult<DirSize> {
+    let root_dev = match one_filesystem {
+        true => Some(std::fs::metadata(path)?.dev()),
+        false => None
+    };
+
+    let size = match qgroup_size(path) {
+        Ok(size) => size,
+        Err(_) => walk_size(path, root_dev, &mut HashSet::new(), &mut 0, &mut |_, _, _| {})?
+    };
+
+    store_size(path, size).log();
+
+    Ok(DirSize::Exact(size))
+}
+
+// Number of files and their size, across all filesystems since that's
+// how far deleting goes. Always walks, quotas don't count files.
+pub fn summary(path: &Path) -> HResult<(u64, DirSize)> {
+    let mut files = 0;
+    let size = walk_size(path, None, &mut HashSet::new(), &mut files, &mut |_, _, _| {})?;
+
+    store_size(path, size).log();
+
+    Ok((files, DirSize::Exact(size)))
+}
+
+// The biggest files and directories below path, biggest first and none
+// smaller than min_size. Directories come with everything in them, so the
+// big ones usually show up together with their biggest parts.
+pub fn largest(path: &Path,
+               one_filesystem: bool,
+               min_size: u64,
+               max: usize) -> HResult<Vec<(PathBuf, u64, bool)>> {
+    let root_dev = match one_filesystem {
+        true => Some(std::fs::metadata(path)?.dev()),
+        false => None
+    };
+
+    let mut biggest = BinaryHeap::new();
+    let size = walk_size(path, root_dev, &mut HashSet::new(), &mut 0, &mut |path: &Path, size, is_dir| {
+        if size < min_size { return; }
+
+        biggest.push(Reverse((size, path.to_path_buf(), is_dir)));
+        if biggest.len() > max {
+            biggest.pop();
+        }
+    })?;
+
+    store_size(path, size).log();
+
+    let mut biggest = biggest.into_iter()
+        .map(|Reverse((size, path, is_dir))| (path, size, is_dir))
+        .collect::<Vec<_>>();
+    biggest.sort_by_key(|(_, size, _)| Reverse(*size));
+
+    Ok(biggest)
+}
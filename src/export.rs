@@ -0,0 +1,183 @@
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::Path;
+
+use crate::files::{File, Files, Kind};
+use crate::fail::{HResult, HError};
+use crate::graphics::json_string;
+
+
+// A directory listing as it's shown, for spreadsheets and scripts. The
+// format is picked by the file extension, plain text for anything else.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Format {
+    Plain,
+    Csv,
+    Json,
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> HResult<Format> {
+        match name {
+            "plain" | "txt" | "text" => Ok(Format::Plain),
+            "csv" => Ok(Format::Csv),
+            "json" => Ok(Format::Json),
+            _ => Err(HError::Error(format!("Unknown export format: {}", name)))
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Format {
+        path.extension()
+            .and_then(|ext| Format::from_name(&ext.to_string_lossy()).ok())
+            .unwrap_or(Format::Plain)
+    }
+}
+
+struct Row {
+    name: String,
+    kind: &'static str,
+    // unknown for directories until their size was calculated
+    size: Option<u64>,
+    mtime: Option<String>,
+    mode: Option<String>,
+    user: Option<String>,
+    group: Option<String>,
+    target: Option<String>,
+}
+
+fn row(file: &File) -> Row {
+    let meta = file.meta();
+    let is_link = meta.map(|meta| meta.file_type().is_symlink()).unwrap_or(false);
+
+    let kind = match file.kind {
+        _ if is_link => "link",
+        Kind::Directory => "dir",
+        _ => "file"
+    };
+
+    let size = match file.is_dir() {
+        true => file.du_size().map(|size| size.bytes()),
+        false => meta.map(|meta| meta.len())
+    };
+
+    Row {
+        name: file.name.clone(),
+        kind: kind,
+        size: size,
+        mtime: meta.map(|meta| {
+            chrono::NaiveDateTime::from_timestamp(meta.mtime(), 0)
+                .format("%FT%T")
+                .to_string()
+        }),
+        mode: meta.map(|meta| format!("{:o}", meta.permissions().mode() & 0o7777)),
+        user: meta.and_then(|meta| users::get_user_by_uid(meta.uid()))
+            .map(|user| user.name().to_string_lossy().to_string()),
+        group: meta.and_then(|meta| users::get_group_by_gid(meta.gid()))
+            .map(|group| group.name().to_string_lossy().to_string()),
+        target: file.target.as_ref().map(|target| target.to_string_lossy().to_string())
+    }
+}
+
+fn or_empty(value: &Option<String>) -> &str {
+    value.as_ref().map(|value| value.as_str()).unwrap_or("")
+}
+
+fn csv_field(field: &str) -> String {
+    match field.contains(|c| c == ',' || c == '"' || c == '\n' || c == '\r') {
+        true => format!("\"{}\"", field.replace('"', "\"\"")),
+        false => field.to_string()
+    }
+}
+
+fn json_value(value: &Option<String>) -> String {
+    value.as_ref()
+        .map(|value| json_string(value))
+        .unwrap_or_else(|| String::from("null"))
+}
+
+const COLUMNS: &[&str] = &["name", "type", "size", "mtime", "mode", "user", "group", "target"];
+
+pub fn render<'a>(files: impl Iterator<Item=&'a File>, format: Format) -> String {
+    let rows = files
+        .filter(|file| file.kind != Kind::Placeholder)
+        .map(row)
+        .collect::<Vec<_>>();
+
+    match format {
+        // name last, it's the only column that can be any length
+        Format::Plain => rows.iter()
+            .map(|row| {
+                let size = row.size.map(|size| size.to_string()).unwrap_or_default();
+                let target = row.target.as_ref()
+                    .map(|target| format!(" -> {}", target))
+                    .unwrap_or_default();
+                format!("{:4} {:>4} {:>12} {:19} {:8} {:8} {}{}\n",
+                        row.kind,
+                        or_empty(&row.mode),
+                        size,
+                        or_empty(&row.mtime),
+                        or_empty(&row.user),
+                        or_empty(&row.group),
+                        row.name,
+                        target)
+            })
+            .collect(),
+        Format::Csv => {
+            let header = COLUMNS.join(",") + "\n";
+            let lines = rows.iter()
+                .map(|row| {
+                    let size = row.size.map(|size| size.to_string()).unwrap_or_default();
+                    [row.name.as_str(),
+                     row.kind,
+                     &size,
+                     or_empty(&row.mtime),
+                     or_empty(&row.mode),
+                     or_empty(&row.user),
+                     or_empty(&row.group),
+                     or_empty(&row.target)]
+                        .iter()
+                        .map(|field| csv_field(field))
+                        .collect::<Vec<_>>()
+                        .join(",") + "\n"
+                })
+                .collect::<String>();
+            header + &lines
+        }
+        Format::Json => {
+            let entries = rows.iter()
+                .map(|row| {
+                    let size = row.size
+                        .map(|size| size.to_string())
+                        .unwrap_or_else(|| String::from("null"));
+                    format!("  {{\"name\": {}, \"type\": {}, \"size\": {}, \"mtime\": {}, \
+                             \"mode\": {}, \"user\": {}, \"group\": {}, \"target\": {}}}",
+                            json_string(&row.name),
+                            json_string(row.kind),
+                            size,
+                            json_value(&row.mtime),
+                            json_value(&row.mode),
+                            json_value(&row.user),
+                            json_value(&row.group),
+                            json_value(&row.target))
+                })
+                .collect::<Vec<_>>();
+
+            match entries.is_empty() {
+                true => String::from("[]\n"),
+                false => format!("[\n{}\n]\n", entries.join(",\n"))
+            }
+        }
+    }
+}
+
+// For --export, sorted like hunter would show it at first
+pub fn render_dir(dir: &Path, format: Format, show_hidden: bool) -> HResult<String> {
+    let mut files = Files::new_from_path(dir)?;
+    files.show_hidden = show_hidden;
+
+    for file in files.files.iter_mut() {
+        file.meta_sync().ok();
+    }
+    files.sort();
+
+    Ok(render(files.iter_files(), format))
+}
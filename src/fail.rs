@@ -9,6 +9,7 @@ use termion::event::Key;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
+use crate::fileops::JobKind;
 use crate::foldview::LogEntry;
 use crate::mediaview::MediaError;
 
@@ -88,6 +89,8 @@ pub enum HError {
     WidgetNoFilesError,
     #[fail(display = "Invalid line in settings file: {}", _0)]
     ConfigLineError(String),
+    #[fail(display = "Invalid style in theme file: {}", _0)]
+    ThemeError(String),
     #[fail(display = "New input in Minibuffer")]
     MiniBufferInputUpdated(String),
     #[fail(display = "Failed to parse into UTF8")]
@@ -103,7 +106,13 @@ pub enum HError {
     #[fail(display = "{}", _0)]
     KeyBind(KeyBindError),
     #[fail(display = "FileBrowser needs to know about all tab's files to run exec!")]
-    FileBrowserNeedTabFiles
+    FileBrowserNeedTabFiles(bool),
+    #[fail(display = "FileBrowser needs new tabs for {:?}", _0)]
+    FileBrowserNeedNewTabs(Vec<PathBuf>),
+    #[fail(display = "FileBrowser needs a new tab for project {:?}", _0)]
+    FileBrowserNeedProjectTab(PathBuf),
+    #[fail(display = "FileBrowser needs the other tab's directory for {:?}", _0)]
+    FileBrowserNeedOtherTab(JobKind)
 }
 
 impl HError {
@@ -166,6 +175,10 @@ impl HError {
         Err(HError::ConfigLineError(line))
     }
 
+    pub fn theme_error<T>(style: &str) -> HResult<T> {
+        Err(HError::ThemeError(style.to_string()))
+    }
+
     pub fn metadata_processed<T>() -> HResult<T> {
         Err(HError::MetadataProcessedError)
     }
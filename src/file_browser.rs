@@ -1,16 +1,17 @@
-use termion::event::Key;
+use termion::event::{Key, MouseButton, MouseEvent};
 use pathbuftools::PathBufTools;
 use osstrtools::OsStrTools;
 use async_value::Stale;
 
 use std::io::Write;
 use std::sync::{Arc, Mutex, RwLock};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::ffi::OsString;
 use std::os::unix::ffi::OsStringExt;
 use std::collections::HashSet;
+use std::time::{Duration, Instant};
 
-use crate::files::{File, Files};
+use crate::files::{File, Files, SortBy};
 use crate::fscache::FsCache;
 use crate::listview::{ListView, FileSource};
 use crate::hbox::HBox;
@@ -20,7 +21,7 @@ use crate::preview::{Previewer, AsyncWidget};
 use crate::textview::TextView;
 use crate::fail::{HResult, HError, ErrorLog};
 use crate::widget::{Events, WidgetCore};
-use crate::proclist::ProcView;
+use crate::proclist::{FinishedJob, ProcView};
 use crate::bookmarks::BMPopup;
 use crate::term;
 use crate::term::ScreenExt;
@@ -28,6 +29,26 @@ use crate::foldview::LogView;
 use crate::coordinates::Coordinates;
 use crate::dirty::Dirtyable;
 use crate::stats::{FsStat, FsExt};
+use crate::unicode_width::UnicodeWidthStr;
+use crate::fileops::{Job, JobKind, JobQueue};
+use crate::trash::TrashView;
+use crate::permissions::PermPopup;
+use crate::qr::QrPopup;
+use crate::hexview::{HexView, PAGER_LIMIT};
+use crate::devices::DevicePicker;
+use crate::projects::{Project, ProjectPicker};
+use crate::recent::RecentFiles;
+use crate::cleanup::{Cleanup, CleanupView};
+use crate::audit::{AuditLog, AuditView};
+use crate::branches::{Branches, BranchPicker, Target};
+use crate::pipelines::{Pipelines, PipelinePicker};
+use crate::maintenance::{Findings, FindingsView, Kind};
+use crate::opener::Opener;
+use crate::local::{Found, LocalSettings};
+use crate::direnv::DirenvExt;
+use crate::help::{HelpMenu, Hints, KeyHints};
+use crate::config::Config;
+use crate::footer;
 
 #[derive(PartialEq)]
 pub enum FileBrowserWidgets {
@@ -74,6 +95,8 @@ impl Widget for FileBrowserWidgets {
     }
 }
 
+const DOUBLE_CLICK_TIME: Duration = Duration::from_millis(400);
+
 pub struct FileBrowser {
     pub columns: HBox<FileBrowserWidgets>,
     pub cwd: File,
@@ -83,13 +106,33 @@ pub struct FileBrowser {
     bookmarks: Arc<Mutex<BMPopup>>,
     log_view: Arc<Mutex<LogView>>,
     fs_cache: FsCache,
-    fs_stat: Arc<RwLock<FsStat>>
+    fs_stat: Arc<RwLock<FsStat>>,
+    jobs: JobQueue,
+    yanked: Arc<Mutex<Option<(JobKind, Vec<PathBuf>)>>>,
+    // top of the project if this is a project tab
+    project: Option<PathBuf>,
+    project_checked: Option<PathBuf>,
+    // what .hunter.toml has to say about the current directory
+    local: Option<LocalSettings>,
+    // one that was found, but isn't trusted yet
+    untrusted: Option<PathBuf>,
+    // when and where the left button was last pressed, for double clicks
+    last_click: Option<(Instant, u16, u16)>,
+    // shown as a dual pane, with whether it was zoomed before
+    pane_zoom: Option<bool>,
+    // what it looked like and where when switching away, shown again when
+    // switching back to this tab as long as nothing changed in the meantime
+    drawlist: Option<(Coordinates, String)>,
+    resumed: bool,
+    // the overlay with the key hints while they're shown and which ones,
+    // same for all tabs
+    key_hints: Arc<Mutex<Option<(usize, Hints)>>>
 }
 
-impl Tabbable for TabView<FileBrowser> {
-    type Tab = FileBrowser;
-
-    fn new_tab(&mut self) -> HResult<()> {
+impl TabView<FileBrowser> {
+    // Starts where the current tab is, with the same settings for every
+    // directory, sharing processes, jobs and the like
+    fn tab_from_active(&mut self) -> HResult<FileBrowser> {
         self.active_tab_mut().save_tab_settings().log();
 
         let cur_tab = self.active_tab();
@@ -105,15 +148,46 @@ impl Tabbable for TabView<FileBrowser> {
         tab.bookmarks = bookmarks;
         tab.log_view  = log_view;
         tab.fs_stat = cur_tab.fs_stat.clone();
+        tab.jobs = cur_tab.jobs.clone();
+        tab.yanked = cur_tab.yanked.clone();
+        tab.key_hints = cur_tab.key_hints.clone();
+
+        Ok(tab)
+    }
+}
 
+impl Tabbable for TabView<FileBrowser> {
+    type Tab = FileBrowser;
+
+    fn new_tab(&mut self) -> HResult<()> {
+        let tab = self.tab_from_active()?;
+
+        self.on_tab_leave(self.active).log();
         self.push_widget(tab)?;
+        self.last_active = Some(self.active);
         self.active = self.widgets.len() - 1;
-        Ok(())
+        self.on_layout()
+    }
+
+    // The previous directory and the project come along, so GotoPrevCwd
+    // and hidden build output work the same in both
+    fn duplicate_tab(&mut self) -> HResult<()> {
+        let mut tab = self.tab_from_active()?;
+
+        let cur_tab = self.active_tab_();
+        tab.prev_cwd = cur_tab.prev_cwd.clone();
+        tab.project = cur_tab.project.clone();
+
+        self.on_tab_leave(self.active).log();
+        self.insert_widget(self.active + 1, tab)?;
+        self.last_active = Some(self.active);
+        self.active += 1;
+        self.on_tab_switch()
     }
 
     fn close_tab(&mut self) -> HResult<()> {
         self.close_tab_().log();
-        Ok(())
+        self.on_layout()
     }
 
     fn next_tab(&mut self) -> HResult<()> {
@@ -126,16 +200,38 @@ impl Tabbable for TabView<FileBrowser> {
         Ok(())
     }
 
+    fn move_tab(&mut self, right: bool) -> HResult<()> {
+        self.move_tab_(right);
+        Ok(())
+    }
+
     fn goto_tab(&mut self, index: usize) -> HResult<()> {
         self.goto_tab_(index)
     }
 
+    fn last_tab(&mut self) -> HResult<()> {
+        self.last_tab_()
+    }
+
+    fn toggle_dual_pane(&mut self) -> HResult<()> {
+        self.toggle_dual_pane_()
+    }
+
+    fn switch_pane(&mut self) -> HResult<()> {
+        self.switch_pane_()
+    }
+
     fn get_tab_names(&self) -> Vec<Option<String>> {
         self.widgets.iter().map(|filebrowser| {
             let path = filebrowser.cwd.path();
             let last_dir = path.components().last().unwrap();
             let dir_name = last_dir.as_os_str().to_string_lossy().to_string();
-            Some(dir_name)
+
+            match &filebrowser.project {
+                Some(root) if *root == path => Some(crate::projects::name(root)),
+                Some(root) => Some(format!("{}:{}", crate::projects::name(root), dir_name)),
+                None => Some(dir_name)
+            }
         }).collect()
     }
 
@@ -147,14 +243,37 @@ impl Tabbable for TabView<FileBrowser> {
         self.active_tab_mut_()
     }
 
+    fn on_tab_leave(&mut self, prev: usize) -> HResult<()> {
+        self.widgets.get_mut(prev)?.suspend()
+    }
+
     fn on_tab_switch(&mut self) -> HResult<()> {
-        self.active_tab_mut().refresh()
+        self.on_layout().log();
+        self.active_tab_mut().resume()
+    }
+
+    // Tabs that aren't in one of the panes get all of the room again
+    fn on_layout(&mut self) -> HResult<()> {
+        let (active_area, other_area) = self.active_pane_areas();
+        let active = self.active;
+        let other = self.other_pane();
+
+        for (i, tab) in self.widgets.iter_mut().enumerate() {
+            let pane = match other {
+                Some(_) if i == active => Some(&active_area),
+                Some(other) if i == other => Some(&other_area),
+                _ => None
+            };
+            tab.show_as_pane(pane).log();
+        }
+
+        Ok(())
     }
 
     fn on_key_sub(&mut self, key: Key) -> HResult<()> {
         match self.active_tab_mut().on_key(key) {
             // returned by specific tab when called with ExecCmd action
-            Err(HError::FileBrowserNeedTabFiles) => {
+            Err(HError::FileBrowserNeedTabFiles(low_priority)) => {
                 let tab_dirs = self.widgets.iter().map(|w| w.cwd.clone())
                     .collect::<Vec<_>>();
                 let selected_files = self
@@ -164,13 +283,47 @@ impl Tabbable for TabView<FileBrowser> {
                         w.selected_files().unwrap_or(vec![])
                     }).collect();
 
-                self.widgets[self.active].exec_cmd(tab_dirs, selected_files)
+                self.widgets[self.active].exec_cmd(tab_dirs, selected_files, low_priority)
+            }
+            Err(HError::FileBrowserNeedProjectTab(root)) => {
+                self.new_tab()?;
+                let tab = self.active_tab_mut();
+                tab.project = Some(root.clone());
+                tab.reveal(&root)
+            }
+            Err(HError::FileBrowserNeedNewTabs(paths)) => {
+                for path in paths {
+                    self.new_tab()?;
+                    self.active_tab_mut().reveal(&path).log();
+                }
+                Ok(())
+            }
+            // the other pane, or like two panes in a commander, the one to
+            // the right, or the first one when this is the last
+            Err(HError::FileBrowserNeedOtherTab(kind)) => {
+                if self.widgets.len() < 2 {
+                    return self.active_tab_().core.show_status("There's no other tab");
+                }
+
+                let other = self.other_pane()
+                    .unwrap_or((self.active + 1) % self.widgets.len());
+                let dir = self.widgets[other].cwd.path();
+                self.active_tab_mut().send_to(kind, dir)
             }
             result @ _ => result
         }
     }
 
     fn on_refresh(&mut self) -> HResult<()> {
+        // where it ran, if that's still open in one of the panes
+        for job in crate::proclist::take_finished() {
+            let done = self.active_tab_mut_().follow_up(&job).log_and().unwrap_or(false);
+
+            if let (false, Some(other)) = (done, self.other_pane()) {
+                self.widgets[other].follow_up(&job).log();
+            }
+        }
+
         let open_dirs = self.widgets
             .iter()
             .fold(HashSet::new(), |mut dirs, tab| {
@@ -193,7 +346,10 @@ impl Tabbable for TabView<FileBrowser> {
     fn on_config_loaded(&mut self) -> HResult<()> {
         let show_hidden = self.core.config().show_hidden();
 
+        crate::trash::clean_up_regularly(&self.core);
+
         for tab in self.widgets.iter_mut() {
+            tab.resumed = false;
             tab.left_async_widget_mut().map(|async_w| {
                 async_w.widget.on_ready(move |mut w, _| {
                     w.as_mut()
@@ -224,7 +380,15 @@ impl Tabbable for TabView<FileBrowser> {
 
             tab.preview_widget_mut().map(|w| w.config_loaded()).ok();
             tab.columns.set_ratios(self.core.config().ratios);
+            tab.jobs.set_limit(self.core.config().transfer_limit);
+        }
+
+        // only loaded once, at the start
+        if self.core.config().dual_pane && !self.dual_pane {
+            self.toggle_dual_pane().log();
         }
+
+        self.core.screen()?.set_mouse(self.core.config().mouse)?;
         Ok(())
     }
 }
@@ -317,7 +481,8 @@ impl FileBrowser {
         let bookmarks = BMPopup::new(&core);
         let log_view = LogView::new(&core, vec![]);
         let fs_stat = FsStat::new().unwrap();
-
+        let jobs = JobQueue::new(core.get_sender());
+        jobs.set_limit(core.config().transfer_limit);
 
 
         Ok(FileBrowser { columns: columns,
@@ -328,7 +493,18 @@ impl FileBrowser {
                          bookmarks: Arc::new(Mutex::new(bookmarks)),
                          log_view: Arc::new(Mutex::new(log_view)),
                          fs_cache: fs_cache,
-                         fs_stat: Arc::new(RwLock::new(fs_stat)) })
+                         fs_stat: Arc::new(RwLock::new(fs_stat)),
+                         jobs: jobs,
+                         yanked: Arc::new(Mutex::new(None)),
+                         project: None,
+                         project_checked: None,
+                         local: None,
+                         untrusted: None,
+                         last_click: None,
+                         pane_zoom: None,
+                         drawlist: None,
+                         resumed: false,
+                         key_hints: Arc::new(Mutex::new(None)) })
     }
 
     pub fn enter_dir(&mut self) -> HResult<()> {
@@ -382,34 +558,80 @@ impl FileBrowser {
                     .build()
                 }).log();
         } else {
-            self.preview_widget_mut().map(|preview| {
-                preview.cancel_animation().log();
-            }).log();
-            self.core.get_sender().send(Events::InputEnabled(false))?;
-            self.core.screen.suspend().log();
+            self.run_foreground("xdg-open", &file)?;
+        }
+
+        Ok(())
+    }
 
-            let status = std::process::Command::new("xdg-open")
-                .args(file.path.file_name())
-                .status();
+    // For things that take over the terminal until they're done. cmd can
+    // have arguments of its own, like EDITOR often does.
+    fn run_foreground(&mut self, cmd: &str, file: &File) -> HResult<()> {
+        let mut args = cmd.split_whitespace();
+        let program = args.next()?;
 
-            self.core.screen.activate().log();
-            self.core.clear().log();
+        self.preview_widget_mut().map(|preview| {
+            preview.cancel_animation().log();
+        }).log();
+        self.core.get_sender().send(Events::InputEnabled(false))?;
+        self.core.screen.suspend().log();
 
-            self.core.get_sender().send(Events::InputEnabled(true))?;
+        let status = std::process::Command::new(program)
+            .args(args)
+            .args(file.path.file_name())
+            .direnv(&self.cwd.path, self.core.config().direnv)
+            .status();
 
-            match status {
-                Ok(status) =>
-                    self.core.show_status(&format!("\"{}\" exited with {}",
-                                                   "xdg-open", status)).log(),
-                Err(err) =>
-                    self.core.show_status(&format!("Can't run this \"{}\": {}",
-                                                   "xdg-open", err)).log()
-            }
+        self.core.screen.activate().log();
+        self.core.clear().log();
+
+        self.core.get_sender().send(Events::InputEnabled(true))?;
+
+        match status {
+            Ok(status) =>
+                self.core.show_status(&format!("\"{}\" exited with {}",
+                                               program, status)).log(),
+            Err(err) =>
+                self.core.show_status(&format!("Can't run this \"{}\": {}",
+                                               program, err)).log()
         }
 
         Ok(())
     }
 
+    pub fn open(&mut self) -> HResult<()> {
+        let file = self.selected_file()?;
+        let rules = self.local
+            .iter()
+            .flat_map(|local| local.open_rules.iter().cloned())
+            .chain(self.core.config().open_rules)
+            .collect::<Vec<_>>();
+
+        match crate::opener::find(&rules, &file) {
+            Opener::Default => self.enter_dir(),
+            Opener::Edit => {
+                let editor = std::env::var("EDITOR").unwrap_or(String::from("vi"));
+                self.run_foreground(&editor, &file)
+            }
+            Opener::Preview => match self.preview_focused() {
+                true => Ok(()),
+                false => self.focus_next()
+            },
+            Opener::Actions => self.quick_action(),
+            Opener::Action(title) => {
+                let files = match self.selected_files()? {
+                    files if files.is_empty() => vec![file],
+                    files => files
+                };
+
+                crate::quick_actions::run_named(files,
+                                                &title,
+                                                &self.core,
+                                                self.proc_view.clone())
+            }
+        }
+    }
+
     pub fn move_down_left_widget(&mut self) -> HResult<()> {
         let left_files_pos = self.left_widget()?.get_selection();
 
@@ -453,7 +675,9 @@ impl FileBrowser {
             cwd: cwd.clone(),
             cwd_files: None,
             tab_files: None,
-            tab_paths: None
+            tab_paths: None,
+            low_priority: false,
+            follow_up: None
         };
 
         self.proc_view.lock()?.run_proc_raw(cmd)?;
@@ -477,20 +701,38 @@ impl FileBrowser {
     pub fn main_widget_goto(&mut self, dir: &File) -> HResult<()> {
         let dir = dir.clone();
         let cache = self.fs_cache.clone();
+        let fs_stat = self.fs_stat.clone();
 
         self.prev_cwd = Some(self.cwd.clone());
         self.cwd = dir.clone();
         let file_source = FileSource::Path(self.cwd.clone());
 
+        self.show_mount_boundary().log();
+
         let main_async_widget = self.main_async_widget_mut()?;
         main_async_widget.change_to(move |stale: &Stale, core| {
-            let view = ListView::builder(core, file_source)
+            let mut view = ListView::builder(core, file_source)
                 .meta_all()
                 // .prerender()
                 .with_cache(cache)
                 .with_stale(stale.clone())
                 .build()?;
 
+            let case_insensitive = fs_stat.read()?
+                .find_fs(&dir.path)
+                .map(|fs| fs.is_case_insensitive())
+                .unwrap_or(false);
+            view.content.case_insensitive = case_insensitive;
+
+            if case_insensitive {
+                if let Some((file, other)) = view.content.case_collisions().first() {
+                    let msg = format!("Warning: {} and {} only differ in case!",
+                                      file.name,
+                                      other.name);
+                    view.core.show_status(&msg).log();
+                }
+            }
+
             Ok(view)
         }).log();
 
@@ -510,6 +752,27 @@ impl FileBrowser {
         Ok(())
     }
 
+    // Bind mounts and subvolumes look just like normal directories, so
+    // say so when one is entered or left
+    fn show_mount_boundary(&self) -> HResult<()> {
+        use std::os::unix::fs::MetadataExt;
+
+        let prev_cwd = self.prev_cwd.as_ref()?;
+        let prev_dev = std::fs::metadata(&prev_cwd.path)?.dev();
+        let dev = std::fs::metadata(&self.cwd.path)?.dev();
+
+        if prev_dev == dev { return Ok(()) }
+
+        let fs_stat = self.fs_stat.read()?;
+        let fs = fs_stat.find_fs(&self.cwd.path)?;
+
+        let msg = format!("Crossed filesystem boundary: {} ({}) on {}",
+                          fs.fs_mounted_from,
+                          fs.fs_type,
+                          fs.fs_mounted_on);
+        self.core.show_status(&msg)
+    }
+
     pub fn left_widget_goto(&mut self, dir: &File) -> HResult<()> {
         // Check if we're in the correct directory already and return
         // if we are
@@ -659,12 +922,112 @@ impl FileBrowser {
     }
 
     pub fn set_title(&self) -> HResult<()> {
+        let config = self.core.config();
+        if !config.title_update { return Ok(()) }
+
         let path = self.cwd.short_string();
 
-        self.core.screen()?.set_title(&path)?;
+        let running = self.proc_view
+            .try_lock()
+            .map(|proc_view| proc_view.running_procs())
+            .unwrap_or(0);
+        let mut activity = match running {
+            0 => String::new(),
+            1 => String::from(" — running 1 process"),
+            n => format!(" — running {} processes", n)
+        };
+
+        if let Some(progress) = self.jobs.progress() {
+            activity += &format!(" — {}", progress.pretty());
+        }
+
+        let title = config.title_format
+            .replace("{dir}", &path)
+            .replace("{activity}", &activity);
+
+        self.core.screen()?.set_title(&title)?;
+        Ok(())
+    }
+
+    // The one under the cursor if none are selected, same as for trashing
+    fn marked_paths(&self) -> HResult<Vec<PathBuf>> {
+        Ok(self.main_widget()?.selected_paths())
+    }
+
+    pub fn yank(&mut self, kind: JobKind) -> HResult<()> {
+        let paths = self.marked_paths()?;
+        if paths.is_empty() { return self.core.show_status("Nothing selected!"); }
+
+        let verb = match kind {
+            JobKind::Copy => "copy",
+            JobKind::Move => "move",
+            JobKind::Send => "send",
+            JobKind::Download => "download",
+            JobKind::Archive => "archive"
+        };
+        self.core.show_status(&format!("Marked {} files to {}",
+                                       paths.len(),
+                                       verb)).log();
+
+        *self.yanked.lock()? = Some((kind, paths));
+        Ok(())
+    }
+
+    pub fn paste(&mut self) -> HResult<()> {
+        let (kind, paths) = match self.yanked.lock()?.take() {
+            Some(yanked) => yanked,
+            None => return self.core.show_status("Nothing to paste!")
+        };
+
+        // copied files can be pasted again, moved ones are gone
+        let started = self.start_job(kind, paths.clone(), self.cwd.path())?;
+        if !started || kind == JobKind::Copy {
+            *self.yanked.lock()? = Some((kind, paths));
+        }
+
         Ok(())
     }
 
+    // Straight there, without marking them first
+    pub fn send_to(&mut self, kind: JobKind, dir: PathBuf) -> HResult<()> {
+        let paths = self.marked_paths()?;
+        if paths.is_empty() { return self.core.show_status("Nothing selected!"); }
+
+        if dir == self.cwd.path {
+            return self.core.show_status("The other tab is in this directory too");
+        }
+
+        self.start_job(kind, paths, dir).map(|_| ())
+    }
+
+    // false when some of it is protected and that wasn't overridden
+    fn start_job(&mut self,
+                 kind: JobKind,
+                 paths: Vec<PathBuf>,
+                 target_dir: PathBuf) -> HResult<bool> {
+        // moving takes things away from where they were too
+        let mut touched = paths.iter()
+            .filter_map(|path| path.file_name())
+            .map(|name| target_dir.join(name))
+            .collect::<Vec<_>>();
+        if kind == JobKind::Move {
+            touched.extend(paths.iter().cloned());
+        }
+        if !crate::protect::guard(&self.core, &touched)? {
+            return Ok(false);
+        }
+
+        let job = Job::new(kind, paths, target_dir);
+        self.jobs.add(job)?;
+
+        let pending = self.jobs.pending();
+        if pending > 1 {
+            self.core.show_status(&format!("Queued, {} jobs waiting", pending - 1))?;
+        }
+
+        Ok(true)
+    }
+
     pub fn update_preview(&mut self) -> HResult<()> {
         if !self.main_async_widget_mut()?.ready() { return Ok(()) }
         if self.main_widget()?
@@ -747,6 +1110,16 @@ impl FileBrowser {
     }
 
 
+    // Sorting or hidden files changed in one directory go for the ones
+    // this tab goes to next too, other tabs keep their own
+    fn save_tab_view(&mut self, before: (SortBy, bool, bool, bool)) -> HResult<()> {
+        let files = self.get_files()?;
+        if files.view() != before {
+            self.fs_cache.set_tab_view(files)?;
+        }
+        Ok(())
+    }
+
     pub fn cwd(&self) -> HResult<&File> {
         Ok(&self.cwd)
     }
@@ -757,6 +1130,92 @@ impl FileBrowser {
         Ok(())
     }
 
+    // Done here since the cwd changes in all kinds of ways, only once
+    // the new directory is loaded
+    fn track_project(&mut self) -> HResult<()> {
+        if !self.main_async_widget_mut()?.ready() { return Ok(()) }
+
+        let dir = self.main_widget()?.content.directory.path.clone();
+        if self.project_checked.as_ref() == Some(&dir) { return Ok(()) }
+        self.project_checked = Some(dir.clone());
+        self.track_local(&dir).log();
+
+        if self.core.config().project_detection {
+            crate::projects::remember(&dir).log();
+        }
+
+        // leaving the project makes it a normal tab again
+        let root = match &self.project {
+            Some(root) if dir.starts_with(root) => root.clone(),
+            Some(_) => { self.project = None; return Ok(()) }
+            None => return Ok(())
+        };
+
+        let main_widget = self.main_widget_mut()?;
+        crate::projects::hide_ignored(&root, &mut main_widget.content);
+        main_widget.content.set_dirty();
+        main_widget.refresh()
+    }
+
+    fn track_local(&mut self, dir: &Path) -> HResult<()> {
+        let (local, untrusted) = match crate::local::load(dir) {
+            Some(Found::Trusted(local)) => (Some(local), None),
+            Some(Found::Untrusted(path)) => (None, Some(path)),
+            None => (None, None)
+        };
+
+        // only once, not in every directory below it
+        if untrusted.is_some() && untrusted != self.untrusted {
+            let path = untrusted.as_ref()?.to_string_lossy();
+            self.core.show_status(&format!("{} isn't trusted yet, TrustLocal uses it", path))
+                .log();
+        }
+
+        self.local = local;
+        self.untrusted = untrusted;
+
+        let (sort, reverse) = match &self.local {
+            Some(LocalSettings { sort: Some(sort), reverse, .. }) => (*sort, *reverse),
+            _ => return Ok(())
+        };
+
+        let dir = self.main_widget()?.content.directory.clone();
+        if !self.fs_cache.set_default_sort(&dir, sort, reverse)? { return Ok(()) }
+
+        let main_widget = self.main_widget_mut()?;
+        let file = main_widget.clone_selected_file();
+        main_widget.content.sort = sort;
+        main_widget.content.reverse = reverse;
+        main_widget.content.sort();
+        main_widget.select_file(&file);
+        main_widget.refresh()
+    }
+
+    pub fn trust_local(&mut self) -> HResult<()> {
+        let path = match crate::local::find(&self.cwd.path) {
+            Some(path) => path,
+            None => return self.core.show_status("No .hunter.toml here or above")
+        };
+
+        let text = std::fs::read_to_string(&path)?;
+        let query = format!("Trust {} with {}? (y/n)",
+                            path.to_string_lossy(),
+                            crate::local::describe(&path, &text)?);
+
+        match self.core.minibuffer(&query)?.as_str() {
+            "y" | "yes" => {},
+            _ => return Ok(())
+        }
+
+        crate::local::trust(&path, &text)?;
+
+        // so refresh looks at it again
+        self.project_checked = None;
+        self.untrusted = None;
+        self.refresh().log();
+        self.core.show_status(&format!("Using {}", path.to_string_lossy()))
+    }
+
     pub fn left_dir(&self) -> HResult<&File> {
         let widget = self.left_widget()?;
         let dir = &widget.content.directory;
@@ -846,10 +1305,87 @@ impl FileBrowser {
     }
 
     pub fn toggle_colums(&mut self) {
+        // a pane has no room for the other columns
+        if self.pane_zoom.is_some() { return; }
+
         self.preview_widget().map(|preview| preview.cancel_animation()).log();
+        // the preview is about to disappear
+        self.columns.focus(1).log();
         self.columns.toggle_zoom().log();
     }
 
+    // In a pane only the file list is shown, in its half of the tab area.
+    // The header, footer and popups still get all of it.
+    pub fn show_as_pane(&mut self, pane: Option<&Coordinates>) -> HResult<()> {
+        match (pane, self.pane_zoom) {
+            (Some(pane), pane_zoom) => {
+                if pane_zoom.is_none() {
+                    self.preview_widget().map(|preview| preview.cancel_animation()).log();
+                    self.pane_zoom = Some(self.columns.zoom_active);
+                    self.columns.focus(1).log();
+                    self.columns.zoom_active = true;
+                }
+                self.columns.set_coordinates(pane)
+            }
+            (None, Some(zoom)) => {
+                self.pane_zoom = None;
+                self.columns.zoom_active = zoom;
+                let area = self.core.coordinates.clone();
+                self.columns.set_coordinates(&area)
+            }
+            (None, None) => Ok(())
+        }
+    }
+
+    // Coming back to this tab it's drawn just like it was left, without
+    // looking at the files or loading the preview again. Only when the
+    // watcher saw something change while it was in the background, or
+    // something was still loading, is it refreshed for real.
+    pub fn resume(&mut self) -> HResult<()> {
+        let unchanged = match &self.drawlist {
+            Some((drawn_in, _)) => drawn_in == self.columns.get_coordinates()? &&
+                !self.changed_while_away(),
+            None => false
+        };
+        self.resumed = unchanged;
+        self.hide_key_hints().log();
+        self.refresh()
+    }
+
+    // Switching to another tab, only then is it worth keeping
+    pub fn suspend(&mut self) -> HResult<()> {
+        let output = self.columns.get_drawlist()?;
+        self.drawlist = Some((self.columns.get_coordinates()?.clone(), output));
+        Ok(())
+    }
+
+    fn changed_while_away(&self) -> bool {
+        let list_changed = |list: HResult<&ListView<Files>>| {
+            list.map(|list| list.content.has_changes()).unwrap_or(true)
+        };
+
+        list_changed(self.main_widget()) ||
+            (self.cwd.parent().is_some() && list_changed(self.left_widget())) ||
+            (!self.columns.zoom_active &&
+             !self.preview_widget().map(|preview| preview.is_settled()).unwrap_or(false))
+    }
+
+    pub fn preview_focused(&self) -> bool {
+        !self.columns.zoom_active && self.columns.focused_index() == Some(2)
+    }
+
+    pub fn focus_next(&mut self) -> HResult<()> {
+        if self.columns.zoom_active {
+            return self.core.show_status("Nothing else to focus!");
+        }
+
+        let next = match self.preview_focused() {
+            true => 1,
+            false => 2
+        };
+        self.columns.focus(next)
+    }
+
     pub fn quit_with_dir(&self) -> HResult<()> {
         let cwd = self.cwd()?.clone().path;
         let selected_file = self.selected_file()?;
@@ -870,20 +1406,63 @@ impl FileBrowser {
 
         let mut file = std::fs::File::create(filepath)?;
         file.write(output.as_bytes())?;
+
+        // just the path, nothing to source
+        if let Some(cd_file) = crate::config::cd_file() {
+            let mut dir = cwd.into_os_string().into_vec();
+            dir.push(b'\n');
+            std::fs::write(cd_file, dir)?;
+        }
+
         HError::quit()
     }
 
     pub fn turbo_cd(&mut self) -> HResult<()> {
         let dir = self.core.minibuffer("cd")?;
 
-        let path = std::path::PathBuf::from(&dir);
+        if crate::remote::is_url(dir.trim()) {
+            return self.open_url(dir.trim());
+        }
+
+        let home = crate::paths::home_path().ok();
+        let path = crate::lineedit::parse_path(&dir,
+                                               &self.cwd.path,
+                                               home.as_ref().map(|h| h.as_path()));
         let dir = File::new_from_path(&path.canonicalize()?, None)?;
         self.main_widget_goto(&dir)?;
 
         Ok(())
     }
 
-    fn external_select(&mut self) -> HResult<()> {
+    fn open_url(&mut self, url: &str) -> HResult<()> {
+        if crate::remote::is_download(url) {
+            self.jobs.add(Job::download(url.to_string(), self.cwd.path()))?;
+
+            let pending = self.jobs.pending();
+            if pending > 1 {
+                self.core.show_status(&format!("Queued, {} jobs waiting", pending - 1))?;
+            }
+            return Ok(());
+        }
+
+        self.core.get_sender().send(Events::InputEnabled(false))?;
+        self.core.screen.suspend().log();
+        self.preview_widget().map(|preview| preview.cancel_animation()).log();
+
+        let mounted = crate::remote::mount(url);
+
+        self.core.screen.activate().log();
+        self.core.clear().log();
+        self.core.get_sender().send(Events::InputEnabled(true))?;
+
+        mounted?;
+        let path = crate::remote::local_path(url)?;
+        let dir = File::new_from_path(&path, None)?;
+        self.main_widget_goto(&dir)
+    }
+
+    // whatever select_cmd printed, relative paths start at the cwd
+    fn run_select_cmd(&mut self) -> HResult<Option<Vec<PathBuf>>> {
         let shell = std::env::var("SHELL").unwrap_or("bash".into());
         let cmd = self.core
             .config.read()?
@@ -925,72 +1504,99 @@ impl FileBrowser {
                         })
                         .collect::<Vec<PathBuf>>();
 
-                    if paths.len() == 1 {
-                        let path = &paths[0];
-                        if path.exists() {
-                            if path.is_dir() {
-                                let dir = File::new_from_path(&path, None)?;
-
-                                self.main_widget_goto(&dir).log();
-                            } else if path.is_file() {
-                                let file = File::new_from_path(&path, None)?;
-                                let dir = file.parent_as_file()?;
-
-                                self.main_widget_goto(&dir).log();
-
-                                self.main_async_widget_mut()?
-                                    .widget
-                                    .on_ready(move |w, _| {
-                                        w?.select_file(&file);
-                                        Ok(())
-                                    })?;
-                            }
-                        } else {
-                            let msg = format!("Can't access path: {}!",
-                                              path.to_string_lossy());
-                            self.core.show_status(&msg).log();
-                        }
-                    } else {
-                        let mut last_file = None;
-                        for file_path in paths {
-                            if !file_path.exists() {
-                                let msg = format!("Can't find: {}",
-                                                  file_path .to_string_lossy());
-                                self.core.show_status(&msg).log();
-                                continue;
-                            }
+                    return Ok(Some(paths));
+                } else {
+                    self.core.show_status("External program failed!").log();
+                }
+            }
+            Err(_) => self.core.show_status("Can't run external program!").log()
+        }
 
-                            let dir_path = file_path.parent()?;
-                            if self.cwd.path != dir_path {
-                                let file_dir = File::new_from_path(&dir_path, None);
+        Ok(None)
+    }
 
-                                self.main_widget_goto_wait(&file_dir?).log();
-                            }
+    // Goes to a directory, or to where a file is with it selected
+    pub fn reveal(&mut self, path: &Path) -> HResult<()> {
+        // broken links can still be selected
+        if std::fs::symlink_metadata(path).is_err() {
+            let msg = format!("Can't access path: {}!",
+                              path.to_string_lossy());
+            self.core.show_status(&msg).log();
+            return Ok(());
+        }
 
-                            self.main_widget_mut()?
-                                .content
-                                .find_file_with_path(&file_path)
-                                .map(|file| {
-                                    file.toggle_selection();
-                                    last_file = Some(file.clone());
-                                });
-                        }
+        if path.is_dir() {
+            let dir = File::new_from_path(&path, None)?;
 
-                        self.main_widget_mut().map(|w| {
-                            last_file.map(|f| w.select_file(&f));
-                            w.content.set_dirty();
-                        }).log();
-                    }
-                } else {
-                    self.core.show_status("External program failed!").log();
+            self.main_widget_goto(&dir).log();
+        } else {
+            let file = File::new_from_path(&path, None)?;
+            let dir = file.parent_as_file()?;
+
+            self.main_widget_goto(&dir).log();
+
+            self.main_async_widget_mut()?
+                .widget
+                .on_ready(move |w, _| {
+                    w?.select_file(&file);
+                    Ok(())
+                })?;
+        }
+
+        Ok(())
+    }
+
+    fn external_select(&mut self) -> HResult<()> {
+        let paths = match self.run_select_cmd()? {
+            Some(paths) => paths,
+            None => return Ok(())
+        };
+
+        if paths.len() == 1 {
+            self.reveal(&paths[0])?;
+        } else {
+            let mut last_file = None;
+            for file_path in paths {
+                if !file_path.exists() {
+                    let msg = format!("Can't find: {}",
+                                      file_path .to_string_lossy());
+                    self.core.show_status(&msg).log();
+                    continue;
+                }
+
+                let dir_path = file_path.parent()?;
+                if self.cwd.path != dir_path {
+                    let file_dir = File::new_from_path(&dir_path, None);
+
+                    self.main_widget_goto_wait(&file_dir?).log();
                 }
+
+                self.main_widget_mut()?
+                    .content
+                    .find_file_with_path(&file_path)
+                    .map(|file| {
+                        file.toggle_selection();
+                        last_file = Some(file.clone());
+                    });
             }
-            Err(_) => self.core.show_status("Can't run external program!").log()
+
+            self.main_widget_mut().map(|w| {
+                last_file.map(|f| w.select_file(&f));
+                w.content.set_dirty();
+            }).log();
         }
 
         Ok(())
     }
 
+    // The tab view opens the tabs, a file browser can't do that itself
+    fn external_select_in_tab(&mut self) -> HResult<()> {
+        match self.run_select_cmd()? {
+            Some(paths) if !paths.is_empty() => Err(HError::FileBrowserNeedNewTabs(paths)),
+            _ => Ok(())
+        }
+    }
+
     fn external_cd(&mut self) -> HResult<()> {
         let shell = std::env::var("SHELL").unwrap_or("bash".into());
         let cmd = self.core
@@ -1054,12 +1660,17 @@ impl FileBrowser {
 
     fn exec_cmd(&mut self,
                 tab_dirs: Vec<File>,
-                tab_files: Vec<Vec<File>>) -> HResult<()> {
+                tab_files: Vec<Vec<File>>,
+                low_priority: bool) -> HResult<()> {
         let cwd = self.cwd()?.clone();
         let selected_file = self.selected_file().ok();
         let selected_files = self.selected_files().ok();
 
-        let cmd = self.core.minibuffer("exec")?.to_string();
+        let query = match low_priority {
+            true => "exec (low priority)",
+            false => "exec"
+        };
+        let cmd = self.core.minibuffer_in_dir(query, &cwd.path)?.to_string();
 
         let cwd_files = selected_files.map(|selected_files| {
             if selected_files.len() == 0 {
@@ -1081,7 +1692,9 @@ impl FileBrowser {
             cwd: cwd,
             cwd_files: cwd_files,
             tab_files: Some(tab_files),
-            tab_paths: Some(tab_dirs)
+            tab_paths: Some(tab_dirs),
+            low_priority: low_priority,
+            follow_up: None
         };
 
         self.proc_view.lock()?.run_proc_subshell(cmd)?;
@@ -1089,14 +1702,81 @@ impl FileBrowser {
         Ok(())
     }
 
-    pub fn run_subshell(&mut self) -> HResult<()> {
-        self.core.get_sender().send(Events::InputEnabled(false))?;
+    // %s is replaced with the selected files, or the one under the cursor
+    // if none are, %f with only that one and %d with the current directory,
+    // all quoted for the shell. The $s and friends from ExecCmd work too.
+    pub fn run_command(&mut self, template: &str) -> HResult<()> {
+        let cwd = self.cwd()?.clone();
+        let selected_file = self.selected_file()?;
+        let files = match self.selected_files()? {
+            files if files.is_empty() => vec![selected_file.clone()],
+            files => files
+        };
 
-        self.preview_widget().map(|preview| preview.cancel_animation()).log();
+        let quote = |path: PathBuf| {
+            let mut quoted = OsString::from("'");
+            quoted.push(path.into_os_string().escape_single_quote());
+            quoted.push("'");
+            quoted
+        };
+
+        let mut cmd = OsString::new();
+        let mut chars = template.chars();
+
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                cmd.push(c.to_string());
+                continue;
+            }
+
+            match chars.next() {
+                Some('s') => {
+                    for (i, file) in files.iter().enumerate() {
+                        if i > 0 { cmd.push(" "); }
+                        cmd.push(quote(file.strip_prefix(&cwd)));
+                    }
+                }
+                Some('f') => cmd.push(quote(selected_file.strip_prefix(&cwd))),
+                Some('d') => cmd.push(quote(cwd.path.clone())),
+                Some('%') => cmd.push("%"),
+                Some(other) => cmd.push(format!("%{}", other)),
+                None => cmd.push("%")
+            }
+        }
+
+        let cmd = crate::proclist::Cmd {
+            cmd: cmd,
+            short_cmd: None,
+            args: None,
+            vars: None,
+            cwd: cwd,
+            cwd_files: Some(files),
+            tab_files: None,
+            tab_paths: None,
+            low_priority: false,
+            follow_up: None
+        };
+
+        self.proc_view.lock()?.run_proc_subshell(cmd)
+    }
+
+    pub fn run_subshell(&mut self) -> HResult<()> {
+        self.core.get_sender().send(Events::InputEnabled(false))?;
+
+        self.preview_widget().map(|preview| preview.cancel_animation()).log();
         self.core.screen.suspend().log();
 
+        // HUNTER_LEVEL tells the shell it's in hunter, how deep too
+        let level = std::env::var("HUNTER_LEVEL")
+            .ok()
+            .and_then(|level| level.parse::<usize>().ok())
+            .unwrap_or(0);
+
         let shell = std::env::var("SHELL").unwrap_or("bash".into());
-        let status = std::process::Command::new(&shell).status();
+        let status = std::process::Command::new(&shell)
+            .current_dir(&self.cwd.path)
+            .env("HUNTER_LEVEL", (level + 1).to_string())
+            .status();
 
         self.core.screen.activate().log();
 
@@ -1119,8 +1799,15 @@ impl FileBrowser {
 
     pub fn show_procview(&mut self) -> HResult<()> {
         self.preview_widget().map(|preview| preview.cancel_animation()).log();
-        self.proc_view.lock()?.popup()?;
-        Ok(())
+
+        let mut proc_view = self.proc_view.lock()?;
+        let ticker = proc_view.start_ticker();
+        crate::proclist::set_visible(true);
+        let result = proc_view.popup();
+        crate::proclist::set_visible(false);
+        ticker.set_stale().ok();
+
+        result
     }
 
     pub fn show_log(&mut self) -> HResult<()> {
@@ -1129,6 +1816,651 @@ impl FileBrowser {
         Ok(())
     }
 
+    pub fn toggle_pause_transfers(&mut self) -> HResult<()> {
+        let paused = !self.jobs.is_paused();
+        self.jobs.set_paused(paused);
+
+        match paused {
+            true => self.core.show_status("Transfers paused"),
+            false => self.core.show_status("Transfers resumed")
+        }
+    }
+
+    pub fn show_trash(&mut self) -> HResult<()> {
+        self.preview_widget().map(|preview| preview.cancel_animation()).log();
+        let mut trash_view = TrashView::new(&self.core, crate::trash::list());
+        trash_view.popup()?;
+        Ok(())
+    }
+
+    pub fn edit_permissions(&mut self) -> HResult<()> {
+        let files = self.selected_files()?;
+        let files = if files.len() > 0 { files }
+        else { vec![self.selected_file()?.clone()] };
+        let paths = files.into_iter().map(|file| file.path).collect();
+
+        self.preview_widget().map(|preview| preview.cancel_animation()).log();
+        let mut popup = PermPopup::new(&self.core, paths)?;
+        popup.edit()?;
+        Ok(())
+    }
+
+    // The path, or what's in the file if it's small text, e.g. a key.
+    // Internet shortcuts get just their link.
+    pub fn show_qr(&mut self, contents: bool) -> HResult<()> {
+        let file = self.selected_file()?;
+
+        let text = match contents {
+            false => file.path.to_string_lossy().to_string(),
+            true => {
+                let size = std::fs::metadata(&file.path)?.len() as usize;
+                if file.is_dir() || size > crate::qr::QR_MAX {
+                    return self.core.show_status(&format!("Only text files up to {} bytes work",
+                                                          crate::qr::QR_MAX));
+                }
+
+                match String::from_utf8(std::fs::read(&file.path)?) {
+                    Ok(text) => match crate::qr::shortcut_url(&text) {
+                        Some(url) => url.to_string(),
+                        None => text
+                    },
+                    Err(_) => return self.core.show_status(&format!("{} isn't a text file",
+                                                                    file.name))
+                }
+            }
+        };
+
+        self.preview_widget().map(|preview| preview.cancel_animation()).log();
+        let mut popup = QrPopup::new(&self.core, &file.name, &text)?;
+        popup.show()
+    }
+
+    pub fn show_hex(&mut self) -> HResult<()> {
+        let file = self.selected_file()?;
+
+        if !file.path.is_file() {
+            return self.core.show_status("Can only show files as hex!");
+        }
+
+        self.preview_widget().map(|preview| preview.cancel_animation()).log();
+        let mut hexview = HexView::new_from_file(&self.core, &file.path, PAGER_LIMIT)?;
+        hexview.show_pager()
+    }
+
+    // Serves the files until a key is pressed, with a QR code to get the
+    // URL onto a phone easily
+    #[cfg(feature = "share")]
+    pub fn share(&mut self) -> HResult<()> {
+        let files = match self.selected_files()? {
+            files if files.is_empty() => vec![self.selected_file()?],
+            files => files
+        };
+        let paths = files.into_iter().map(|file| file.path).collect();
+
+        let share = crate::share::Share::start(paths)?;
+
+        self.preview_widget().map(|preview| preview.cancel_animation()).log();
+        self.core.show_status("Sharing, press any key to stop").log();
+
+        match QrPopup::new(&self.core, &share.url, &share.url) {
+            Ok(mut popup) => popup.show()?,
+            // the URL will have to do without qrencode
+            Err(_) => {
+                self.core.minibuffer(&format!("{} (Enter to stop)", share.url)).ok();
+            }
+        }
+
+        drop(share);
+        self.core.show_status("Stopped sharing")
+    }
+
+    #[cfg(not(feature = "share"))]
+    pub fn share(&mut self) -> HResult<()> {
+        self.core.show_status("hunter was built without sharing, see the share feature")
+    }
+
+    pub fn send_to_device(&mut self) -> HResult<()> {
+        let files = match self.selected_files()? {
+            files if files.is_empty() => vec![self.selected_file()?],
+            files => files
+        };
+        let paths = files.into_iter()
+            .map(|file| file.path)
+            .filter(|path| path.is_file())
+            .collect::<Vec<_>>();
+
+        if paths.is_empty() {
+            return self.core.show_status("Only files can be sent");
+        }
+
+        self.core.show_status("Looking for devices...").log();
+        let devices = crate::devices::discover()?;
+
+        if devices.is_empty() {
+            return self.core.show_status("No paired devices reachable");
+        }
+
+        self.preview_widget().map(|preview| preview.cancel_animation()).log();
+
+        let mut picker: DevicePicker = ListView::new(&self.core, devices);
+
+        match picker.popup() {
+            Ok(_) | Err(HError::PopupFinnished) => {},
+            err @ Err(_) => err?
+        }
+        self.core.clear()?;
+
+        let device = match picker.picked() {
+            Some(device) => device,
+            None => return Ok(())
+        };
+
+        self.jobs.add(Job::send(paths, device))?;
+
+        let pending = self.jobs.pending();
+        if pending > 1 {
+            self.core.show_status(&format!("Queued, {} jobs waiting", pending - 1))?;
+        }
+
+        Ok(())
+    }
+
+    // The tab view opens the tab, see on_key_sub
+    pub fn show_projects(&mut self) -> HResult<()> {
+        let projects = crate::projects::recent()
+            .into_iter()
+            .map(Project::new)
+            .collect::<Vec<_>>();
+
+        if projects.is_empty() {
+            let msg = match self.core.config().project_detection {
+                true => "No projects used yet",
+                false => "No projects known, project_detection is off"
+            };
+            return self.core.show_status(msg);
+        }
+
+        self.preview_widget().map(|preview| preview.cancel_animation()).log();
+
+        let mut picker: ProjectPicker = ListView::new(&self.core, projects);
+
+        match picker.popup() {
+            Ok(_) | Err(HError::PopupFinnished) => {},
+            err @ Err(_) => err?
+        }
+        self.core.clear()?;
+
+        match picker.picked() {
+            Some(root) => Err(HError::FileBrowserNeedProjectTab(root)),
+            None => Ok(())
+        }
+    }
+
+    pub fn show_recent_files(&mut self) -> HResult<()> {
+        let roots = crate::recent::roots(&self.core.config().recent_roots);
+        if roots.is_empty() {
+            return self.core.show_status("None of the recent_roots exist");
+        }
+
+        self.core.show_status("Looking for recently modified files...").log();
+        let files = crate::recent::find(&roots);
+
+        if files.is_empty() {
+            return self.core.show_status("Nothing found");
+        }
+
+        self.preview_widget().map(|preview| preview.cancel_animation()).log();
+
+        let mut picker: RecentFiles = ListView::new(&self.core, files);
+
+        match picker.popup() {
+            Ok(_) | Err(HError::PopupFinnished) => {},
+            err @ Err(_) => err?
+        }
+        self.core.clear()?;
+
+        match picker.picked() {
+            Some((path, true)) => Err(HError::FileBrowserNeedNewTabs(vec![path])),
+            Some((path, false)) => self.reveal(&path),
+            None => Ok(())
+        }
+    }
+
+    pub fn show_audit(&mut self) -> HResult<()> {
+        let entries = match crate::audit::load() {
+            Ok(entries) => entries,
+            Err(_) => return self.core.show_status("Nothing done to any files yet")
+        };
+
+        self.preview_widget().map(|preview| preview.cancel_animation()).log();
+
+        let mut audit: AuditView = ListView::new(&self.core, AuditLog::new(entries));
+
+        match audit.popup() {
+            Ok(_) | Err(HError::PopupFinnished) => {},
+            err @ Err(_) => err?
+        }
+        self.core.clear()?;
+
+        match audit.picked() {
+            Some(path) => self.reveal(&path),
+            None => Ok(())
+        }
+    }
+
+    pub fn show_branches(&mut self) -> HResult<()> {
+        let branches = match Branches::new(&self.cwd.path) {
+            Ok(branches) => branches,
+            Err(_) => return self.core.show_status("Not in a git repository")
+        };
+
+        if branches.is_empty() {
+            return self.core.show_status("No branches yet");
+        }
+
+        self.preview_widget().map(|preview| preview.cancel_animation()).log();
+
+        let root = branches.root.clone();
+        let mut picker: BranchPicker = ListView::new(&self.core, branches);
+
+        match picker.popup() {
+            Ok(_) | Err(HError::PopupFinnished) => {},
+            err @ Err(_) => err?
+        }
+        self.core.clear()?;
+
+        // the same directory over there, or as close as it gets
+        let relative = self.cwd.path.strip_prefix(&root).map(|dir| dir.to_path_buf());
+        let same_dir = |other_root: &Path| -> Option<PathBuf> {
+            other_root.join(relative.as_ref().ok()?)
+                .ancestors()
+                .find(|dir| dir.is_dir())
+                .map(|dir| dir.to_path_buf())
+        };
+
+        match picker.picked() {
+            Some(Target::Worktree(path)) => {
+                let dir = same_dir(&path).unwrap_or(path);
+                self.reveal(&dir)
+            }
+            Some(Target::Branch(branch)) => {
+                self.core.show_status(&format!("Checking out {}...", branch)).log();
+
+                if let Err(e) = crate::branches::switch(&root, &branch) {
+                    return self.core.show_status(&e.to_string());
+                }
+
+                // files come and go, the directory itself might be gone too
+                let dir = same_dir(&root).unwrap_or(root);
+                let prev_cwd = self.prev_cwd.clone();
+                self.main_widget_goto(&File::new_from_path(&dir, None)?)?;
+                self.prev_cwd = prev_cwd;
+
+                self.core.show_status(&format!("Switched to {}", branch))
+            }
+            None => Ok(())
+        }
+    }
+
+    pub fn next_conflict(&mut self) -> HResult<()> {
+        let selected = self.selected_file()
+            .map(|file| file.path)
+            .unwrap_or_else(|_| self.cwd.path.clone());
+
+        match crate::conflicts::next(&self.cwd.path, &selected) {
+            Some(path) => self.reveal(&path),
+            None => self.core.show_status("No conflicts left")
+        }
+    }
+
+    pub fn show_pipelines(&mut self) -> HResult<()> {
+        let pipelines = match crate::pipelines::load() {
+            Ok(pipelines) => pipelines,
+            Err(_) => return self.core.show_status("No pipelines saved yet")
+        };
+
+        if pipelines.is_empty() {
+            return self.core.show_status("No pipelines saved yet");
+        }
+
+        self.preview_widget().map(|preview| preview.cancel_animation()).log();
+
+        let mut picker: PipelinePicker = ListView::new(&self.core, Pipelines::new(pipelines));
+
+        match picker.popup() {
+            Ok(_) | Err(HError::PopupFinnished) => {},
+            err @ Err(_) => err?
+        }
+        self.core.clear()?;
+
+        let pipeline = match picker.picked() {
+            Some(pipeline) => pipeline,
+            None => return Ok(())
+        };
+
+        let mut values = vec![];
+        for (name, default) in pipeline.params() {
+            let value = self.core.minibuffer_prefilled(&name, &default)?;
+            values.push((name, value));
+        }
+
+        let cwd = self.cwd()?.clone();
+        let sources = self.marked_paths()?;
+        let files = sources.iter()
+            .map(|path| path.strip_prefix(&cwd.path)
+                 .map(|path| path.to_path_buf())
+                 .unwrap_or(path.clone()))
+            .collect::<Vec<_>>();
+
+        let follow_up = match pipeline.follow_up.is_empty() {
+            true => None,
+            false => Some((pipeline.follow_up.clone(), sources))
+        };
+
+        let cmd = crate::proclist::Cmd {
+            cmd: pipeline.shell_command(&values, &files, &cwd.path),
+            short_cmd: None,
+            args: None,
+            vars: None,
+            cwd: cwd,
+            cwd_files: None,
+            tab_files: None,
+            tab_paths: None,
+            low_priority: pipeline.low_priority,
+            follow_up: follow_up
+        };
+
+        self.proc_view.lock()?.run_proc_subshell(cmd)?;
+
+        if pipeline.follow_up.show_output {
+            self.preview_widget().map(|preview| preview.cancel_animation()).log();
+            self.proc_view.lock()?.show_last()?;
+        }
+
+        Ok(())
+    }
+
+    // After a job from a pipeline or quick action, if it ran right here
+    pub fn follow_up(&mut self, job: &FinishedJob) -> HResult<bool> {
+        if self.cwd.path != job.cwd { return Ok(false); }
+        self.resumed = false;
+
+        let follow_up = &job.follow_up;
+
+        if job.success && (follow_up.refresh || follow_up.select_output) {
+            let output = match follow_up.select_output {
+                true => job.output(),
+                false => None
+            };
+            let select = match output {
+                Some(output) => Some(File::new_from_path(&output, None)?),
+                None => self.selected_file().ok()
+            };
+
+            let dir = self.cwd.clone();
+            let prev_cwd = self.prev_cwd.clone();
+            self.main_widget_goto(&dir)?;
+            self.prev_cwd = prev_cwd;
+
+            if let Some(file) = select {
+                self.main_async_widget_mut()?
+                    .widget
+                    .on_ready(move |w, _| {
+                        w?.select_file(&file);
+                        Ok(())
+                    })?;
+            }
+        }
+
+        if !job.success && follow_up.mark_sources {
+            let list = self.main_widget_mut()?;
+            for file in list.content.iter_files_mut() {
+                if job.sources.contains(&file.path) {
+                    file.selected = true;
+                }
+            }
+            list.content.set_dirty();
+            list.refresh().log();
+        }
+
+        Ok(true)
+    }
+
+    pub fn show_cleanup(&mut self) -> HResult<()> {
+        let root = self.cwd.path();
+        let config = self.core.config();
+
+        self.core.show_status("Adding up sizes...").log();
+        let cleanup = Cleanup::new(root,
+                                   config.one_filesystem,
+                                   config.cleanup_threshold,
+                                   self.jobs.clone())?;
+
+        if cleanup.is_empty() {
+            let size = crate::files::pretty_size(config.cleanup_threshold);
+            return self.core.show_status(&format!("Nothing here is bigger than {}", size));
+        }
+
+        self.preview_widget().map(|preview| preview.cancel_animation()).log();
+
+        let mut view: CleanupView = ListView::new(&self.core, cleanup);
+
+        match view.popup() {
+            Ok(_) | Err(HError::PopupFinnished) => {},
+            err @ Err(_) => err?
+        }
+        self.core.clear()?;
+
+        match view.picked() {
+            Some((path, true)) => Err(HError::FileBrowserNeedNewTabs(vec![path])),
+            Some((path, false)) => self.reveal(&path),
+            None => Ok(())
+        }
+    }
+
+    // Commands from .hunter.toml first, then names from [palette], any file
+    // browser or file list action works by its own name too
+    pub fn command_palette(&mut self) -> HResult<()> {
+        let palette = self.local
+            .iter()
+            .flat_map(|local| local.actions.iter())
+            .map(|(name, command)| (name.clone(), format!("!{}", command)))
+            .chain(self.core.config().keybinds.palette)
+            .collect::<Vec<_>>();
+        let choices = palette.iter()
+            .map(|(name, _)| name.clone())
+            .chain(FileBrowserAction::iter().map(|action| action.to_string()))
+            .chain(FileListAction::iter().map(|action| action.to_string()))
+            .collect::<Vec<_>>();
+
+        let name = self.core.minibuffer_choices(":", &choices)?;
+        let name = name.trim();
+        if name.is_empty() { return Ok(()); }
+
+        let target = palette.iter()
+            .find(|(palette_name, _)| palette_name == name)
+            .map(|(_, target)| target.clone())
+            .unwrap_or_else(|| name.to_string());
+
+        if target.starts_with('!') {
+            return self.run_command(&target[1..]);
+        }
+
+        fn parse<T>(target: &str) -> Option<T>
+        where
+            T: BindingSection,
+            Bindings<T>: Default
+        {
+            let (action, param) = T::process_action_str(target);
+            let action = T::from_str(action).ok()?;
+            match param {
+                Some(param) => Some(action.insert_config_param(param)),
+                None => Some(action.as_default())
+            }
+        }
+
+        if let Some(action) = parse::<FileBrowserAction>(&target) {
+            return self.do_action(&action);
+        }
+        if let Some(action) = parse::<FileListAction>(&target) {
+            return self.main_widget_mut()?.do_action(&action);
+        }
+
+        self.core.show_status(&format!("No command or action named {}", name))
+    }
+
+    pub fn find_leftovers(&mut self, kind: Kind) -> HResult<()> {
+        let root = self.cwd.path();
+        let one_filesystem = self.core.config().one_filesystem;
+
+        self.core.show_status("Searching...").log();
+        let findings = Findings::new(kind, root, one_filesystem)?;
+
+        if findings.is_empty() {
+            let msg = match kind {
+                Kind::BrokenLinks => "No broken links here",
+                Kind::EmptyDirs => "No empty directories here"
+            };
+            return self.core.show_status(msg);
+        }
+
+        self.preview_widget().map(|preview| preview.cancel_animation()).log();
+
+        let mut view: FindingsView = ListView::new(&self.core, findings);
+
+        match view.popup() {
+            Ok(_) | Err(HError::PopupFinnished) => {},
+            err @ Err(_) => err?
+        }
+        self.core.clear()?;
+
+        match view.picked() {
+            Some((path, true)) => Err(HError::FileBrowserNeedNewTabs(vec![path])),
+            Some((path, false)) => self.reveal(&path),
+            None => Ok(())
+        }
+    }
+
+    pub fn record_macro(&mut self) -> HResult<()> {
+        match crate::macros::record()? {
+            Some((register, keys)) => {
+                let msg = format!("Recorded macro {}, {} keys", register, keys);
+                self.core.show_status(&msg)
+            }
+            None => self.core.show_status("Record into which macro?")
+        }
+    }
+
+    // Drawn on top of the columns until the next key, which only closes it
+    pub fn show_key_hints(&mut self) -> HResult<()> {
+        self.show_hints(Hints::All)
+    }
+
+    fn show_hints(&mut self, which: Hints) -> HResult<()> {
+        self.hide_key_hints()?;
+        self.preview_widget().map(|preview| preview.cancel_animation()).log();
+
+        let hints = KeyHints::new(&self.core, which)?;
+        let id = self.core.show_overlay(1,
+                                        hints.get_coordinates()?.clone(),
+                                        hints.get_drawlist()?)?;
+        *self.key_hints.lock()? = Some((id, which));
+        Ok(())
+    }
+
+    // true if they were shown
+    fn hide_key_hints(&mut self) -> HResult<Option<Hints>> {
+        let shown = self.key_hints.lock()?.take();
+        match shown {
+            Some((id, which)) => { self.core.close_overlay(id)?; Ok(Some(which)) }
+            None => Ok(None)
+        }
+    }
+
+    // only what can follow the count or macro key
+    fn show_prefix_hints(&mut self) -> HResult<()> {
+        if !self.core.config().key_hints { return Ok(()); }
+
+        if let Some(count) = crate::keybind::typed_count() {
+            self.show_hints(Hints::Count(count))
+        } else if let Some(pending) = crate::macros::pending() {
+            self.show_hints(Hints::Macro(pending))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn dispatch_key(&mut self, key: Key) -> HResult<()> {
+        // the preview only gets keys when it has the focus, falling
+        // back to the global ones so it can give it back
+        if self.preview_focused() {
+            match self.preview_widget_mut()?.on_key(key) {
+                Err(HError::WidgetUndefinedKeyError{..}) => self.do_key(key)?,
+                e @ _ => e?
+            }
+            self.update_preview().log();
+            return Ok(());
+        }
+
+        // visual and search mode have their own keys that go first
+        let in_mode = self.main_widget_mut().map(|list| list.mode_key(key));
+        match in_mode {
+            Ok(Err(HError::WidgetUndefinedKeyError{..})) | Err(_) => {}
+            Ok(result) => {
+                result?;
+                self.update_preview().log();
+                return Ok(());
+            }
+        }
+
+        // bound on purpose, so they go before any action on the same key
+        let command = self.core.config().keybinds.commands.get(key).cloned();
+        if let Some(command) = command {
+            return self.run_command(&command);
+        }
+
+        match self.do_key(key) {
+            Err(HError::WidgetUndefinedKeyError{..}) => {
+                let view = self.main_widget()?.content.view();
+                match self.main_widget_mut()?.on_key(key) {
+                    Ok(_) => {
+                        self.save_tab_settings()?;
+                        self.save_tab_view(view).log();
+                    }
+                    Err(HError::WidgetUndefinedKeyError{..}) => {
+                        match self.preview_widget_mut()?.on_key(key) {
+                            Err(e @ HError::WidgetUndefinedKeyError{..}) => {
+                                // a count before a key that does nothing is gone too
+                                crate::keybind::clear_count();
+                                Err(e)?
+                            }
+                            e @ _ => e?
+                        }
+                    }
+                    e @ _ => e?
+                }
+            }
+            e @ _ => e?
+        };
+
+        if !self.columns.zoom_active { self.update_preview().log(); }
+        Ok(())
+    }
+
+    pub fn show_help(&mut self) -> HResult<()> {
+        let entries = crate::help::entries(&self.core.config().keybinds);
+        let mut menu: HelpMenu = ListView::new(&self.core, entries);
+
+        self.preview_widget().map(|preview| preview.cancel_animation()).log();
+
+        match menu.popup() {
+            Ok(_) | Err(HError::PopupFinnished) => {},
+            err @ Err(_) => err?
+        }
+
+        self.core.clear()
+    }
+
     pub fn quick_action(&self) -> HResult<()> {
         let files = self.selected_files()?;
         let files = if files.len() > 0 { files }
@@ -1145,65 +2477,208 @@ impl FileBrowser {
         Ok(())
     }
 
-    pub fn get_footer(&self) -> HResult<String> {
-        let xsize = self.get_coordinates()?.xsize();
-        let ypos = self.get_coordinates()?.position().y();
-        let file = self.selected_file()?;
+    // SGR mouse reports look like ESC [ < button ; x ; y M, with 35 meaning
+    // motion without any button pressed
+    fn parse_hover(event: &[u8]) -> Option<(u16, u16)> {
+        let event = std::str::from_utf8(event).ok()?;
+        let mut parts = event.trim_start_matches("\x1b[<")
+                             .trim_end_matches('M')
+                             .split(';');
 
+        match parts.next()? {
+            "35" => {}
+            _ => return None
+        }
+
+        let x = parts.next()?.parse::<u16>().ok()?;
+        let y = parts.next()?.parse::<u16>().ok()?;
+        Some((x, y))
+    }
+
+    fn show_hover_info(&self, x: u16, y: u16) -> HResult<()> {
+        let file = match self.main_widget()?.file_at(x, y) {
+            Some(file) => file,
+            None => return Ok(())
+        };
 
         let permissions = file.pretty_print_permissions().unwrap_or("NOPERMS".into());
-        let user = file.pretty_user().unwrap_or("NOUSER".into());
-        let group = file.pretty_group().unwrap_or("NOGROUP".into());
         let mtime = file.pretty_mtime().unwrap_or("NOMTIME".into());
-        let target = if let Some(target) = &file.target {
-            "--> ".to_string() + &target.short_string()
-        } else { "".to_string() };
-
-        let main_widget = self.main_widget()?;
-        let selection = main_widget.get_selection() + 1;
-        let file_count = main_widget.content.len();
-        let file_count = format!("{}", file_count);
-        let digits = file_count.len();
-        let file_count = format!("{:digits$}/{:digits$}",
-                                 selection,
-                                 file_count,
-                                 digits = digits);
-        let count_xpos = xsize - file_count.len() as u16;
-        let count_ypos = ypos + self.get_coordinates()?.ysize();
-
-        let fs = self.fs_stat.read()?.find_fs(&file.path)?.clone();
-
-        let dev = fs.get_dev().unwrap_or(String::from(""));
-        let free_space = fs.get_free();
-        let total_space = fs.get_total();
-        let space = format!("{}{} / {}",
-                            dev,
-                            free_space,
-                            total_space);
-
-        let space_xpos = count_xpos - space.len() as u16 - 5; // - 3;
-
-        let status = format!("{} {}:{} {}{} {}{}",
-                             permissions,
-                             user,
-                             group,
-                             crate::term::header_color(),
-                             mtime,
-                             crate::term::color_yellow(),
-                             target
-        );
-        let status = crate::term::sized_string_u(&status, (xsize-1) as usize);
-
-        let status = format!("{}{}{}{}{}{} | {}",
-                             status,
-                             crate::term::header_color(),
-                             crate::term::goto_xy(space_xpos, count_ypos),
-                             crate::term::color_orange(),
-                             space,
-                             crate::term::header_color(),
-                             file_count);
-
-        Ok(status)
+        let (size, unit) = file.calculate_size().unwrap_or((0, ""));
+
+        let info = format!("{}  {}  {}{}{}  {}",
+                           file.name,
+                           permissions,
+                           file.size_prefix(),
+                           size,
+                           unit,
+                           mtime);
+
+        // not using show_status, that would spam the log on every move
+        *self.core.status_bar_content.lock()? = Some(info);
+        self.core.draw_status()
+    }
+
+    // Two left clicks on the same spot make a double click, a third one
+    // starts over
+    fn mouse_key(&mut self, button: MouseButton, x: u16, y: u16) -> MouseKey {
+        match button {
+            MouseButton::Left => {
+                let double = match self.last_click {
+                    Some((time, last_x, last_y)) => {
+                        time.elapsed() < DOUBLE_CLICK_TIME && (last_x, last_y) == (x, y)
+                    }
+                    None => false
+                };
+
+                self.last_click = match double {
+                    true => None,
+                    false => Some((Instant::now(), x, y))
+                };
+
+                match double {
+                    true => MouseKey::DoubleLeft,
+                    false => MouseKey::Left
+                }
+            }
+            MouseButton::Right => MouseKey::Right,
+            MouseButton::Middle => MouseKey::Middle,
+            MouseButton::WheelUp => MouseKey::WheelUp,
+            MouseButton::WheelDown => MouseKey::WheelDown
+        }
+    }
+
+    // Only the file list reacts to the mouse, clicks anywhere else do nothing
+    fn mouse_action(&mut self, action: MouseAction, x: u16, y: u16) -> HResult<()> {
+        use MouseAction::*;
+
+        let pos = self.main_widget()?.get_selection();
+        let selected = match action {
+            ScrollUp(n) => return self.movement(&Movement::Up(n)),
+            ScrollDown(n) => return self.movement(&Movement::Down(n)),
+            _ => self.main_widget_mut()?.select_at(x, y)
+        };
+
+        if !selected { return Ok(()); }
+
+        if self.main_widget()?.get_selection() != pos {
+            self.preview_widget_mut()?.set_stale().log();
+            self.preview_widget_mut()?.cancel_animation().log();
+        }
+        self.save_selected_file()?;
+
+        match action {
+            Open => self.open(),
+            ContextMenu => self.quick_action(),
+            _ => Ok(())
+        }
+    }
+
+    pub fn get_footer(&self) -> HResult<String> {
+        let xsize = self.get_coordinates()?.xsize_u();
+        let file = self.selected_file()?;
+        let config = self.core.config();
+
+        let footer = footer::render(&config.footer_format, xsize, |segment, arg| {
+            self.footer_segment(&file, &config, segment, arg).ok()
+        });
+
+        Ok(footer)
+    }
+
+    fn footer_segment(&self,
+                      file: &File,
+                      config: &Config,
+                      segment: &str,
+                      arg: Option<&str>) -> HResult<String> {
+        let xsize = self.get_coordinates()?.xsize_u();
+        let keep = config.path_keep;
+
+        let segment = match segment {
+            "permissions" => file.pretty_print_permissions()
+                .unwrap_or("NOPERMS".into()),
+            "owner" => {
+                let user = file.pretty_user().unwrap_or("NOUSER".into());
+                let group = file.pretty_group().unwrap_or("NOGROUP".into());
+                format!("{}:{}", user, group)
+            }
+            "mtime" => file.pretty_mtime().unwrap_or("NOMTIME".into()),
+            "sparse" => match file.is_sparse() {
+                true => format!("{}sparse: {}",
+                                term::color_cyan(),
+                                file.pretty_disk_usage()?),
+                false => String::new()
+            },
+            "target" => match &file.target {
+                Some(target) => {
+                    let target_xsize = (xsize / 3).max(10);
+                    format!("{}--> {}",
+                            term::color_yellow(),
+                            term::sized_path(&target.short_string(),
+                                             target_xsize,
+                                             keep))
+                }
+                None => String::new()
+            },
+            "path" => term::sized_path(&self.cwd()?.short_string(),
+                                       (xsize / 3).max(10),
+                                       keep),
+            "selected" => match self.selected_files()?.len() {
+                0 => String::new(),
+                n => format!("{}{} selected", term::color_yellow(), n)
+            },
+            "count" => {
+                let main_widget = self.main_widget()?;
+                let selection = main_widget.get_selection() + 1;
+                let file_count = main_widget.content.len().to_string();
+                format!("{:digits$}/{}",
+                        selection,
+                        file_count,
+                        digits = file_count.len())
+            }
+            "device" => {
+                let fs = self.fs_stat.read()?.find_fs(&file.path)?.clone();
+                format!("{}{}", term::color_orange(), fs.get_dev().unwrap_or_default())
+            }
+            "space" => {
+                let fs = self.fs_stat.read()?.find_fs(&file.path)?.clone();
+                format!("{}{} / {}",
+                        term::color_orange(),
+                        fs.get_free(),
+                        fs.get_total())
+            }
+            "jobs" => match (self.jobs.progress(), self.jobs.pending()) {
+                (Some(progress), 1) => format!("{}{}",
+                                               term::color_orange(),
+                                               progress.pretty()),
+                (Some(progress), pending) => format!("{}{} +{}",
+                                                     term::color_orange(),
+                                                     progress.pretty(),
+                                                     pending - 1),
+                (None, _) => String::new()
+            },
+            "mode" => match self.main_widget()?.mode() {
+                Mode::Normal => String::new(),
+                Mode::Visual => format!("{}VISUAL", term::color_yellow()),
+                Mode::Search => format!("{}SEARCH", term::color_cyan())
+            },
+            "macro" => match crate::macros::recording() {
+                Some(register) => format!("{}recording {}", term::color_red(), register),
+                None => String::new()
+            },
+            "git" => match footer::git_branch(&self.cwd()?.path) {
+                Some(branch) => format!("{}{}", term::color_green(), branch),
+                None => String::new()
+            },
+            "blame" => match footer::git_blame(&file.path, &self.core.get_sender()) {
+                Some(blame) => format!("{}{}", term::color_cyan(), blame),
+                None => String::new()
+            },
+            // typos should stand out
+            _ => footer::common_segment(segment, arg)
+                .unwrap_or_else(|| format!("{{{}}}", segment))
+        };
+
+        Ok(segment)
     }
 }
 
@@ -1216,6 +2691,7 @@ impl Widget for FileBrowser {
     }
 
     fn set_coordinates(&mut self, coordinates: &Coordinates) -> HResult<()> {
+        self.resumed = false;
         self.core.coordinates = coordinates.clone();
         self.columns.set_coordinates(&coordinates).log();
         self.proc_view.lock()?.set_coordinates(&coordinates).log();
@@ -1245,6 +2721,10 @@ impl Widget for FileBrowser {
         if &path == "/" { path.pop(); }
 
 
+        let keep = self.core.config().path_keep;
+        let path_xsize = (xsize as usize).saturating_sub(name.width() + 1);
+        let path = term::sized_path(&path, path_xsize, keep);
+
         let pretty_path = format!("{}/{}{}", path, &color, name );
         let sized_path = crate::term::sized_string(&pretty_path, xsize);
         Ok(sized_path.to_string())
@@ -1252,46 +2732,83 @@ impl Widget for FileBrowser {
     fn render_footer(&self) -> HResult<String> {
         let xsize = term::xsize_u();
         match self.get_core()?.status_bar_content.lock()?.as_mut().take() {
-            Some(status) => Ok(term::sized_string_u(&status, xsize)),
+            Some(status) => Ok(self.core.sized_status(&status, xsize)),
             _ => { self.get_footer() },
         }
     }
     fn refresh(&mut self) -> HResult<()> {
+        // the register key never gets here, it's taken before
+        let macro_hints = match *self.key_hints.lock()? {
+            Some((_, Hints::Macro(_))) => crate::macros::pending().is_none(),
+            _ => false
+        };
+        if macro_hints { self.hide_key_hints().log(); }
+
         self.set_title().log();
+        self.set_cwd().log();
+        // still looks like it did before switching to another tab
+        if self.resumed && !self.changed_while_away() { return Ok(()); }
+        self.resumed = false;
+
         self.columns.refresh().log();
         self.set_left_selection().log();
-        self.set_cwd().log();
+        self.track_project().log();
         if !self.columns.zoom_active { self.update_preview().log(); }
         self.columns.refresh().log();
         Ok(())
     }
 
     fn get_drawlist(&self) -> HResult<String> {
+        if let (true, Some((_, cached))) = (self.resumed, &self.drawlist) {
+            return Ok(cached.clone());
+        }
+
         self.columns.get_drawlist()
     }
 
     fn on_key(&mut self, key: Key) -> HResult<()> {
-        match self.do_key(key) {
-            Err(HError::WidgetUndefinedKeyError{..}) => {
-                match self.main_widget_mut()?.on_key(key) {
-                    Ok(_) => {
-                        self.save_tab_settings()?;
-                    }
-                    Err(HError::WidgetUndefinedKeyError{..}) => {
-                        self.preview_widget_mut()?.on_key(key)?
-                    }
-                    e @ _ => e?
-                }
-            }
-            e @ _ => e?
+        self.resumed = false;
+        // asked for they only get closed, after a prefix the key goes on
+        if let Some(Hints::All) = self.hide_key_hints()? { return Ok(()); }
+
+        let result = self.dispatch_key(key);
+        self.show_prefix_hints().log();
+        result
+    }
+
+    fn on_mouse(&mut self, event: MouseEvent) -> HResult<()> {
+        self.resumed = false;
+        self.hide_key_hints().log();
+        let (button, x, y) = match event {
+            // moving while a button is held down
+            MouseEvent::Hold(x, y) => return self.show_hover_info(x, y),
+            MouseEvent::Release(..) => return Ok(()),
+            MouseEvent::Press(button, x, y) => (button, x, y)
         };
 
+        let key = self.mouse_key(button, x, y);
+        let action = match self.core.config().keybinds.mouse.get(key) {
+            Some(action) => *action,
+            None => return Ok(())
+        };
+
+        self.mouse_action(action, x, y)?;
+
         if !self.columns.zoom_active { self.update_preview().log(); }
         Ok(())
     }
+
+    fn on_wtf(&mut self, event: Vec<u8>) -> HResult<()> {
+        match FileBrowser::parse_hover(&event) {
+            Some((x, y)) => self.show_hover_info(x, y),
+            None => self.bad(termion::event::Event::Unsupported(event))
+        }
+    }
 }
 
-use crate::keybind::{Acting, Bindings, FileBrowserAction, Movement};
+use crate::keybind::{Acting, Bindings, BindingSection, FileBrowserAction, FileListAction,
+                     Movement, Mode, MouseAction, MouseKey};
+use strum::IntoEnumIterator;
 
 impl Acting for FileBrowser {
     type Action=FileBrowserAction;
@@ -1329,6 +2846,7 @@ impl Acting for FileBrowser {
             GotoHome => self.go_home()?,
             TurboCd => self.turbo_cd()?,
             SelectExternal => self.external_select()?,
+            SelectExternalInTab => self.external_select_in_tab()?,
             EnterDirExternal => self.external_cd()?,
             RunInBackground => self.open_bg()?,
             GotoPrevCwd => self.goto_prev_cwd()?,
@@ -1337,10 +2855,43 @@ impl Acting for FileBrowser {
             ShowProcesses => self.show_procview()?,
             ShowLog => self.show_log()?,
             ShowQuickActions => self.quick_action()?,
+            Open => self.open()?,
+            OpenWith => self.quick_action()?,
             RunSubshell => self.run_subshell()?,
             ToggleColumns => self.toggle_colums(),
+            Yank => self.yank(JobKind::Copy)?,
+            Cut => self.yank(JobKind::Move)?,
+            Paste => self.paste()?,
+            CopyToOtherTab => Err(HError::FileBrowserNeedOtherTab(JobKind::Copy))?,
+            MoveToOtherTab => Err(HError::FileBrowserNeedOtherTab(JobKind::Move))?,
+            ShowTrash => self.show_trash()?,
+            EditPermissions => self.edit_permissions()?,
+            FocusNext => self.focus_next()?,
+            TogglePauseTransfers => self.toggle_pause_transfers()?,
+            ShowQrPath => self.show_qr(false)?,
+            ShowQrContents => self.show_qr(true)?,
+            ShowHex => self.show_hex()?,
+            ShareSelection => self.share()?,
+            SendToDevice => self.send_to_device()?,
+            ShowProjects => self.show_projects()?,
+            ShowRecentFiles => self.show_recent_files()?,
+            ShowCleanup => self.show_cleanup()?,
+            CommandPalette => self.command_palette()?,
+            TrustLocal => self.trust_local()?,
+            ShowAudit => self.show_audit()?,
+            ShowBranches => self.show_branches()?,
+            NextConflict => self.next_conflict()?,
+            ShowPipelines => self.show_pipelines()?,
+            FindBrokenLinks => self.find_leftovers(Kind::BrokenLinks)?,
+            FindEmptyDirs => self.find_leftovers(Kind::EmptyDirs)?,
+            ShowKeyHints => self.show_key_hints()?,
+            RecordMacro => self.record_macro()?,
+            PlayMacro => crate::macros::play()?,
+            ShowHelp => self.show_help()?,
+            TailPreview => self.preview_widget_mut()?.toggle_tail()?,
             // Tab implementation needs to call exec_cmd because ALL files are needed
-            ExecCmd => Err(HError::FileBrowserNeedTabFiles)?
+            ExecCmd => Err(HError::FileBrowserNeedTabFiles(false))?,
+            ExecCmdLowPriority => Err(HError::FileBrowserNeedTabFiles(true))?
         }
         Ok(())
     }
@@ -0,0 +1,984 @@
+use std::fs::{Metadata, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Sender};
+use std::time::{Duration, Instant};
+
+use crate::devices::Device;
+use crate::fail::{HResult, HError, ErrorLog};
+use crate::widget::Events;
+use crate::files::pretty_size;
+
+
+pub fn is_sparse(meta: &Metadata) -> bool {
+    // st_blocks is always in 512 byte units
+    meta.is_file() && meta.blocks() * 512 < meta.size()
+}
+
+// Returns the next (data, hole) range at or after pos, None if only a
+// hole is left. Err means the filesystem can't tell us about holes.
+fn next_data(file: &std::fs::File, pos: u64, len: u64) -> HResult<Option<(u64, u64)>> {
+    let fd = file.as_raw_fd();
+
+    let data = unsafe { libc::lseek(fd, pos as libc::off_t, libc::SEEK_DATA) };
+    if data < 0 {
+        let err = std::io::Error::last_os_error();
+        return match err.raw_os_error() {
+            Some(libc::ENXIO) => Ok(None),
+            _ => Err(err)?
+        };
+    }
+
+    let hole = unsafe { libc::lseek(fd, data, libc::SEEK_HOLE) };
+    let hole = if hole < 0 { len } else { hole as u64 };
+
+    Ok(Some((data as u64, hole)))
+}
+
+// Copies len bytes from the current positions, reporting each chunk
+fn copy_range(src: &mut std::fs::File,
+              dest: &mut std::fs::File,
+              len: u64,
+              buffer: &mut [u8],
+              on_progress: &mut dyn FnMut(u64)) -> HResult<()> {
+    let mut left = len;
+    while left > 0 {
+        let chunk = std::cmp::min(left, buffer.len() as u64) as usize;
+        let read = src.read(&mut buffer[..chunk])?;
+        if read == 0 { break; }
+        dest.write_all(&buffer[..read])?;
+        left -= read as u64;
+        on_progress(read as u64);
+    }
+    Ok(())
+}
+
+pub fn copy_file(from: &Path, to: &Path) -> HResult<u64> {
+    copy_file_with(from, to, &mut |_| {})
+}
+
+// Copies only the data parts of sparse files, so holes stay holes in the
+// copy. Anything else is just copied normally.
+pub fn copy_file_with(from: &Path,
+                      to: &Path,
+                      on_progress: &mut dyn FnMut(u64)) -> HResult<u64> {
+    let meta = std::fs::metadata(from)?;
+    let len = meta.len();
+    let mut src = std::fs::File::open(from)?;
+
+    // Check before creating the target, so we can still fall back
+    let first = match is_sparse(&meta) {
+        true => next_data(&src, 0, len).ok(),
+        false => None
+    };
+
+    let mut dest = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(to)?;
+
+    let mut buffer = vec![0; 1024 * 1024];
+
+    match first {
+        Some(mut range) => {
+            while let Some((data, hole)) = range {
+                src.seek(SeekFrom::Start(data))?;
+                dest.seek(SeekFrom::Start(data))?;
+
+                copy_range(&mut src, &mut dest, hole - data, &mut buffer, on_progress)?;
+
+                range = match hole < len {
+                    true => next_data(&src, hole, len)?,
+                    false => None
+                };
+            }
+
+            // trailing hole
+            dest.set_len(len)?;
+        }
+        None => copy_range(&mut src, &mut dest, len, &mut buffer, on_progress)?
+    }
+
+    std::fs::set_permissions(to, meta.permissions())?;
+
+    Ok(len)
+}
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JobKind {
+    Copy,
+    Move,
+    Send,
+    Download,
+    Archive
+}
+
+impl JobKind {
+    fn name(&self) -> &'static str {
+        match self {
+            JobKind::Copy => "copy",
+            JobKind::Move => "move",
+            JobKind::Send => "send",
+            JobKind::Download => "download",
+            JobKind::Archive => "archive"
+        }
+    }
+
+    fn verb(&self) -> &'static str {
+        match self {
+            JobKind::Copy => "Copying",
+            JobKind::Move => "Moving",
+            JobKind::Send => "Sending",
+            JobKind::Download => "Downloading",
+            JobKind::Archive => "Archiving"
+        }
+    }
+
+    fn past(&self) -> &'static str {
+        match self {
+            JobKind::Copy => "Copied",
+            JobKind::Move => "Moved",
+            JobKind::Send => "Sent",
+            JobKind::Download => "Downloaded",
+            JobKind::Archive => "Archived"
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Job {
+    pub kind: JobKind,
+    pub sources: Vec<PathBuf>,
+    pub target_dir: PathBuf,
+    // where JobKind::Send sends things, there's no target_dir then
+    pub device: Option<Device>,
+    // what JobKind::Download downloads, there are no sources then
+    pub url: Option<String>,
+}
+
+impl Job {
+    pub fn new(kind: JobKind, sources: Vec<PathBuf>, target_dir: PathBuf) -> Job {
+        Job {
+            kind: kind,
+            sources: sources,
+            target_dir: target_dir,
+            device: None,
+            url: None
+        }
+    }
+
+    pub fn send(sources: Vec<PathBuf>, device: Device) -> Job {
+        Job {
+            kind: JobKind::Send,
+            sources: sources,
+            target_dir: PathBuf::new(),
+            device: Some(device),
+            url: None
+        }
+    }
+
+    pub fn download(url: String, target_dir: PathBuf) -> Job {
+        Job {
+            kind: JobKind::Download,
+            sources: vec![],
+            target_dir: target_dir,
+            device: None,
+            url: Some(url)
+        }
+    }
+
+    fn target(&self) -> String {
+        match &self.device {
+            Some(device) => device.name.clone(),
+            None => self.target_dir.to_string_lossy().to_string()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JobState {
+    Running,
+    Paused
+}
+
+#[derive(Debug, Clone)]
+pub struct Progress {
+    pub kind: JobKind,
+    pub state: JobState,
+    pub files_done: usize,
+    pub files_total: usize,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+}
+
+impl Progress {
+    pub fn percent(&self) -> u64 {
+        match self.bytes_total {
+            0 => 100,
+            // more than counted when holes had to be copied after all
+            total => (self.bytes_done * 100 / total).min(100)
+        }
+    }
+
+    pub fn pretty(&self) -> String {
+        let kind = self.kind.name();
+
+        let state = match self.state {
+            JobState::Running => "",
+            JobState::Paused => " (paused)"
+        };
+
+        format!("{} {}/{} {}%{}",
+                kind,
+                self.files_done,
+                self.files_total,
+                self.percent(),
+                state)
+    }
+}
+
+// Shared between the queue and whatever job is running
+#[derive(Debug, Default)]
+struct JobControl {
+    paused: AtomicBool,
+    // bytes per second, 0 for no limit
+    limit: AtomicU64,
+}
+
+impl JobControl {
+    fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    fn wait_while_paused(&self) {
+        while self.is_paused() {
+            std::thread::sleep(Duration::from_millis(100));
+        }
+    }
+}
+
+// Keeps the shared progress up to date and makes sure the footer gets
+// redrawn, but not for every single chunk
+struct Tracker<'a> {
+    progress: &'a Mutex<Option<Progress>>,
+    events: &'a Sender<Events>,
+    control: &'a JobControl,
+    last_update: Instant,
+    window_start: Instant,
+    window_bytes: u64,
+}
+
+impl<'a> Tracker<'a> {
+    fn set_state(&self, state: JobState) {
+        if let Ok(mut progress) = self.progress.lock() {
+            if let Some(progress) = progress.as_mut() {
+                progress.state = state;
+            }
+        }
+        self.events.send(Events::WidgetReady).ok();
+    }
+
+    // Only for data that's actually copied, renames are free. Blocks
+    // while paused and sleeps to stay below the limit.
+    fn throttle(&mut self, bytes: u64) {
+        if self.control.is_paused() {
+            self.set_state(JobState::Paused);
+            self.control.wait_while_paused();
+            self.set_state(JobState::Running);
+
+            // or the time spent paused counts as credit
+            self.window_start = Instant::now();
+            self.window_bytes = 0;
+        }
+
+        let limit = self.control.limit.load(Ordering::Relaxed);
+        if limit == 0 { return }
+
+        self.window_bytes += bytes;
+        let due = Duration::from_millis(self.window_bytes * 1000 / limit);
+        let elapsed = self.window_start.elapsed();
+
+        if due > elapsed {
+            std::thread::sleep(due - elapsed);
+        }
+
+        if self.window_start.elapsed() > Duration::from_secs(1) {
+            self.window_start = Instant::now();
+            self.window_bytes = 0;
+        }
+    }
+
+    fn update(&mut self, bytes: u64, files: usize) {
+        if let Ok(mut progress) = self.progress.lock() {
+            if let Some(progress) = progress.as_mut() {
+                progress.bytes_done += bytes;
+                progress.files_done += files;
+            }
+        }
+
+        if self.last_update.elapsed() > Duration::from_millis(100) {
+            self.last_update = Instant::now();
+            self.events.send(Events::WidgetReady).ok();
+        }
+    }
+}
+
+// Jobs run one after another in a single thread, so two big transfers
+// don't fight over the same disk
+#[derive(Clone, Debug)]
+pub struct JobQueue {
+    sender: Arc<Mutex<Sender<Job>>>,
+    progress: Arc<Mutex<Option<Progress>>>,
+    pending: Arc<AtomicUsize>,
+    control: Arc<JobControl>,
+}
+
+impl JobQueue {
+    pub fn new(events: Sender<Events>) -> JobQueue {
+        let (tx_job, rx_job) = channel::<Job>();
+        let progress = Arc::new(Mutex::new(None));
+        let pending = Arc::new(AtomicUsize::new(0));
+        let control = Arc::new(JobControl::default());
+
+        let job_progress = progress.clone();
+        let job_pending = pending.clone();
+        let job_control = control.clone();
+
+        std::thread::spawn(move || {
+            for job in rx_job.iter() {
+                // queued jobs stay queued while paused
+                job_control.wait_while_paused();
+                run_job(job, &job_progress, &job_control, &events).log();
+                job_progress.lock().map(|mut p| p.take()).ok();
+                job_pending.fetch_sub(1, Ordering::Relaxed);
+                events.send(Events::WidgetReady).ok();
+            }
+        });
+
+        JobQueue {
+            sender: Arc::new(Mutex::new(tx_job)),
+            progress: progress,
+            pending: pending,
+            control: control
+        }
+    }
+
+    pub fn add(&self, job: Job) -> HResult<()> {
+        self.pending.fetch_add(1, Ordering::Relaxed);
+        self.sender.lock()?.send(job)?;
+        Ok(())
+    }
+
+    pub fn pending(&self) -> usize {
+        self.pending.load(Ordering::Relaxed)
+    }
+
+    pub fn progress(&self) -> Option<Progress> {
+        self.progress.lock().ok()?.clone()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.control.is_paused()
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.control.paused.store(paused, Ordering::Relaxed);
+    }
+
+    pub fn set_limit(&self, bytes_per_second: u64) {
+        self.control.limit.store(bytes_per_second, Ordering::Relaxed);
+    }
+}
+
+// Number of files and bytes below path, without following symlinks. Only
+// the data of sparse files is copied, so the holes don't count.
+fn count(path: &Path) -> (usize, u64) {
+    let meta = match std::fs::symlink_metadata(path) {
+        Ok(meta) => meta,
+        Err(_) => return (0, 0)
+    };
+
+    if is_sparse(&meta) { return (1, meta.blocks() * 512) }
+    if !meta.is_dir() { return (1, meta.len()) }
+
+    std::fs::read_dir(path)
+        .map(|entries| {
+            entries.filter_map(|entry| entry.ok())
+                   .map(|entry| count(&entry.path()))
+                   .fold((0, 0), |(files, bytes), (f, b)| (files + f, bytes + b))
+        })
+        .unwrap_or((0, 0))
+}
+
+// Something else in the same directory with the same name if case is
+// ignored. On case-insensitive filesystems that's the one a new file
+// would run into.
+pub fn case_collision(target: &Path) -> Option<PathBuf> {
+    let name = target.file_name()?.to_string_lossy().to_lowercase();
+    let exact = target.file_name()?;
+
+    std::fs::read_dir(target.parent()?).ok()?
+        .filter_map(|entry| entry.ok())
+        .find(|entry| entry.file_name() != exact &&
+              entry.file_name().to_string_lossy().to_lowercase() == name)
+        .map(|entry| entry.path())
+}
+
+// Adds _1, _2, ... to the name until nothing is in the way, before the
+// extension so the copy still opens like the original. On
+// case-insensitive filesystems symlink_metadata() already runs into
+// collisions that only differ in case.
+fn free_path(target: &Path) -> PathBuf {
+    if std::fs::symlink_metadata(target).is_err() { return target.to_path_buf() }
+
+    (1..).map(|n| target.with_file_name(numbered_name(target, n)))
+         .find(|path| std::fs::symlink_metadata(path).is_err())
+         .unwrap()
+}
+
+fn numbered_name(path: &Path, n: usize) -> String {
+    let stem = path.file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    match path.extension() {
+        Some(ext) => format!("{}_{}.{}", stem, n, ext.to_string_lossy()),
+        None => format!("{}_{}", stem, n)
+    }
+}
+
+fn remove_tree(path: &Path) -> HResult<()> {
+    match std::fs::symlink_metadata(path)?.is_dir() {
+        true => std::fs::remove_dir_all(path)?,
+        false => std::fs::remove_file(path)?
+    }
+    Ok(())
+}
+
+// Plain recursive copy for small things like templates, no progress
+pub fn copy_skeleton(from: &Path, to: &Path) -> HResult<()> {
+    if std::fs::metadata(from)?.is_dir() {
+        std::fs::create_dir(to)?;
+        for entry in std::fs::read_dir(from)? {
+            let entry = entry?;
+            copy_skeleton(&entry.path(), &to.join(entry.file_name()))?;
+        }
+    } else {
+        copy_file(from, to)?;
+    }
+
+    Ok(())
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LinkKind {
+    Symlink,
+    RelativeSymlink,
+    Hardlink
+}
+
+// How to get from inside dir to path, e.g. "../../foo/bar". Both need
+// to be absolute and free of "..", which canonicalize() takes care of
+pub fn relative_path(dir: &Path, path: &Path) -> PathBuf {
+    let dir = dir.components().collect::<Vec<_>>();
+    let path = path.components().collect::<Vec<_>>();
+
+    let common = dir.iter()
+        .zip(path.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut relative = PathBuf::new();
+    for _ in common..dir.len() {
+        relative.push("..");
+    }
+    for component in &path[common..] {
+        relative.push(component);
+    }
+
+    relative
+}
+
+// Links source into dir, returns where the link ended up
+pub fn link(kind: LinkKind, source: &Path, dir: &Path) -> HResult<PathBuf> {
+    let name = source.file_name()?;
+    let target = free_path(&dir.join(name));
+
+    let result = (|| -> HResult<()> {
+        match kind {
+            LinkKind::Symlink => std::os::unix::fs::symlink(source, &target)?,
+            LinkKind::RelativeSymlink => {
+                // only resolve the parents, source might be a link itself
+                let dir = dir.canonicalize()?;
+                let source = source.parent()?.canonicalize()?.join(name);
+                let relative = relative_path(&dir, &source);
+                std::os::unix::fs::symlink(relative, &target)?
+            }
+            LinkKind::Hardlink => std::fs::hard_link(source, &target)?
+        }
+        Ok(())
+    })();
+
+    let op = match kind {
+        LinkKind::Hardlink => "hardlink",
+        _ => "symlink"
+    };
+    crate::audit::record(op, source, Some(&target), &result);
+    result?;
+
+    Ok(target)
+}
+
+fn copy_tree(from: &Path, to: &Path, tracker: &mut Tracker) -> HResult<()> {
+    let meta = std::fs::symlink_metadata(from)?;
+    let file_type = meta.file_type();
+
+    if file_type.is_symlink() {
+        let link = std::fs::read_link(from)?;
+        std::os::unix::fs::symlink(link, to)?;
+        tracker.update(meta.len(), 1);
+    } else if file_type.is_dir() {
+        std::fs::create_dir(to)?;
+        for entry in std::fs::read_dir(from)? {
+            let entry = entry?;
+            let target = to.join(entry.file_name());
+
+            // the new directory was empty, so only a name that differs in
+            // case can be in the way, don't copy over it
+            if std::fs::symlink_metadata(&target).is_ok() {
+                HError::log::<()>(&format!("Skipped {}, it only differs in case from a file already copied",
+                                           entry.path().to_string_lossy())).ok();
+                continue;
+            }
+
+            copy_tree(&entry.path(), &target, tracker)?;
+        }
+        std::fs::set_permissions(to, meta.permissions())?;
+    } else {
+        copy_file_with(from, to, &mut |bytes| {
+            tracker.update(bytes, 0);
+            tracker.throttle(bytes);
+        })?;
+        tracker.update(0, 1);
+    }
+
+    Ok(())
+}
+
+// KDE Connect takes it from here, so a file counts as done once the
+// daemon has it. That's also all there is to throttle.
+fn send_file(device: &Device, file: &Path, tracker: &mut Tracker) -> HResult<()> {
+    let size = std::fs::metadata(file)?.len();
+
+    tracker.throttle(0);
+    crate::devices::send(device, file)?;
+    tracker.update(size, 1);
+
+    Ok(())
+}
+
+fn move_tree(from: &Path, to: &Path, tracker: &mut Tracker) -> HResult<()> {
+    match std::fs::rename(from, to) {
+        Ok(()) => {
+            let (files, bytes) = count(to);
+            tracker.update(bytes, files);
+            Ok(())
+        }
+        // different filesystem, no way around copying
+        Err(ref e) if e.raw_os_error() == Some(libc::EXDEV) => {
+            let was_there = std::fs::symlink_metadata(to).is_ok();
+
+            // half a copy is no use, the original is still there
+            if let Err(e) = copy_tree(from, to, tracker) {
+                if !was_there {
+                    remove_tree(to).log();
+                }
+                return Err(e);
+            }
+
+            remove_tree(from)
+        }
+        Err(e) => Err(e)?
+    }
+}
+
+// tar does the packing, the original is only removed once that worked
+fn archive_tree(from: &Path, to: &Path, tracker: &mut Tracker) -> HResult<()> {
+    let (files, bytes) = count(from);
+    tracker.throttle(0);
+
+    let status = Command::new("tar")
+        .arg("-czf")
+        .arg(to)
+        .arg("-C")
+        .arg(from.parent()?)
+        .arg(from.file_name()?)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|_| HError::Error(String::from("Can't find tar!")))?;
+
+    if !status.success() {
+        std::fs::remove_file(to).ok();
+        return Err(HError::Error(format!("tar failed: {}", status)));
+    }
+
+    match std::fs::symlink_metadata(from)?.is_dir() {
+        true => std::fs::remove_dir_all(from)?,
+        false => std::fs::remove_file(from)?
+    }
+    tracker.update(bytes, files);
+
+    Ok(())
+}
+
+fn run_job(job: Job,
+           progress: &Mutex<Option<Progress>>,
+           control: &JobControl,
+           events: &Sender<Events>) -> HResult<()> {
+    if let Some(url) = &job.url {
+        return run_download(url, &job.target_dir, progress, control, events);
+    }
+
+    // count everything first, or there's no total to show
+    let (files_total, bytes_total) = job.sources
+        .iter()
+        .map(|source| count(source))
+        .fold((0, 0), |(files, bytes), (f, b)| (files + f, bytes + b));
+
+    *progress.lock()? = Some(Progress {
+        kind: job.kind,
+        state: JobState::Running,
+        files_done: 0,
+        files_total: files_total,
+        bytes_done: 0,
+        bytes_total: bytes_total
+    });
+
+    let mut tracker = Tracker {
+        progress: progress,
+        events: events,
+        control: control,
+        last_update: Instant::now(),
+        window_start: Instant::now(),
+        window_bytes: 0
+    };
+
+    let mut failed = 0;
+    let mut collisions = 0;
+    let source_count = job.sources.len();
+
+    for (i, source) in job.sources.iter().enumerate() {
+        let name = match source.file_name() {
+            Some(name) => name,
+            None => continue
+        };
+
+        events.send(Events::Status(format!("{} {}/{}: {}",
+                                           job.kind.verb(),
+                                           i + 1,
+                                           source_count,
+                                           name.to_string_lossy())))?;
+
+        let result = if let Some(device) = &job.device {
+            send_file(device, source, &mut tracker)
+        } else if job.target_dir.starts_with(source) {
+            Err(HError::Error(format!("Can't put {} into itself",
+                                      source.to_string_lossy())))
+        } else {
+            let name = match job.kind {
+                JobKind::Archive => {
+                    let mut archive = name.to_os_string();
+                    archive.push(".tar.gz");
+                    archive
+                }
+                _ => name.to_os_string()
+            };
+            let target = job.target_dir.join(name);
+            if let Some(other) = case_collision(&target) {
+                collisions += 1;
+                HError::log::<()>(&format!("{} only differs in case from {}",
+                                           target.to_string_lossy(),
+                                           other.to_string_lossy())).ok();
+            }
+            let target = free_path(&target);
+            let result = match job.kind {
+                JobKind::Copy => copy_tree(source, &target, &mut tracker),
+                JobKind::Move => move_tree(source, &target, &mut tracker),
+                JobKind::Archive => archive_tree(source, &target, &mut tracker),
+                JobKind::Send => Err(HError::Error(String::from("Nowhere to send to"))),
+                JobKind::Download => Err(HError::Error(String::from("Nothing to download")))
+            };
+            crate::audit::record(job.kind.name(), source, Some(&target), &result);
+            result
+        };
+
+        if let Err(e) = result {
+            failed += 1;
+            HError::log::<()>(&format!("{} {} failed: {}",
+                                       job.kind.verb(),
+                                       source.to_string_lossy(),
+                                       e)).ok();
+        }
+    }
+
+    let status = match failed {
+        0 => format!("{} {} files ({}) to {}",
+                     job.kind.past(),
+                     files_total,
+                     pretty_size(bytes_total),
+                     job.target()),
+        _ => format!("{} to {} finished with {} errors, check the log",
+                     job.kind.verb(),
+                     job.target(),
+                     failed)
+    };
+    let status = match collisions {
+        0 => status,
+        _ => format!("{}, {} names only differ in case from existing ones",
+                     status,
+                     collisions)
+    };
+    events.send(Events::Status(status))?;
+
+    Ok(())
+}
+
+// curl does the actual work, how big the file on disk has grown is the
+// progress. It can't be paused, but it can be stopped and continued.
+fn run_download(url: &str,
+                target_dir: &Path,
+                progress: &Mutex<Option<Progress>>,
+                control: &JobControl,
+                events: &Sender<Events>) -> HResult<()> {
+    let name = crate::remote::file_name(url);
+    let target = free_path(&target_dir.join(&name));
+    let partial = target.with_file_name(format!("{}.part",
+                                                target.file_name()?.to_string_lossy()));
+
+    events.send(Events::Status(format!("Downloading {}", url)))?;
+
+    *progress.lock()? = Some(Progress {
+        kind: JobKind::Download,
+        state: JobState::Running,
+        files_done: 0,
+        files_total: 1,
+        bytes_done: 0,
+        bytes_total: crate::remote::remote_size(url).unwrap_or(0)
+    });
+
+    let mut tracker = Tracker {
+        progress: progress,
+        events: events,
+        control: control,
+        last_update: Instant::now(),
+        window_start: Instant::now(),
+        window_bytes: 0
+    };
+
+    let mut curl = Command::new("curl");
+    curl.args(&["-sSL", "--fail", "-o"]).arg(&partial);
+
+    let limit = control.limit.load(Ordering::Relaxed);
+    if limit > 0 {
+        curl.arg("--limit-rate").arg(limit.to_string());
+    }
+
+    let mut child = curl.arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|_| HError::Error(String::from("Can't find curl!")))?;
+
+    let mut paused = false;
+    let mut done = 0;
+
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+
+        if control.is_paused() != paused {
+            paused = control.is_paused();
+            let signal = match paused {
+                true => libc::SIGSTOP,
+                false => libc::SIGCONT
+            };
+            unsafe { libc::kill(child.id() as libc::pid_t, signal) };
+            tracker.set_state(if paused { JobState::Paused } else { JobState::Running });
+        }
+
+        let size = std::fs::metadata(&partial).map(|meta| meta.len()).unwrap_or(0);
+        tracker.update(size.saturating_sub(done), 0);
+        done = done.max(size);
+
+        std::thread::sleep(Duration::from_millis(100));
+    };
+
+    if !status.success() {
+        std::fs::remove_file(&partial).ok();
+        return Err(HError::Error(format!("Downloading {} failed", url)));
+    }
+
+    let size = std::fs::metadata(&partial)?.len();
+    tracker.update(size.saturating_sub(done), 1);
+    done = size;
+
+    std::fs::rename(&partial, &target)?;
+
+    events.send(Events::Status(format!("Downloaded {} ({}) to {}",
+                                       name,
+                                       pretty_size(done),
+                                       target_dir.to_string_lossy())))?;
+
+    Ok(())
+}
+
+// What to set access and modification times to
+#[derive(Clone, Debug, PartialEq)]
+pub enum TimeChange {
+    Now,
+    // seconds since the epoch
+    At(i64),
+    // added to what each file has, for a camera clock that was off
+    Shift(i64),
+    Like(PathBuf),
+}
+
+// "1d2h30m", "90s" and so on, without a unit it's seconds
+fn parse_duration(input: &str) -> Option<i64> {
+    if input.is_empty() { return None; }
+
+    let mut total = 0i64;
+    let mut number = String::new();
+
+    for c in input.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+
+        let unit = match c {
+            'w' => 7 * 24 * 3600,
+            'd' => 24 * 3600,
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return None
+        };
+        total = total.checked_add(number.parse::<i64>().ok()?.checked_mul(unit)?)?;
+        number.clear();
+    }
+
+    if !number.is_empty() {
+        total = total.checked_add(number.parse::<i64>().ok()?)?;
+    }
+
+    Some(total)
+}
+
+// Empty is now, +/- shifts, a date sets it, and anything else is taken to
+// be a file to copy the times from
+pub fn parse_time_change(input: &str, cwd: &Path, home: Option<&Path>) -> HResult<TimeChange> {
+    use chrono::TimeZone;
+
+    let input = input.trim();
+    if input.is_empty() || input == "now" {
+        return Ok(TimeChange::Now);
+    }
+
+    let bad_time = || HError::Error(format!("Can't make sense of time: {}", input));
+
+    if input.starts_with('+') || input.starts_with('-') {
+        let shift = parse_duration(&input[1..]).ok_or_else(bad_time)?;
+        return match input.starts_with('-') {
+            true => Ok(TimeChange::Shift(-shift)),
+            false => Ok(TimeChange::Shift(shift))
+        };
+    }
+
+    let formats = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S"];
+    let date = formats.iter()
+        .filter_map(|format| chrono::NaiveDateTime::parse_from_str(input, format).ok())
+        .chain(chrono::NaiveDate::parse_from_str(input, "%Y-%m-%d")
+               .ok()
+               .map(|date| date.and_hms(0, 0, 0)))
+        .next();
+
+    if let Some(date) = date {
+        let time = chrono::Local.from_local_datetime(&date)
+            .earliest()
+            .ok_or_else(bad_time)?;
+        return Ok(TimeChange::At(time.timestamp()));
+    }
+
+    let path = crate::lineedit::parse_path(input, cwd, home);
+    match path.exists() {
+        true => Ok(TimeChange::Like(path)),
+        false => Err(bad_time())
+    }
+}
+
+pub fn set_times(path: &Path, change: &TimeChange) -> HResult<()> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let time = |sec: i64, nsec: i64| libc::timespec {
+        tv_sec: sec as libc::time_t,
+        tv_nsec: nsec as libc::c_long
+    };
+
+    let times = match change {
+        TimeChange::Now => [time(0, libc::UTIME_NOW), time(0, libc::UTIME_NOW)],
+        TimeChange::At(at) => [time(*at, 0), time(*at, 0)],
+        TimeChange::Shift(shift) => {
+            let meta = std::fs::metadata(path)?;
+            [time(meta.atime() + shift, meta.atime_nsec()),
+             time(meta.mtime() + shift, meta.mtime_nsec())]
+        }
+        TimeChange::Like(other) => {
+            let meta = std::fs::metadata(other)?;
+            [time(meta.atime(), meta.atime_nsec()),
+             time(meta.mtime(), meta.mtime_nsec())]
+        }
+    };
+
+    let c_path = std::ffi::CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| HError::Error(e.to_string()))?;
+    let result = unsafe {
+        libc::utimensat(libc::AT_FDCWD, c_path.as_ptr(), times.as_ptr(), 0)
+    };
+
+    if result != 0 {
+        Err(std::io::Error::last_os_error())?
+    }
+
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn number_goes_before_the_extension() {
+        assert_eq!(numbered_name(Path::new("/tmp/file.txt"), 1), "file_1.txt");
+        assert_eq!(numbered_name(Path::new("/tmp/archive.tar.gz"), 2), "archive.tar_2.gz");
+    }
+
+    #[test]
+    fn no_extension_gets_number_at_the_end() {
+        assert_eq!(numbered_name(Path::new("/tmp/Makefile"), 3), "Makefile_3");
+        assert_eq!(numbered_name(Path::new("/tmp/.bashrc"), 1), ".bashrc_1");
+    }
+}
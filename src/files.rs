@@ -31,6 +31,7 @@ use crate::dirty::{AsyncDirtyBit, DirtyBit, Dirtyable};
 use crate::widget::Events;
 use crate::icon::Icons;
 use crate::fscache::FsEvent;
+use crate::dirsize::{self, DirSize};
 
 
 lazy_static! {
@@ -101,11 +102,54 @@ pub fn tags_loaded() -> HResult<()> {
 }
 
 
+fn size_with_unit(size: u64) -> (u32, &'static str) {
+    let mut unit = 0;
+    let mut size = size;
+    while size > 1024 {
+        size /= 1024;
+        unit += 1;
+    }
+    let unit = match unit {
+        0 => "",
+        1 => " KB",
+        2 => " MB",
+        3 => " GB",
+        4 => " TB",
+        5 => " wtf are you doing",
+        _ => "",
+    };
+
+    (size as u32, unit)
+}
+
+pub fn pretty_size(size: u64) -> String {
+    let mut unit = 0;
+    let mut size = size as f64;
+    while size > 1024.0 && unit < 5 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    let unit = match unit {
+        0 => "B",
+        1 => "KB",
+        2 => "MB",
+        3 => "GB",
+        4 => "TB",
+        _ => "PB",
+    };
+
+    match unit {
+        "B" => format!("{} {}", size, unit),
+        _ => format!("{:.1} {}", size, unit)
+    }
+}
+
+
 #[derive(PartialEq, Eq, Hash, Clone, Debug)]
 pub struct RefreshPackage {
     pub new_files: Option<Vec<File>>,
-    pub new_buffer: Option<Vec<String>>,
     pub new_len: usize,
+    pub conflicts: Vec<PathBuf>,
 }
 
 
@@ -113,9 +157,7 @@ pub struct RefreshPackage {
 
 impl RefreshPackage {
     fn new(mut files: Files,
-           old_buffer: Vec<String>,
-           events: Vec<FsEvent>,
-           render_fn: impl Fn(&File) -> String) -> RefreshPackage {
+           events: Vec<FsEvent>) -> RefreshPackage {
         use FsEvent::*;
 
         // If there is only a placeholder at this point, remove it now
@@ -137,34 +179,36 @@ impl RefreshPackage {
             .map(|(i, file)| (file, i))
             .collect();
 
-
-        // Need to know which line of the ListView buffer belongs to which file
-        let list_pos_map: HashMap<&File, usize> = static_files
-            .iter_files()
-            .enumerate()
-            .take_while(|&(i, _)| i < old_buffer.len())
-            .map(|(i, file)| (file, i))
-            .collect();
-
         // Save new files to add them all at once later
         let mut new_files = Vec::with_capacity(event_count);
 
-        // Files that need rerendering to make all changes visible (size, etc.)
-        let mut changed_files = HashSet::with_capacity(event_count);
-
         // Save deletions to delete them efficiently later
         let mut deleted_files = HashSet::with_capacity(event_count);
 
         for event in events.into_iter() {
             match event {
                 Create(mut file) => {
+                    // on case-insensitive filesystems a case-only rename
+                    // can show up as a create, don't list the file twice
+                    if files.case_insensitive {
+                        if let Some(old) = static_files.find_case_collision(&file.name) {
+                            if let Some(&fpos) = file_pos_map.get(old) {
+                                files.files[fpos].rename(&file.path).log();
+                                files.files[fpos].meta_sync().log();
+                                continue;
+                            }
+                        }
+                    }
+                    // might have been added already by whoever created it
+                    if file_pos_map.contains_key(&file) {
+                        continue;
+                    }
                     file.meta_sync().log();
                     new_files.push(file);
                 }
                 Change(file) => {
                     if let Some(&fpos) = file_pos_map.get(&file) {
                         files.files[fpos].meta_sync().log();
-                        changed_files.insert(file);
                     }
                 }
                 Rename(old, new) => {
@@ -192,37 +236,24 @@ impl RefreshPackage {
         files.recalculate_len();
         files.sort();
 
-        // Prerender new buffer in current thread
-        let mut old_buffer = old_buffer;
-
-        let new_buffer = files.iter_files()
-            .map(|file| {
-                match list_pos_map.get(&file) {
-                    Some(&old_pos) =>
-                        match changed_files.contains(&file) {
-                            true => render_fn(&file),
-                            false => std::mem::take(&mut old_buffer[old_pos])
-                        }
-                    None => render_fn(&file)
-                }
-            }).collect();
+        // files in here might have been fixed or broken by the merge
+        let conflicts = crate::conflicts::conflicted(&files.directory.path);
 
         // Need to unpack this to prevent issue with recursive Files type
         // Also, if no files remain add placeholder and set len
-        let (files, new_len, new_buffer) = if files.len() > 0 {
-            (files.files, files.len, new_buffer)
+        let (files, new_len) = if files.len() > 0 {
+            (files.files, files.len)
         } else {
             let placeholder = File::new_placeholder(&files.directory.path).unwrap();
-            let buffer = vec![render_fn(&placeholder)];
             files.files.push(placeholder);
-            (files.files, 1, buffer)
+            (files.files, 1)
         };
 
 
         RefreshPackage {
             new_files: Some(files),
-            new_buffer: Some(new_buffer),
-            new_len: new_len
+            new_len: new_len,
+            conflicts: conflicts
         }
     }
 }
@@ -249,6 +280,18 @@ pub struct Files {
     pub show_hidden: bool,
     pub filter: Option<String>,
     pub filter_selected: bool,
+    pub case_insensitive: bool,
+    pub opened_at: i64,
+    #[derivative(PartialEq="ignore")]
+    #[derivative(Hash="ignore")]
+    pub conflicts: Vec<PathBuf>,
+    #[derivative(Debug="ignore")]
+    #[derivative(PartialEq="ignore")]
+    #[derivative(Hash="ignore")]
+    pub conflicts_check: Option<Async<Vec<PathBuf>>>,
+    #[derivative(PartialEq="ignore")]
+    #[derivative(Hash="ignore")]
+    pub conflicts_outdated: bool,
     pub dirty: DirtyBit,
 }
 
@@ -291,6 +334,11 @@ impl Default for Files {
             show_hidden: false,
             filter: None,
             filter_selected: false,
+            case_insensitive: false,
+            opened_at: chrono::Local::now().timestamp(),
+            conflicts: vec![],
+            conflicts_check: None,
+            conflicts_outdated: false,
             dirty: DirtyBit::new(),
         }
     }
@@ -329,6 +377,7 @@ impl Files {
     pub fn new_from_path_cancellable(path: &Path,
                                      stale: Stale)
                                      -> HResult<Files> {
+        let _span = crate::profile::span("dir_load");
         let direntries: Result<Vec<_>, _> = std::fs::read_dir(&path)?.collect();
         let dirty = DirtyBit::new();
         let dirty_meta = AsyncDirtyBit::new();
@@ -363,6 +412,11 @@ impl Files {
             show_hidden: false,
             filter: None,
             filter_selected: false,
+            case_insensitive: false,
+            opened_at: chrono::Local::now().timestamp(),
+            conflicts: crate::conflicts::conflicted(&path),
+            conflicts_check: None,
+            conflicts_outdated: false,
             dirty: dirty,
         };
 
@@ -371,6 +425,11 @@ impl Files {
         Ok(files)
     }
 
+    // how it's listed, apart from filters
+    pub fn view(&self) -> (SortBy, bool, bool, bool) {
+        (self.sort, self.dirs_first, self.reverse, self.show_hidden)
+    }
+
     pub fn recalculate_len(&mut self) {
         self.len = self.par_iter_files().count();
     }
@@ -556,7 +615,7 @@ impl Files {
         self.recalculate_len();
     }
 
-    fn remove_placeholder(&mut self) {
+    pub fn remove_placeholder(&mut self) {
         let dirpath = self.directory.path.clone();
         self.find_file_with_path(&dirpath).cloned()
             .map(|placeholder| {
@@ -567,6 +626,13 @@ impl Files {
             });
     }
 
+    // Something the watcher or a loader changed that isn't shown yet
+    pub fn has_changes(&self) -> bool {
+        self.is_dirty() ||
+            self.refresh.is_some() ||
+            self.pending_events.read().map(|events| !events.is_empty()).unwrap_or(true)
+    }
+
     pub fn ready_to_refresh(&self) -> HResult<bool> {
         let pending = self.pending_events.read()?.len();
         let running = self.refresh.is_some();
@@ -579,6 +645,7 @@ impl Files {
                 refresh.pull_async()?;
                 let mut refresh = refresh.value?;
                 self.files = refresh.new_files.take()?;
+                self.conflicts = std::mem::replace(&mut refresh.conflicts, vec![]);
                 if refresh.new_len != self.len() {
                     self.len = refresh.new_len;
                 }
@@ -591,11 +658,95 @@ impl Files {
         return Ok(None)
     }
 
-    pub fn process_fs_events(&mut self,
-                             buffer: Vec<String>,
-                             sender: Sender<Events>,
-                             render_fn: impl Fn(&File) -> String + Send + 'static)
-                             -> HResult<()> {
+    // Plain changes to existing files are common (e.g. build output being
+    // written) and only need that file's metadata reloaded, so handle those
+    // right away and leave the rest for a full refresh
+    pub fn apply_change_events(&mut self) -> HResult<bool> {
+        let changes = {
+            let mut pending = self.pending_events.write()?;
+            let (changes, rest) = pending
+                .drain(..)
+                .partition::<Vec<FsEvent>, _>(|event| match event {
+                    FsEvent::Change(_) => true,
+                    _ => false
+                });
+            *pending = rest;
+            changes
+        };
+
+        if changes.is_empty() { return Ok(false) }
+
+        // looking each one up in the list would take files × events
+        let positions: Vec<usize> = {
+            let file_pos_map: HashMap<&Path, usize> = self.files
+                .iter()
+                .enumerate()
+                .map(|(i, file)| (file.path.as_path(), i))
+                .collect();
+
+            changes.iter()
+                .filter_map(|event| file_pos_map.get(event.file().path.as_path()))
+                .cloned()
+                .collect()
+        };
+
+        let changed = !positions.is_empty();
+
+        for pos in positions {
+            self.files[pos].meta_sync().log();
+        }
+
+        // size or mtime might have changed the order
+        if changed && self.sort != SortBy::Name {
+            self.sort();
+        }
+
+        Ok(changed)
+    }
+
+    // That can mean running git and reading through files, so it's done in
+    // the background. Changes coming in while it runs get another look
+    // once it's done.
+    pub fn check_conflicts(&mut self, sender: Sender<Events>) -> HResult<()> {
+        if self.conflicts_check.is_some() {
+            self.conflicts_outdated = true;
+            return Ok(());
+        }
+
+        let dir = self.directory.path.clone();
+        let mut check = Async::new(move |_| {
+            Ok(crate::conflicts::conflicted(&dir))
+        });
+
+        check.on_ready(move |_,_| {
+            Ok(sender.send(Events::WidgetReady)?)
+        })?;
+
+        check.run()?;
+
+        self.conflicts_check = Some(check);
+        self.conflicts_outdated = false;
+        Ok(())
+    }
+
+    pub fn get_conflicts(&mut self, sender: Sender<Events>) -> HResult<bool> {
+        match &self.conflicts_check {
+            Some(check) if check.is_ready() => {}
+            _ => return Ok(false)
+        }
+
+        let mut check = self.conflicts_check.take()?;
+        check.pull_async()?;
+        self.conflicts = check.value?;
+
+        if self.conflicts_outdated {
+            self.check_conflicts(sender)?;
+        }
+
+        Ok(true)
+    }
+
+    pub fn process_fs_events(&mut self, sender: Sender<Events>) -> HResult<()> {
         let pending = self.pending_events.read()?.len();
 
         if pending > 0 {
@@ -606,10 +757,7 @@ impl Files {
             let files = self.clone();
 
             let mut refresh = Async::new(move |_| {
-                let refresh = RefreshPackage::new(files,
-                                                  buffer,
-                                                  events,
-                                                  render_fn);
+                let refresh = RefreshPackage::new(files, events);
                 Ok(refresh)
             });
 
@@ -635,6 +783,29 @@ impl Files {
         }
     }
 
+    // Returns a file that has the same name as name if case is ignored, but
+    // isn't exactly the same. Only matters on case-insensitive filesystems.
+    pub fn find_case_collision(&self, name: &str) -> Option<&File> {
+        let lower_name = name.to_lowercase();
+        self.files
+            .iter()
+            .find(|f| f.name != name && f.name.to_lowercase() == lower_name)
+    }
+
+    pub fn case_collisions(&self) -> Vec<(&File, &File)> {
+        let mut seen: HashMap<String, &File> = HashMap::new();
+
+        self.files
+            .iter()
+            .filter_map(|file| {
+                match seen.insert(file.name.to_lowercase(), file) {
+                    Some(other) => Some((other, file)),
+                    None => None
+                }
+            })
+            .collect()
+    }
+
     pub fn find_file_with_name(&self, name: &str) -> Option<&File> {
         self.iter_files()
             .find(|f| f.name.to_lowercase().contains(name))
@@ -765,6 +936,7 @@ pub struct File {
     pub hidden: bool,
     pub kind: Kind,
     pub dirsize: Option<Arc<AtomicU32>>,
+    pub du: Option<Arc<Mutex<Option<DirSize>>>>,
     pub target: Option<PathBuf>,
     pub color: Option<lscolors::Color>,
     pub meta: Option<Metadata>,
@@ -787,6 +959,7 @@ impl File {
             kind: if path.is_dir() { Kind::Directory } else { Kind::File },
             path: path,
             dirsize: None,
+            du: None,
             target: None,
             meta: None,
             meta_processed: false,
@@ -808,6 +981,7 @@ impl File {
             kind: if path.is_dir() { Kind::Directory } else { Kind::File },
             path: path,
             dirsize: None,
+            du: None,
             target: None,
             meta: None,
             meta_processed: false,
@@ -840,6 +1014,7 @@ impl File {
             kind: kind,
             path: path,
             dirsize: None,
+            du: None,
             target: None,
             meta: None,
             meta_processed: false,
@@ -875,6 +1050,7 @@ impl File {
     }
 
     pub fn meta_sync(&mut self) -> HResult<()> {
+        let _span = crate::profile::span("metadata");
         let meta = std::fs::symlink_metadata(&self.path)?;
         self.meta = Some(meta);
         self.process_meta().log();
@@ -890,6 +1066,7 @@ impl File {
     pub fn run_dirsize(&mut self) {
         let dirsize = Arc::new(AtomicU32::new(0));
         self.dirsize = Some(dirsize.clone());
+        let du = self.du.get_or_insert_with(|| Arc::new(Mutex::new(None))).clone();
         let path = self.path.clone();
         rayon::spawn(move || {
             std::fs::read_dir(&path)
@@ -898,9 +1075,33 @@ impl File {
                     dirsize.store(size as u32, Ordering::Release);
                 }).map_err(HError::from)
                   .log();
+
+            if let Some(size) = dirsize::cached_size(&path) {
+                if let Ok(mut du) = du.lock() {
+                    du.get_or_insert(size);
+                }
+            }
         });
     }
 
+    // None for directories until their real size is known
+    pub fn size_bytes(&self) -> Option<u64> {
+        match self.is_dir() {
+            true => self.du_size().map(|size| size.bytes()),
+            false => self.meta().map(|meta| meta.size())
+        }
+    }
+
+    pub fn du_size(&self) -> Option<DirSize> {
+        self.du.as_ref()?.lock().ok()?.clone()
+    }
+
+    pub fn size_prefix(&self) -> &'static str {
+        self.du_size()
+            .map(|size| size.prefix())
+            .unwrap_or("")
+    }
+
     pub fn meta(&self) -> Option<&Metadata> {
         self.meta.as_ref()
     }
@@ -933,32 +1134,17 @@ impl File {
 
     pub fn calculate_size(&self) -> HResult<(u32, &str)> {
         if self.is_dir() {
-            let size = match self.dirsize {
-                Some(ref size) => (size.load(Ordering::Acquire), ""),
-                None => (0, ""),
+            // show the real size once it's known
+            let size = match (self.du_size(), &self.dirsize) {
+                (Some(du), _) => size_with_unit(du.bytes()),
+                (None, Some(size)) => (size.load(Ordering::Acquire), ""),
+                (None, None) => (0, ""),
             };
 
             return Ok(size);
         }
 
-
-        let mut unit = 0;
-        let mut size = self.meta()?.size();
-        while size > 1024 {
-            size /= 1024;
-            unit += 1;
-        }
-        let unit = match unit {
-            0 => "",
-            1 => " KB",
-            2 => " MB",
-            3 => " GB",
-            4 => " TB",
-            5 => " wtf are you doing",
-            _ => "",
-        };
-
-        Ok((size as u32, unit))
+        Ok(size_with_unit(self.meta()?.size()))
     }
 
     // Sadly tree_magic tends to panic (in unwraps a None) when called
@@ -1150,6 +1336,19 @@ impl File {
         }
     }
 
+    pub fn is_sparse(&self) -> bool {
+        self.meta()
+            .map(|meta| crate::fileops::is_sparse(meta))
+            .unwrap_or(false)
+    }
+
+    pub fn pretty_disk_usage(&self) -> Option<String> {
+        let meta = self.meta()?;
+        let apparent = pretty_size(meta.size());
+        let actual = pretty_size(meta.blocks() * 512);
+        Some(format!("{} ({} on disk)", apparent, actual))
+    }
+
     pub fn pretty_print_permissions(&self) -> HResult<String> {
         let perms: usize = format!("{:o}", self.meta()?.mode()).parse().unwrap();
         let perms: usize  = perms % 800;
@@ -1200,6 +1399,12 @@ impl File {
         Some(format!("{}{}", color, file_group.name().to_string_lossy()))
     }
 
+    pub fn modified_since(&self, time: i64) -> bool {
+        self.meta()
+            .map(|meta| meta.mtime() > time)
+            .unwrap_or(false)
+    }
+
     pub fn pretty_mtime(&self) -> Option<String> {
         if self.meta().is_none() { return None }
         let time: chrono::DateTime<chrono::Local>
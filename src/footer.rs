@@ -0,0 +1,381 @@
+use unicode_width::UnicodeWidthStr;
+use lazy_static;
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::sync::mpsc::Sender;
+use std::time::SystemTime;
+
+use crate::term;
+use crate::widget::Events;
+
+
+// The footer is put together from a format like
+//
+//   {permissions} {owner}({jobs} | ){>}{space} | {count}
+//
+// Every {segment} is replaced with what the caller has for it, anything
+// else is printed as is. Segments can take an argument, e.g. {clock:%H:%M}.
+// A group in parentheses only shows up if one of the segments in it isn't
+// empty, {>} pushes everything after it to the right edge.
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Text(String),
+    Segment(String, Option<String>),
+    Group(Vec<Part>),
+    Right,
+}
+
+impl Part {
+    fn from_name(name: &str) -> Part {
+        if name == ">" {
+            return Part::Right;
+        }
+
+        let mut name = name.splitn(2, ':');
+        let segment = name.next().unwrap_or("").to_string();
+        let arg = name.next().map(String::from);
+        Part::Segment(segment, arg)
+    }
+}
+
+fn push_text(parts: &mut Vec<Part>, text: &mut String) {
+    if !text.is_empty() {
+        parts.push(Part::Text(std::mem::replace(text, String::new())));
+    }
+}
+
+fn parse(format: &str) -> Vec<Part> {
+    let mut parts = vec![];
+    let mut group: Option<Vec<Part>> = None;
+    let mut text = String::new();
+    let mut chars = format.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '(' if group.is_none() => {
+                push_text(&mut parts, &mut text);
+                group = Some(vec![]);
+            }
+            ')' if group.is_some() => {
+                let mut group_parts = group.take().unwrap();
+                push_text(&mut group_parts, &mut text);
+                parts.push(Part::Group(group_parts));
+            }
+            '{' => {
+                let name = chars.by_ref()
+                    .take_while(|&c| c != '}')
+                    .collect::<String>();
+                let parts = group.as_mut().unwrap_or(&mut parts);
+                push_text(parts, &mut text);
+                parts.push(Part::from_name(&name));
+            }
+            _ => text.push(c)
+        }
+    }
+
+    // a group that's never closed just ends with the format
+    if let Some(mut group_parts) = group.take() {
+        push_text(&mut group_parts, &mut text);
+        parts.push(Part::Group(group_parts));
+    }
+    push_text(&mut parts, &mut text);
+
+    parts
+}
+
+fn render_part<F>(part: &Part, segment: &mut F) -> Option<String>
+where
+    F: FnMut(&str, Option<&str>) -> Option<String>
+{
+    match part {
+        Part::Text(text) => Some(text.clone()),
+        // segments bring their own colors, so go back to normal after
+        Part::Segment(name, arg) => {
+            segment(name, arg.as_ref().map(|arg| arg.as_str()))
+                .filter(|rendered| !rendered.is_empty())
+                .map(|rendered| rendered + &term::header_color())
+        }
+        Part::Group(parts) => {
+            let mut shown = false;
+            let rendered = parts.iter()
+                .filter_map(|part| {
+                    let rendered = render_part(part, segment);
+                    if let (Part::Segment(..), Some(_)) = (part, &rendered) {
+                        shown = true;
+                    }
+                    rendered
+                })
+                .collect::<String>();
+
+            Some(rendered).filter(|_| shown)
+        }
+        // only means something at the top level
+        Part::Right => None
+    }
+}
+
+// Everything in one piece, without padding. {>} doesn't do anything here.
+pub fn render_inline<F>(format: &str, segment: F) -> String
+where
+    F: FnMut(&str, Option<&str>) -> Option<String>
+{
+    let (left, right) = render_sides(format, segment);
+    left + &right
+}
+
+pub fn render<F>(format: &str, xsize: usize, segment: F) -> String
+where
+    F: FnMut(&str, Option<&str>) -> Option<String>
+{
+    let (left, right) = render_sides(format, segment);
+
+    let right_xsize = term::strip_ansi(&right).width();
+    let left = term::sized_string_u(&left, xsize.saturating_sub(right_xsize));
+
+    left + &right
+}
+
+fn render_sides<F>(format: &str, mut segment: F) -> (String, String)
+where
+    F: FnMut(&str, Option<&str>) -> Option<String>
+{
+    let mut left = String::new();
+    let mut right = String::new();
+    let mut right_side = false;
+
+    for part in parse(format) {
+        match part {
+            Part::Right => right_side = true,
+            part => {
+                let rendered = render_part(&part, &mut segment)
+                    .unwrap_or_else(String::new);
+                match right_side {
+                    true => right += &rendered,
+                    false => left += &rendered
+                }
+            }
+        }
+    }
+
+    (left, right)
+}
+
+// Segments that don't depend on where hunter is, usable everywhere
+pub fn common_segment(segment: &str, arg: Option<&str>) -> Option<String> {
+    match segment {
+        "clock" => Some(chrono::Local::now()
+                        .format(arg.unwrap_or("%H:%M"))
+                        .to_string()),
+        "battery" => battery(),
+        _ => None
+    }
+}
+
+// Charge of all batteries together, with a + while charging. Goes red when
+// it's running low. None on machines without one.
+pub fn battery() -> Option<String> {
+    let supplies = std::fs::read_dir("/sys/class/power_supply").ok()?;
+
+    let read = |dir: &Path, name: &str| {
+        std::fs::read_to_string(dir.join(name))
+            .map(|value| value.trim().to_string())
+            .ok()
+    };
+
+    let batteries = supplies
+        .filter_map(|supply| supply.ok())
+        .map(|supply| supply.path())
+        .filter(|dir| read(dir, "type").as_ref().map(|t| t.as_str()) == Some("Battery"))
+        .filter_map(|dir| {
+            let capacity = read(&dir, "capacity")?.parse::<usize>().ok()?;
+            let charging = read(&dir, "status")
+                .map(|status| status == "Charging")
+                .unwrap_or(false);
+            Some((capacity, charging))
+        })
+        .collect::<Vec<_>>();
+
+    if batteries.is_empty() { return None; }
+
+    let capacity = batteries.iter().map(|(capacity, _)| capacity).sum::<usize>()
+        / batteries.len();
+    let charging = batteries.iter().any(|(_, charging)| *charging);
+
+    let battery = match (charging, capacity) {
+        (true, _) => format!("+{}%", capacity),
+        (false, 0..=15) => format!("{}{}%", term::color_red(), capacity),
+        (false, _) => format!("{}%", capacity)
+    };
+
+    Some(battery)
+}
+
+// The repository dir is in
+pub fn git_dir(dir: &Path) -> Option<PathBuf> {
+    let git_dir = dir.ancestors()
+        .map(|dir| dir.join(".git"))
+        .find(|git| git.exists())?;
+
+    // worktrees and submodules have a file pointing to the real one
+    match git_dir.is_file() {
+        true => {
+            let pointer = std::fs::read_to_string(&git_dir).ok()?;
+            let target = pointer.trim().trim_start_matches("gitdir:").trim();
+            Some(git_dir.parent()?.join(PathBuf::from(target)))
+        }
+        false => Some(git_dir)
+    }
+}
+
+// Per directory, the branch and the file whose mtime says it's still
+// right. That's HEAD, which checkouts rewrite, or outside of a repository
+// the directory itself, where git init would show up.
+const BRANCHES_MAX: usize = 1000;
+
+lazy_static! {
+    static ref BRANCHES: Mutex<HashMap<PathBuf, (PathBuf, SystemTime, Option<String>)>> =
+        Mutex::new(HashMap::new());
+}
+
+fn mtime(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+// Reads the branch from HEAD, or the short hash if it's detached. Doesn't
+// need git to be installed.
+pub fn git_branch(dir: &Path) -> Option<String> {
+    let mut branches = BRANCHES.lock().ok()?;
+
+    if let Some((watched, seen, branch)) = branches.get(dir) {
+        if mtime(watched).as_ref() == Some(seen) {
+            return branch.clone();
+        }
+    }
+
+    let (watched, branch) = match git_dir(dir) {
+        Some(git_dir) => (git_dir.join("HEAD"), read_branch(&git_dir)),
+        None => (dir.to_path_buf(), None)
+    };
+
+    if branches.len() >= BRANCHES_MAX { branches.clear(); }
+    if let Some(seen) = mtime(&watched) {
+        branches.insert(dir.to_path_buf(), (watched, seen, branch.clone()));
+    }
+
+    branch
+}
+
+fn read_branch(git_dir: &Path) -> Option<String> {
+    let head = std::fs::read_to_string(git_dir.join("HEAD")).ok()?;
+    let head = head.trim();
+
+    match head.starts_with("ref: ") {
+        true => Some(head.trim_start_matches("ref: ")
+                     .trim_start_matches("refs/heads/")
+                     .to_string()),
+        false => Some(head.chars().take(7).collect())
+    }
+}
+
+// The last commit that touched a file, like "author, 3 days ago: subject".
+// That needs the git command and takes a moment, so it's looked up in the
+// background and the footer is drawn again once it's there. Commits,
+// checkouts and the like all write the index, so its mtime is part of
+// what's cached. The age is worked out when it's shown, so it doesn't get
+// stuck at "2 minutes ago".
+const BLAME_SUBJECT_MAX: usize = 50;
+const BLAMES_MAX: usize = 1000;
+
+type BlameKey = (PathBuf, SystemTime);
+
+#[derive(Debug, Clone)]
+struct Commit {
+    author: String,
+    time: i64,
+    subject: String,
+}
+
+lazy_static! {
+    static ref BLAMES: Mutex<HashMap<BlameKey, Option<Commit>>> = Mutex::new(HashMap::new());
+}
+
+// Close to what git says for %ar
+fn age(secs: i64) -> String {
+    let (n, unit) = match secs.max(0) {
+        secs if secs < 90 => return String::from("just now"),
+        secs if secs < 90 * 60 => (secs / 60, "minute"),
+        secs if secs < 36 * 3600 => (secs / 3600, "hour"),
+        secs if secs < 14 * 86400 => (secs / 86400, "day"),
+        secs if secs < 60 * 86400 => (secs / (7 * 86400), "week"),
+        secs if secs < 365 * 86400 => (secs / (30 * 86400), "month"),
+        secs => (secs / (365 * 86400), "year")
+    };
+
+    match n {
+        1 => format!("1 {} ago", unit),
+        _ => format!("{} {}s ago", n, unit)
+    }
+}
+
+pub fn git_blame(path: &Path, sender: &Sender<Events>) -> Option<String> {
+    let git_dir = git_dir(path.parent()?)?;
+    let index = std::fs::metadata(git_dir.join("index"))
+        .and_then(|meta| meta.modified())
+        .ok()?;
+    let key = (path.to_path_buf(), index);
+
+    let mut blames = BLAMES.lock().ok()?;
+    if let Some(commit) = blames.get(&key) {
+        let commit = commit.as_ref()?;
+        let now = chrono::Local::now().timestamp();
+
+        return Some(format!("{}, {}: {}",
+                            commit.author,
+                            age(now - commit.time),
+                            commit.subject));
+    }
+
+    if blames.len() >= BLAMES_MAX { blames.clear(); }
+
+    // nothing until it's there, so it's only looked up once
+    blames.insert(key.clone(), None);
+    drop(blames);
+
+    let sender = sender.clone();
+    std::thread::spawn(move || {
+        let blame = last_commit(&key.0);
+        if blame.is_none() { return; }
+
+        BLAMES.lock().map(|mut blames| blames.insert(key, blame)).ok();
+        sender.send(Events::WidgetReady).ok();
+    });
+
+    None
+}
+
+fn last_commit(path: &Path) -> Option<Commit> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(path.parent()?)
+        .args(&["log", "-1", "--format=%an%x09%at%x09%s", "--"])
+        .arg(path.file_name()?)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+
+    let text = String::from_utf8_lossy(&output.stdout);
+    let mut fields = text.trim_end().splitn(3, '\t');
+    let author = fields.next().filter(|author| !author.is_empty())?;
+    let time = fields.next()?.parse().ok()?;
+    let subject = fields.next()?;
+
+    Some(Commit {
+        author: term::sanitize(author),
+        time: time,
+        subject: term::ellipsize(&term::sanitize(subject), BLAME_SUBJECT_MAX)
+    })
+}
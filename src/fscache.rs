@@ -2,9 +2,10 @@ use notify::{RecommendedWatcher, Watcher, DebouncedEvent, RecursiveMode};
 
 use async_value::{Async, Stale};
 
-use std::sync::{Arc, RwLock, Weak};
+use std::sync::{Arc, Mutex, RwLock, Weak};
 use std::sync::mpsc::{channel, Sender, Receiver};
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::os::unix::fs::MetadataExt;
 use std::time::Duration;
 use std::path::PathBuf;
 
@@ -124,10 +125,85 @@ impl FsEventDispatcher {
     // fn remove_unnecessary
 }
 
+// Path, mtime in seconds and nanoseconds, and the size of the preview
+// column, previewers get that too
+type PreviewKey = (PathBuf, i64, i64, u16, u16);
+
+#[derive(Debug, Default)]
+struct PreviewEntries {
+    previews: HashMap<PreviewKey, Arc<Vec<String>>>,
+    // least recently used first
+    order: VecDeque<PreviewKey>,
+    size: usize,
+}
+
+// What external previewers printed, so going back and forth over the same
+// files doesn't run them again every time. A changed mtime makes a new key,
+// the old entry is just never used again and eventually pushed out.
+#[derive(Clone, Debug, Default)]
+pub struct PreviewCache {
+    entries: Arc<Mutex<PreviewEntries>>,
+}
+
+impl PreviewCache {
+    pub fn key(file: &File, size: (u16, u16)) -> Option<PreviewKey> {
+        let meta = std::fs::metadata(&file.path).ok()?;
+        Some((file.path.clone(), meta.mtime(), meta.mtime_nsec(), size.0, size.1))
+    }
+
+    pub fn get(&self, key: &PreviewKey) -> Option<Arc<Vec<String>>> {
+        let mut entries = self.entries.lock().ok()?;
+        let preview = entries.previews.get(key)?.clone();
+
+        let pos = entries.order.iter().position(|k| k == key)?;
+        let key = entries.order.remove(pos)?;
+        entries.order.push_back(key);
+
+        Some(preview)
+    }
+
+    // limit is in bytes, roughly, 0 doesn't cache anything
+    pub fn put(&self, key: PreviewKey, lines: Vec<String>, limit: usize) -> HResult<()> {
+        let size = preview_size(&lines);
+        if limit == 0 || size > limit { return Ok(()) }
+
+        let mut entries = self.entries.lock()?;
+
+        if let Some(old) = entries.previews.insert(key.clone(), Arc::new(lines)) {
+            entries.size -= preview_size(&old);
+            entries.order.retain(|k| k != &key);
+        }
+        entries.order.push_back(key);
+        entries.size += size;
+
+        while entries.size > limit {
+            let oldest = match entries.order.pop_front() {
+                Some(oldest) => oldest,
+                None => break
+            };
+            if let Some(old) = entries.previews.remove(&oldest) {
+                entries.size -= preview_size(&old);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn preview_size(lines: &[String]) -> usize {
+    lines.iter()
+        .map(|line| line.len() + std::mem::size_of::<String>())
+        .sum()
+}
+
 #[derive(Clone)]
 pub struct FsCache {
     files: Arc<RwLock<HashMap<File, Files>>>,
     pub tab_settings: Arc<RwLock<HashMap<File, TabSettings>>>,
+    // Sorting and hidden files as last changed in this tab, for the
+    // directories it wasn't in yet. Filters stay where they were set.
+    tab_view: Arc<RwLock<Option<DirSettings>>>,
+    pub previews: PreviewCache,
     watched_dirs: Arc<RwLock<HashSet<File>>>,
     watcher: Arc<RwLock<RecommendedWatcher>>,
     fs_event_dispatcher: FsEventDispatcher
@@ -143,6 +219,8 @@ impl FsCache {
         let fs_cache = FsCache {
             files: Arc::new(RwLock::new(HashMap::new())),
             tab_settings: Arc::new(RwLock::new(HashMap::new())),
+            tab_view: Arc::new(RwLock::new(None)),
+            previews: PreviewCache::default(),
             watched_dirs: Arc::new(RwLock::new(HashSet::new())),
             watcher: Arc::new(RwLock::new(watcher)),
             fs_event_dispatcher: FsEventDispatcher::new()
@@ -158,8 +236,26 @@ impl FsCache {
     pub fn new_client(&self, settings: HashMap<File, TabSettings>) -> HResult<FsCache> {
         let mut cache = self.clone();
         cache.tab_settings = Arc::new(RwLock::new(settings));
+        // a new tab starts out looking like the one it came from
+        cache.tab_view = Arc::new(RwLock::new(self.tab_view.read()?.clone()));
         Ok(cache)
     }
+
+    // The ones for dir, or the tab's view if it wasn't there yet
+    fn settings_for(&self, dir: &File) -> HResult<Option<TabSettings>> {
+        if let Some(settings) = self.tab_settings.read()?.get(dir) {
+            return Ok(Some(settings.clone()));
+        }
+
+        Ok(self.tab_view.read()?.clone().map(|mut view| {
+            view.filter = None;
+            view.filter_selected = false;
+
+            let mut settings = TabSettings::new();
+            settings.dir_settings = view;
+            settings
+        }))
+    }
 }
 
 impl FsCache {
@@ -216,6 +312,18 @@ impl FsCache {
         Ok(())
     }
 
+    // Only for directories this tab hasn't been in yet, false otherwise
+    pub fn set_default_sort(&self, dir: &File, sort: SortBy, reverse: bool) -> HResult<bool> {
+        let mut settings = self.tab_settings.write()?;
+        if settings.contains_key(dir) { return Ok(false); }
+
+        let mut setting = TabSettings::new();
+        setting.dir_settings.sort = sort;
+        setting.dir_settings.reverse = reverse;
+        settings.insert(dir.clone(), setting);
+        Ok(true)
+    }
+
     pub fn save_settings(&self, files: &Files, selection: Option<File>) -> HResult<()> {
         let dir = files.directory.clone();
         let tab_settings = FsCache::extract_tab_settings(&files, selection);
@@ -223,6 +331,12 @@ impl FsCache {
         Ok(())
     }
 
+    pub fn set_tab_view(&self, files: &Files) -> HResult<()> {
+        let tab_settings = FsCache::extract_tab_settings(&files, None);
+        *self.tab_view.write()? = Some(tab_settings.dir_settings);
+        Ok(())
+    }
+
     pub fn is_cached(&self, dir: &File) -> HResult<bool> {
         Ok(self.files.read()?.contains_key(dir))
     }
@@ -258,10 +372,7 @@ impl FsCache {
     }
 
     fn get_cached_files(&self, dir: &File) -> HResult<CachedFiles> {
-        let tab_settings = match self.tab_settings.read()?.get(&dir) {
-                Some(tab_settings) => tab_settings.clone(),
-                None => TabSettings::new()
-        };
+        let tab_settings = self.settings_for(dir)?.unwrap_or_else(TabSettings::new);
         let selection = tab_settings.selection.clone();
         let file_cache = self.files.clone();
         let dir = dir.clone();
@@ -301,10 +412,10 @@ impl FsCache {
     pub fn apply_settingss(cache: &FsCache,
                        files: &mut Files)
                        -> HResult<()> {
-        let dir = &files.directory;
-        let tab_settings = cache.tab_settings.read()?.get(&dir).cloned();
-        if tab_settings.is_none() { return Ok(()) }
-        let tab_settings = tab_settings?;
+        let tab_settings = match cache.settings_for(&files.directory)? {
+            Some(tab_settings) => tab_settings,
+            None => return Ok(())
+        };
 
         if files.show_hidden != tab_settings.dir_settings.show_hidden ||
             files.filter != tab_settings.dir_settings.filter ||
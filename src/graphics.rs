@@ -0,0 +1,222 @@
+use lazy_static;
+
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::sync::Mutex;
+
+use crate::config::Config;
+use crate::coordinates::Coordinates;
+use crate::fail::{HResult, HError, ErrorLog};
+
+
+// Shows images without hunter-media, using whatever the terminal or the
+// system has to offer. Kitty and sixel images are just part of the
+// drawlist, ueberzug puts a window on top of the terminal that has to be
+// taken away again explicitly.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Backend {
+    Kitty,
+    Sixel,
+    Ueberzug,
+}
+
+lazy_static! {
+    static ref CHAFA: bool = in_path("chafa");
+    static ref IMG2SIXEL: bool = in_path("img2sixel");
+    static ref UEBERZUG_BIN: bool = in_path("ueberzug");
+    static ref UEBERZUG: Mutex<Option<Child>> = Mutex::new(None);
+}
+
+pub fn in_path(name: &str) -> bool {
+    std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths)
+             .any(|path| path.join(name).is_file()))
+        .unwrap_or(false)
+}
+
+fn sixel_terminal() -> bool {
+    let term = std::env::var("TERM").unwrap_or(String::new());
+    ["mlterm", "foot", "yaft", "contour"].iter()
+        .any(|sixel_term| term.starts_with(sixel_term))
+}
+
+impl Backend {
+    pub fn detect(config: &Config) -> Option<Backend> {
+        let can_sixel = *CHAFA || *IMG2SIXEL;
+        let has_display = std::env::var_os("DISPLAY").is_some();
+
+        match config.graphics.as_str() {
+            "kitty" => Some(Backend::Kitty),
+            "sixel" if can_sixel => Some(Backend::Sixel),
+            _ if can_sixel && sixel_terminal() => Some(Backend::Sixel),
+            _ if *UEBERZUG_BIN && has_display => Some(Backend::Ueberzug),
+            _ => None
+        }
+    }
+
+    // Lines to draw one below the other, starting at the top left corner
+    pub fn encode(&self,
+                  file: &Path,
+                  coordinates: &Coordinates) -> HResult<Vec<String>> {
+        let (xsize, ysize) = coordinates.size_u();
+        let (xsize, ysize) = (xsize + 1, ysize + 1);
+
+        match self {
+            Backend::Kitty if *CHAFA => chafa("kitty", file, xsize, ysize),
+            Backend::Kitty => kitty_png(file, coordinates),
+            Backend::Sixel if *CHAFA => chafa("sixels", file, xsize, ysize),
+            Backend::Sixel => {
+                let (xpix, _) = coordinates.size_pixels()?;
+                run(Command::new("img2sixel")
+                    .arg("-w")
+                    .arg(xpix.to_string())
+                    .arg(file))
+            }
+            // drawn by ueberzug itself
+            Backend::Ueberzug => Ok(vec![])
+        }
+    }
+
+    // Called on every draw, ueberzug doesn't notice when something is
+    // drawn over its window, so it has to be put back each time
+    pub fn show(&self, file: &Path, coordinates: &Coordinates) -> HResult<()> {
+        if *self != Backend::Ueberzug { return Ok(()); }
+
+        let (xpos, ypos) = coordinates.position_u();
+        let (xsize, ysize) = coordinates.size_u();
+
+        ueberzug(&format!("{{\"action\": \"add\", \"identifier\": \"hunter\", \
+                           \"x\": {}, \"y\": {}, \
+                           \"max_width\": {}, \"max_height\": {}, \
+                           \"path\": {}}}",
+                          xpos,
+                          ypos,
+                          xsize + 1,
+                          ysize + 1,
+                          json_string(&file.to_string_lossy())))
+    }
+
+    pub fn clear(&self) {
+        match self {
+            Backend::Kitty => print!("\x1b_Ga=d\x1b\\"),
+            Backend::Ueberzug => clear_ueberzug(),
+            // sixels are just overdrawn
+            Backend::Sixel => {}
+        }
+    }
+}
+
+// Takes down the ueberzug window, if there is one, e.g. before a popup
+pub fn clear_ueberzug() {
+    let running = UEBERZUG.lock()
+        .map(|ueberzug| ueberzug.is_some())
+        .unwrap_or(false);
+
+    if running {
+        ueberzug("{\"action\": \"remove\", \"identifier\": \"hunter\"}").log();
+    }
+}
+
+fn ueberzug(command: &str) -> HResult<()> {
+    let mut ueberzug = UEBERZUG.lock()?;
+
+    if ueberzug.is_none() {
+        let child = Command::new("ueberzug")
+            .args(&["layer", "--silent", "--parser", "json"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        *ueberzug = Some(child);
+    }
+
+    let stdin = ueberzug.as_mut()?.stdin.as_mut()?;
+    let result = writeln!(stdin, "{}", command);
+
+    // it died, start a new one next time
+    if result.is_err() {
+        ueberzug.take();
+    }
+
+    Ok(result?)
+}
+
+fn chafa(format: &str,
+         file: &Path,
+         xsize: usize,
+         ysize: usize) -> HResult<Vec<String>> {
+    run(Command::new("chafa")
+        .arg("-f")
+        .arg(format)
+        .arg("-s")
+        .arg(format!("{}x{}", xsize, ysize))
+        .arg(file))
+}
+
+fn run(command: &mut Command) -> HResult<Vec<String>> {
+    let output = command.stderr(Stdio::null()).output()?;
+
+    if !output.status.success() {
+        return HError::log(&format!("{:?} failed", command));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+       .lines()
+       .map(|line| line.to_string())
+       .collect())
+}
+
+// Kitty can read PNGs all by itself, it only needs the path. Anything else
+// would need decoding first, that's what chafa/hunter-media are for.
+fn kitty_png(file: &Path, coordinates: &Coordinates) -> HResult<Vec<String>> {
+    let (width, height) = png_size(file)?;
+    let (xsize, ysize) = coordinates.size_u();
+    let (xsize, ysize) = (xsize + 1, ysize + 1);
+    let (xpix, ypix) = coordinates.size_pixels()?;
+
+    // fit into the column without stretching
+    let scale = (xpix as f32 / width as f32)
+        .min(ypix as f32 / height as f32)
+        .min(1.0);
+    let cols = (width as f32 * scale / (xpix as f32 / xsize as f32)).ceil();
+    let rows = (height as f32 * scale / (ypix as f32 / ysize as f32)).ceil();
+
+    let path = base64::encode(file.to_string_lossy().as_bytes());
+
+    Ok(vec![format!("\x1b_Ga=T,t=f,f=100,q=2,c={},r={};{}\x1b\\",
+                    (cols as usize).max(1),
+                    (rows as usize).max(1),
+                    path)])
+}
+
+fn png_size(file: &Path) -> HResult<(u32, u32)> {
+    let mut header = [0u8; 24];
+    std::fs::File::open(file)?.read_exact(&mut header)?;
+
+    if &header[..8] != b"\x89PNG\r\n\x1a\n" {
+        return Err(HError::Error(format!("Not a PNG: {}", file.to_string_lossy())));
+    }
+
+    let width = u32::from_be_bytes([header[16], header[17], header[18], header[19]]);
+    let height = u32::from_be_bytes([header[20], header[21], header[22], header[23]]);
+
+    if width == 0 || height == 0 {
+        return Err(HError::Error(format!("Empty PNG: {}", file.to_string_lossy())));
+    }
+
+    Ok((width, height))
+}
+
+pub fn json_string(string: &str) -> String {
+    let escaped = string.chars()
+        .map(|c| match c {
+            '"' => "\\\"".to_string(),
+            '\\' => "\\\\".to_string(),
+            c if c.is_control() => format!("\\u{:04x}", c as u32),
+            c => c.to_string()
+        })
+        .collect::<String>();
+
+    format!("\"{}\"", escaped)
+}
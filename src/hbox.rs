@@ -11,6 +11,8 @@ pub struct HBox<T: Widget> {
     pub ratios: Option<Vec<usize>>,
     pub zoom_active: bool,
     pub active: Option<usize>,
+    // gets the keys, falls back to active
+    pub focused: Option<usize>,
 }
 
 
@@ -20,7 +22,8 @@ impl<T> HBox<T> where T: Widget + PartialEq {
                widgets: vec![],
                ratios: None,
                zoom_active: false,
-               active: None
+               active: None,
+               focused: None
          }
     }
 
@@ -154,6 +157,68 @@ impl<T> HBox<T> where T: Widget + PartialEq {
         Ok(())
     }
 
+    pub fn focused_index(&self) -> Option<usize> {
+        self.focused.or(self.active)
+    }
+
+    pub fn focus(&mut self, i: usize) -> HResult<()> {
+        if i+1 > self.widgets.len() {
+            HError::no_widget()?
+        }
+
+        if self.focused_index() != Some(i) {
+            // gets rid of the old focus border
+            self.core.clear().log();
+        }
+
+        self.focused = Some(i);
+
+        for (n, widget) in self.widgets.iter_mut().enumerate() {
+            widget.set_focus(n == i).log();
+        }
+
+        Ok(())
+    }
+
+    pub fn focus_next(&mut self) -> HResult<()> {
+        let len = self.widgets.len();
+        if len == 0 { return HError::no_widget(); }
+
+        let next = self.focused_index()
+            .map(|i| (i + 1) % len)
+            .unwrap_or(0);
+        self.focus(next)
+    }
+
+    pub fn focused_widget(&self) -> Option<&T> {
+        self.widgets.get(self.focused_index()?)
+    }
+
+    pub fn focused_widget_mut(&mut self) -> Option<&mut T> {
+        let index = self.focused_index()?;
+        self.widgets.get_mut(index)
+    }
+
+    // a line in the gap left of the focused widget, but only when the
+    // focus isn't where it usually is
+    fn focus_border(&self) -> HResult<String> {
+        let focused = self.focused_index()?;
+        if Some(focused) == self.active || self.zoom_active {
+            return Ok(String::new());
+        }
+
+        let (xpos, ypos) = self.widgets.get(focused)?.get_coordinates()?.u16position();
+        let ysize = self.widgets.get(focused)?.get_coordinates()?.ysize();
+        if xpos < 2 { return Ok(String::new()); }
+
+        Ok((ypos..ypos+ysize)
+           .map(|y| format!("{}{}│{}",
+                            crate::term::goto_xy(xpos - 1, y),
+                            crate::term::highlight_color(),
+                            crate::term::reset()))
+           .collect())
+    }
+
     pub fn active_widget(&self) -> Option<&T> {
         self.widgets.get(self.active?)
     }
@@ -201,13 +266,15 @@ impl<T> Widget for HBox<T> where T: Widget + PartialEq {
             return self.active_widget()?.get_drawlist();
         }
 
-        Ok(self.widgets.iter().map(|child| {
+        let drawlist: String = self.widgets.iter().map(|child| {
             child.get_drawlist().log_and().unwrap_or_else(|_| String::new())
-        }).collect())
+        }).collect();
+
+        Ok(drawlist + &self.focus_border().unwrap_or_default())
     }
 
     fn on_event(&mut self, event: Event) -> HResult<()> {
-        self.active_widget_mut()?.on_event(event)?;
+        self.focused_widget_mut()?.on_event(event)?;
         Ok(())
     }
 }
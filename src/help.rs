@@ -0,0 +1,464 @@
+use strum::{EnumMessage, EnumProperty, IntoEnumIterator};
+use termion::event::Key;
+use unicode_width::UnicodeWidthStr;
+
+use crate::coordinates::Coordinates;
+use crate::fail::{HResult, HError, ErrorLog};
+use crate::keybind::{Acting, AnyKey, BindingSection, Bindings, HelpAction, KeyBinds, Movement};
+use crate::listview::{Listable, ListView};
+use crate::macros::Pending;
+use crate::term;
+use crate::widget::{Widget, WidgetCore};
+
+
+// Everything hunter can do, straight from the action enums. The
+// descriptions and config options live there, next to the actions.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct HelpEntry {
+    section: &'static str,
+    action: String,
+    message: String,
+    config: Option<&'static str>,
+    keys: String,
+    default_keys: String,
+}
+
+fn keys_string(keys: Vec<AnyKey>) -> String {
+    keys.iter()
+        .map(|key| key.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn section_entries<T>(bindings: &Bindings<T>) -> Vec<HelpEntry>
+where
+    T: BindingSection + IntoEnumIterator + EnumMessage + EnumProperty,
+    T::Iterator: Iterator<Item=T>,
+    Bindings<T>: Default
+{
+    let defaults = Bindings::<T>::default();
+
+    T::iter()
+        .map(|action| HelpEntry {
+            section: T::section(),
+            action: action.to_string(),
+            message: action.get_message().unwrap_or("").to_string(),
+            config: action.get_str("config"),
+            keys: keys_string(bindings.keys_for(&action)),
+            default_keys: keys_string(defaults.keys_for(&action))
+        })
+        .collect()
+}
+
+pub fn entries(keybinds: &KeyBinds) -> Vec<HelpEntry> {
+    let mut entries = vec![];
+
+    entries.extend(section_entries(&keybinds.movement));
+    entries.extend(section_entries(&keybinds.filebrowser));
+    entries.extend(section_entries(&keybinds.filelist));
+    entries.extend(section_entries(&keybinds.visual));
+    entries.extend(section_entries(&keybinds.search));
+    entries.extend(section_entries(&keybinds.tab));
+    entries.extend(section_entries(&keybinds.tabpicker));
+    entries.extend(section_entries(&keybinds.devicepicker));
+    entries.extend(section_entries(&keybinds.projectpicker));
+    entries.extend(section_entries(&keybinds.branchpicker));
+    entries.extend(section_entries(&keybinds.pipelinepicker));
+    entries.extend(section_entries(&keybinds.recentfiles));
+    entries.extend(section_entries(&keybinds.cleanup));
+    entries.extend(section_entries(&keybinds.findings));
+    entries.extend(section_entries(&keybinds.audit));
+    entries.extend(section_entries(&keybinds.mouse));
+    entries.extend(section_entries(&keybinds.media));
+    entries.extend(section_entries(&keybinds.bookmark));
+    entries.extend(section_entries(&keybinds.process));
+    entries.extend(section_entries(&keybinds.minibuffer));
+    entries.extend(section_entries(&keybinds.fold));
+    entries.extend(section_entries(&keybinds.log));
+    entries.extend(section_entries(&keybinds.quickaction));
+    entries.extend(section_entries(&keybinds.trash));
+    entries.extend(section_entries(&keybinds.permissions));
+    entries.extend(section_entries(&keybinds.help));
+    entries.extend(section_entries(&keybinds.pager));
+
+    // commands from the keys file don't have an action of their own
+    entries.extend(keybinds.commands.iter().map(|(key, cmd)| HelpEntry {
+        section: "commands",
+        action: cmd.clone(),
+        message: String::from("Run this shell command"),
+        config: None,
+        keys: key.to_string(),
+        default_keys: String::new()
+    }));
+
+    entries.extend(keybinds.palette.iter().map(|(name, target)| HelpEntry {
+        section: "palette",
+        action: name.clone(),
+        message: format!("Runs {}", target),
+        config: None,
+        keys: String::from(":"),
+        default_keys: String::new()
+    }));
+
+    entries
+}
+
+pub type HelpMenu = ListView<Vec<HelpEntry>>;
+
+impl Listable for ListView<Vec<HelpEntry>> {
+    type Item = ();
+
+    fn len(&self) -> usize {
+        self.content.len()
+    }
+
+    fn render(&self) -> Vec<String> {
+        let xsize = self.core.coordinates.xsize() as usize;
+        let section_width = self.content.iter()
+            .map(|entry| entry.section.len())
+            .max()
+            .unwrap_or(0);
+        let action_width = self.content.iter()
+            .map(|entry| entry.action.len())
+            .max()
+            .unwrap_or(0);
+
+        self.content
+            .iter()
+            .map(|entry| {
+                let line = format!("{}{:sw$} {}{:aw$} {}{:12}{} {}",
+                                   term::color_cyan(),
+                                   entry.section,
+                                   term::normal_color(),
+                                   entry.action,
+                                   term::color_yellow(),
+                                   term::ellipsize(&entry.keys, 12),
+                                   term::normal_color(),
+                                   entry.message,
+                                   sw = section_width,
+                                   aw = action_width);
+                term::sized_string_u(&line, xsize)
+            })
+            .collect()
+    }
+
+    fn render_header(&self) -> HResult<String> {
+        Ok(format!("Help: {} actions, F1 or Enter for details", self.content.len()))
+    }
+
+    fn on_key(&mut self, key: Key) -> HResult<()> {
+        self.do_key(key)
+    }
+}
+
+impl Acting for ListView<Vec<HelpEntry>> {
+    type Action = HelpAction;
+
+    fn search_in(&self) -> Bindings<Self::Action> {
+        self.core.config().keybinds.help
+    }
+
+    fn movement(&mut self, movement: &Movement) -> HResult<()> {
+        use Movement::*;
+
+        match movement {
+            Up(n) => { for _ in 0..*n { self.move_up(); }; self.refresh()?; }
+            Down(n) => { for _ in 0..*n { self.move_down(); }; self.refresh()?; }
+            PageUp => self.page_up(),
+            PageDown => self.page_down(),
+            Top => self.move_top(),
+            Bottom => self.move_bottom(),
+            Left | Right => {}
+        }
+
+        Ok(())
+    }
+
+    fn do_action(&mut self, action: &Self::Action) -> HResult<()> {
+        use HelpAction::*;
+
+        match action {
+            Close => self.popup_finnished()?,
+            ShowPage => {
+                let entry = self.content.get(self.get_selection())?;
+                HelpPage::new(&self.core, entry)?.show().log();
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// One action in detail, centered on top of the list
+pub struct HelpPage {
+    core: WidgetCore,
+    title: String,
+    lines: Vec<String>,
+}
+
+impl HelpPage {
+    pub fn new(core: &WidgetCore, entry: &HelpEntry) -> HResult<HelpPage> {
+        let none = || String::from("none");
+        let or_none = |keys: &str| match keys.is_empty() {
+            true => none(),
+            false => keys.to_string()
+        };
+
+        let mut lines = vec![
+            entry.message.clone(),
+            String::new(),
+            format!("Keys:          {}", or_none(&entry.keys)),
+            format!("Default keys:  {}", or_none(&entry.default_keys)),
+            format!("Config option: {}", entry.config.map(String::from).unwrap_or_else(none)),
+            format!("Keys file:     [{}] {}=...", entry.section, entry.action),
+        ];
+
+        if entry.message.is_empty() {
+            lines.drain(..2);
+        }
+
+        let mut page = HelpPage {
+            core: core.clone(),
+            title: format!("{}: {}", entry.section, entry.action),
+            lines: lines
+        };
+        page.set_coordinates(&core.coordinates)?;
+        Ok(page)
+    }
+
+    pub fn show(&mut self) -> HResult<()> {
+        match self.popup() {
+            Ok(_) | Err(HError::PopupFinnished) => {},
+            err @ Err(_) => err?,
+        }
+        self.get_core()?.clear()?;
+        Ok(())
+    }
+}
+
+impl Widget for HelpPage {
+    fn get_core(&self) -> HResult<&WidgetCore> {
+        Ok(&self.core)
+    }
+    fn get_core_mut(&mut self) -> HResult<&mut WidgetCore> {
+        Ok(&mut self.core)
+    }
+    fn refresh(&mut self) -> HResult<()> {
+        Ok(())
+    }
+
+    // with a column of space around it, so it stands out from the list
+    fn set_coordinates(&mut self, _: &Coordinates) -> HResult<()> {
+        let (xsize, ysize) = term::size()?;
+        let width = self.lines.iter()
+            .map(|line| line.width())
+            .chain(std::iter::once(self.title.width()))
+            .max()
+            .unwrap_or(0) + 2;
+        let width = width.min(xsize);
+        let height = (self.lines.len() + 2).min(ysize);
+
+        self.core.coordinates = Coordinates::new_at(width as u16,
+                                                    height as u16,
+                                                    ((xsize - width) / 2 + 1) as u16,
+                                                    ((ysize - height) / 2 + 1) as u16);
+        Ok(())
+    }
+
+    fn get_drawlist(&self) -> HResult<String> {
+        let (xpos, ypos) = self.get_coordinates()?.u16position();
+        let (xsize, ysize) = self.get_coordinates()?.u16size();
+        let xsize = xsize as usize;
+
+        let title = format!("{}{}{}{}",
+                            term::goto_xy(xpos, ypos),
+                            term::header_color(),
+                            term::sized_string_u(&format!(" {}", self.title), xsize),
+                            term::reset());
+
+        // the empty line at the bottom is part of the page
+        let lines = self.lines
+            .iter()
+            .chain(std::iter::once(&String::new()))
+            .take((ysize as usize).saturating_sub(1))
+            .enumerate()
+            .map(|(i, line)| format!("{}{}{}{}",
+                                     term::goto_xy(xpos, ypos + 1 + i as u16),
+                                     term::normal_color(),
+                                     term::sized_string_u(&format!(" {}", line), xsize),
+                                     term::reset()))
+            .collect::<String>();
+
+        Ok(title + &lines)
+    }
+
+    // any key closes it again
+    fn on_key(&mut self, _: Key) -> HResult<()> {
+        self.popup_finnished()
+    }
+}
+
+// Which-key style, the keys that do something in the file browser right
+// now, packed into columns along the bottom. It's not a popup, it goes on
+// the overlay layer while the file browser keeps the keys. Asked for it's
+// all of them, after a count or RecordMacro/PlayMacro only what can come
+// next.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Hints {
+    All,
+    Count(usize),
+    Macro(Pending),
+}
+
+pub struct KeyHints {
+    core: WidgetCore,
+    title: String,
+    hints: Vec<(String, String)>,
+}
+
+fn section_hints<T>(bindings: &Bindings<T>, counted_only: bool) -> Vec<(String, String)>
+where
+    T: std::fmt::Display + EnumProperty
+{
+    let mut hints = bindings.iter()
+        .filter(|(_, action)| !counted_only || action.get_str("count").is_some())
+        .map(|(key, action)| (key.to_string(), action.to_string()))
+        .collect::<Vec<_>>();
+    hints.sort();
+    hints
+}
+
+fn macro_hints(pending: Pending) -> Vec<(String, String)> {
+    let (registers, last) = crate::macros::registers();
+    let registers = registers.into_iter()
+        .map(|(register, keys)| (register.to_string(), format!("{} keys", keys)));
+
+    match pending {
+        Pending::Record => std::iter::once((String::from("a-z"), String::from("record")))
+            .chain(registers.map(|(register, keys)| (register, format!("replace {}", keys))))
+            .collect(),
+        Pending::Play => last.map(|last| (String::from("@"), format!("again: {}", last)))
+            .into_iter()
+            .chain(registers.map(|(register, keys)| (register, format!("play {}", keys))))
+            .collect()
+    }
+}
+
+impl KeyHints {
+    pub fn new(core: &WidgetCore, which: Hints) -> HResult<KeyHints> {
+        let keybinds = &core.config().keybinds;
+
+        let mut hints = vec![];
+        let title = match which {
+            Hints::All => {
+                hints.extend(section_hints(&keybinds.movement, false));
+                hints.extend(section_hints(&keybinds.filebrowser, false));
+                hints.extend(section_hints(&keybinds.filelist, false));
+                hints.extend(section_hints(&keybinds.tab, false));
+                format!("Keys: {} bound, any key to close", hints.len())
+            }
+            // movements go further, actions on files take that many
+            Hints::Count(count) => {
+                hints.extend(section_hints(&keybinds.movement, false));
+                hints.extend(section_hints(&keybinds.filebrowser, true));
+                hints.extend(section_hints(&keybinds.filelist, true));
+                format!("{} times or files", count)
+            }
+            Hints::Macro(pending) => {
+                hints.extend(macro_hints(pending));
+                match pending {
+                    Pending::Record => String::from("Record into which macro?"),
+                    Pending::Play => String::from("Play which macro?")
+                }
+            }
+        };
+
+        let mut key_hints = KeyHints {
+            core: core.clone(),
+            title: title,
+            hints: hints
+        };
+        key_hints.set_coordinates(&core.coordinates)?;
+        Ok(key_hints)
+    }
+
+    // key and action width, how many columns and rows that makes
+    fn layout(&self, xsize: usize) -> (usize, usize, usize, usize) {
+        let key_width = self.hints.iter().map(|(key, _)| key.width()).max().unwrap_or(0);
+        let action_width = self.hints.iter().map(|(_, action)| action.width()).max().unwrap_or(0);
+        let column_width = key_width + action_width + 3;
+
+        let columns = (xsize / column_width).max(1);
+        let rows = (self.hints.len() + columns - 1) / columns;
+
+        (key_width, action_width, columns, rows)
+    }
+}
+
+impl Widget for KeyHints {
+    fn get_core(&self) -> HResult<&WidgetCore> {
+        Ok(&self.core)
+    }
+    fn get_core_mut(&mut self) -> HResult<&mut WidgetCore> {
+        Ok(&mut self.core)
+    }
+    fn refresh(&mut self) -> HResult<()> {
+        Ok(())
+    }
+
+    fn set_coordinates(&mut self, _: &Coordinates) -> HResult<()> {
+        let (xsize, ysize) = term::size()?;
+        let (_, _, _, rows) = self.layout(xsize);
+        let height = (rows + 1).min(ysize.saturating_sub(1)).max(1);
+
+        self.core.coordinates = Coordinates::new_at(xsize as u16,
+                                                    height as u16,
+                                                    1,
+                                                    (ysize - height + 1) as u16);
+        Ok(())
+    }
+
+    fn get_drawlist(&self) -> HResult<String> {
+        let (xpos, ypos) = self.get_coordinates()?.u16position();
+        let (xsize, ysize) = self.get_coordinates()?.u16size();
+        let (xsize, ysize) = (xsize as usize, ysize as usize);
+        let (key_width, action_width, columns, rows) = self.layout(xsize);
+
+        // what doesn't fit is still in the help
+        let room = ysize.saturating_sub(1);
+        let more = match rows > room {
+            true => ", the rest is in the help",
+            false => ""
+        };
+
+        let title = format!("{}{}{}{}",
+                            term::goto_xy(xpos, ypos),
+                            term::header_color(),
+                            term::sized_string_u(&format!(" {}{}", self.title, more),
+                                                 xsize),
+                            term::reset());
+
+        let lines = (0..rows.min(room))
+            .map(|row| {
+                let line = (0..columns)
+                    .filter_map(|column| self.hints.get(column * rows + row))
+                    .map(|(key, action)| format!("{}{:kw$} {}{:aw$}  ",
+                                                 term::color_yellow(),
+                                                 key,
+                                                 term::normal_color(),
+                                                 action,
+                                                 kw = key_width,
+                                                 aw = action_width))
+                    .collect::<String>();
+
+                format!("{}{}{}{}",
+                        term::goto_xy(xpos, ypos + 1 + row as u16),
+                        term::normal_color(),
+                        term::sized_string_u(&format!(" {}", line), xsize),
+                        term::reset())
+            })
+            .collect::<String>();
+
+        Ok(title + &lines)
+    }
+}
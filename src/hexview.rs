@@ -0,0 +1,188 @@
+use termion::event::Key;
+
+use std::io::Read;
+use std::path::Path;
+
+use crate::coordinates::Coordinates;
+use crate::fail::{HResult, HError};
+use crate::keybind::{Acting, Bindings, Movement};
+use crate::term;
+use crate::widget::{Widget, WidgetCore};
+
+
+// How much is read in, the preview only needs the start of the file
+pub const PREVIEW_LIMIT: u64 = 64 * 1024;
+pub const PAGER_LIMIT: u64 = 16 * 1024 * 1024;
+
+#[derive(Debug, PartialEq)]
+pub struct HexView {
+    pub core: WidgetCore,
+    bytes: Vec<u8>,
+    truncated: bool,
+    offset: usize,
+    // any key that doesn't scroll closes it
+    pager: bool,
+}
+
+impl HexView {
+    pub fn new_from_file(core: &WidgetCore, file: &Path, limit: u64) -> HResult<HexView> {
+        let mut bytes = vec![];
+        std::fs::File::open(file)?
+            .take(limit)
+            .read_to_end(&mut bytes)?;
+        let size = std::fs::metadata(file)?.len();
+
+        Ok(HexView {
+            core: core.clone(),
+            bytes: bytes,
+            truncated: size > limit,
+            offset: 0,
+            pager: false
+        })
+    }
+
+    pub fn show_pager(&mut self) -> HResult<()> {
+        self.pager = true;
+        match self.popup() {
+            Ok(_) | Err(HError::PopupFinnished) => {},
+            err @ Err(_) => err?,
+        }
+        self.get_core()?.clear()?;
+        Ok(())
+    }
+
+    // offset, a space and the ASCII column take 11 columns, each byte 4.
+    // Powers of two keep the offsets readable.
+    fn bytes_per_line(&self) -> usize {
+        let xsize = self.core.coordinates.xsize() as usize;
+        let fits = xsize.saturating_sub(11) / 4;
+
+        [16, 8, 4, 2, 1].iter()
+            .cloned()
+            .find(|&n| n <= fits)
+            .unwrap_or(1)
+    }
+
+    fn line_count(&self) -> usize {
+        let per_line = self.bytes_per_line();
+        (self.bytes.len() + per_line - 1) / per_line + self.truncated as usize
+    }
+
+    fn max_offset(&self) -> usize {
+        let ysize = self.core.coordinates.ysize() as usize;
+        self.line_count().saturating_sub(ysize)
+    }
+
+    fn scroll(&mut self, lines: isize) {
+        let offset = self.offset as isize + lines;
+        self.offset = (offset.max(0) as usize).min(self.max_offset());
+    }
+
+    fn render_line(&self, line: usize, per_line: usize) -> String {
+        let start = line * per_line;
+
+        // past what was read in
+        if start >= self.bytes.len() {
+            return format!("{}…", term::color_yellow());
+        }
+
+        let end = (start + per_line).min(self.bytes.len());
+        let chunk = &self.bytes[start..end];
+
+        let hex = chunk.iter()
+            .map(|byte| format!("{:02x} ", byte))
+            .collect::<String>();
+        let ascii = chunk.iter()
+            .map(|&byte| match byte {
+                0x20..=0x7e => byte as char,
+                _ => '.'
+            })
+            .collect::<String>();
+
+        format!("{}{:08x}{} {:hexwidth$}{}{}",
+                term::color_cyan(),
+                start,
+                term::normal_color(),
+                hex,
+                term::highlight_color(),
+                ascii,
+                hexwidth = per_line * 3)
+    }
+}
+
+impl Widget for HexView {
+    fn get_core(&self) -> HResult<&WidgetCore> {
+        Ok(&self.core)
+    }
+    fn get_core_mut(&mut self) -> HResult<&mut WidgetCore> {
+        Ok(&mut self.core)
+    }
+
+    fn set_coordinates(&mut self, coordinates: &Coordinates) -> HResult<()> {
+        self.core.coordinates = coordinates.clone();
+        // the number of bytes per line might have changed
+        self.scroll(0);
+        Ok(())
+    }
+
+    fn refresh(&mut self) -> HResult<()> {
+        Ok(())
+    }
+
+    fn get_drawlist(&self) -> HResult<String> {
+        let (xpos, ypos) = self.core.coordinates.u16position();
+        let (xsize, ysize) = self.core.coordinates.u16size();
+        let per_line = self.bytes_per_line();
+        let shown = self.line_count().saturating_sub(self.offset).min(ysize as usize);
+
+        let lines = (self.offset..self.offset + shown)
+            .enumerate()
+            .map(|(i, line)| format!("{}{}{}",
+                                     term::goto_xy(xpos, ypos + i as u16),
+                                     term::reset(),
+                                     term::sized_string_u(&self.render_line(line, per_line),
+                                                          xsize as usize)))
+            .collect::<String>();
+
+        Ok(lines + &self.get_redraw_empty_list(shown)?)
+    }
+
+    fn on_key(&mut self, key: Key) -> HResult<()> {
+        match self.do_key(key) {
+            Err(HError::WidgetUndefinedKeyError{..}) if self.pager => {
+                self.popup_finnished()
+            }
+            result => result
+        }
+    }
+}
+
+impl Acting for HexView {
+    type Action = Movement;
+
+    fn search_in(&self) -> Bindings<Self::Action> {
+        self.core.config().keybinds.movement
+    }
+
+    fn movement(&mut self, movement: &Movement) -> HResult<()> {
+        use Movement::*;
+
+        let page = self.core.coordinates.ysize() as isize;
+
+        match movement {
+            Up(n) => self.scroll(-(*n as isize)),
+            Down(n) => self.scroll(*n as isize),
+            PageUp => self.scroll(-page),
+            PageDown => self.scroll(page),
+            Top => self.offset = 0,
+            Bottom => self.offset = self.max_offset(),
+            Left | Right => {}
+        }
+
+        Ok(())
+    }
+
+    fn do_action(&mut self, action: &Self::Action) -> HResult<()> {
+        self.movement(action)
+    }
+}
@@ -1,10 +1,11 @@
 use crate::widget::{Widget, WidgetCore};
 use crate::coordinates::Coordinates;
-use crate::fail::HResult;
+use crate::fail::{HResult, ErrorLog};
 
 use std::path::{Path, PathBuf};
 
 use crate::mediaview::MediaError;
+use crate::graphics::Backend;
 
 impl std::cmp::PartialEq for ImgView {
     fn eq(&self, other: &Self) -> bool {
@@ -16,7 +17,9 @@ impl std::cmp::PartialEq for ImgView {
 pub struct ImgView {
     pub core: WidgetCore,
     pub buffer: Vec<String>,
-    pub file: Option<PathBuf>
+    pub file: Option<PathBuf>,
+    // None means hunter-media does the drawing
+    pub backend: Option<Backend>
 }
 
 impl ImgView {
@@ -24,7 +27,20 @@ impl ImgView {
         let mut view = ImgView {
             core: core,
             buffer: vec![],
-            file: Some(file.to_path_buf())
+            file: Some(file.to_path_buf()),
+            backend: None
+        };
+
+        view.encode_file()?;
+        Ok(view)
+    }
+
+    pub fn new_inline(core: WidgetCore, file: &Path, backend: Backend) -> HResult<ImgView> {
+        let mut view = ImgView {
+            core: core,
+            buffer: vec![],
+            file: Some(file.to_path_buf()),
+            backend: Some(backend)
         };
 
         view.encode_file()?;
@@ -32,6 +48,12 @@ impl ImgView {
     }
 
     pub fn encode_file(&mut self) -> HResult<()> {
+        if let Some(backend) = self.backend {
+            let file = self.file.as_ref()?;
+            self.buffer = backend.encode(file, &self.core.coordinates)?;
+            return Ok(());
+        }
+
         let (xsize, ysize) = self.core.coordinates.size_u();
         let (xpix, ypix) = self.core.coordinates.size_pixels()?;
         let cell_ratio = crate::term::cell_ratio()?;
@@ -126,12 +148,21 @@ impl Widget for ImgView {
 
         draw += &format!("{}", termion::style::Reset);
 
+        if let (Some(backend), Some(file)) = (self.backend, &self.file) {
+            backend.show(file, &self.core.coordinates).log();
+        }
+
         Ok(draw)
     }
 }
 
 impl Drop for ImgView {
     fn drop(&mut self) {
+        if let Some(backend) = self.backend {
+            backend.clear();
+            return;
+        }
+
         let g_mode = self.core.config().graphics;
         if g_mode == "kitty" || g_mode == "auto" {
             print!("\x1b_Ga=d\x1b\\");
@@ -1,14 +1,17 @@
 use termion::event::Key;
 use ini::Ini;
 use strum::IntoEnumIterator;
+use lazy_static;
 
 use std::collections::HashMap;
 use std::default::Default;
 use std::str::FromStr;
 use std::fmt::{Display, Debug};
+use std::sync::Mutex;
+use std::sync::mpsc::Sender;
 
 use crate::fail::{HError, HResult, KeyBindError, ErrorLog};
-use crate::widget::Widget;
+use crate::widget::{Events, Widget};
 
 
 pub type KbResult<T> = Result<T, KeyBindError>;
@@ -32,10 +35,84 @@ impl<T> Bindings<T> {
     pub fn new() -> Self {
         Bindings(HashMap::new())
     }
+
+    pub fn iter(&self) -> impl Iterator<Item=(&AnyKey, &T)> {
+        self.0.iter()
+    }
+
+    // all keys bound to an action, whatever its parameter is
+    pub fn keys_for(&self, action: &T) -> Vec<AnyKey>
+    where
+        T: Display
+    {
+        let name = action.to_string();
+        let mut keys = self.0
+            .iter()
+            .filter(|(_, bound)| bound.to_string() == name)
+            .map(|(key, _)| *key)
+            .collect::<Vec<_>>();
+
+        keys.sort_by_key(|key| key.to_string());
+        keys
+    }
+}
+
+
+
+
+// Digits typed before a key, like in vim. Movements go that much further
+// or are repeated, actions can ask for it, e.g. to work on that many files.
+#[derive(Debug, Default)]
+struct Count {
+    typed: Option<usize>,
+    // what the action running right now got
+    current: Option<usize>,
+}
+
+const COUNT_MAX: usize = 100_000;
+
+lazy_static! {
+    static ref COUNT: Mutex<Count> = Mutex::new(Count::default());
+}
+
+// A 0 on its own isn't a count
+fn push_digit(digit: usize) -> Option<usize> {
+    let mut count = COUNT.lock().ok()?;
+    if digit == 0 && count.typed.is_none() { return None; }
+
+    let typed = count.typed.unwrap_or(0) * 10 + digit;
+    count.typed = Some(typed.min(COUNT_MAX));
+    count.typed
 }
 
+pub fn typed_count() -> Option<usize> {
+    COUNT.lock().ok()?.typed
+}
 
+fn start_action() {
+    COUNT.lock().map(|mut count| count.current = count.typed.take()).ok();
+}
 
+fn finish_action() {
+    COUNT.lock().map(|mut count| count.current = None).ok();
+}
+
+pub fn count() -> Option<usize> {
+    COUNT.lock().ok()?.current
+}
+
+pub fn clear_count() {
+    COUNT.lock().map(|mut count| *count = Count::default()).ok();
+}
+
+// A file list can be in one of these, visual and search mode have bindings
+// of their own that go before everything else while they last
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    Normal,
+    Visual,
+    Search,
+}
 
 pub trait Acting
 where
@@ -61,10 +138,17 @@ where
             .keybinds
             .movement
             .get(gkey) {
-                match self.movement(movement) {
-                    Ok(()) => return Ok(()),
+                let result = match (*movement, typed_count()) {
+                    (_, None) => self.movement(movement),
+                    (Movement::Up(n), Some(count)) => self.movement(&Movement::Up(n * count)),
+                    (Movement::Down(n), Some(count)) => self.movement(&Movement::Down(n * count)),
+                    (_, Some(count)) => (0..count).map(|_| self.movement(movement)).collect()
+                };
+
+                match result {
+                    Ok(()) => { clear_count(); return Ok(()) }
                     Err(HError::KeyBind(KeyBindError::MovementUndefined)) => {}
-                    Err(e) => Err(e)?
+                    Err(e) => { clear_count(); Err(e)? }
                 }
             }
 
@@ -72,12 +156,31 @@ where
 
         let bindings = self.search_in();
 
-        if let Some(action) = bindings.get(key) {
-            return self.do_action(action)
-        } else if let Some(any_key) = gkey.any() {
-            if let Some(action) = bindings.get(any_key) {
-                let action = action.insert_key_param(key);
-                return self.do_action(&action);
+        let action = match bindings.get(key) {
+            Some(action) => Some(*action),
+            None => gkey.any()
+                .and_then(|any_key| bindings.get(any_key))
+                .map(|action| action.insert_key_param(key))
+        };
+
+        if let Some(action) = action {
+            start_action();
+            let result = self.do_action(&action);
+            finish_action();
+            return result;
+        }
+
+        // nothing wants the digit, so it's a count for the next key
+        if let Key::Char(digit @ '0'..='9') = key {
+            if let Some(count) = push_digit(digit as usize - '0' as usize) {
+                return self.get_core()?.show_status(&format!("{}", count));
+            }
+        }
+
+        // maybe it's just the wrong keyboard layout
+        if self.get_core()?.config().layout_translation {
+            if let Some(key) = crate::keylayout::translate(key) {
+                return self.do_key(key);
             }
         }
 
@@ -91,6 +194,8 @@ pub struct KeyBinds {
     pub movement: Bindings<Movement>,
     pub filebrowser: Bindings<FileBrowserAction>,
     pub filelist: Bindings<FileListAction>,
+    pub visual: Bindings<VisualAction>,
+    pub search: Bindings<SearchAction>,
     pub tab: Bindings<TabAction>,
     pub media: Bindings<MediaAction>,
     pub bookmark: Bindings<BookmarkAction>,
@@ -99,6 +204,24 @@ pub struct KeyBinds {
     pub fold: Bindings<FoldAction>,
     pub log: Bindings<LogAction>,
     pub quickaction: Bindings<QuickActionAction>,
+    pub trash: Bindings<TrashAction>,
+    pub permissions: Bindings<PermissionAction>,
+    pub tabpicker: Bindings<TabPickerAction>,
+    pub devicepicker: Bindings<DevicePickerAction>,
+    pub projectpicker: Bindings<ProjectPickerAction>,
+    pub branchpicker: Bindings<BranchPickerAction>,
+    pub pipelinepicker: Bindings<PipelinePickerAction>,
+    pub recentfiles: Bindings<RecentFilesAction>,
+    pub cleanup: Bindings<CleanupAction>,
+    pub findings: Bindings<FindingsAction>,
+    pub audit: Bindings<AuditAction>,
+    pub mouse: Bindings<MouseAction>,
+    pub help: Bindings<HelpAction>,
+    pub pager: Bindings<PagerAction>,
+    // shell commands bound to keys, with placeholders for files
+    pub commands: Bindings<String>,
+    // names for the command palette and the action or command they run
+    pub palette: Vec<(String, String)>,
 }
 
 impl Default for KeyBinds {
@@ -107,6 +230,8 @@ impl Default for KeyBinds {
             movement: Bindings::default(),
             filebrowser: Bindings::default(),
             filelist: Bindings::default(),
+            visual: Bindings::default(),
+            search: Bindings::default(),
             tab: Bindings::default(),
             media: Bindings::default(),
             bookmark: Bindings::default(),
@@ -114,7 +239,23 @@ impl Default for KeyBinds {
             minibuffer: Bindings::default(),
             fold: Bindings::default(),
             log: Bindings::default(),
-            quickaction: Bindings::default()
+            quickaction: Bindings::default(),
+            trash: Bindings::default(),
+            permissions: Bindings::default(),
+            tabpicker: Bindings::default(),
+            devicepicker: Bindings::default(),
+            projectpicker: Bindings::default(),
+            branchpicker: Bindings::default(),
+            pipelinepicker: Bindings::default(),
+            recentfiles: Bindings::default(),
+            cleanup: Bindings::default(),
+            findings: Bindings::default(),
+            audit: Bindings::default(),
+            mouse: Bindings::default(),
+            help: Bindings::default(),
+            pager: Bindings::default(),
+            commands: Bindings::new(),
+            palette: vec![]
         }
     }
 }
@@ -129,6 +270,8 @@ impl KeyBinds {
         let movement = Movement::load_section(&ini);
         let filebrowser = FileBrowserAction::load_section(&ini);
         let filelist = FileListAction::load_section(&ini);
+        let visual = VisualAction::load_section(&ini);
+        let search = SearchAction::load_section(&ini);
         let tab = TabAction::load_section(&ini);
         let media = MediaAction::load_section(&ini);
         let bookmark = BookmarkAction::load_section(&ini);
@@ -137,11 +280,29 @@ impl KeyBinds {
         let fold = FoldAction::load_section(&ini);
         let log = LogAction::load_section(&ini);
         let quickaction = QuickActionAction::load_section(&ini);
+        let trash = TrashAction::load_section(&ini);
+        let permissions = PermissionAction::load_section(&ini);
+        let tabpicker = TabPickerAction::load_section(&ini);
+        let devicepicker = DevicePickerAction::load_section(&ini);
+        let projectpicker = ProjectPickerAction::load_section(&ini);
+        let branchpicker = BranchPickerAction::load_section(&ini);
+        let pipelinepicker = PipelinePickerAction::load_section(&ini);
+        let recentfiles = RecentFilesAction::load_section(&ini);
+        let cleanup = CleanupAction::load_section(&ini);
+        let findings = FindingsAction::load_section(&ini);
+        let audit = AuditAction::load_section(&ini);
+        let mouse = MouseAction::load_section(&ini);
+        let help = HelpAction::load_section(&ini);
+        let pager = PagerAction::load_section(&ini);
+        let commands = load_commands(&ini);
+        let palette = load_palette(&ini);
 
         Ok(KeyBinds {
             movement,
             filebrowser,
             filelist,
+            visual,
+            search,
             tab,
             media,
             bookmark,
@@ -149,14 +310,150 @@ impl KeyBinds {
             minibuffer,
             fold,
             log,
-            quickaction
+            quickaction,
+            trash,
+            permissions,
+            tabpicker,
+            devicepicker,
+            projectpicker,
+            branchpicker,
+            pipelinepicker,
+            recentfiles,
+            cleanup,
+            findings,
+            audit,
+            mouse,
+            help,
+            pager,
+            commands,
+            palette
+        })
+    }
+}
+
+// Saving the keys file is enough to try out new bindings, no restart needed.
+// Editors often write a new file and move it over the old one, so it's the
+// directory that's watched.
+pub fn watch_keys(sender: Sender<Events>) -> HResult<()> {
+    use notify::{DebouncedEvent, RecommendedWatcher, RecursiveMode, Watcher};
+
+    let path = crate::paths::bindings_path()?;
+    let dir = path.parent()?.to_path_buf();
+
+    std::thread::spawn(move || -> HResult<()> {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut watcher = RecommendedWatcher::new(tx, std::time::Duration::from_millis(500))?;
+        watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+
+        for event in rx.iter() {
+            match event {
+                DebouncedEvent::Create(changed) |
+                DebouncedEvent::Write(changed) |
+                DebouncedEvent::Rename(_, changed) if changed == path => {
+                    sender.send(Events::KeysChanged)?;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    });
+
+    Ok(())
+}
+
+// Lines in [commands] are key=command, the other way around than for
+// actions, since commands can have just about anything in them
+fn load_commands(ini: &Ini) -> Bindings<String> {
+    let mut commands = Bindings::new();
+
+    let section = match ini.section(Some("commands")) {
+        Some(section) => section,
+        None => return commands
+    };
+
+    for (key_str, cmd) in section.iter() {
+        match key_str.trim().parse::<AnyKey>() {
+            Ok(key) => { commands.insert(key, cmd.trim().to_string()); }
+            Err(_) => KeyBindError::WrongKey(cmd.to_string(), key_str.to_string()).log()
+        }
+    }
+
+    commands
+}
+
+// Lines in [palette] are name=Action or name=!command, sorted by name
+fn load_palette(ini: &Ini) -> Vec<(String, String)> {
+    let mut palette = ini.section(Some("palette"))
+        .map(|section| {
+            section.iter()
+                .map(|(name, target)| (name.trim().to_string(), target.trim().to_string()))
+                .collect::<Vec<_>>()
         })
+        .unwrap_or_default();
+
+    palette.sort();
+    palette
+}
+
+// Every section of the keys file with the actions in it, to check it with
+pub fn sections() -> Vec<(&'static str, Vec<String>)> {
+    fn section<T>() -> (&'static str, Vec<String>)
+    where
+        T: BindingSection + IntoEnumIterator,
+        T::Iterator: Iterator<Item=T>,
+        Bindings<T>: Default
+    {
+        (T::section(), T::iter().map(|action| action.to_string()).collect())
     }
+
+    vec![
+        section::<Movement>(),
+        section::<FileBrowserAction>(),
+        section::<FileListAction>(),
+        section::<VisualAction>(),
+        section::<SearchAction>(),
+        section::<TabAction>(),
+        section::<MediaAction>(),
+        section::<BookmarkAction>(),
+        section::<ProcessAction>(),
+        section::<MiniBufferAction>(),
+        section::<FoldAction>(),
+        section::<LogAction>(),
+        section::<QuickActionAction>(),
+        section::<TrashAction>(),
+        section::<PermissionAction>(),
+        section::<TabPickerAction>(),
+        section::<DevicePickerAction>(),
+        section::<ProjectPickerAction>(),
+        section::<BranchPickerAction>(),
+        section::<PipelinePickerAction>(),
+        section::<RecentFilesAction>(),
+        section::<CleanupAction>(),
+        section::<FindingsAction>(),
+        section::<AuditAction>(),
+        section::<MouseAction>(),
+        section::<HelpAction>(),
+        section::<PagerAction>(),
+    ]
+}
+
+// Mouse buttons, bound like keys. Termion has no double clicks, that's
+// two left clicks on the same spot in quick succession.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MouseKey {
+    Left,
+    DoubleLeft,
+    Right,
+    Middle,
+    WheelUp,
+    WheelDown
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum AnyKey {
     Key(Key),
+    Mouse(MouseKey),
     AnyChar,
     AnyF,
     AnyCtrl,
@@ -170,12 +467,25 @@ impl Display for AnyKey {
 
         match self {
             Key(key) => match key {
+                // the same names they're written as in the keys file
+                Char('\n') => write!(fmt, "Enter"),
+                Char(' ') => write!(fmt, "Space"),
+                Char('\t') => write!(fmt, "Tab"),
+                Alt('\r') => write!(fmt, "M-Enter"),
                 Char(ch) => write!(fmt, "{}", ch),
                 Alt(ch) => write!(fmt, "M-{}", ch),
                 Ctrl(ch) => write!(fmt, "C-{}", ch),
                 F(n) => write!(fmt, "F{}", n),
                 k @ _ => write!(fmt, "{:?}", k)
             }
+            Mouse(button) => match button {
+                MouseKey::Left => write!(fmt, "MouseLeft"),
+                MouseKey::DoubleLeft => write!(fmt, "MouseDouble"),
+                MouseKey::Right => write!(fmt, "MouseRight"),
+                MouseKey::Middle => write!(fmt, "MouseMiddle"),
+                MouseKey::WheelUp => write!(fmt, "WheelUp"),
+                MouseKey::WheelDown => write!(fmt, "WheelDown")
+            }
             AnyChar => write!(fmt, "_"),
             AnyF => write!(fmt, "F_"),
             AnyCtrl => write!(fmt, "C-_"),
@@ -205,6 +515,12 @@ impl From<Key> for AnyKey {
     }
 }
 
+impl From<MouseKey> for AnyKey {
+    fn from(button: MouseKey) -> Self {
+        AnyKey::Mouse(button)
+    }
+}
+
 impl FromStr for AnyKey {
     type Err = KeyBindError;
 
@@ -232,10 +548,18 @@ impl FromStr for AnyKey {
                 "Tab" => Some(Key(Char('\t'))),
                 "BackTab" => Some(Key(BackTab)),
                 "Enter" => Some(Key(Char('\n'))),
+                // that's what terminals send for it
+                "M-Enter" => Some(Key(Alt('\r'))),
                 "Space" => Some(Key(Char(' '))),
                 "\\_" => Some(Key(Char('_'))),
                 "_" => Some(AnyChar),
                 "Esc" => Some(Key(Esc)),
+                "MouseLeft" => Some(Mouse(MouseKey::Left)),
+                "MouseDouble" => Some(Mouse(MouseKey::DoubleLeft)),
+                "MouseRight" => Some(Mouse(MouseKey::Right)),
+                "MouseMiddle" => Some(Mouse(MouseKey::Middle)),
+                "WheelUp" => Some(Mouse(MouseKey::WheelUp)),
+                "WheelDown" => Some(Mouse(MouseKey::WheelDown)),
                 _ => None
             }
         };
@@ -448,146 +772,598 @@ where
 
 
 
-#[derive(EnumString, EnumIter, Copy, Clone, Display, Debug)]
+#[derive(EnumString, EnumIter, EnumMessage, EnumProperty, Copy, Clone, Display, Debug)]
 pub enum Movement {
+    #[strum(message = "Move up n lines")]
     Up(usize),
+    #[strum(message = "Move down n lines")]
     Down(usize),
+    #[strum(message = "Go to the parent directory, or scroll left in a preview")]
     Left,
+    #[strum(message = "Enter the selected directory or open the selected file")]
     Right,
+    #[strum(message = "Go to the first entry")]
     Top,
+    #[strum(message = "Go to the last entry")]
     Bottom,
+    #[strum(message = "Move up one page")]
     PageUp,
+    #[strum(message = "Move down one page")]
     PageDown,
 }
 
 
 
-#[derive(EnumString, EnumIter, Copy, Clone, Display, Debug)]
+#[derive(EnumString, EnumIter, EnumMessage, EnumProperty, Copy, Clone, Display, Debug)]
 pub enum FileBrowserAction {
+    #[strum(message = "Move down in the parent directory")]
     LeftColumnDown,
+    #[strum(message = "Move up in the parent directory")]
     LeftColumnUp,
+    #[strum(message = "Go to the home directory")]
     GotoHome,
+    #[strum(message = "Go to a directory typed in, downloads or mounts URLs")]
     TurboCd,
+    #[strum(message = "Select files with an external program")]
+    #[strum(props(config = "select_cmd"))]
     SelectExternal,
+    #[strum(message = "Open where the files picked with an external program are in new tabs")]
+    #[strum(props(config = "select_cmd"))]
+    SelectExternalInTab,
+    #[strum(message = "Go to a directory picked with an external program")]
+    #[strum(props(config = "cd_cmd"))]
     EnterDirExternal,
+    #[strum(message = "Quit hunter")]
     Quit,
+    #[strum(message = "Quit and let the shell cd to the current directory")]
     QuitWithDir,
+    #[strum(message = "Open the selected file in the background")]
     RunInBackground,
+    #[strum(message = "Go back to the previous directory")]
     GotoPrevCwd,
+    #[strum(message = "Show the bookmarks")]
     ShowBookmarks,
+    #[strum(message = "Bookmark the current directory")]
     AddBookmark,
+    #[strum(message = "Show the processes started from hunter")]
     ShowProcesses,
+    #[strum(message = "Show the log")]
     ShowLog,
+    #[strum(message = "Show quick actions for the selected files")]
     ShowQuickActions,
+    #[strum(message = "Open the selected file the way open_rules says")]
+    #[strum(props(config = "open_rules"))]
+    Open,
+    #[strum(message = "Pick a quick action to open the selected files with")]
+    OpenWith,
+    #[strum(message = "Start a shell in the current directory")]
     RunSubshell,
+    #[strum(message = "Toggle between one and three columns")]
+    #[strum(props(config = "ratios"))]
     ToggleColumns,
-    ExecCmd
+    #[strum(message = "Run a shell command on the selected files")]
+    ExecCmd,
+    #[strum(message = "Run a command in the background with low priority")]
+    #[strum(props(config = "nice_level"))]
+    ExecCmdLowPriority,
+    #[strum(message = "Mark the selected files for copying")]
+    #[strum(props(count = "files"))]
+    Yank,
+    #[strum(message = "Mark the selected files for moving")]
+    #[strum(props(count = "files"))]
+    Cut,
+    #[strum(message = "Copy or move the marked files here in the background")]
+    #[strum(props(config = "transfer_limit"))]
+    Paste,
+    #[strum(message = "Copy the selected files to where the next tab is")]
+    #[strum(props(count = "files"))]
+    CopyToOtherTab,
+    #[strum(message = "Move the selected files to where the next tab is")]
+    #[strum(props(count = "files"))]
+    MoveToOtherTab,
+    #[strum(message = "Show the trash")]
+    ShowTrash,
+    #[strum(message = "Follow the end of the previewed file, like tail -f")]
+    TailPreview,
+    #[strum(message = "Edit permissions and owner of the selected files")]
+    EditPermissions,
+    #[strum(message = "Move the focus between file list and preview")]
+    FocusNext,
+    #[strum(message = "Pause or continue background copies and moves")]
+    #[strum(props(config = "transfer_limit"))]
+    TogglePauseTransfers,
+    #[strum(message = "Show a QR code of the selected file's path")]
+    ShowQrPath,
+    #[strum(message = "Show a QR code of the selected file's contents")]
+    ShowQrContents,
+    #[strum(message = "Show a hexdump of the selected file")]
+    ShowHex,
+    #[strum(message = "Share the selected files over HTTP on the local network")]
+    ShareSelection,
+    #[strum(message = "Send the selected files to a KDE Connect device")]
+    SendToDevice,
+    #[strum(message = "Open a recently used project in a new tab")]
+    #[strum(props(config = "project_detection"))]
+    ShowProjects,
+    #[strum(message = "Show the files modified last")]
+    #[strum(props(config = "recent_roots"))]
+    ShowRecentFiles,
+    #[strum(message = "List the biggest files and directories below here to clean up")]
+    #[strum(props(config = "cleanup_threshold"))]
+    ShowCleanup,
+    #[strum(message = "List all symlinks below here that point nowhere")]
+    FindBrokenLinks,
+    #[strum(message = "List all empty directories below here")]
+    FindEmptyDirs,
+    #[strum(message = "Show everything hunter did to files, newest first")]
+    ShowAudit,
+    #[strum(message = "Switch to another branch or worktree of this git repository")]
+    ShowBranches,
+    #[strum(message = "Go to the next file with merge conflicts in it")]
+    NextConflict,
+    #[strum(message = "Run one of the saved pipelines on the selected files")]
+    ShowPipelines,
+    #[strum(message = "Run a command or action by its name")]
+    CommandPalette,
+    #[strum(message = "Use the .hunter.toml of this directory from now on")]
+    TrustLocal,
+    #[strum(message = "Show what the keys do at a glance")]
+    #[strum(props(config = "key_hints"))]
+    ShowKeyHints,
+    #[strum(message = "Record keys into the macro named by the next key, again to stop")]
+    RecordMacro,
+    #[strum(message = "Play the macro named by the next key, @ for the last one")]
+    PlayMacro,
+    #[strum(message = "Show all actions with their keys")]
+    ShowHelp
 }
 
 
 
-#[derive(EnumString, EnumIter, Copy, Clone, Display, Debug)]
+#[derive(EnumString, EnumIter, EnumMessage, EnumProperty, Copy, Clone, Display, Debug)]
 pub enum FileListAction {
+    #[strum(message = "Search for a file by name")]
     Search,
+    #[strum(message = "Go to the next search match")]
     SearchNext,
+    #[strum(message = "Go to the previous search match")]
     SearchPrev,
+    #[strum(message = "Only show files matching a filter")]
     Filter,
+    #[strum(message = "Select or unselect the current file")]
     Select,
+    #[strum(message = "Select everything from here to where the cursor goes")]
+    VisualMode,
+    #[strum(message = "Invert the selection")]
     InvertSelection,
+    #[strum(message = "Unselect everything")]
     ClearSelection,
+    #[strum(message = "Only show the selected files")]
     FilterSelection,
+    #[strum(message = "Tag or untag the current file")]
     ToggleTag,
+    #[strum(message = "Show or hide hidden files")]
+    #[strum(props(config = "show_hidden"))]
     ToggleHidden,
+    #[strum(message = "Reverse the sort order")]
     ReverseSort,
+    #[strum(message = "Sort by the next criterion")]
     CycleSort,
+    #[strum(message = "Go to the next file by modification time")]
     ToNextMtime,
+    #[strum(message = "Go to the previous file by modification time")]
     ToPrevMtime,
+    #[strum(message = "Sort directories before files or not")]
     ToggleDirsFirst,
+    #[strum(message = "Calculate the real size of the selected directory")]
+    #[strum(props(config = "one_filesystem"))]
+    RefineSize,
+    #[strum(message = "Move the selected files to the trash")]
+    #[strum(props(count = "files"))]
+    Trash,
+    #[strum(message = "Delete the selected files without the trash")]
+    #[strum(props(count = "files"))]
+    DeleteForever,
+    #[strum(message = "Create symlinks to the selected files")]
+    #[strum(props(count = "files"))]
+    Symlink,
+    #[strum(message = "Create relative symlinks to the selected files")]
+    #[strum(props(count = "files"))]
+    RelativeSymlink,
+    #[strum(message = "Create hard links to the selected files")]
+    #[strum(props(count = "files"))]
+    Hardlink,
+    #[strum(message = "Create an empty file")]
+    NewFile,
+    #[strum(message = "Create a directory")]
+    NewDir,
+    #[strum(message = "Create a file or directory from a template")]
+    NewFromTemplate,
+    #[strum(message = "Copy the contents of the selected file to the clipboard")]
+    CopyContents,
+    #[strum(message = "Write the listing to a text, CSV or JSON file")]
+    ExportListing,
+    #[strum(message = "Write the directory tree to a file or the clipboard")]
+    ExportTree,
+    #[strum(message = "Set the modification and access times of the selected files")]
+    #[strum(props(count = "files"))]
+    SetTimes,
+    #[strum(message = "Rename the selected files after a pattern with a counter")]
+    #[strum(props(count = "files"))]
+    RenameSequence,
+    #[strum(message = "Change the case of the selected files' names or clean them up")]
+    #[strum(props(count = "files"))]
+    TransformNames,
+    #[strum(message = "Fix garbled names from old archives, in the selection or everywhere")]
+    FixEncoding,
+}
+
+
+
+#[derive(EnumString, EnumIter, EnumMessage, EnumProperty, Copy, Clone, Display, Debug)]
+pub enum VisualAction {
+    #[strum(message = "Stop extending the selection and keep it")]
+    Leave,
+    #[strum(message = "Undo what was selected since entering visual mode")]
+    Cancel,
+    #[strum(message = "Jump to the other end of the selected range")]
+    SwapEnds,
+}
+
+
+
+#[derive(EnumString, EnumIter, EnumMessage, EnumProperty, Copy, Clone, Display, Debug)]
+pub enum SearchAction {
+    #[strum(message = "Go to the next match")]
+    Next,
+    #[strum(message = "Go to the previous match")]
+    Prev,
+    #[strum(message = "Stop jumping between matches")]
+    Leave,
 }
 
 
 
-#[derive(EnumString, EnumIter, Copy, Clone, Display, Debug)]
+#[derive(EnumString, EnumIter, EnumMessage, EnumProperty, Copy, Clone, Display, Debug)]
 pub enum TabAction {
+    #[strum(message = "Open a new tab")]
     NewTab,
+    #[strum(message = "Open a copy of the current tab next to it")]
+    DuplicateTab,
+    #[strum(message = "Close the current tab")]
     CloseTab,
+    #[strum(message = "Go to the next tab")]
     NextTab,
+    #[strum(message = "Go to the previous tab")]
     PrevTab,
+    #[strum(message = "Move the current tab one to the left")]
+    MoveTabLeft,
+    #[strum(message = "Move the current tab one to the right")]
+    MoveTabRight,
+    #[strum(message = "Go to tab n")]
     GotoTab(usize),
+    #[strum(message = "Go back to the tab that was current before")]
+    LastTab,
+    #[strum(message = "Pick a tab from a list")]
+    PickTab,
+    #[strum(message = "Show two tabs side by side, or just one again")]
+    #[strum(props(config = "dual_pane"))]
+    ToggleDualPane,
+    #[strum(message = "Switch to the other pane")]
+    SwitchPane,
+}
+
+#[derive(EnumString, EnumIter, EnumMessage, EnumProperty, Copy, Clone, Display, Debug)]
+pub enum TabPickerAction {
+    #[strum(message = "Close the tab picker")]
+    Close,
+    #[strum(message = "Go to the selected tab")]
+    Pick
+}
+
+#[derive(EnumString, EnumIter, EnumMessage, EnumProperty, Copy, Clone, Display, Debug)]
+pub enum DevicePickerAction {
+    #[strum(message = "Close the device picker")]
+    Close,
+    #[strum(message = "Send to the selected device")]
+    Pick
+}
+
+#[derive(EnumString, EnumIter, EnumMessage, EnumProperty, Copy, Clone, Display, Debug)]
+pub enum ProjectPickerAction {
+    #[strum(message = "Close the project list")]
+    Close,
+    #[strum(message = "Open the selected project in a new tab")]
+    Pick
+}
+
+#[derive(EnumString, EnumIter, EnumMessage, EnumProperty, Copy, Clone, Display, Debug)]
+pub enum BranchPickerAction {
+    #[strum(message = "Close the list of branches")]
+    Close,
+    #[strum(message = "Check out the selected branch, or go to the worktree")]
+    Pick
+}
+
+#[derive(EnumString, EnumIter, EnumMessage, EnumProperty, Copy, Clone, Display, Debug)]
+pub enum PipelinePickerAction {
+    #[strum(message = "Close the list of pipelines")]
+    Close,
+    #[strum(message = "Run the selected pipeline")]
+    Pick
+}
+
+#[derive(EnumString, EnumIter, EnumMessage, EnumProperty, Copy, Clone, Display, Debug)]
+pub enum RecentFilesAction {
+    #[strum(message = "Close the list of recently modified files")]
+    Close,
+    #[strum(message = "Go to the selected file")]
+    Pick,
+    #[strum(message = "Go to the selected file in a new tab")]
+    PickInTab
+}
+
+
+
+#[derive(EnumString, EnumIter, EnumMessage, EnumProperty, Copy, Clone, Display, Debug)]
+pub enum CleanupAction {
+    #[strum(message = "Close the cleanup list")]
+    Close,
+    #[strum(message = "Go to the selected file or directory")]
+    Pick,
+    #[strum(message = "Go to the selected file or directory in a new tab")]
+    PickInTab,
+    #[strum(message = "Move the selected file or directory to the trash")]
+    Trash,
+    #[strum(message = "Delete the selected file or directory for good")]
+    DeleteForever,
+    #[strum(message = "Pack the selected file or directory into a .tar.gz and remove it")]
+    Archive
+}
+
+
+
+#[derive(EnumString, EnumIter, EnumMessage, EnumProperty, Copy, Clone, Display, Debug)]
+pub enum FindingsAction {
+    #[strum(message = "Close the list of broken links or empty directories")]
+    Close,
+    #[strum(message = "Go to the selected one")]
+    Pick,
+    #[strum(message = "Go to the selected one in a new tab")]
+    PickInTab,
+    #[strum(message = "Select or unselect the one under the cursor")]
+    Select,
+    #[strum(message = "Select all of them, or none if they all are")]
+    SelectAll,
+    #[strum(message = "Delete the selected ones, or the one under the cursor")]
+    DeleteForever,
+    #[strum(message = "Point the selected links somewhere else")]
+    Retarget
+}
+
+
+
+#[derive(EnumString, EnumIter, EnumMessage, EnumProperty, Copy, Clone, Display, Debug)]
+pub enum AuditAction {
+    #[strum(message = "Close the list of file operations")]
+    Close,
+    #[strum(message = "Go to where the file ended up")]
+    Pick,
+    #[strum(message = "Only show operations on matching paths")]
+    Filter
+}
+
+
+
+#[derive(EnumString, EnumIter, EnumMessage, EnumProperty, Copy, Clone, Display, Debug)]
+pub enum MouseAction {
+    #[strum(message = "Select the file under the pointer")]
+    Select,
+    #[strum(message = "Enter or open the file under the pointer")]
+    Open,
+    #[strum(message = "Scroll up n lines")]
+    ScrollUp(usize),
+    #[strum(message = "Scroll down n lines")]
+    ScrollDown(usize),
+    #[strum(message = "Show quick actions for the file under the pointer")]
+    ContextMenu
 }
 
 
 
-#[derive(EnumString, EnumIter, Copy, Clone, Display, Debug)]
+#[derive(EnumString, EnumIter, EnumMessage, EnumProperty, Copy, Clone, Display, Debug)]
+pub enum HelpAction {
+    #[strum(message = "Close the help")]
+    Close,
+    #[strum(message = "Show what the selected action does")]
+    ShowPage
+}
+
+
+
+#[derive(EnumString, EnumIter, EnumMessage, EnumProperty, Copy, Clone, Display, Debug)]
 pub enum MediaAction {
+    #[strum(message = "Pause or play")]
+    #[strum(props(config = "media_autostart"))]
     TogglePause,
+    #[strum(message = "Mute or unmute")]
+    #[strum(props(config = "media_mute"))]
     ToggleMute,
+    #[strum(message = "Seek forward")]
     SeekForward,
+    #[strum(message = "Seek backward")]
     SeekBackward,
 }
 
 
 
-#[derive(EnumString, EnumIter, Copy, Clone, Display, Debug)]
+#[derive(EnumString, EnumIter, EnumMessage, EnumProperty, Copy, Clone, Display, Debug)]
 pub enum BookmarkAction {
+    #[strum(message = "Close without going anywhere")]
+    Close,
+    #[strum(message = "Go to the directory hunter was last in")]
     GotoLastCwd,
+    #[strum(message = "Go to the bookmark on that key")]
     Goto(char),
+    #[strum(message = "Delete the bookmark on that key")]
     Delete(char)
 }
 
 
 
-#[derive(EnumString, EnumIter, Copy, Clone, Display, Debug)]
+#[derive(EnumString, EnumIter, EnumMessage, EnumProperty, Copy, Clone, Display, Debug)]
 pub enum ProcessAction {
+    #[strum(message = "Close the process list")]
     Close,
+    #[strum(message = "Remove the selected process from the list")]
     Remove,
+    #[strum(message = "Kill the selected process")]
     Kill,
+    #[strum(message = "Send a signal like STOP, CONT or HUP to the selected process")]
+    SendSignal,
+    #[strum(message = "Run the selected process again, once it's finished")]
+    Rerun,
+    #[strum(message = "Edit the command of the selected process and run it again")]
+    EditRerun,
+    #[strum(message = "Keep scrolling to new output")]
     FollowOutput,
+    #[strum(message = "Scroll the output down")]
     ScrollOutputDown,
+    #[strum(message = "Scroll the output up")]
     ScrollOutputUp,
+    #[strum(message = "Scroll the output down a page")]
     ScrollOutputPageDown,
+    #[strum(message = "Scroll the output up a page")]
     ScrollOutputPageUp,
+    #[strum(message = "Scroll to the end of the output")]
     ScrollOutputBottom,
-    ScrollOutputTop
+    #[strum(message = "Scroll to the start of the output")]
+    ScrollOutputTop,
+    #[strum(message = "Select lines of the output")]
+    SelectOutput,
+    #[strum(message = "Copy the output to the clipboard")]
+    CopyOutput,
+    #[strum(message = "Save all of the output to a file or the clipboard")]
+    SaveOutput,
+    #[strum(message = "Wrap long lines of output or not")]
+    ToggleWrap,
+    #[strum(message = "Show stdout and stderr, stdout only or stderr only")]
+    ToggleStreams,
+    #[strum(message = "Open the saved output of the selected process in the pager")]
+    #[strum(props(config = "process_logs"))]
+    ShowLog,
+    #[strum(message = "Search the output and highlight the matches")]
+    SearchOutput,
+    #[strum(message = "Go to the next line matching the search")]
+    SearchOutputNext,
+    #[strum(message = "Go to the previous line matching the search")]
+    SearchOutputPrev,
+    #[strum(message = "Move the focus between list and output")]
+    FocusNext
 }
 
 
 
-#[derive(EnumString, EnumIter, Copy, Clone, Display, Debug)]
+#[derive(EnumString, EnumIter, EnumMessage, EnumProperty, Copy, Clone, Display, Debug)]
 pub enum MiniBufferAction {
+    #[strum(message = "Insert a character")]
     InsertChar(char),
+    #[strum(message = "Insert the directory of tab n")]
     InsertTab(usize),
+    #[strum(message = "Cancel")]
     Cancel,
+    #[strum(message = "Accept the input")]
     Finish,
+    #[strum(message = "Complete the path or command")]
     Complete,
+    #[strum(message = "Delete the character under the cursor")]
     DeleteChar,
+    #[strum(message = "Delete the character before the cursor")]
     BackwardDeleteChar,
+    #[strum(message = "Move the cursor left")]
     CursorLeft,
+    #[strum(message = "Move the cursor right")]
     CursorRight,
+    #[strum(message = "Go back in the history")]
     HistoryUp,
+    #[strum(message = "Go forward in the history")]
     HistoryDown,
+    #[strum(message = "Clear the input")]
     ClearLine,
+    #[strum(message = "Delete the word before the cursor")]
     DeleteWord,
+    #[strum(message = "Move the cursor to the start")]
     CursorToStart,
+    #[strum(message = "Move the cursor to the end")]
     CursorToEnd
 }
 
-#[derive(EnumString, EnumIter, Copy, Clone, Display, Debug)]
+#[derive(EnumString, EnumIter, EnumMessage, EnumProperty, Copy, Clone, Display, Debug)]
 pub enum FoldAction {
+    #[strum(message = "Fold or unfold the entry")]
     ToggleFold
 }
 
-#[derive(EnumString, EnumIter, Copy, Clone, Display, Debug)]
+#[derive(EnumString, EnumIter, EnumMessage, EnumProperty, Copy, Clone, Display, Debug)]
 pub enum LogAction {
+    #[strum(message = "Close the log")]
     Close
 }
 
-#[derive(EnumString, EnumIter, Copy, Clone, Display, Debug)]
+#[derive(EnumString, EnumIter, EnumMessage, EnumProperty, Copy, Clone, Display, Debug)]
 pub enum QuickActionAction {
+    #[strum(message = "Close the quick actions")]
     Close,
+    #[strum(message = "Run the quick action on that key")]
     SelectOrRun(char)
 }
 
+#[derive(EnumString, EnumIter, EnumMessage, EnumProperty, Copy, Clone, Display, Debug)]
+pub enum TrashAction {
+    #[strum(message = "Close the trash")]
+    Close,
+    #[strum(message = "Put the selected files back where they were")]
+    Restore,
+    #[strum(message = "Delete the selected files for good")]
+    DeleteForever,
+    #[strum(message = "Empty the trash")]
+    Empty
+}
+
+#[derive(EnumString, EnumIter, EnumMessage, EnumProperty, Copy, Clone, Display, Debug)]
+pub enum PermissionAction {
+    #[strum(message = "Close without changing anything")]
+    Close,
+    #[strum(message = "Toggle the permission under the cursor")]
+    ToggleBit,
+    #[strum(message = "Type in an octal mode")]
+    SetMode,
+    #[strum(message = "Type in a new owner")]
+    SetOwner,
+    #[strum(message = "Apply to everything inside directories or not")]
+    ToggleRecursive,
+    #[strum(message = "Apply the changes")]
+    Apply
+}
+
+#[derive(EnumString, EnumIter, EnumMessage, EnumProperty, Copy, Clone, Display, Debug)]
+pub enum PagerAction {
+    #[strum(message = "Close the popup, cancelling if it asks something")]
+    Close,
+    #[strum(message = "Go ahead with what the popup asks")]
+    Accept,
+    #[strum(message = "Wrap long lines or not")]
+    ToggleWrap,
+    #[strum(message = "Scroll to the left")]
+    ScrollLeft,
+    #[strum(message = "Scroll to the right")]
+    ScrollRight,
+    #[strum(message = "Start or stop selecting lines")]
+    SelectLines,
+    #[strum(message = "Copy the selected lines to the clipboard")]
+    CopySelection
+}
+
 
 
 
@@ -674,6 +1450,7 @@ impl Default for Bindings<FileBrowserAction> {
                 GotoHome => Char('~'),
                 TurboCd => Char('/'),
                 SelectExternal => Alt(' '),
+                SelectExternalInTab => Alt('o'),
                 EnterDirExternal => Char('/'),
                 Quit => Char('q'),
                 QuitWithDir => Char('Q'),
@@ -684,9 +1461,42 @@ impl Default for Bindings<FileBrowserAction> {
                 ShowProcesses => Char('w'),
                 ShowLog => Char('l'),
                 ShowQuickActions => Char('a'),
+                Open => Char('\n'),
+                OpenWith => Alt('\r'),
                 RunSubshell => Char('z'),
                 ToggleColumns => Char('c'),
-                ExecCmd => Char('!')
+                ExecCmd => Char('!'),
+                ExecCmdLowPriority => Alt('!'),
+                Yank => Char('y'),
+                Cut => Char('x'),
+                Paste => Char('p'),
+                CopyToOtherTab => Char('Y'),
+                MoveToOtherTab => Char('M'),
+                ShowTrash => Char('T'),
+                TailPreview => Alt('f'),
+                EditPermissions => Char('P'),
+                FocusNext => Ctrl('o'),
+                TogglePauseTransfers => Alt('p'),
+                ShowQrPath => Alt('q'),
+                ShowQrContents => Alt('Q'),
+                ShowHex => Char('X'),
+                ShareSelection => Alt('h'),
+                SendToDevice => Alt('k'),
+                ShowProjects => Alt('j'),
+                ShowRecentFiles => Alt('w'),
+                ShowCleanup => Alt('c'),
+                FindBrokenLinks => Alt('l'),
+                FindEmptyDirs => Alt('z'),
+                ShowAudit => Alt('a'),
+                ShowBranches => Alt('g'),
+                NextConflict => Char('C'),
+                ShowPipelines => Alt('b'),
+                TrustLocal => Alt('x'),
+                CommandPalette => Char(':'),
+                ShowKeyHints => Alt('?'),
+                RecordMacro => Char('m'),
+                PlayMacro => Char('@'),
+                ShowHelp => Char('?')
             };
 
             filebrowser.insert(key, action.as_default());
@@ -716,6 +1526,7 @@ impl Default for Bindings<FileListAction> {
                 SearchPrev => Alt('S'),
                 Filter => Ctrl('f'),
                 Select => Char(' '),
+                VisualMode => Alt('v'),
                 InvertSelection => Char('v'),
                 ClearSelection => Char('V'),
                 FilterSelection => Alt('V'),
@@ -725,7 +1536,23 @@ impl Default for Bindings<FileListAction> {
                 CycleSort => Char('s'),
                 ToNextMtime => Char('K'),
                 ToPrevMtime => Char('k'),
-                ToggleDirsFirst => Char('d')
+                ToggleDirsFirst => Char('d'),
+                RefineSize => Alt('d'),
+                Trash => Delete,
+                DeleteForever => Char('D'),
+                Symlink => Char('L'),
+                RelativeSymlink => Alt('L'),
+                Hardlink => Char('H'),
+                NewFile => Char('n'),
+                NewDir => Char('N'),
+                NewFromTemplate => Alt('n'),
+                CopyContents => Alt('y'),
+                ExportListing => Alt('e'),
+                ExportTree => Alt('E'),
+                SetTimes => Alt('T'),
+                RenameSequence => Alt('r'),
+                TransformNames => Alt('R'),
+                FixEncoding => Alt('u')
             };
 
             filelist.insert(key, action.as_default());
@@ -741,6 +1568,62 @@ impl BindingSection for FileListAction {
     }
 }
 
+impl Default for Bindings<VisualAction> {
+    fn default() -> Self {
+        use Key::*;
+        use VisualAction::*;
+
+        let mut visual = Bindings::new();
+
+        for action in VisualAction::iter() {
+            let key = match action {
+                Leave => Esc,
+                Cancel => Ctrl('c'),
+                SwapEnds => Char('o')
+            };
+
+            visual.insert(key, action.as_default());
+        }
+
+        visual.insert(Alt('v'), Leave);
+
+        visual
+    }
+}
+
+impl BindingSection for VisualAction {
+    fn section() -> &'static str {
+        "visual"
+    }
+}
+
+impl Default for Bindings<SearchAction> {
+    fn default() -> Self {
+        use Key::*;
+        use SearchAction::*;
+
+        let mut search = Bindings::new();
+
+        for action in SearchAction::iter() {
+            let key = match action {
+                Next => Char('n'),
+                Prev => Char('N'),
+                Leave => Esc
+            };
+
+            search.insert(key, action.as_default());
+        }
+
+        search
+    }
+}
+
+impl BindingSection for SearchAction {
+    fn section() -> &'static str {
+        "search"
+    }
+}
+
 impl Default for Bindings<TabAction> {
     fn default() -> Self {
         use Key::*;
@@ -751,15 +1634,28 @@ impl Default for Bindings<TabAction> {
         for action in TabAction::iter() {
             let key = match action {
                 NewTab => Ctrl('t').into(),
+                DuplicateTab => Ctrl('d').into(),
                 NextTab => Char('\t').into(),
                 PrevTab => BackTab.into(),
+                MoveTabLeft => Alt('{').into(),
+                MoveTabRight => Alt('}').into(),
                 CloseTab => Ctrl('w').into(),
-                GotoTab(_) => AnyKey::AnyF
+                GotoTab(_) => AnyKey::AnyF,
+                LastTab => Alt('`').into(),
+                PickTab => Alt('t').into(),
+                ToggleDualPane => Ctrl('b').into(),
+                SwitchPane => Ctrl('e').into()
             };
 
             tab.insert(key, action.as_default());
         }
 
+        // M-1 to M-9 and M-0 for the tenth
+        for n in 0..10 {
+            let digit = std::char::from_digit((n + 1) % 10, 10).unwrap();
+            tab.insert(Alt(digit), GotoTab(n as usize));
+        }
+
         tab
     }
 }
@@ -788,6 +1684,352 @@ impl BindingSection for TabAction {
     }
 }
 
+impl Default for Bindings<TabPickerAction> {
+    fn default() -> Self {
+        use Key::*;
+        use TabPickerAction::*;
+
+        let mut tabpicker = Bindings::new();
+
+        for action in TabPickerAction::iter() {
+            let key = match action {
+                Close => Esc,
+                Pick => Char('\n')
+            };
+
+            tabpicker.insert(key, action.as_default());
+        }
+
+        tabpicker.insert(Alt('t'), Close);
+        tabpicker.insert(Ctrl('c'), Close);
+
+        tabpicker
+    }
+}
+
+impl BindingSection for TabPickerAction {
+    fn section() -> &'static str {
+        "tabpicker"
+    }
+}
+
+impl Default for Bindings<DevicePickerAction> {
+    fn default() -> Self {
+        use Key::*;
+        use DevicePickerAction::*;
+
+        let mut devicepicker = Bindings::new();
+
+        for action in DevicePickerAction::iter() {
+            let key = match action {
+                Close => Esc,
+                Pick => Char('\n')
+            };
+
+            devicepicker.insert(key, action.as_default());
+        }
+
+        devicepicker.insert(Ctrl('c'), Close);
+
+        devicepicker
+    }
+}
+
+impl BindingSection for DevicePickerAction {
+    fn section() -> &'static str {
+        "devicepicker"
+    }
+}
+
+impl Default for Bindings<ProjectPickerAction> {
+    fn default() -> Self {
+        use Key::*;
+        use ProjectPickerAction::*;
+
+        let mut projectpicker = Bindings::new();
+
+        for action in ProjectPickerAction::iter() {
+            let key = match action {
+                Close => Esc,
+                Pick => Char('\n')
+            };
+
+            projectpicker.insert(key, action.as_default());
+        }
+
+        projectpicker.insert(Ctrl('c'), Close);
+
+        projectpicker
+    }
+}
+
+impl BindingSection for ProjectPickerAction {
+    fn section() -> &'static str {
+        "projectpicker"
+    }
+}
+
+impl Default for Bindings<BranchPickerAction> {
+    fn default() -> Self {
+        use Key::*;
+        use BranchPickerAction::*;
+
+        let mut branchpicker = Bindings::new();
+
+        for action in BranchPickerAction::iter() {
+            let key = match action {
+                Close => Esc,
+                Pick => Char('\n')
+            };
+
+            branchpicker.insert(key, action.as_default());
+        }
+
+        branchpicker.insert(Ctrl('c'), Close);
+
+        branchpicker
+    }
+}
+
+impl BindingSection for BranchPickerAction {
+    fn section() -> &'static str {
+        "branchpicker"
+    }
+}
+
+impl Default for Bindings<PipelinePickerAction> {
+    fn default() -> Self {
+        use Key::*;
+        use PipelinePickerAction::*;
+
+        let mut pipelinepicker = Bindings::new();
+
+        for action in PipelinePickerAction::iter() {
+            let key = match action {
+                Close => Esc,
+                Pick => Char('\n')
+            };
+
+            pipelinepicker.insert(key, action.as_default());
+        }
+
+        pipelinepicker.insert(Ctrl('c'), Close);
+
+        pipelinepicker
+    }
+}
+
+impl BindingSection for PipelinePickerAction {
+    fn section() -> &'static str {
+        "pipelinepicker"
+    }
+}
+
+impl Default for Bindings<RecentFilesAction> {
+    fn default() -> Self {
+        use Key::*;
+        use RecentFilesAction::*;
+
+        let mut recentfiles = Bindings::new();
+
+        for action in RecentFilesAction::iter() {
+            let key = match action {
+                Close => Esc,
+                Pick => Char('\n'),
+                PickInTab => Char('t')
+            };
+
+            recentfiles.insert(key, action.as_default());
+        }
+
+        recentfiles.insert(Ctrl('c'), Close);
+
+        recentfiles
+    }
+}
+
+impl BindingSection for RecentFilesAction {
+    fn section() -> &'static str {
+        "recentfiles"
+    }
+}
+
+impl Default for Bindings<CleanupAction> {
+    fn default() -> Self {
+        use Key::*;
+        use CleanupAction::*;
+
+        let mut cleanup = Bindings::new();
+
+        for action in CleanupAction::iter() {
+            let key = match action {
+                Close => Esc,
+                Pick => Char('\n'),
+                PickInTab => Char('t'),
+                Trash => Delete,
+                DeleteForever => Char('D'),
+                Archive => Char('A')
+            };
+
+            cleanup.insert(key, action.as_default());
+        }
+
+        cleanup.insert(Ctrl('c'), Close);
+
+        cleanup
+    }
+}
+
+impl BindingSection for CleanupAction {
+    fn section() -> &'static str {
+        "cleanup"
+    }
+}
+
+impl Default for Bindings<FindingsAction> {
+    fn default() -> Self {
+        use Key::*;
+        use FindingsAction::*;
+
+        let mut findings = Bindings::new();
+
+        for action in FindingsAction::iter() {
+            let key = match action {
+                Close => Esc,
+                Pick => Char('\n'),
+                PickInTab => Char('t'),
+                Select => Char(' '),
+                SelectAll => Char('a'),
+                DeleteForever => Char('D'),
+                Retarget => Char('r')
+            };
+
+            findings.insert(key, action.as_default());
+        }
+
+        findings.insert(Ctrl('c'), Close);
+        findings.insert(Delete, DeleteForever);
+
+        findings
+    }
+}
+
+impl BindingSection for FindingsAction {
+    fn section() -> &'static str {
+        "findings"
+    }
+}
+
+impl Default for Bindings<AuditAction> {
+    fn default() -> Self {
+        use Key::*;
+        use AuditAction::*;
+
+        let mut audit = Bindings::new();
+
+        for action in AuditAction::iter() {
+            let key = match action {
+                Close => Esc,
+                Pick => Char('\n'),
+                Filter => Ctrl('f')
+            };
+
+            audit.insert(key, action.as_default());
+        }
+
+        audit.insert(Ctrl('c'), Close);
+        audit.insert(Char('/'), Filter);
+
+        audit
+    }
+}
+
+impl BindingSection for AuditAction {
+    fn section() -> &'static str {
+        "audit"
+    }
+}
+
+impl Default for Bindings<MouseAction> {
+    fn default() -> Self {
+        use MouseAction::*;
+
+        let mut mouse = Bindings::new();
+
+        for action in MouseAction::iter() {
+            let key = match action {
+                Select => MouseKey::Left,
+                Open => MouseKey::DoubleLeft,
+                ScrollUp(_) => MouseKey::WheelUp,
+                ScrollDown(_) => MouseKey::WheelDown,
+                ContextMenu => MouseKey::Right
+            };
+
+            mouse.insert(key, action.as_default());
+        }
+
+        mouse
+    }
+}
+
+impl BindingSection for MouseAction {
+    fn section() -> &'static str {
+        "mouse"
+    }
+
+    fn insert_config_param(self, param: CharOrNum) -> Self {
+        use MouseAction::*;
+
+        let n = param.num_or(3);
+
+        match self {
+            ScrollUp(_) => ScrollUp(n),
+            ScrollDown(_) => ScrollDown(n),
+            _ => self
+        }
+    }
+
+    fn as_default(self) -> Self {
+        use MouseAction::*;
+
+        match self {
+            ScrollUp(_) => ScrollUp(3),
+            ScrollDown(_) => ScrollDown(3),
+            _ => self
+        }
+    }
+}
+
+impl Default for Bindings<HelpAction> {
+    fn default() -> Self {
+        use Key::*;
+        use HelpAction::*;
+
+        let mut help = Bindings::new();
+
+        for action in HelpAction::iter() {
+            let key = match action {
+                Close => Esc,
+                ShowPage => Char('\n')
+            };
+
+            help.insert(key, action.as_default());
+        }
+
+        help.insert(F(1), ShowPage);
+        help.insert(Char('?'), Close);
+        help.insert(Ctrl('c'), Close);
+
+        help
+    }
+}
+
+impl BindingSection for HelpAction {
+    fn section() -> &'static str {
+        "help"
+    }
+}
+
 impl Default for Bindings<MediaAction> {
     fn default() -> Self {
         use Key::*;
@@ -825,6 +2067,7 @@ impl Default for Bindings<BookmarkAction> {
 
         for action in BookmarkAction::iter() {
             let key = match action {
+                Close => Esc.into(),
                 GotoLastCwd => Char('`').into(),
                 Goto(_) => AnyKey::AnyChar,
                 BookmarkAction::Delete(_) => AnyKey::AnyAlt
@@ -833,7 +2076,7 @@ impl Default for Bindings<BookmarkAction> {
             bookmark.insert(key, action.as_default());
         }
 
-
+        bookmark.insert(Ctrl('c'), Close);
 
         bookmark
     }
@@ -862,6 +2105,7 @@ impl BindingSection for BookmarkAction {
         match (self, key) {
             (Goto(_), Key::Char(ch)) => Goto(ch),
             (Delete(_), Key::Char(ch)) => Delete(ch),
+            (Delete(_), Key::Alt(ch)) => Delete(ch),
             _ => self
         }
     }
@@ -880,13 +2124,26 @@ impl Default for Bindings<ProcessAction> {
                 Close => Char('w'),
                 Remove => Char('d'),
                 Kill => Char('k'),
+                SendSignal => Char('s'),
+                Rerun => Char('r'),
+                EditRerun => Char('R'),
                 FollowOutput => Char('f'),
                 ScrollOutputDown => Ctrl('n'),
                 ScrollOutputUp => Ctrl('p'),
                 ScrollOutputPageDown => Ctrl('v'),
                 ScrollOutputPageUp => Ctrl('V'),
                 ScrollOutputBottom => Char('>'),
-                ScrollOutputTop => Ctrl('<')
+                ScrollOutputTop => Ctrl('<'),
+                SelectOutput => Char('v'),
+                CopyOutput => Char('y'),
+                SaveOutput => Char('S'),
+                ToggleWrap => Char('W'),
+                ToggleStreams => Char('e'),
+                ShowLog => Char('o'),
+                SearchOutput => Char('/'),
+                SearchOutputNext => Char('n'),
+                SearchOutputPrev => Char('N'),
+                FocusNext => Ctrl('o')
             };
 
             process.insert(key, action.as_default());
@@ -1081,6 +2338,103 @@ impl BindingSection for QuickActionAction {
     }
 }
 
+impl Default for Bindings<TrashAction> {
+    fn default() -> Self {
+        use Key::*;
+        use TrashAction::*;
+
+        let mut trash = Bindings::new();
+
+        for action in TrashAction::iter() {
+            let key = match action {
+                Close => Char('T'),
+                Restore => Char('r'),
+                DeleteForever => Char('D'),
+                Empty => Alt('D')
+            };
+
+            trash.insert(key, action.as_default());
+        }
+
+        trash.insert(Esc, Close);
+
+        trash
+    }
+}
+
+impl BindingSection for TrashAction {
+    fn section() -> &'static str {
+        "trash"
+    }
+}
+
+impl Default for Bindings<PermissionAction> {
+    fn default() -> Self {
+        use Key::*;
+        use PermissionAction::*;
+
+        let mut permissions = Bindings::new();
+
+        for action in PermissionAction::iter() {
+            let key = match action {
+                Close => Esc,
+                ToggleBit => Char(' '),
+                SetMode => Char('o'),
+                SetOwner => Char('O'),
+                ToggleRecursive => Char('R'),
+                Apply => Char('\n')
+            };
+
+            permissions.insert(key, action.as_default());
+        }
+
+        permissions.insert(Ctrl('c'), Close);
+
+        permissions
+    }
+}
+
+impl BindingSection for PermissionAction {
+    fn section() -> &'static str {
+        "permissions"
+    }
+}
+
+impl Default for Bindings<PagerAction> {
+    fn default() -> Self {
+        use Key::*;
+        use PagerAction::*;
+
+        let mut pager = Bindings::new();
+
+        for action in PagerAction::iter() {
+            let key = match action {
+                Close => Esc,
+                Accept => Char('\n'),
+                ToggleWrap => Char('w'),
+                ScrollLeft => Char('<'),
+                ScrollRight => Char('>'),
+                SelectLines => Char('v'),
+                CopySelection => Char('c')
+            };
+
+            pager.insert(key, action.as_default());
+        }
+
+        pager.insert(Char('q'), Close);
+        pager.insert(Ctrl('c'), Close);
+        pager.insert(Char('y'), Accept);
+
+        pager
+    }
+}
+
+impl BindingSection for PagerAction {
+    fn section() -> &'static str {
+        "pager"
+    }
+}
+
 
 #[test]
 fn test_keyparse() {
@@ -0,0 +1,47 @@
+use termion::event::Key;
+use lazy_static;
+
+use std::collections::HashMap;
+
+
+// The terminal only ever sees characters, not which key was pressed, so
+// switching the OS layout breaks every binding. These line up the keys of
+// other layouts with the QWERTY key in the same spot. None of the
+// characters overlap with latin ones, so they can all live in one table.
+const LAYOUTS: [(&str, &str); 6] = [
+    // russian
+    ("йцукенгшщзхъфывапролджэячсмитьбюё",
+     "qwertyuiop[]asdfghjkl;'zxcvbnm,.`"),
+    ("ЙЦУКЕНГШЩЗХЪФЫВАПРОЛДЖЭЯЧСМИТЬБЮЁ",
+     "QWERTYUIOP{}ASDFGHJKL:\"ZXCVBNM<>~"),
+    // ukrainian and belarusian, on top of russian
+    ("іїєў",
+     "s]'o"),
+    ("ІЇЄЎ",
+     "S}\"O"),
+    // greek, Σ is on both w and s, so it's left to s
+    ("ςερτυθιοπασδφγηξκλζχψωβνμ",
+     "wertyuiopasdfghjklzxcvbnm"),
+    ("ΕΡΤΥΘΙΟΠΑΣΔΦΓΗΞΚΛΖΧΨΩΒΝΜ",
+     "ERTYUIOPASDFGHJKLZXCVBNM"),
+];
+
+lazy_static! {
+    static ref TRANSLATION: HashMap<char, char> = {
+        LAYOUTS.iter()
+            .flat_map(|(from, to)| from.chars().zip(to.chars()))
+            .collect()
+    };
+}
+
+// The key at the same spot on a QWERTY keyboard, if it's any different
+pub fn translate(key: Key) -> Option<Key> {
+    let translate = |c: char| TRANSLATION.get(&c).cloned();
+
+    match key {
+        Key::Char(c) => translate(c).map(Key::Char),
+        Key::Alt(c) => translate(c).map(Key::Alt),
+        Key::Ctrl(c) => translate(c.to_lowercase().next()?).map(Key::Ctrl),
+        _ => None
+    }
+}
@@ -0,0 +1,273 @@
+use unicode_width::UnicodeWidthChar;
+
+use std::path::{Component, Path, PathBuf};
+
+
+// What the minibuffer does to its input, without the widget around it.
+// Everything takes the text and a cursor, a byte index, and gives back new
+// ones. Any cursor is fine, even one in the middle of a char or past the
+// end, so none of this can panic whatever gets typed or pasted. Only std
+// and unicode-width are used, so these can be fed random input on their own.
+
+// The closest char boundary at or before position
+pub fn clamp(input: &str, position: usize) -> usize {
+    let mut position = position.min(input.len());
+    while !input.is_char_boundary(position) {
+        position -= 1;
+    }
+    position
+}
+
+// combining marks belong to the char before them
+pub fn prev_boundary(input: &str, position: usize) -> usize {
+    let position = clamp(input, position);
+    let mut boundary = position;
+
+    for (i, ch) in input[..position].char_indices().rev() {
+        boundary = i;
+        if ch.width() != Some(0) { break; }
+    }
+
+    boundary
+}
+
+pub fn next_boundary(input: &str, position: usize) -> usize {
+    let position = clamp(input, position);
+    let mut chars = input[position..].char_indices();
+
+    let mut boundary = match chars.next() {
+        Some((_, ch)) => position + ch.len_utf8(),
+        None => return position
+    };
+
+    for (i, ch) in chars {
+        if ch.width() != Some(0) { break; }
+        boundary = position + i + ch.len_utf8();
+    }
+
+    boundary
+}
+
+pub fn insert(input: &str, position: usize, text: &str) -> (String, usize) {
+    let position = clamp(input, position);
+    let mut input = input.to_string();
+    input.insert_str(position, text);
+    (input, position + text.len())
+}
+
+pub fn delete_char(input: &str, position: usize) -> (String, usize) {
+    let position = clamp(input, position);
+    let next = next_boundary(input, position);
+    let mut input = input.to_string();
+    input.replace_range(position..next, "");
+    (input, position)
+}
+
+pub fn backward_delete_char(input: &str, position: usize) -> (String, usize) {
+    let position = clamp(input, position);
+    let prev = prev_boundary(input, position);
+    let mut input = input.to_string();
+    input.replace_range(prev..position, "");
+    (input, prev)
+}
+
+// Trailing spaces first, then back to the last / or space
+pub fn delete_word(input: &str, position: usize) -> (String, usize) {
+    let position = clamp(input, position);
+    let (before_cursor, after_cursor) = input.split_at(position);
+    let trimmed = before_cursor.trim_end();
+
+    let kept = if trimmed.len() != before_cursor.len() {
+        trimmed.to_string()
+    } else if trimmed.ends_with('/') {
+        trimmed[..trimmed.len() - 1].to_string()
+    } else {
+        match trimmed.rfind(|c| c == '/' || c == ' ') {
+            // both are one byte, so this stays on a boundary
+            Some(boundary) => trimmed[..=boundary].to_string(),
+            None => String::new()
+        }
+    };
+
+    let position = kept.len();
+    (kept + after_cursor, position)
+}
+
+// The word the cursor is at the end of, what gets completed
+pub fn last_word(input: &str, position: usize) -> &str {
+    let before_cursor = &input[..clamp(input, position)];
+    before_cursor.rsplit(' ').next().unwrap_or("")
+}
+
+// Swaps the end of the word before the cursor for something else, like
+// the next completion. Doesn't touch anything if it's not there.
+pub fn replace_before(input: &str,
+                      position: usize,
+                      old: &str,
+                      new: &str) -> (String, usize) {
+    let position = clamp(input, position);
+    let (before_cursor, after_cursor) = input.split_at(position);
+
+    if !before_cursor.ends_with(old) {
+        return (input.to_string(), position);
+    }
+
+    let start = before_cursor.len() - old.len();
+    let replaced = format!("{}{}", &before_cursor[..start], new);
+    let position = replaced.len();
+    (replaced + after_cursor, position)
+}
+
+// What was typed into the cd prompt and the like. ~ is the home directory,
+// relative paths start at cwd and . and .. are resolved without looking at
+// the filesystem, so symlinks are left alone.
+pub fn parse_path(input: &str, cwd: &Path, home: Option<&Path>) -> PathBuf {
+    let input = input.trim_matches(|c: char| c.is_whitespace() || c == '\0');
+
+    let path = match (input, home) {
+        ("~", Some(home)) => home.to_path_buf(),
+        (_, Some(home)) if input.starts_with("~/") => home.join(&input[2..]),
+        ("", _) => cwd.to_path_buf(),
+        _ => cwd.join(input)
+    };
+
+    let mut normalized = PathBuf::from("/");
+    for component in path.components() {
+        match component {
+            Component::ParentDir => { normalized.pop(); }
+            Component::Normal(part) => normalized.push(part),
+            Component::CurDir | Component::RootDir | Component::Prefix(_) => {}
+        }
+    }
+
+    normalized
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    // e + combining acute, a wide char, a 4 byte emoji and plain ascii
+    const MIXED: &str = "e\u{301}日🦀x";
+
+    #[test]
+    fn clamp_inside_a_char() {
+        assert_eq!(clamp("é", 1), 0);
+        assert_eq!(clamp("日", 2), 0);
+        assert_eq!(clamp("🦀", 3), 0);
+        assert_eq!(clamp("ab", 100), 2);
+        assert_eq!(clamp("", 5), 0);
+    }
+
+    #[test]
+    fn boundaries_keep_combining_marks() {
+        assert_eq!(next_boundary(MIXED, 0), 3);
+        assert_eq!(prev_boundary(MIXED, 3), 0);
+        // from the middle of the combining mark
+        assert_eq!(prev_boundary(MIXED, 2), 0);
+    }
+
+    #[test]
+    fn boundaries_over_wide_chars() {
+        assert_eq!(next_boundary(MIXED, 3), 6);
+        assert_eq!(next_boundary(MIXED, 6), 10);
+        assert_eq!(prev_boundary(MIXED, 10), 6);
+        assert_eq!(prev_boundary(MIXED, 6), 3);
+    }
+
+    #[test]
+    fn boundaries_at_the_ends() {
+        assert_eq!(prev_boundary(MIXED, 0), 0);
+        assert_eq!(next_boundary(MIXED, MIXED.len()), MIXED.len());
+        assert_eq!(next_boundary(MIXED, MIXED.len() + 3), MIXED.len());
+        assert_eq!(prev_boundary("", 0), 0);
+        assert_eq!(next_boundary("", 0), 0);
+    }
+
+    #[test]
+    fn insert_anywhere() {
+        assert_eq!(insert("日本", 1, "x"), ("x日本".to_string(), 1));
+        assert_eq!(insert("日本", 3, "🦀"), ("日🦀本".to_string(), 7));
+        assert_eq!(insert("ab", 10, "c"), ("abc".to_string(), 3));
+    }
+
+    #[test]
+    fn delete_whole_chars() {
+        assert_eq!(delete_char(MIXED, 0), ("日🦀x".to_string(), 0));
+        assert_eq!(delete_char(MIXED, 4), ("e\u{301}🦀x".to_string(), 3));
+        assert_eq!(delete_char("ab", 2), ("ab".to_string(), 2));
+        assert_eq!(backward_delete_char(MIXED, 3), ("日🦀x".to_string(), 0));
+        assert_eq!(backward_delete_char(MIXED, 10), ("e\u{301}日x".to_string(), 6));
+        assert_eq!(backward_delete_char("ab", 0), ("ab".to_string(), 0));
+    }
+
+    #[test]
+    fn delete_word_back_to_slash_or_space() {
+        assert_eq!(delete_word("~/straße/日本", 100), ("~/straße/".to_string(), 10));
+        // a trailing slash goes on its own first
+        assert_eq!(delete_word("~/straße/", 10), ("~/straße".to_string(), 9));
+        assert_eq!(delete_word("~/straße", 9), ("~/".to_string(), 2));
+        assert_eq!(delete_word("mv 日本  ", 11), ("mv 日本".to_string(), 9));
+        assert_eq!(delete_word("日本", 6), (String::new(), 0));
+        assert_eq!(delete_word("", 0), (String::new(), 0));
+    }
+
+    #[test]
+    fn last_word_and_replace() {
+        assert_eq!(last_word("cd 日本", 100), "日本");
+        assert_eq!(last_word("cd 日本", 7), "日");
+        assert_eq!(replace_before("cd 日", 6, "日", "日本/"), ("cd 日本/".to_string(), 10));
+        assert_eq!(replace_before("cd x", 4, "日", "y"), ("cd x".to_string(), 4));
+    }
+
+    #[test]
+    fn parse_path_stays_absolute() {
+        let cwd = Path::new("/home/user");
+        let home = Some(Path::new("/home/user"));
+
+        assert_eq!(parse_path("../../../..", cwd, home), PathBuf::from("/"));
+        assert_eq!(parse_path("~/日本/./x", cwd, home), PathBuf::from("/home/user/日本/x"));
+        assert_eq!(parse_path(" \0", cwd, home), PathBuf::from("/home/user"));
+        assert_eq!(parse_path("~", cwd, None), PathBuf::from("/home/user/~"));
+    }
+
+    proptest! {
+        // whatever is typed and wherever the cursor is, nothing panics and
+        // the cursor ends up on a char boundary inside the text
+        #[test]
+        fn editing_never_panics(input in "\\PC*", text in "\\PC{0,4}", position in 0usize..64) {
+            let on_boundary = |(output, cursor): (String, usize)| {
+                prop_assert!(cursor <= output.len());
+                prop_assert!(output.is_char_boundary(cursor));
+                Ok(())
+            };
+
+            on_boundary((input.clone(), clamp(&input, position)))?;
+            on_boundary((input.clone(), prev_boundary(&input, position)))?;
+            on_boundary((input.clone(), next_boundary(&input, position)))?;
+            on_boundary(insert(&input, position, &text))?;
+            on_boundary(delete_char(&input, position))?;
+            on_boundary(backward_delete_char(&input, position))?;
+            on_boundary(delete_word(&input, position))?;
+            on_boundary(replace_before(&input, position, &text, "x"))?;
+            last_word(&input, position);
+            parse_path(&input, Path::new("/"), None);
+        }
+
+        #[test]
+        fn deleting_backwards_undoes_typing(input in "\\PC*", ch in "\\PC", position in 0usize..64) {
+            let position = clamp(&input, position);
+            let (typed, cursor) = insert(&input, position, &ch);
+            let (deleted, cursor) = backward_delete_char(&typed, cursor);
+
+            // a combining mark goes together with what's before it
+            if ch.chars().all(|c| c.width() != Some(0)) &&
+                input[position..].chars().next().map(|c| c.width() != Some(0)).unwrap_or(true) {
+                prop_assert_eq!(deleted, input);
+                prop_assert_eq!(cursor, position);
+            }
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use std::fmt::Debug;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 
 use termion::event::Key;
 use unicode_width::UnicodeWidthStr;
@@ -7,12 +8,15 @@ use rayon::prelude::*;
 
 use async_value::{Stale, StopIter};
 
-use crate::files::{File, Files};
+use crate::files::{File, Files, Kind, pretty_size};
+use crate::coordinates::Coordinates;
+use crate::dirsize;
 use crate::fail::{HResult, HError, ErrorLog};
 use crate::term;
-use crate::widget::{Widget, WidgetCore};
+use crate::widget::{Widget, WidgetCore, Events};
 use crate::dirty::Dirtyable;
 use crate::fscache::FsCache;
+use crate::fileops::LinkKind;
 
 
 pub trait Listable {
@@ -26,7 +30,7 @@ pub trait Listable {
     fn on_key(&mut self, _key: Key) -> HResult<()> { Ok(()) }
 }
 
-use crate::keybind::{Acting, Bindings, FileListAction, Movement};
+use crate::keybind::{Acting, Bindings, FileListAction, Movement, Mode, SearchAction, VisualAction};
 
 
 impl Acting for ListView<Files> {
@@ -53,6 +57,7 @@ impl Acting for ListView<Files> {
 
         if pos != self.get_selection() {
             self.update_selected_file();
+            self.select_visual_range();
         }
 
         Ok(())
@@ -69,6 +74,7 @@ impl Acting for ListView<Files> {
             SearchPrev => self.search_prev()?,
             Filter => self.filter()?,
             Select => self.multi_select_file(),
+            VisualMode => self.enter_visual(),
             InvertSelection => self.invert_selection(),
             ClearSelection => self.clear_selections(),
             FilterSelection => self.toggle_filter_selected(),
@@ -79,6 +85,22 @@ impl Acting for ListView<Files> {
             ToNextMtime => self.select_next_mtime(),
             ToPrevMtime => self.select_prev_mtime(),
             ToggleDirsFirst => self.toggle_dirs_first(),
+            RefineSize => self.refine_size()?,
+            Trash => self.trash()?,
+            DeleteForever => self.delete_forever()?,
+            Symlink => self.link(LinkKind::Symlink)?,
+            RelativeSymlink => self.link(LinkKind::RelativeSymlink)?,
+            Hardlink => self.link(LinkKind::Hardlink)?,
+            NewFile => self.create_new(false)?,
+            NewDir => self.create_new(true)?,
+            NewFromTemplate => self.new_from_template()?,
+            CopyContents => self.copy_contents()?,
+            ExportListing => self.export_listing()?,
+            ExportTree => self.export_tree()?,
+            SetTimes => self.set_times()?,
+            RenameSequence => self.rename_sequence()?,
+            TransformNames => self.transform_names()?,
+            FixEncoding => self.fix_encoding()?,
         }
 
         if pos != self.get_selection() {
@@ -140,6 +162,13 @@ impl Listable for ListView<Files> {
     }
 }
 
+// Where visual mode started and what was selected before, that stays
+#[derive(Debug, PartialEq)]
+struct Visual {
+    anchor: usize,
+    kept: Vec<PathBuf>,
+}
+
 #[derive(Debug, PartialEq)]
 pub struct ListView<T>
 where
@@ -154,6 +183,27 @@ where
     pub core: WidgetCore,
     seeking: bool,
     searching: Option<String>,
+    mode: Mode,
+    visual: Option<Visual>,
+    // a line above the list, takes away one line from it. Filled in by
+    // another thread when it takes a while to figure out.
+    header: Option<Header>,
+}
+
+lazy_static! {
+    // what DeleteForever is counting or has counted, files, bytes and
+    // whether there were directories
+    static ref DELETE_COUNT: Mutex<Option<(Vec<PathBuf>, Option<(u64, u64, bool)>)>> =
+        Mutex::new(None);
+}
+
+#[derive(Debug, Clone)]
+struct Header(Arc<Mutex<String>>);
+
+impl PartialEq for Header {
+    fn eq(&self, other: &Header) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
 }
 
 impl<T> ListView<T>
@@ -171,7 +221,10 @@ where
             // buffer: Vec::new(),
             core: core.clone(),
             seeking: false,
-            searching: None
+            searching: None,
+            mode: Mode::Normal,
+            visual: None,
+            header: None
         };
         view.on_new().log();
         view
@@ -189,9 +242,23 @@ where
         self.selection -= 1;
         self.seeking = false;
     }
+    // Where the entries go, the header is above them. The coordinates
+    // themselves stay what they were set to, so setting them again doesn't
+    // move anything.
+    fn list_coordinates(&self) -> Coordinates {
+        let mut coordinates = self.core.coordinates.clone();
+
+        if self.header.is_some() {
+            coordinates.set_ypos(coordinates.ypos() + 1);
+            coordinates.set_ysize(coordinates.ysize().saturating_sub(1));
+        }
+
+        coordinates
+    }
+
     pub fn move_down(&mut self) {
         let lines = self.len();
-        let y_size = self.get_coordinates().unwrap().ysize() as usize;
+        let y_size = self.list_coordinates().ysize() as usize;
 
         if lines == 0 || self.selection == lines - 1 {
             return;
@@ -215,7 +282,7 @@ where
     }
 
     pub fn page_up(&mut self) {
-        let ysize = self.get_coordinates().unwrap().ysize_u();
+        let ysize = self.list_coordinates().ysize_u();
 
         for _ in 0..ysize {
             self.move_up();
@@ -223,7 +290,7 @@ where
     }
 
     pub fn page_down(&mut self) {
-        let ysize = self.get_coordinates().unwrap().ysize_u();
+        let ysize = self.list_coordinates().ysize_u();
 
         for _ in 0..ysize {
             self.move_down();
@@ -235,7 +302,7 @@ where
     }
 
     pub fn set_selection(&mut self, position: usize) {
-        let ysize = self.get_coordinates().unwrap().ysize() as usize;
+        let ysize = self.list_coordinates().ysize() as usize;
         let mut offset = 0;
 
         while position >= ysize + offset {
@@ -262,7 +329,8 @@ pub struct FileListBuilder {
     stale: Option<Stale>,
     meta_upto: usize,
     meta_all: bool,
-    prerender: bool
+    prerender: bool,
+    stats_header: bool
 }
 
 impl FileListBuilder {
@@ -275,7 +343,8 @@ impl FileListBuilder {
             stale: None,
             meta_upto: 0,
             meta_all: false,
-            prerender: false
+            prerender: false,
+            stats_header: false
         }
     }
 
@@ -309,6 +378,11 @@ impl FileListBuilder {
         self
     }
 
+    pub fn with_stats_header(mut self) -> Self {
+        self.stats_header = true;
+        self
+    }
+
     pub fn build(self) -> HResult<ListView<Files>> {
         let c = &self.cache;
         let s = self.stale.clone();
@@ -324,6 +398,15 @@ impl FileListBuilder {
 
         let mut view = ListView::new(&self.core, files);
 
+        // show_hidden from the config is only for tabs that didn't say
+        if let Some(c) = c {
+            FsCache::apply_settingss(c, &mut view.content).log();
+        }
+
+        if self.stats_header {
+            view.header = Some(dir_stats(&view.content, &self.core));
+        }
+
         let selected_file = match self.selected_file {
             Some(f) => Some(f),
             None => {
@@ -381,6 +464,59 @@ impl FileListBuilder {
     }
 }
 
+// Entries, size, permissions and mtime of the directory itself. The size
+// is only what's known without walking it: the cached size from an earlier
+// scan, or else just the files right in it. Those get looked at in the
+// background, until then there's a … where the size goes.
+fn dir_stats(files: &Files, core: &WidgetCore) -> Header {
+    let mut dir = files.directory.clone();
+    if dir.meta.is_none() {
+        dir.meta_sync().log();
+    }
+
+    let count = files.iter_files().count();
+    let format = move |size: &str| {
+        format!("{}{} entries, {} {} {}{}",
+                term::color_cyan(),
+                count,
+                size,
+                dir.pretty_print_permissions().unwrap_or_default(),
+                term::normal_color(),
+                dir.pretty_mtime().unwrap_or_default())
+    };
+
+    if let Some(size) = dirsize::cached_size(&files.directory.path) {
+        let size = format!("{}{}", size.prefix(), pretty_size(size.bytes()));
+        return Header(Arc::new(Mutex::new(format(&size))));
+    }
+
+    let header = Arc::new(Mutex::new(format("…")));
+    let paths = files.files
+        .iter()
+        .map(|file| file.path.clone())
+        .collect::<Vec<_>>();
+    let sender = core.get_sender();
+    let filled_in = header.clone();
+
+    rayon::spawn(move || {
+        let (bytes, subdirs) = paths
+            .iter()
+            .filter_map(|path| std::fs::symlink_metadata(path).ok())
+            .fold((0, false), |(bytes, subdirs), meta| match meta.is_dir() {
+                true => (bytes, true),
+                false => (bytes + meta.len(), subdirs)
+            });
+        let prefix = if subdirs { "≥" } else { "" };
+
+        if let Ok(mut header) = filled_in.lock() {
+            *header = format(&format!("{}{}", prefix, pretty_size(bytes)));
+        }
+        sender.send(Events::WidgetReady).ok();
+    });
+
+    Header(header)
+}
+
 impl ListView<Files>
 {
     pub fn builder(core: WidgetCore, source: FileSource) -> FileListBuilder {
@@ -421,6 +557,46 @@ impl ListView<Files>
         }
     }
 
+    // x and y are terminal coordinates, like in mouse events
+    fn row_at(&self, x: u16, y: u16) -> Option<usize> {
+        let coords = self.list_coordinates();
+        let (xpos, ypos) = coords.u16position();
+
+        if x < xpos || x >= xpos + coords.xsize() ||
+           y < ypos || y >= ypos + coords.ysize() {
+            return None;
+        }
+
+        let row = (y - ypos) as usize + self.offset;
+        match row < self.content.len() {
+            true => Some(row),
+            false => None
+        }
+    }
+
+    pub fn file_at(&self, x: u16, y: u16) -> Option<&File> {
+        let row = self.row_at(x, y)?;
+        self.content.iter_files().nth(row)
+    }
+
+    // The row is on screen already, so nothing scrolls. False if there's no
+    // file there.
+    pub fn select_at(&mut self, x: u16, y: u16) -> bool {
+        match self.row_at(x, y) {
+            Some(row) => {
+                if row != self.selection {
+                    self.selection = row;
+                    self.seeking = false;
+                    self.update_selected_file();
+                    self.select_visual_range();
+                }
+                self.refresh().log();
+                true
+            }
+            None => false
+        }
+    }
+
     pub fn clone_selected_file(&self) -> File {
         let file = self.selected_file().clone();
         file
@@ -608,6 +784,573 @@ impl ListView<Files>
         self.refresh().log();
     }
 
+    fn refine_size(&mut self) -> HResult<()> {
+        let file = self.selected_file_mut();
+        if !file.is_dir() {
+            self.core.show_status("Not a directory!").log();
+            return Ok(());
+        }
+
+        let du = file.du.get_or_insert_with(|| Arc::new(Mutex::new(None))).clone();
+        let path = file.path.clone();
+        let name = file.name.clone();
+        let sender = self.core.get_sender();
+        let one_filesystem = self.core.config().one_filesystem;
+
+        self.core.show_status(&format!("Calculating size of {}...", name)).log();
+
+        std::thread::spawn(move || -> HResult<()> {
+            let size = crate::dirsize::refine(&path, one_filesystem)?;
+            *du.lock()? = Some(size);
+
+            let msg = format!("Size of {}: {}", name, crate::files::pretty_size(size.bytes()));
+            sender.send(Events::Status(msg))?;
+            sender.send(Events::WidgetReady)?;
+            Ok(())
+        });
+
+        Ok(())
+    }
+
+    // A count takes that many files from the selected one on instead. The
+    // placeholder of an empty directory has the directory's own path, so
+    // that one's never in there.
+    pub fn selected_paths(&self) -> Vec<PathBuf> {
+        if let Some(count) = crate::keybind::count() {
+            let selection = self.get_selection();
+            return self.content
+                .iter_files()
+                .skip(selection)
+                .take(count)
+                .filter(|file| file.kind != Kind::Placeholder)
+                .map(|file| file.path.clone())
+                .collect();
+        }
+
+        let selected = self.content
+            .get_selected()
+            .filter(|file| file.kind != Kind::Placeholder)
+            .map(|file| file.path.clone())
+            .collect::<Vec<_>>();
+
+        match selected.is_empty() {
+            true => Some(self.selected_file())
+                .filter(|file| file.kind != Kind::Placeholder)
+                .map(|file| file.path.clone())
+                .into_iter()
+                .collect(),
+            false => selected
+        }
+    }
+
+    fn trash(&mut self) -> HResult<()> {
+        let paths = self.selected_paths();
+        if paths.is_empty() { return self.core.show_status("Nothing selected!"); }
+        if !crate::protect::guard(&self.core, &paths)? { return Ok(()); }
+
+        let mut trashed = 0;
+
+        for path in &paths {
+            match crate::trash::trash(path) {
+                Ok(()) => trashed += 1,
+                Err(e) => {
+                    HError::log::<()>(&format!("Can't trash {}: {}",
+                                               path.to_string_lossy(),
+                                               e)).ok();
+                }
+            }
+        }
+
+        self.core.show_status(&format!("Moved {}/{} files to trash",
+                                       trashed,
+                                       paths.len()))
+    }
+
+    fn create_new(&mut self, dir: bool) -> HResult<()> {
+        let query = match dir {
+            true => "new directory",
+            false => "new file"
+        };
+        let name = self.core.minibuffer(query)?;
+        let path = match self.new_path(&name) {
+            Some(path) => path,
+            None => return self.core.show_status(&format!("Can't create {} here", name))
+        };
+
+        let result = match dir {
+            // "a/b/c" works too
+            true => std::fs::create_dir_all(&path),
+            false => std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+                .map(|_| ())
+        };
+        crate::audit::record("create", &path, None, &result);
+        result?;
+
+        self.add_and_select(&path)
+    }
+
+    fn new_from_template(&mut self) -> HResult<()> {
+        let templates_path = crate::paths::templates_path()?;
+        let mut templates = std::fs::read_dir(&templates_path)
+            .map(|entries| {
+                entries.filter_map(|entry| entry.ok())
+                    .map(|entry| entry.file_name().to_string_lossy().to_string())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        templates.sort();
+
+        if templates.is_empty() {
+            let status = format!("No templates in {}", templates_path.to_string_lossy());
+            return self.core.show_status(&status);
+        }
+
+        let query = format!("template ({})", templates.join(", "));
+        let template = self.core.minibuffer(&query)?;
+        if !templates.contains(&template) {
+            return self.core.show_status(&format!("No template named {}", template));
+        }
+
+        let name = match self.core.minibuffer("name") {
+            Ok(name) => name,
+            Err(HError::MiniBufferEmptyInput) => template.clone(),
+            Err(e) => Err(e)?
+        };
+
+        let path = match self.new_path(&name) {
+            Some(path) => path,
+            None => return self.core.show_status(&format!("Can't create {} here", name))
+        };
+        if std::fs::symlink_metadata(&path).is_ok() {
+            return self.core.show_status(&format!("{} already exists!", name));
+        }
+
+        let result = crate::fileops::copy_skeleton(&templates_path.join(&template), &path);
+        crate::audit::record("create", &path, None, &result);
+        result?;
+        self.add_and_select(&path)
+    }
+
+    // Somewhere in this directory, "a/b" is fine, "/a" or "../a" aren't
+    fn new_path(&self, name: &str) -> Option<PathBuf> {
+        use std::path::Component;
+
+        let parts = Path::new(name).components().collect::<Vec<_>>();
+        let inside = parts.iter().all(|part| match part {
+            Component::Normal(_) | Component::CurDir => true,
+            _ => false
+        });
+        let named = parts.iter().any(|part| match part {
+            Component::Normal(_) => true,
+            _ => false
+        });
+
+        match inside && named {
+            true => Some(self.content.directory.path.join(name)),
+            false => None
+        }
+    }
+
+    // Doesn't wait for the watcher, so the new file can be selected right away
+    fn add_and_select(&mut self, path: &Path) -> HResult<()> {
+        // for "a/b/c" it's "a" that shows up in here
+        let dir = self.content.directory.path.clone();
+        let top = path.strip_prefix(&dir)
+            .ok()
+            .and_then(|rest| rest.components().next())
+            .map(|top| dir.join(top))?;
+
+        let mut file = File::new_from_path(&top, None)?;
+        file.meta_sync().log();
+
+        self.content.remove_placeholder();
+        if !self.content.files.contains(&file) {
+            self.content.files.push(file.clone());
+        }
+        self.content.recalculate_len();
+        self.content.sort();
+
+        self.select_file(&file);
+        self.refresh().log();
+
+        self.core.show_status(&format!("Created {}", file.name))
+    }
+
+    // Terminals don't take much more through OSC 52
+    fn copy_contents(&mut self) -> HResult<()> {
+        const CLIPBOARD_MAX: u64 = 100 * 1024;
+
+        let file = self.selected_file().clone();
+
+        if file.is_dir() {
+            return self.core.show_status("Can't copy a directory!");
+        }
+
+        let size = std::fs::metadata(&file.path)?.len();
+        if size > CLIPBOARD_MAX {
+            return self.core.show_status(&format!("{} is too big to copy, {}K max",
+                                                  file.name,
+                                                  CLIPBOARD_MAX / 1024));
+        }
+
+        let text = match String::from_utf8(std::fs::read(&file.path)?) {
+            Ok(text) => text,
+            Err(_) => return self.core.show_status(&format!("{} isn't a text file",
+                                                            file.name))
+        };
+
+        self.core.screen()?.set_clipboard(&text)?;
+        self.core.show_status(&format!("Copied {} lines of {} to clipboard",
+                                       text.lines().count(),
+                                       file.name))
+    }
+
+    // What's shown, so filtered and sorted just the same
+    fn export_listing(&mut self) -> HResult<()> {
+        let cwd = self.content.directory.path.clone();
+        let path = self.core.minibuffer("export to (.csv, .json or text)")?;
+        let home = crate::paths::home_path().ok();
+        let path = crate::lineedit::parse_path(&path,
+                                               &cwd,
+                                               home.as_ref().map(|h| h.as_path()));
+
+        let format = crate::export::Format::from_path(&path);
+        let listing = crate::export::render(self.content.iter_files(), format);
+        std::fs::write(&path, listing)?;
+
+        self.core.show_status(&format!("Exported {} files to {}",
+                                       self.content.len(),
+                                       path.to_string_lossy()))
+    }
+
+    fn export_tree(&mut self) -> HResult<()> {
+        const CLIPBOARD_MAX: usize = 100 * 1024;
+
+        let depth = match self.core.minibuffer("depth (empty for 3)") {
+            Ok(depth) => match depth.trim().parse::<usize>() {
+                Ok(depth) if depth > 0 => depth,
+                _ => return self.core.show_status(&format!("Not a depth: {}", depth))
+            }
+            Err(HError::MiniBufferEmptyInput) => 3,
+            Err(e) => Err(e)?
+        };
+
+        let cwd = self.content.directory.path.clone();
+        let options = crate::tree::Options {
+            depth: depth,
+            show_hidden: self.content.show_hidden,
+            filter: self.content.filter.clone()
+        };
+        let tree = crate::tree::render(&cwd, &options)?;
+
+        match self.core.minibuffer("export to (empty for clipboard)") {
+            Ok(path) => {
+                let home = crate::paths::home_path().ok();
+                let path = crate::lineedit::parse_path(&path,
+                                                       &cwd,
+                                                       home.as_ref().map(|h| h.as_path()));
+                std::fs::write(&path, &tree)?;
+                self.core.show_status(&format!("Wrote tree to {}", path.to_string_lossy()))
+            }
+            Err(HError::MiniBufferEmptyInput) => {
+                if tree.len() > CLIPBOARD_MAX {
+                    return self.core.show_status(&format!("Tree is too big to copy, {}K max",
+                                                          CLIPBOARD_MAX / 1024));
+                }
+                self.core.screen()?.set_clipboard(&tree)?;
+                self.core.show_status(&format!("Copied {} lines of tree to clipboard",
+                                               tree.lines().count()))
+            }
+            Err(e) => Err(e)?
+        }
+    }
+
+    fn set_times(&mut self) -> HResult<()> {
+        let paths = self.selected_paths();
+        if paths.is_empty() { return self.core.show_status("Nothing selected!"); }
+        if !crate::protect::guard_shallow(&self.core, &paths)? { return Ok(()); }
+
+        let cwd = self.content.directory.path.clone();
+
+        let query = "time (empty for now, 2020-01-31 12:00, +1h/-2d30m to shift, or a file)";
+        let input = match self.core.minibuffer(query) {
+            Ok(input) => input,
+            Err(HError::MiniBufferEmptyInput) => String::new(),
+            Err(e) => Err(e)?
+        };
+
+        let home = crate::paths::home_path().ok();
+        let change = crate::fileops::parse_time_change(&input,
+                                                       &cwd,
+                                                       home.as_ref().map(|h| h.as_path()));
+        let change = match change {
+            Ok(change) => change,
+            Err(e) => return self.core.show_status(&e.to_string())
+        };
+
+        let mut changed = 0;
+        for path in &paths {
+            let result = crate::fileops::set_times(path, &change);
+            crate::audit::record("touch", path, None, &result);
+
+            match result {
+                Ok(_) => changed += 1,
+                Err(e) => {
+                    HError::log::<()>(&format!("Can't set times of {}: {}",
+                                               path.to_string_lossy(),
+                                               e)).ok();
+                }
+            }
+        }
+
+        // sorting by mtime can look different now
+        for file in self.content.files.iter_mut() {
+            if paths.contains(&file.path) {
+                file.reload_meta().log();
+            }
+        }
+        self.content.sort();
+        self.refresh().log();
+
+        self.core.show_status(&format!("Set times of {}/{} files", changed, paths.len()))
+    }
+
+    fn rename_sequence(&mut self) -> HResult<()> {
+        let paths = self.selected_paths();
+        let query = "rename to ({n:04}, {name}, {ext}, {date}, {time})";
+        let pattern = self.core.minibuffer(query)?;
+
+        let renames = crate::rename::plan(&pattern, &paths);
+        self.review_renames(renames)
+    }
+
+    fn transform_names(&mut self) -> HResult<()> {
+        let paths = self.selected_paths();
+        let query = "transform (lower, upper, underscores, ascii, safe, several go in order)";
+        let transforms = self.core.minibuffer(query)?
+            .split_whitespace()
+            .map(crate::rename::Transform::from_name)
+            .collect::<HResult<Vec<_>>>();
+
+        let renames = transforms
+            .and_then(|transforms| crate::rename::plan_transforms(&transforms, &paths));
+        self.review_renames(renames)
+    }
+
+    // Looks at everything listed unless something's selected
+    fn fix_encoding(&mut self) -> HResult<()> {
+        let selected = self.content
+            .get_selected()
+            .map(|file| file.path.clone())
+            .collect::<Vec<_>>();
+        let paths = match selected.is_empty() {
+            true => self.content.iter_files().map(|file| file.path.clone()).collect(),
+            false => selected
+        };
+
+        let renames = crate::rename::plan_fixes(&paths);
+        self.review_renames(renames)
+    }
+
+    fn review_renames(&mut self,
+                      renames: HResult<Vec<(PathBuf, PathBuf)>>) -> HResult<()> {
+        let renames = match renames {
+            Ok(renames) => renames,
+            Err(e) => return self.core.show_status(&e.to_string())
+        };
+
+        if renames.is_empty() {
+            return self.core.show_status("Nothing would change!");
+        }
+
+        let paths = renames.iter().map(|(from, _)| from.clone()).collect::<Vec<_>>();
+        if !crate::protect::guard(&self.core, &paths)? { return Ok(()); }
+
+        let mut preview = crate::rename::RenamePreview::new(&self.core, &renames)?;
+        if !preview.ask()? {
+            return self.core.show_status("Nothing renamed");
+        }
+
+        let renamed = crate::rename::apply(&renames)?;
+        self.clear_selections();
+        self.core.show_status(&format!("Renamed {}/{} files", renamed, renames.len()))
+    }
+
+    fn link(&mut self, kind: LinkKind) -> HResult<()> {
+        let paths = self.selected_paths();
+        if paths.is_empty() { return self.core.show_status("Nothing selected!"); }
+        let cwd = self.content.directory.path.clone();
+
+        let dir = match self.core.minibuffer("link into (empty for here)") {
+            Ok(dir) => {
+                let home = crate::paths::home_path().ok();
+                crate::lineedit::parse_path(&dir, &cwd, home.as_ref().map(|h| h.as_path()))
+            }
+            Err(HError::MiniBufferEmptyInput) => cwd,
+            Err(e) => Err(e)?
+        };
+
+        let mut linked = 0;
+
+        for path in &paths {
+            match crate::fileops::link(kind, path, &dir) {
+                Ok(_) => linked += 1,
+                Err(e) => {
+                    HError::log::<()>(&format!("Can't link {}: {}",
+                                               path.to_string_lossy(),
+                                               e)).ok();
+                }
+            }
+        }
+
+        self.core.show_status(&format!("Linked {}/{} files into {}",
+                                       linked,
+                                       paths.len(),
+                                       dir.to_string_lossy()))
+    }
+
+    // Directories get walked, so it's clear how much goes with them. That
+    // can take a while, so it happens in the background, like RefineSize,
+    // and DeleteForever asks once it's pressed again after that's done.
+    // Files alone are known right away.
+    fn delete_summary(&mut self, paths: &[PathBuf]) -> HResult<Option<(u64, u64, bool)>> {
+        let (dirs, files): (Vec<&File>, Vec<&File>) = self.content
+            .iter_files()
+            .filter(|file| paths.contains(&file.path))
+            .partition(|file| file.is_dir() && file.target.is_none());
+
+        let file_count = files.len() as u64;
+        let file_bytes = files.iter()
+            .map(|file| file.size_bytes().unwrap_or(0))
+            .sum::<u64>();
+        let dirs = dirs.into_iter()
+            .map(|dir| dir.path.clone())
+            .collect::<Vec<_>>();
+
+        if dirs.is_empty() {
+            return Ok(Some((file_count, file_bytes, false)));
+        }
+
+        let mut counted = DELETE_COUNT.lock()?;
+        let known = match &*counted {
+            Some((counted_paths, count)) if counted_paths.as_slice() == paths => Some(*count),
+            _ => None
+        };
+
+        match known {
+            Some(Some(count)) => {
+                *counted = None;
+                return Ok(Some(count));
+            }
+            Some(None) => {
+                drop(counted);
+                self.core.show_status("Still counting files...")?;
+                return Ok(None);
+            }
+            None => *counted = Some((paths.to_vec(), None))
+        }
+        drop(counted);
+
+        let paths = paths.to_vec();
+        let sender = self.core.get_sender();
+        self.core.show_status("Counting files...")?;
+
+        std::thread::spawn(move || -> HResult<()> {
+            let (mut files, mut bytes) = (file_count, file_bytes);
+
+            for dir in &dirs {
+                match crate::dirsize::summary(dir) {
+                    Ok((count, size)) => {
+                        files += count;
+                        bytes += size.bytes();
+                    }
+                    Err(e) => {
+                        HError::log::<()>(&format!("Can't count files in {}: {}",
+                                                   dir.to_string_lossy(),
+                                                   e)).ok();
+                    }
+                }
+            }
+
+            // unless it was pressed for some other files in the meantime
+            let mut counted = DELETE_COUNT.lock()?;
+            if let Some((counted_paths, count)) = &mut *counted {
+                if *counted_paths == paths && count.is_none() {
+                    *count = Some((files, bytes, true));
+                    let msg = format!("{} files ({}) would be deleted, DeleteForever again to go ahead",
+                                      files,
+                                      pretty_size(bytes));
+                    sender.send(Events::Status(msg))?;
+                }
+            }
+
+            sender.send(Events::WidgetReady)?;
+            Ok(())
+        });
+
+        Ok(None)
+    }
+
+    fn delete_forever(&mut self) -> HResult<()> {
+        let paths = self.selected_paths();
+        if paths.is_empty() { return self.core.show_status("Nothing selected!"); }
+        if !crate::protect::guard(&self.core, &paths)? { return Ok(()); }
+
+        let (files, bytes, dirs) = match self.delete_summary(&paths)? {
+            Some(summary) => summary,
+            None => return Ok(())
+        };
+        let size = crate::files::pretty_size(bytes);
+
+        // one stray keypress shouldn't take a whole tree with it
+        let confirmed = match dirs {
+            true => {
+                let query = format!("Delete {} files ({}) for good? Type yes",
+                                    files,
+                                    size);
+                self.core.minibuffer(&query)? == "yes"
+            }
+            false => {
+                let query = format!("Delete {} files ({}) for good? (y/n)",
+                                    files,
+                                    size);
+                match self.core.minibuffer(&query)?.as_str() {
+                    "y" | "yes" => true,
+                    _ => false
+                }
+            }
+        };
+
+        if !confirmed {
+            return self.core.show_status("Nothing deleted");
+        }
+
+        let sender = self.core.get_sender();
+
+        // could be a big tree
+        std::thread::spawn(move || -> HResult<()> {
+            let failed = paths.iter()
+                .filter(|path| {
+                    let result = match path.is_dir() && !path.read_link().is_ok() {
+                        true => std::fs::remove_dir_all(path),
+                        false => std::fs::remove_file(path)
+                    };
+                    crate::audit::record("delete", path, None, &result);
+                    result.map_err(HError::from).log_and().is_err()
+                })
+                .count();
+
+            let msg = format!("Deleted {}/{} files", paths.len() - failed, paths.len());
+            sender.send(Events::Status(msg))?;
+            Ok(())
+        });
+
+        Ok(())
+    }
+
     fn toggle_tag(&mut self) -> HResult<()> {
         self.selected_file_mut().toggle_tag()?;
 
@@ -633,6 +1376,7 @@ impl ListView<Files>
                 Ok(input) => {
                     // Only set this, search is on-the-fly
                     self.searching = Some(input);
+                    self.mode = Mode::Search;
                 }
                 Err(HError::MiniBufferInputUpdated(input)) => {
                     let file = self.content
@@ -754,6 +1498,132 @@ impl ListView<Files>
         Ok(())
     }
 
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    fn leave_mode(&mut self) {
+        self.mode = Mode::Normal;
+        self.visual = None;
+    }
+
+    // Keys bound in visual or search mode go first. Anything else ends the
+    // mode and is handled as usual, only movements and counts keep it.
+    pub fn mode_key(&mut self, key: Key) -> HResult<()> {
+        let config = self.core.config();
+
+        match self.mode {
+            Mode::Normal => return HError::undefined_key(key),
+            Mode::Visual => if let Some(action) = config.keybinds.visual.get(key) {
+                return self.visual_action(*action);
+            },
+            Mode::Search => if let Some(action) = config.keybinds.search.get(key) {
+                return self.search_action(*action);
+            }
+        }
+
+        let moving = config.keybinds.movement.get(key).is_some();
+        let counting = match key {
+            Key::Char('0'..='9') => true,
+            _ => false
+        };
+
+        if !moving && !counting {
+            self.leave_mode();
+        }
+
+        HError::undefined_key(key)
+    }
+
+    fn enter_visual(&mut self) {
+        let kept = self.content
+            .iter_files()
+            .filter(|file| file.selected)
+            .map(|file| file.path.clone())
+            .collect();
+
+        self.visual = Some(Visual {
+            anchor: self.get_selection(),
+            kept: kept
+        });
+        self.mode = Mode::Visual;
+        self.select_visual_range();
+    }
+
+    fn select_visual_range(&mut self) {
+        let selection = self.get_selection();
+        let visual = match &self.visual {
+            Some(visual) => visual,
+            None => return
+        };
+
+        let (from, to) = match visual.anchor <= selection {
+            true => (visual.anchor, selection),
+            false => (selection, visual.anchor)
+        };
+
+        for (i, file) in self.content.iter_files_mut().enumerate() {
+            file.selected = (from <= i && i <= to) || visual.kept.contains(&file.path);
+        }
+
+        self.content.set_dirty();
+        self.refresh().log();
+    }
+
+    fn visual_action(&mut self, action: VisualAction) -> HResult<()> {
+        match action {
+            VisualAction::Leave => {
+                self.leave_mode();
+                let selected = self.content
+                    .iter_files()
+                    .filter(|file| file.selected)
+                    .count();
+                self.core.show_status(&format!("{} selected", selected))?;
+            }
+            VisualAction::Cancel => {
+                if let Some(visual) = self.visual.take() {
+                    for file in self.content.iter_files_mut() {
+                        file.selected = visual.kept.contains(&file.path);
+                    }
+                    self.content.set_dirty();
+                }
+                self.leave_mode();
+                self.refresh()?;
+            }
+            VisualAction::SwapEnds => {
+                let selection = self.get_selection();
+                let anchor = match self.visual.as_mut() {
+                    Some(visual) => std::mem::replace(&mut visual.anchor, selection),
+                    None => return Ok(())
+                };
+                self.set_selection(anchor);
+                self.update_selected_file();
+                self.refresh()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn search_action(&mut self, action: SearchAction) -> HResult<()> {
+        let pos = self.get_selection();
+
+        match action {
+            SearchAction::Next => self.search_next()?,
+            SearchAction::Prev => self.search_prev()?,
+            SearchAction::Leave => {
+                self.leave_mode();
+                self.core.clear_status().log();
+            }
+        }
+
+        if pos != self.get_selection() {
+            self.update_selected_file();
+        }
+
+        Ok(())
+    }
+
     fn toggle_filter_selected(&mut self) {
         self.content.toggle_filter_selected();
 
@@ -775,6 +1645,9 @@ impl ListView<Files>
         use std::fmt::Write;
         let xsize = self.get_coordinates().unwrap().xsize();
         let icons = self.core.config().icons;
+        let theme = self.core.config().theme;
+        let opened_at = self.content.opened_at;
+        let conflicts = self.content.conflicts.clone();
 
         move |file| -> String {
             let mut line = String::with_capacity(500);
@@ -784,7 +1657,8 @@ impl ListView<Files>
                 false => ""
             };
 
-            let name = &file.name;
+            // a name can be anything but / and NUL
+            let name = &term::sanitize(&file.name);
 
             let size = file.calculate_size();
             let (size, unit) = match size {
@@ -801,6 +1675,27 @@ impl ListView<Files>
                          .map(|t| t.as_str())
                          .unwrap_or("");
 
+            // directories have them somewhere inside
+            let in_conflict = conflicts.iter().any(|path| path.starts_with(&file.path));
+            let (conflict, conflict_len) = match in_conflict {
+                true => (Some(term::color_orange() + "!"), 1),
+                false => (None, 0)
+            };
+
+            let conflict = conflict.as_ref()
+                                   .map(|c| c.as_str())
+                                   .unwrap_or("");
+
+            // changed since the directory was opened
+            let (modified, modified_len) = match file.modified_since(opened_at) {
+                true => (Some(term::color_cyan() + "•"), 1),
+                false => (None, 0)
+            };
+
+            let modified = modified.as_ref()
+                                   .map(|m| m.as_str())
+                                   .unwrap_or("");
+
             let selection_color = crate::term::color_yellow();
             let (selection_gap, selection_color) = match file.is_selected() {
                 true => (" ", selection_color.as_str()),
@@ -822,22 +1717,40 @@ impl ListView<Files>
 
             let sized_string = term::sized_string(&name, xsize);
 
-            let size = size.to_string();
-            let size_pos = xsize - (size.len() as u16 +
-                                    unit.len() as u16 +
-                                    link_indicator_len as u16);
+            // columns can get very narrow
+            let size = format!("{}{}", file.size_prefix(), size);
+            let size_pos = xsize.saturating_sub(size.width() as u16 +
+                                                unit.len() as u16 +
+                                                link_indicator_len as u16);
 
-            let padding = sized_string.len() - sized_string.width_cjk();
-            let padding = xsize - padding as u16;
-            let padding = padding - tag_len;
-            let padding = padding - icon.width() as u16;
+            let padding = sized_string.len().saturating_sub(sized_string.width_cjk());
+            let padding = xsize.saturating_sub(padding as u16);
+            let padding = padding.saturating_sub(tag_len);
+            let padding = padding.saturating_sub(modified_len);
+            let padding = padding.saturating_sub(conflict_len);
+            let padding = padding.saturating_sub(icon.width() as u16);
 
             write!(&mut line, "{}", termion::cursor::Save).unwrap();
 
-            match &file.color {
-                Some(color) => write!(&mut line,
-                                      "{}{}{}{}{}{:padding$}{}",
+            match (theme.file_style(&file.name), &file.color) {
+                (Some(style), _) => write!(&mut line,
+                                           "{}{}{}{}{}{}{}{:padding$}{}{}",
+                                           tag,
+                                           conflict,
+                                           modified,
+                                           style.on,
+                                           selection_color,
+                                           selection_gap,
+                                           icon,
+                                           &sized_string,
+                                           style.off,
+                                           term::normal_color(),
+                                           padding = padding as usize),
+                (None, Some(color)) => write!(&mut line,
+                                      "{}{}{}{}{}{}{}{:padding$}{}",
                                       tag,
+                                      conflict,
+                                      modified,
                                       term::from_lscolor(color),
                                       selection_color,
                                       selection_gap,
@@ -845,9 +1758,11 @@ impl ListView<Files>
                                       &sized_string,
                                       term::normal_color(),
                                       padding = padding as usize),
-                None => write!(&mut line,
-                               "{}{}{}{}{}{:padding$}{}",
+                (None, None) => write!(&mut line,
+                               "{}{}{}{}{}{}{}{:padding$}{}",
                                tag,
+                               conflict,
+                               modified,
                                term::normal_color(),
                                selection_color,
                                selection_gap,
@@ -857,14 +1772,22 @@ impl ListView<Files>
                                padding = padding as usize),
             }.unwrap();
 
+            let (size_style, size_style_off) = match file.size_bytes()
+                .and_then(|bytes| theme.size_style(bytes)) {
+                    Some(style) => (style.on.as_str(), style.off.as_str()),
+                    None => ("", "")
+                };
+
             write!(&mut line,
-                   "{}{}{}{}{}{}",
+                   "{}{}{}{}{}{}{}{}",
                    termion::cursor::Restore,
                    termion::cursor::Right(size_pos),
                    link_indicator,
                    term::highlight_color(),
+                   size_style,
                    size,
-                   unit).unwrap();
+                   unit,
+                   size_style_off).unwrap();
 
 
             line
@@ -873,8 +1796,9 @@ impl ListView<Files>
 
 
     fn render(&self) -> Vec<String> {
+        let _span = crate::profile::span("list_render");
         let render_fn = self.render_line_fn();
-        let ysize = self.get_coordinates().unwrap().ysize_u();
+        let ysize = self.list_coordinates().ysize_u();
         self.content
             .iter_files()
             .skip(self.offset)
@@ -919,21 +1843,27 @@ impl ListView<Files>
     }
 
     fn refresh_files(&mut self) -> HResult<()> {
-        // if let Ok(Some(mut refresh)) = self.content.get_refresh() {
-        //     let file = self.clone_selected_file();
+        if let Ok(Some(_)) = self.content.get_refresh() {
+            let file = self.clone_selected_file();
+            self.select_file(&file);
+            self.core.set_dirty();
+        }
 
-        //     self.buffer = refresh.new_buffer.take()?;
-        //     self.lines = self.buffer.len() - 1;
+        if self.content.get_conflicts(self.core.get_sender())? {
+            self.core.set_dirty();
+        }
 
-        //     self.select_file(&file);
-        // }
+        if self.content.ready_to_refresh()? {
+            if self.content.apply_change_events()? {
+                self.core.set_dirty();
+                // markers might have been taken out
+                self.content.check_conflicts(self.core.get_sender())?;
+            }
 
-        // if self.content.ready_to_refresh()? {
-        //     let render_fn = self.render_line_fn();
-        //     self.content.process_fs_events(self.buffer.clone(),
-        //                                    self.core.get_sender(),
-        //                                    render_fn)?;
-        // }
+            if self.content.ready_to_refresh()? {
+                self.content.process_fs_events(self.core.get_sender())?;
+            }
+        }
 
         Ok(())
     }
@@ -968,6 +1898,14 @@ where
         Ok(())
     }
 
+    fn set_coordinates(&mut self, coordinates: &Coordinates) -> HResult<()> {
+        if self.core.coordinates != *coordinates {
+            self.core.coordinates = coordinates.clone();
+            self.core.set_dirty();
+        }
+        Ok(())
+    }
+
     fn render_header(&self) -> HResult<String> {
         Listable::render_header(self)
     }
@@ -978,7 +1916,15 @@ where
 
     fn get_drawlist(&self) -> HResult<String> {
         let mut output = term::reset();
-        let (xpos, ypos) = self.get_coordinates().unwrap().position().position();
+        let (xpos, ypos) = self.list_coordinates().position().position();
+
+        if let Some(header) = &self.header {
+            let xsize = self.get_coordinates()?.xsize() as usize;
+            output += &format!("{}{}{}",
+                               term::goto_xy(xpos, ypos - 1),
+                               term::sized_string_u(&header.0.lock()?, xsize),
+                               term::reset());
+        }
 
         let render = self.render();
 
@@ -1003,7 +1949,8 @@ where
             })
             .collect::<String>();
 
-        output += &self.get_redraw_empty_list(self.len())?;
+        let header_lines = if self.header.is_some() { 1 } else { 0 };
+        output += &self.get_redraw_empty_list(self.len() + header_lines)?;
 
         Ok(output)
     }
@@ -0,0 +1,181 @@
+use ini::Ini;
+
+use std::path::{Path, PathBuf};
+
+use crate::fail::{HResult, HError, ErrorLog};
+use crate::files::SortBy;
+use crate::opener::OpenRule;
+
+
+// A .hunter.toml in a directory goes for it and everything below it, so a
+// project can bring its own commands, sort order and open rules. Only the
+// simple part of TOML is understood, [tables] with key = "value" lines.
+// Since it can run commands, nothing in it is used before it's trusted, and
+// trusted is that exact content. Once it changes it has to be trusted again.
+pub const FILE_NAME: &str = ".hunter.toml";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalSettings {
+    pub path: PathBuf,
+    // name and shell command, like in [commands]
+    pub actions: Vec<(String, String)>,
+    pub sort: Option<SortBy>,
+    pub reverse: bool,
+    // these go before the ones from open_rules
+    pub open_rules: Vec<OpenRule>,
+}
+
+impl LocalSettings {
+    pub fn root(&self) -> &Path {
+        self.path.parent().unwrap_or(&self.path)
+    }
+}
+
+pub enum Found {
+    Trusted(LocalSettings),
+    Untrusted(PathBuf),
+}
+
+// The closest one, in dir or any of the directories it's in
+pub fn find(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|dir| dir.join(FILE_NAME))
+        .find(|path| path.is_file())
+}
+
+fn unquote(text: &str) -> &str {
+    let text = text.trim();
+    match text.len() >= 2 && text.starts_with('"') && text.ends_with('"') {
+        true => &text[1..text.len() - 1],
+        false => text
+    }
+}
+
+fn parse(path: &Path, text: &str) -> HResult<LocalSettings> {
+    // only to complain about what can't be read at all
+    Ini::load_from_str(text)
+        .map_err(|e| HError::Error(format!("Can't read {}: {}", path.to_string_lossy(), e)))?;
+
+    // in the order they're written, the first open rule that matches wins
+    let section = |name: &str| -> Vec<(String, String)> {
+        crate::config::ini_section(text, name)
+            .iter()
+            .map(|(key, value)| (unquote(key).to_string(), unquote(value).to_string()))
+            .collect()
+    };
+
+    let mut sort = None;
+    let mut reverse = false;
+
+    for (key, value) in section("settings") {
+        match (key.as_str(), value.as_str()) {
+            ("sort", "name") => sort = Some(SortBy::Name),
+            ("sort", "size") => sort = Some(SortBy::Size),
+            ("sort", "mtime") => sort = Some(SortBy::MTime),
+            ("reverse", "true") => reverse = true,
+            ("reverse", "false") => reverse = false,
+            _ => {
+                HError::log::<()>(&format!("{}: can't make sense of {} = {}",
+                                           path.to_string_lossy(),
+                                           key,
+                                           value)).ok();
+            }
+        }
+    }
+
+    let open = section("open")
+        .iter()
+        .map(|(pattern, opener)| format!("{}:{}", pattern, opener))
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let open_rules = match crate::opener::parse(&open) {
+        Some(rules) => rules,
+        None => {
+            HError::log::<()>(&format!("{}: can't make sense of [open]",
+                                       path.to_string_lossy())).ok();
+            vec![]
+        }
+    };
+
+    Ok(LocalSettings {
+        path: path.to_path_buf(),
+        actions: section("actions"),
+        sort: sort,
+        reverse: reverse,
+        open_rules: open_rules
+    })
+}
+
+// The whole text that was trusted, not some hash of it. Each one is a line
+// with its length and path, then the text itself.
+fn trusted() -> Vec<(PathBuf, String)> {
+    let all = crate::paths::trusted_path()
+        .and_then(|path| Ok(std::fs::read_to_string(path)?))
+        .unwrap_or_default();
+
+    let mut trusted = vec![];
+    let mut rest = all.as_str();
+
+    while let Some(end) = rest.find('\n') {
+        let mut parts = rest[..end].splitn(2, '\t');
+        let len = parts.next().and_then(|len| len.parse::<usize>().ok());
+        let path = parts.next().map(PathBuf::from);
+        let text = len
+            .and_then(|len| (end + 1).checked_add(len))
+            .and_then(|text_end| rest.get(end + 1..text_end));
+
+        match (path, text) {
+            (Some(path), Some(text)) => {
+                rest = &rest[end + 1 + text.len()..];
+                trusted.push((path, text.to_string()));
+            }
+            // left by an older hunter, those have to be trusted again
+            _ => break
+        }
+    }
+
+    trusted
+}
+
+pub fn load(dir: &Path) -> Option<Found> {
+    let path = find(dir)?;
+    let text = std::fs::read_to_string(&path).ok()?;
+
+    match trusted().contains(&(path.clone(), text.clone())) {
+        true => parse(&path, &text).log_and().ok().map(Found::Trusted),
+        false => Some(Found::Untrusted(path))
+    }
+}
+
+// What it would do, to decide whether to trust it
+pub fn describe(path: &Path, text: &str) -> HResult<String> {
+    let settings = parse(path, text)?;
+
+    let commands = settings.actions
+        .iter()
+        .map(|(_, command)| command.as_str())
+        .collect::<Vec<_>>();
+
+    match commands.is_empty() {
+        true => Ok(String::from("no commands")),
+        false => Ok(format!("commands: {}", commands.join(", ")))
+    }
+}
+
+// The text that was shown, not whatever is in the file by now. Forgets
+// what was trusted for that file before.
+pub fn trust(path: &Path, text: &str) -> HResult<()> {
+    let mut trusted = trusted();
+    trusted.retain(|(trusted_path, _)| trusted_path != path);
+    trusted.push((path.to_path_buf(), text.to_string()));
+
+    let lines = trusted.iter()
+        .map(|(path, text)| format!("{}\t{}\n{}", text.len(), path.to_string_lossy(), text))
+        .collect::<String>();
+
+    let trusted_path = crate::paths::trusted_path()?;
+    std::fs::create_dir_all(trusted_path.parent()?)?;
+    std::fs::write(trusted_path, lines)?;
+    Ok(())
+}
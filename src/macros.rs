@@ -0,0 +1,163 @@
+use lazy_static;
+use termion::event::{Event, Key};
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::fail::HResult;
+
+
+// Keyboard macros, vim style. RecordMacro and PlayMacro only say that the
+// next key names a register, the input thread takes that key and does the
+// rest. Everything typed while recording is kept, popups and the
+// minibuffer included, and played back from the input thread as if it was
+// typed again, one key after the other.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Pending {
+    Record,
+    Play,
+}
+
+#[derive(Debug, Default)]
+struct Macros {
+    registers: HashMap<char, Vec<Event>>,
+    recording: Option<(char, Vec<Event>)>,
+    pending: Option<Pending>,
+    playback: Option<char>,
+    last_played: Option<char>,
+    // macros can play other macros, up to a point
+    depth: usize,
+}
+
+const DEPTH_MAX: usize = 8;
+
+lazy_static! {
+    static ref MACROS: Mutex<Macros> = Mutex::new(Macros::default());
+}
+
+// Asks for a register or stops recording. Returns the register and how
+// many keys went into it once it stopped.
+pub fn record() -> HResult<Option<(char, usize)>> {
+    let mut macros = MACROS.lock()?;
+
+    match macros.recording.take() {
+        Some((register, mut events)) => {
+            // that's the key that stopped it
+            events.pop();
+            let len = events.len();
+            macros.registers.insert(register, events);
+            Ok(Some((register, len)))
+        }
+        None => {
+            macros.pending = Some(Pending::Record);
+            Ok(None)
+        }
+    }
+}
+
+pub fn play() -> HResult<()> {
+    MACROS.lock()?.pending = Some(Pending::Play);
+    Ok(())
+}
+
+// Waiting for the register after RecordMacro or PlayMacro
+pub fn pending() -> Option<Pending> {
+    MACROS.lock().ok()?.pending
+}
+
+// Names and how many keys are in them, and the one played last
+pub fn registers() -> (Vec<(char, usize)>, Option<char>) {
+    let macros = match MACROS.lock() {
+        Ok(macros) => macros,
+        Err(_) => return (vec![], None)
+    };
+
+    let mut registers = macros.registers
+        .iter()
+        .map(|(register, events)| (*register, events.len()))
+        .collect::<Vec<_>>();
+    registers.sort();
+
+    (registers, macros.last_played)
+}
+
+pub fn recording() -> Option<char> {
+    MACROS.lock()
+        .ok()
+        .and_then(|macros| macros.recording.as_ref().map(|(register, _)| *register))
+}
+
+// Called with every key before it's sent on, false if it was a register
+// name and shouldn't go anywhere. The message is for the status bar.
+pub fn intercept(event: &Event) -> (bool, Option<String>) {
+    let mut macros = match MACROS.lock() {
+        Ok(macros) => macros,
+        Err(_) => return (true, None)
+    };
+
+    let pending = macros.pending.take();
+    let depth = macros.depth;
+
+    let register = match (pending, event) {
+        (None, _) => {
+            if depth == 0 {
+                if let Some((_, events)) = macros.recording.as_mut() {
+                    events.push(event.clone());
+                }
+            }
+            return (true, None);
+        }
+        (Some(_), Event::Key(Key::Char(register))) => *register,
+        // Esc or whatever else cancels
+        (Some(_), _) => return (false, Some(String::from("No macro then")))
+    };
+
+    match pending {
+        Some(Pending::Record) => {
+            macros.recording = Some((register, vec![]));
+            (false, Some(format!("Recording macro {}", register)))
+        }
+        _ => {
+            // "@@" plays the last one again
+            let register = match register {
+                '@' => match macros.last_played {
+                    Some(register) => register,
+                    None => return (false, Some(String::from("No macro played yet")))
+                },
+                register => register
+            };
+
+            if !macros.registers.contains_key(&register) {
+                return (false, Some(format!("No macro {}", register)));
+            }
+
+            // so playing it while recording plays it again later
+            if depth == 0 {
+                if let Some((_, events)) = macros.recording.as_mut() {
+                    events.push(event.clone());
+                }
+            }
+
+            macros.playback = Some(register);
+            (false, None)
+        }
+    }
+}
+
+pub fn take_playback() -> Option<Vec<Event>> {
+    let mut macros = MACROS.lock().ok()?;
+    let register = macros.playback.take()?;
+
+    if macros.depth >= DEPTH_MAX { return None; }
+
+    macros.last_played = Some(register);
+    macros.registers.get(&register).cloned()
+}
+
+pub fn enter_playback() {
+    MACROS.lock().map(|mut macros| macros.depth += 1).ok();
+}
+
+pub fn leave_playback() {
+    MACROS.lock().map(|mut macros| macros.depth = macros.depth.saturating_sub(1)).ok();
+}
@@ -32,6 +32,7 @@ extern crate derivative;
 
 extern crate osstrtools;
 extern crate pathbuftools;
+extern crate base64;
 extern crate async_value;
 
 use failure::Fail;
@@ -47,11 +48,15 @@ mod miller_columns;
 mod preview;
 mod term;
 mod textview;
+mod hexview;
 mod widget;
 mod hbox;
+mod vbox;
+mod footer;
 mod tabview;
 mod fail;
 mod minibuffer;
+mod lineedit;
 mod proclist;
 mod bookmarks;
 mod paths;
@@ -65,13 +70,51 @@ mod quick_actions;
 mod trait_ext;
 mod config_installer;
 mod imgview;
+mod graphics;
+mod thumbs;
 mod mediaview;
 mod keybind;
-
-
-
-
-
+mod keylayout;
+mod fileops;
+mod dirsize;
+mod theme;
+mod trash;
+mod permissions;
+mod qr;
+#[cfg(feature = "share")]
+mod share;
+mod devices;
+mod remote;
+mod help;
+mod markdown;
+mod export;
+mod tree;
+mod rename;
+mod mojibake;
+mod projects;
+mod recent;
+mod macros;
+mod cleanup;
+mod maintenance;
+mod opener;
+mod audit;
+mod branches;
+mod pipelines;
+mod conflicts;
+mod protect;
+mod local;
+mod direnv;
+mod profile;
+mod session;
+mod validate;
+mod overlay;
+mod animation;
+
+
+
+
+
+use vbox::{VBox, Constraint};
 use widget::{Widget, WidgetCore};
 use term::ScreenExt;
 use fail::{HResult, HError, MimeError, ErrorLog};
@@ -98,6 +141,11 @@ fn die_gracefully(core: &WidgetCore) {
 fn main() -> HResult<()> {
     let args = parse_args();
 
+    // before the terminal is taken over, the output might go to a pipe
+    if let Some(format) = args.value_of("export") {
+        return export(format, &args);
+    }
+
     // do this early so it might be ready when needed
     crate::files::load_tags().ok();
 
@@ -108,7 +156,10 @@ fn main() -> HResult<()> {
     // Resets terminal when hunter crashes :(
     die_gracefully(&core);
 
-    match run(core.clone()) {
+    let result = run(core.clone());
+    crate::profile::finish().log();
+
+    match result {
         Ok(_) | Err(HError::Quit) => reset_screen(&mut core),
         Err(err) => {
             reset_screen(&mut core)?;
@@ -132,7 +183,15 @@ fn run(mut core: WidgetCore) -> HResult<()> {
     let mut tabview = TabView::new(&core);
     tabview.push_widget(filebrowser)?;
 
-    tabview.handle_input()?;
+    let mut screen = VBox::new(&core);
+    screen.push_header();
+    screen.push_widget(tabview, Constraint::Ratio(1))?;
+    screen.push_footer();
+
+    crate::validate::show_problems(&core).log();
+    crate::keybind::watch_keys(core.get_sender()).log();
+
+    screen.handle_input()?;
 
     // core.screen.cursor_show()?;
     // core.screen.flush()?;
@@ -185,6 +244,45 @@ fn parse_args() -> clap::ArgMatches<'static> {
                 .long("mime")
                 .help("Print MIME type of file")
                 .takes_value(false))
+        .arg(
+            Arg::with_name("export")
+                .long("export")
+                .value_name("format")
+                .possible_values(&["plain", "csv", "json"])
+                .help("Print the listing of <path> as plain text, CSV or JSON and quit")
+                .takes_value(true))
+        .arg(
+            Arg::with_name("profile")
+                .long("profile")
+                .value_name("report")
+                .help("Write timings of loading, rendering, etc. to <report> on quit")
+                .takes_value(true))
+        .arg(
+            Arg::with_name("profile-trace")
+                .long("profile-trace")
+                .value_name("trace")
+                .help("Also write every timed span to <trace> in Chrome's trace format")
+                .requires("profile")
+                .takes_value(true))
+        .arg(
+            Arg::with_name("record")
+                .long("record")
+                .value_name("session")
+                .help("Record keys and their timing to <session> for --replay")
+                .conflicts_with("replay")
+                .takes_value(true))
+        .arg(
+            Arg::with_name("replay")
+                .long("replay")
+                .value_name("session")
+                .help("Replay a session recorded with --record, in the directory it started in")
+                .takes_value(true))
+        .arg(
+            Arg::with_name("cd-file")
+                .long("cd-file")
+                .value_name("file")
+                .help("Write the current directory to <file> when quitting with QuitWithDir")
+                .takes_value(true))
         .arg(
             Arg::with_name("path")
                 .index(1)
@@ -206,6 +304,11 @@ fn process_args(args: clap::ArgMatches, core: WidgetCore) {
         std::process::exit(1)
     }
 
+    if let Some(report) = args.value_of("profile") {
+        let trace = args.value_of("profile-trace").map(std::path::Path::new);
+        crate::profile::enable(std::path::Path::new(report), trace);
+    }
+
     if args.is_present("update") {
         crate::config_installer::update_config(core, true).log();
     }
@@ -216,6 +319,15 @@ fn process_args(args: clap::ArgMatches, core: WidgetCore) {
             .log();
     }
 
+    // after changing directory, that's where the session starts
+    if let Some(session) = args.value_of("record") {
+        crate::session::record(std::path::Path::new(session)).log();
+    }
+
+    if let Some(session) = args.value_of("replay") {
+        crate::session::replay(std::path::Path::new(session)).log();
+    }
+
     crate::config::set_argv_config(args).log();
 }
 
@@ -224,6 +336,14 @@ fn process_args(args: clap::ArgMatches, core: WidgetCore) {
 
 
 
+fn export(format: &str, args: &clap::ArgMatches) -> HResult<()> {
+    let dir = std::path::Path::new(args.value_of("path").unwrap_or("."));
+    let format = crate::export::Format::from_name(format)?;
+    let listing = crate::export::render_dir(dir, format, args.is_present("show-hidden"))?;
+    print!("{}", listing);
+    Ok(())
+}
+
 fn get_mime(path: Option<&str>) -> HResult<()> {
     let path = path.ok_or(MimeError::NoFileProvided)?;
     let path = std::path::PathBuf::from(path);
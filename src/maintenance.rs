@@ -0,0 +1,441 @@
+use termion::event::Key;
+
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+use crate::fail::{HResult, HError, ErrorLog};
+use crate::keybind::{Acting, Bindings, FindingsAction, Movement};
+use crate::listview::{Listable, ListView};
+use crate::term;
+use crate::widget::Widget;
+
+
+// Leftovers that pile up over time: symlinks pointing nowhere and
+// directories with nothing in them. Both are collected from the whole tree
+// below a directory into one list, where they can be dealt with in bulk.
+const VISIT_MAX: usize = 500_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    BrokenLinks,
+    EmptyDirs,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    pub path: PathBuf,
+    // where a broken link points to
+    pub target: Option<PathBuf>,
+    selected: bool,
+}
+
+pub struct Findings {
+    pub kind: Kind,
+    pub root: PathBuf,
+    entries: Vec<Finding>,
+    picked: Option<(PathBuf, bool)>,
+}
+
+struct Search {
+    kind: Kind,
+    root: PathBuf,
+    root_dev: Option<u64>,
+    found: Vec<Finding>,
+    visited: usize,
+}
+
+// Whether dir ends up empty, which is also the case if there's nothing
+// but empty directories in it. Only the topmost of those is listed.
+fn walk(dir: &Path, search: &mut Search) -> bool {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return false
+    };
+
+    let mut empty = true;
+    let mut empty_subdirs = vec![];
+
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        if search.visited >= VISIT_MAX { return false; }
+        search.visited += 1;
+
+        let path = entry.path();
+        let meta = match std::fs::symlink_metadata(&path) {
+            Ok(meta) => meta,
+            Err(_) => { empty = false; continue }
+        };
+
+        if meta.file_type().is_symlink() {
+            empty = false;
+            if search.kind == Kind::BrokenLinks && std::fs::metadata(&path).is_err() {
+                search.found.push(Finding {
+                    target: std::fs::read_link(&path).ok(),
+                    path: path,
+                    selected: false
+                });
+            }
+        } else if meta.is_dir() {
+            let other_fs = search.root_dev.map(|dev| dev != meta.dev()).unwrap_or(false);
+            match !other_fs && walk(&path, search) {
+                true => empty_subdirs.push(path),
+                false => empty = false
+            }
+        } else {
+            empty = false;
+        }
+    }
+
+    // hunter is in root, that one stays
+    if search.kind == Kind::EmptyDirs && (!empty || dir == search.root) {
+        search.found.extend(empty_subdirs.into_iter().map(|path| Finding {
+            path: path,
+            target: None,
+            selected: false
+        }));
+    }
+
+    empty
+}
+
+// Takes out a directory that only has empty directories in it, without
+// ever touching anything else that might have appeared in the meantime
+fn remove_empty(dir: &Path) -> HResult<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if std::fs::symlink_metadata(entry.path())?.is_dir() {
+            remove_empty(&entry.path())?;
+        }
+    }
+
+    std::fs::remove_dir(dir)?;
+    Ok(())
+}
+
+impl Findings {
+    pub fn new(kind: Kind, root: PathBuf, one_filesystem: bool) -> HResult<Findings> {
+        let root_dev = match one_filesystem {
+            true => Some(std::fs::metadata(&root)?.dev()),
+            false => None
+        };
+
+        let mut search = Search {
+            kind: kind,
+            root: root.clone(),
+            root_dev: root_dev,
+            found: vec![],
+            visited: 0
+        };
+
+        walk(&root, &mut search);
+        let mut entries = search.found;
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+        Ok(Findings {
+            kind: kind,
+            root: root,
+            entries: entries,
+            picked: None
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn what(&self) -> &'static str {
+        match self.kind {
+            Kind::BrokenLinks => "broken links",
+            Kind::EmptyDirs => "empty directories"
+        }
+    }
+}
+
+pub type FindingsView = ListView<Findings>;
+
+impl FindingsView {
+    // the path and whether it should get its own tab
+    pub fn picked(&self) -> Option<(PathBuf, bool)> {
+        self.content.picked.clone()
+    }
+
+    fn pick(&mut self, in_tab: bool) -> HResult<()> {
+        let selection = self.get_selection();
+        let path = self.content.entries.get(selection)?.path.clone();
+        self.content.picked = Some((path, in_tab));
+        self.popup_finnished()
+    }
+
+    // The selected ones, or the one under the cursor if none are
+    fn targets(&self) -> Vec<usize> {
+        let selected = self.content.entries
+            .iter()
+            .enumerate()
+            .filter(|(_, finding)| finding.selected)
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+
+        match selected.is_empty() && !self.content.entries.is_empty() {
+            true => vec![self.get_selection()],
+            false => selected
+        }
+    }
+
+    fn paths(&self, targets: &[usize]) -> Vec<PathBuf> {
+        targets.iter()
+            .map(|&i| self.content.entries[i].path.clone())
+            .collect()
+    }
+
+    fn toggle_select(&mut self) -> HResult<()> {
+        let selection = self.get_selection();
+        let finding = self.content.entries.get_mut(selection)?;
+        finding.selected = !finding.selected;
+        self.move_down();
+        self.refresh()
+    }
+
+    fn select_all(&mut self) -> HResult<()> {
+        let all = self.content.entries.iter().all(|finding| finding.selected);
+        for finding in self.content.entries.iter_mut() {
+            finding.selected = !all;
+        }
+        self.refresh()
+    }
+
+    fn remove(&mut self, done: &[usize]) {
+        let mut i = 0;
+        self.content.entries.retain(|_| {
+            i += 1;
+            !done.contains(&(i - 1))
+        });
+
+        let last = self.content.entries.len().saturating_sub(1);
+        if self.get_selection() > last {
+            self.set_selection(last);
+        }
+
+        self.refresh().log();
+        self.core.clear().log();
+    }
+
+    fn delete(&mut self) -> HResult<()> {
+        let targets = self.targets();
+        if targets.is_empty() { return Ok(()); }
+        if !crate::protect::guard(&self.core, &self.paths(&targets))? { return Ok(()); }
+
+        let query = format!("Delete {} {}? (y/n)", targets.len(), self.content.what());
+        match self.core.minibuffer(&query)?.as_str() {
+            "y" | "yes" => {},
+            _ => return Ok(())
+        }
+
+        let kind = self.content.kind;
+        let deleted = targets.iter()
+            .cloned()
+            .filter(|&i| {
+                let path = &self.content.entries[i].path;
+                let result = match kind {
+                    Kind::BrokenLinks => std::fs::remove_file(path).map_err(HError::from),
+                    Kind::EmptyDirs => remove_empty(path)
+                };
+                crate::audit::record("delete", path, None, &result);
+
+                match result {
+                    Ok(()) => true,
+                    Err(e) => {
+                        HError::log::<()>(&format!("Can't delete {}: {}",
+                                                   path.to_string_lossy(),
+                                                   e)).ok();
+                        false
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+
+        self.remove(&deleted);
+        self.core.show_status(&format!("Deleted {}/{} {}",
+                                       deleted.len(),
+                                       targets.len(),
+                                       self.content.what()))
+    }
+
+    // One link gets a new target, for more of them part of the old target
+    // is replaced, like after moving a directory they all pointed into
+    fn retarget(&mut self) -> HResult<()> {
+        if self.content.kind != Kind::BrokenLinks { return Ok(()); }
+
+        let targets = self.targets();
+        let new_target = |finding: &Finding, from: &str, to: &str| -> Option<PathBuf> {
+            let old = finding.target.as_ref()?.to_string_lossy().to_string();
+            match from.is_empty() {
+                true => Some(PathBuf::from(to)),
+                false if old.contains(from) => Some(PathBuf::from(old.replacen(from, to, 1))),
+                false => None
+            }
+        };
+
+        if !crate::protect::guard(&self.core, &self.paths(&targets))? { return Ok(()); }
+
+        let (from, to) = match targets.len() {
+            0 => return Ok(()),
+            1 => {
+                let old = self.content.entries[targets[0]].target
+                    .as_ref()
+                    .map(|target| target.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let to = self.core.minibuffer_prefilled("New target", &old)?;
+                (String::new(), to)
+            }
+            _ => {
+                let from = self.core.minibuffer("Replace in targets")?;
+                let to = self.core.minibuffer_prefilled("Replace with", &from)?;
+                (from, to)
+            }
+        };
+
+        if to.is_empty() { return Ok(()); }
+
+        let mut fixed = vec![];
+        let mut still_broken = 0;
+
+        for &i in &targets {
+            let finding = &self.content.entries[i];
+            let target = match new_target(finding, &from, &to) {
+                Some(target) => target,
+                None => continue
+            };
+
+            let result = std::fs::remove_file(&finding.path)
+                .and_then(|_| std::os::unix::fs::symlink(&target, &finding.path));
+            crate::audit::record("retarget", &finding.path, Some(&target), &result);
+
+            match result {
+                Ok(()) => {
+                    // relative targets start at the link
+                    let resolved = finding.path.parent()
+                        .map(|dir| dir.join(&target))
+                        .unwrap_or_else(|| target.clone());
+
+                    match resolved.exists() {
+                        true => fixed.push(i),
+                        false => still_broken += 1
+                    }
+
+                    self.content.entries[i].target = Some(target);
+                }
+                Err(e) => {
+                    HError::log::<()>(&format!("Can't retarget {}: {}",
+                                               finding.path.to_string_lossy(),
+                                               e)).ok();
+                }
+            }
+        }
+
+        let fixed_count = fixed.len();
+        self.remove(&fixed);
+
+        match still_broken {
+            0 => self.core.show_status(&format!("Fixed {}/{} links",
+                                                fixed_count,
+                                                targets.len())),
+            _ => self.core.show_status(&format!("Fixed {}/{} links, {} still point nowhere",
+                                                fixed_count,
+                                                targets.len(),
+                                                still_broken))
+        }
+    }
+}
+
+impl Listable for ListView<Findings> {
+    type Item = ();
+
+    fn len(&self) -> usize {
+        self.content.entries.len()
+    }
+
+    fn render(&self) -> Vec<String> {
+        let root = &self.content.root;
+
+        self.content.entries
+            .iter()
+            .map(|finding| {
+                let path = finding.path.strip_prefix(root).unwrap_or(&finding.path);
+                let mark = match finding.selected {
+                    true => format!("{}* ", term::color_yellow()),
+                    false => String::from("  ")
+                };
+                let target = match &finding.target {
+                    Some(target) => format!(" {}-> {}",
+                                            term::color_red(),
+                                            term::sanitize(&target.to_string_lossy())),
+                    None => String::new()
+                };
+
+                format!("{}{}{}{}",
+                        mark,
+                        term::normal_color(),
+                        term::sanitize(&path.to_string_lossy()),
+                        target)
+            })
+            .collect()
+    }
+
+    fn render_header(&self) -> HResult<String> {
+        Ok(format!("{} {} below {}",
+                   self.content.entries.len(),
+                   self.content.what(),
+                   self.content.root.to_string_lossy()))
+    }
+
+    fn render_footer(&self) -> HResult<String> {
+        let selected = self.content.entries.iter().filter(|finding| finding.selected).count();
+        match selected {
+            0 => Ok(String::new()),
+            _ => Ok(format!("{} selected", selected))
+        }
+    }
+
+    fn on_key(&mut self, key: Key) -> HResult<()> {
+        self.do_key(key)
+    }
+}
+
+impl Acting for ListView<Findings> {
+    type Action = FindingsAction;
+
+    fn search_in(&self) -> Bindings<Self::Action> {
+        self.core.config().keybinds.findings
+    }
+
+    fn movement(&mut self, movement: &Movement) -> HResult<()> {
+        use Movement::*;
+
+        match movement {
+            Up(n) => { for _ in 0..*n { self.move_up(); }; self.refresh()?; }
+            Down(n) => { for _ in 0..*n { self.move_down(); }; self.refresh()?; }
+            PageUp => self.page_up(),
+            PageDown => self.page_down(),
+            Top => self.move_top(),
+            Bottom => self.move_bottom(),
+            Left | Right => {}
+        }
+
+        Ok(())
+    }
+
+    fn do_action(&mut self, action: &Self::Action) -> HResult<()> {
+        use FindingsAction::*;
+
+        match action {
+            Close => self.popup_finnished()?,
+            Pick => self.pick(false)?,
+            PickInTab => self.pick(true)?,
+            Select => self.toggle_select()?,
+            SelectAll => self.select_all()?,
+            DeleteForever => self.delete()?,
+            Retarget => self.retarget()?
+        }
+
+        Ok(())
+    }
+}
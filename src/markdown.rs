@@ -0,0 +1,251 @@
+use termion::style;
+
+use crate::term;
+
+
+// Just enough Markdown to make READMEs nice to look at in the preview
+// column. Works line by line, so things spanning lines like tables or
+// emphasis across a line break are shown as they are.
+pub fn render(lines: &[String], xsize: usize) -> Vec<String> {
+    let mut rendered = Vec::with_capacity(lines.len());
+    let mut fence: Option<&str> = None;
+
+    for line in lines {
+        let trimmed = line.trim_start();
+
+        match fence {
+            Some(marker) if trimmed.starts_with(marker) => {
+                fence = None;
+                continue;
+            }
+            Some(_) => {
+                rendered.push(code(line));
+                continue;
+            }
+            None if trimmed.starts_with("```") || trimmed.starts_with("~~~") => {
+                fence = Some(&trimmed[..3]);
+                continue;
+            }
+            None => rendered.push(render_line(line, xsize))
+        }
+    }
+
+    rendered
+}
+
+fn code(line: &str) -> String {
+    format!("{}  {}{}", term::color_yellow(), line, style::Reset)
+}
+
+fn render_line(line: &str, xsize: usize) -> String {
+    let trimmed = line.trim_start();
+    let indent = &line[..line.len() - trimmed.len()];
+    let base = term::normal_color();
+
+    let level = trimmed.chars().take_while(|&c| c == '#').count();
+    if level > 0 && level <= 6 {
+        let text = &trimmed[level..];
+        if text.is_empty() || text.starts_with(' ') {
+            let text = text.trim().trim_end_matches('#').trim_end();
+            let color = match level {
+                1 => term::color_cyan(),
+                2 => term::color_light_green(),
+                _ => term::color_light_yellow()
+            };
+            let base = format!("{}{}", style::Bold, color);
+            let text = match level {
+                1 => text.to_uppercase(),
+                _ => text.to_string()
+            };
+
+            return format!("{}{}{}", base, inline(&text, &base), style::Reset);
+        }
+    }
+
+    if is_rule(trimmed) {
+        return format!("{}{}{}",
+                       term::color_cyan(),
+                       "─".repeat(xsize),
+                       style::Reset);
+    }
+
+    if trimmed.starts_with('>') {
+        let text = trimmed.trim_start_matches(|c| c == '>' || c == ' ');
+        let depth = trimmed.chars()
+            .take_while(|&c| c == '>' || c == ' ')
+            .filter(|&c| c == '>')
+            .count();
+        return format!("{}{}{}{}{}{}",
+                       indent,
+                       term::color_green(),
+                       "│ ".repeat(depth),
+                       base,
+                       inline(text, &base),
+                       style::Reset);
+    }
+
+    if let Some(text) = bullet(trimmed) {
+        let (mark, text) = match text {
+            _ if text.starts_with("[ ] ") => ("☐ ", &text[4..]),
+            _ if text.starts_with("[x] ") || text.starts_with("[X] ") => ("☑ ", &text[4..]),
+            _ => ("• ", text)
+        };
+        return format!("{}{}{}{}{}{}",
+                       indent,
+                       term::color_cyan(),
+                       mark,
+                       base,
+                       inline(text, &base),
+                       style::Reset);
+    }
+
+    if let Some((number, text)) = numbered(trimmed) {
+        return format!("{}{}{}{}{}{}",
+                       indent,
+                       term::color_cyan(),
+                       number,
+                       base,
+                       inline(text, &base),
+                       style::Reset);
+    }
+
+    // nested lists are indented too, so this comes last
+    if line.starts_with("    ") || line.starts_with('\t') {
+        return code(trimmed);
+    }
+
+    format!("{}{}{}", base, inline(line, &base), style::Reset)
+}
+
+// ---, *** or ___, spaces in between are fine
+fn is_rule(line: &str) -> bool {
+    let chars = line.chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<Vec<_>>();
+
+    chars.len() >= 3 &&
+        ['-', '*', '_'].iter().any(|&rule| chars.iter().all(|&c| c == rule))
+}
+
+fn bullet(line: &str) -> Option<&str> {
+    match line.get(..2) {
+        Some("- ") | Some("* ") | Some("+ ") => Some(&line[2..]),
+        _ => None
+    }
+}
+
+// "1. " or "1) ", the number stays
+fn numbered(line: &str) -> Option<(&str, &str)> {
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits == 0 { return None; }
+
+    match line.get(digits..digits + 2) {
+        Some(". ") | Some(") ") => Some((&line[..digits + 2], &line[digits + 2..])),
+        _ => None
+    }
+}
+
+// Emphasis, inline code and links. Styles can't be turned off one by one
+// reliably, so everything is reset and the ones still active are set again.
+fn inline(text: &str, base: &str) -> String {
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut out = String::with_capacity(text.len());
+    let mut bold = false;
+    let mut italic = false;
+    let mut i = 0;
+
+    let restyle = |bold: bool, italic: bool| {
+        format!("{}{}{}{}",
+                style::Reset,
+                base,
+                if bold { style::Bold.to_string() } else { String::new() },
+                if italic { style::Italic.to_string() } else { String::new() })
+    };
+    let find = |from: usize, needle: char| {
+        chars[from..].iter().position(|&c| c == needle).map(|pos| from + pos)
+    };
+
+    while i < chars.len() {
+        let c = chars[i];
+        let prev = if i > 0 { chars.get(i - 1).cloned() } else { None };
+        let next = chars.get(i + 1).cloned();
+
+        match c {
+            '\\' if next.map(|c| c.is_ascii_punctuation()).unwrap_or(false) => {
+                out.push(chars[i + 1]);
+                i += 2;
+            }
+            '`' => match find(i + 1, '`') {
+                Some(end) => {
+                    let code = chars[i + 1..end].iter().collect::<String>();
+                    out += &format!("{}{}{}",
+                                    term::color_yellow(),
+                                    code,
+                                    restyle(bold, italic));
+                    i = end + 1;
+                }
+                None => {
+                    out.push(c);
+                    i += 1;
+                }
+            }
+            '*' | '_' if next == Some(c) => {
+                bold = !bold;
+                out += &restyle(bold, italic);
+                i += 2;
+            }
+            // snake_case and 2 * 3 aren't emphasis
+            '*' | '_' => {
+                let opens = !italic &&
+                    next.map(|n| !n.is_whitespace()).unwrap_or(false) &&
+                    (c == '*' || prev.map(|p| !p.is_alphanumeric()).unwrap_or(true));
+                let closes = italic &&
+                    prev.map(|p| !p.is_whitespace()).unwrap_or(false) &&
+                    (c == '*' || next.map(|n| !n.is_alphanumeric()).unwrap_or(true));
+
+                if opens || closes {
+                    italic = !italic;
+                    out += &restyle(bold, italic);
+                } else {
+                    out.push(c);
+                }
+                i += 1;
+            }
+            '[' | '!' => {
+                let start = if c == '!' { i + 1 } else { i };
+                let link = match chars.get(start) {
+                    Some('[') => find(start + 1, ']')
+                        .filter(|&close| chars.get(close + 1) == Some(&'('))
+                        .and_then(|close| find(close + 2, ')').map(|end| (close, end))),
+                    _ => None
+                };
+
+                match link {
+                    Some((close, end)) => {
+                        let label = chars[start + 1..close].iter().collect::<String>();
+                        let label = match c {
+                            '!' => format!("[image: {}]", label),
+                            _ => label
+                        };
+                        out += &format!("{}{}{}{}",
+                                        term::color_cyan(),
+                                        style::Underline,
+                                        label,
+                                        restyle(bold, italic));
+                        i = end + 1;
+                    }
+                    None => {
+                        out.push(c);
+                        i += 1;
+                    }
+                }
+            }
+            _ => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    out
+}
@@ -80,7 +80,8 @@ impl MediaView {
         let imgview = ImgView {
             core: core.clone(),
             buffer: vec![],
-            file: None
+            file: None,
+            backend: None
         };
 
         let (tx_cmd, rx_cmd) = channel();
@@ -2,19 +2,32 @@ use termion::event::Key;
 
 use std::collections::HashMap;
 use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+
+use unicode_width::UnicodeWidthStr;
 
 use crate::coordinates::{Coordinates};
 use crate::widget::{Widget, WidgetCore};
 use crate::fail::{HResult, HError, ErrorLog};
 use crate::term::ScreenExt;
+use crate::lineedit;
 
 type HMap = HashMap<String, Vec<String>>;
 
+// Commands run in a project are remembered for it, or for the directory
+// outside of one, and come up first there. Kept in their own file as
+// "dir<TAB>command" lines, not too many for each.
+const DIR_HISTORY_MAX: usize = 100;
+
 #[derive(Debug)]
 struct History {
     history: HMap,
     position: Option<usize>,
-    loaded: bool
+    loaded: bool,
+    // where the commands asked for now are run
+    dir: Option<PathBuf>,
+    dir_history: HashMap<PathBuf, Vec<String>>,
+    dirs_loaded: bool
 }
 
 impl History {
@@ -22,8 +35,64 @@ impl History {
         History {
             history: HashMap::new(),
             position: None,
-            loaded: false
+            loaded: false,
+            dir: None,
+            dir_history: HashMap::new(),
+            dirs_loaded: false
+        }
+    }
+
+    fn load_dirs(&mut self) -> HResult<()> {
+        if self.dirs_loaded { return Ok(()) }
+        self.dirs_loaded = true;
+
+        let content = std::fs::read_to_string(crate::paths::dir_history_path()?)?;
+
+        for line in content.lines() {
+            let mut parts = line.splitn(2, '\t');
+            if let (Some(dir), Some(cmd)) = (parts.next(), parts.next()) {
+                self.dir_history
+                    .entry(PathBuf::from(dir))
+                    .or_insert_with(Vec::new)
+                    .push(cmd.to_string());
+            }
         }
+
+        Ok(())
+    }
+
+    fn save_dirs(&self) -> HResult<()> {
+        let history = self.dir_history.iter().map(|(dir, cmds)| {
+            cmds.iter().map(|cmd| format!("{}\t{}\n", dir.to_string_lossy(), cmd))
+                .collect::<String>()
+        }).collect::<String>();
+
+        std::fs::write(crate::paths::dir_history_path()?, history)?;
+        Ok(())
+    }
+
+    fn set_dir(&mut self, dir: Option<&Path>) {
+        self.dir = dir.map(|dir| {
+            crate::projects::find_root(dir).unwrap_or_else(|| dir.to_path_buf())
+        });
+    }
+
+    // The ones from here go last, so they're the first ones going back
+    fn entries(&mut self, htype: &str) -> Vec<String> {
+        self.load().ok();
+        self.load_dirs().ok();
+
+        let global = self.history.get(htype).cloned().unwrap_or_default();
+        let local = match &self.dir {
+            Some(dir) => self.dir_history.get(dir).cloned().unwrap_or_default(),
+            None => return global
+        };
+
+        let mut entries: Vec<String> = global.into_iter()
+            .filter(|entry| !local.contains(entry))
+            .collect();
+        entries.extend(local);
+        entries
     }
 
     fn load(&mut self) -> HResult<()> {
@@ -82,11 +151,23 @@ impl History {
         };
         history.push(input.to_string());
         self.save().log();
+
+        let dir = match &self.dir {
+            Some(dir) => dir.clone(),
+            None => return
+        };
+
+        self.load_dirs().ok();
+        let cmds = self.dir_history.entry(dir).or_insert_with(Vec::new);
+        cmds.retain(|cmd| cmd != input);
+        cmds.push(input.to_string());
+        let too_many = cmds.len().saturating_sub(DIR_HISTORY_MAX);
+        cmds.drain(..too_many);
+        self.save_dirs().log();
     }
 
     fn get_prev(&mut self, htype: &str) -> HResult<String> {
-        self.load()?;
-        let history = self.history.get(htype)?;
+        let history = self.entries(htype);
         let mut position = self.position;
         let hist_len = history.len();
 
@@ -106,8 +187,7 @@ impl History {
     }
 
     fn get_next(&mut self, htype: &str) -> HResult<String> {
-        self.load()?;
-        let history = self.history.get(htype)?;
+        let history = self.entries(htype);
         let mut position = self.position;
         let hist_len = history.len();
 
@@ -137,6 +217,8 @@ pub struct MiniBuffer {
     history: History,
     completions: Vec<OsString>,
     last_completion: Option<String>,
+    // completed from these instead of files and executables
+    choices: Option<Vec<String>>,
     continuous: bool
 }
 
@@ -155,12 +237,15 @@ impl MiniBuffer {
             history: History::new(),
             completions: vec![],
             last_completion: None,
+            choices: None,
             continuous: false
         }
     }
 
     pub fn query(&mut self, query: &str, cont: bool) -> HResult<String> {
         self.continuous = cont;
+        self.choices = None;
+        self.history.set_dir(None);
 
         if !cont || self.query != query {
             self.query = query.to_string();
@@ -168,6 +253,46 @@ impl MiniBuffer {
             self.clear();
         }
 
+        self.ask()
+    }
+
+    // starts out with text already typed in, ready to be changed
+    pub fn query_prefilled(&mut self, query: &str, text: &str) -> HResult<String> {
+        self.continuous = false;
+        self.choices = None;
+        self.history.set_dir(None);
+        self.query = query.to_string();
+        self.clear();
+
+        self.input = text.to_string();
+        self.position = self.input.len();
+
+        self.ask()
+    }
+
+    pub fn query_choices(&mut self, query: &str, choices: &[String]) -> HResult<String> {
+        self.continuous = false;
+        self.history.set_dir(None);
+        self.query = query.to_string();
+        self.clear();
+
+        self.choices = Some(choices.to_vec());
+
+        self.ask()
+    }
+
+    // history from dir comes first
+    pub fn query_in_dir(&mut self, query: &str, dir: &Path) -> HResult<String> {
+        self.continuous = false;
+        self.choices = None;
+        self.history.set_dir(Some(dir));
+        self.query = query.to_string();
+        self.clear();
+
+        self.ask()
+    }
+
+    fn ask(&mut self) -> HResult<String> {
         self.core.screen()?.cursor_hide().log();
 
         match self.popup() {
@@ -198,58 +323,45 @@ impl MiniBuffer {
                 return Ok(());
             }
 
-            let part = self.input
-                .rsplitn(2, " ")
-                .take(1)
-                .map(|s| s.to_string())
-                .collect::<String>();
-            let completions = find_files(&part);
+            let part = lineedit::last_word(&self.input, self.position).to_string();
+            let completions = match &self.choices {
+                Some(choices) => find_choices(choices, &part),
+                None => find_files(&part).or_else(|_| find_bins(&part))
+            };
 
             if let Ok(mut completions) = completions {
                 let completion = completions.pop()?;
                 let completion = completion.to_string_lossy();
 
-                self.input
-                    = self.input[..self.input.len() - part.len()].to_string();
-                self.input.push_str(&completion);
-                self.position += &completion.len() - part.len();
+                let (input, position) = lineedit::replace_before(&self.input,
+                                                                 self.position,
+                                                                 &part,
+                                                                 &completion);
+                self.input = input;
+                self.position = position;
 
                 self.last_completion = Some(completion.to_string());
                 self.completions = completions;
-            } else {
-                let completions = find_bins(&part);
-
-                if let Ok(mut completions) = completions {
-                    let completion = completions.pop()?;
-                    let completion = completion.to_string_lossy();
-
-                    self.input = self.input[..self.input.len()
-                                            - part.len()].to_string();
-                    self.input.push_str(&completion);
-                    self.position += &completion.len() - part.len();
-
-                    self.last_completion = Some(completion.to_string());
-                    self.completions = completions;
-                }
             }
         } else {
-            self.input += "$s";
-            self.position += 2
+            let (input, position) = lineedit::insert(&self.input, self.position, "$s");
+            self.input = input;
+            self.position = position;
         }
         Ok(())
     }
 
     pub fn cycle_completions(&mut self) -> HResult<()> {
-        let last_comp = self.last_completion.as_ref()?;
-        let last_len = last_comp.len();
-
-        self.input = self.input.trim_end_matches(last_comp).to_string();
-        self.position = self.position.saturating_sub(last_len);
-
+        let last_comp = self.last_completion.take()?;
         let next_comp = self.completions.pop()?;
         let next_comp = next_comp.to_string_lossy();
-        self.input.push_str(&next_comp);
-        self.position += next_comp.len();
+
+        let (input, position) = lineedit::replace_before(&self.input,
+                                                         self.position,
+                                                         &last_comp,
+                                                         &next_comp);
+        self.input = input;
+        self.position = position;
         self.last_completion = Some(next_comp.to_string());
         Ok(())
     }
@@ -277,63 +389,16 @@ impl MiniBuffer {
     }
 
     pub fn delete_word(&mut self) -> HResult<()> {
-        let old_input_len = self.input.len();
-        let (before_cursor, after_cursor) = self.input.split_at(self.position);
-
-        let no_trim_len = before_cursor.len();
-        let before_cursor = before_cursor.trim_end();
-
-        if no_trim_len != before_cursor.len() {
-            self.position -= no_trim_len - before_cursor.len();
-            self.input = before_cursor.to_string() + after_cursor;
-            return Ok(());
-        }
-
-        if before_cursor.ends_with("/") {
-            let mut new_input = before_cursor.to_string();
-            new_input.pop();
-            self.input = new_input + after_cursor;
-            self.position -= 1;
-            return Ok(());
-        };
-
-        let dir_boundary = before_cursor.rfind("/");
-        let word_boundary = before_cursor.rfind(" ");
-        let boundaries = (dir_boundary, word_boundary);
-
-        let new_input = match boundaries {
-            (Some(dir_boundary), Some(word_boundary)) => {
-                if dir_boundary > word_boundary {
-                    before_cursor
-                        .split_at(dir_boundary).0
-                        .to_string() + "/"
-                } else {
-                    before_cursor
-                        .split_at(word_boundary).0
-                        .to_string() + " "
-                }
-            }
-            (Some(dir_boundary), None) => {
-                before_cursor
-                    .split_at(dir_boundary).0
-                    .to_string() + "/"
-            }
-            (None, Some(word_boundary)) => {
-                before_cursor
-                    .split_at(word_boundary).0
-                    .to_string() + " "
-            }
-            (None, None) => "".to_string()
-        } + after_cursor;
-
-        let len_difference = old_input_len - new_input.len();
-        self.position -= len_difference;
-
-        self.input = new_input;
-
+        self.edit(lineedit::delete_word);
         Ok(())
     }
 
+    fn edit(&mut self, edit: fn(&str, usize) -> (String, usize)) {
+        let (input, position) = edit(&self.input, self.position);
+        self.input = input;
+        self.position = position;
+    }
+
     pub fn input_finnished(&self) -> HResult<()> {
         return HError::popup_finnished()
     }
@@ -353,6 +418,19 @@ impl MiniBuffer {
     }
 }
 
+// Popped from the back, so the first one alphabetically comes first
+fn find_choices(choices: &[String], comp_name: &str) -> HResult<Vec<OsString>> {
+    let mut completions = choices.iter()
+        .filter(|choice| choice.starts_with(comp_name))
+        .map(OsString::from)
+        .collect::<Vec<_>>();
+    completions.sort();
+    completions.reverse();
+
+    if completions.is_empty() { return Err(HError::NoCompletionsError); }
+    Ok(completions)
+}
+
 pub fn find_bins(comp_name: &str) -> HResult<Vec<OsString>> {
     use osstrtools::OsStrTools;
 
@@ -460,9 +538,10 @@ impl Widget for MiniBuffer {
     }
 
     fn after_draw(&self) -> HResult<()> {
-        let cursor_pos = crate::term::string_len(&self.query) +
+        // wide chars take up two cells, combining marks none
+        let cursor_pos = self.query.width() +
                          ": ".len() +
-                         self.position;
+                         self.input[..lineedit::clamp(&self.input, self.position)].width();
 
         let mut screen = self.core.screen()?;
         let ysize = screen.ysize()?;
@@ -488,13 +567,19 @@ impl Acting for MiniBuffer {
 
         match action {
             InsertChar(ch) => {
-                self.input.insert(self.position, *ch);
-                self.position += 1;
+                let (input, position) = lineedit::insert(&self.input,
+                                                         self.position,
+                                                         &ch.to_string());
+                self.input = input;
+                self.position = position;
             }
             InsertTab(n) => {
-                let fnstr = format!("${}", n-1);
-                self.input.insert_str(self.position, &fnstr);
-                self.position += 2;
+                let fnstr = format!("${}", n.saturating_sub(1));
+                let (input, position) = lineedit::insert(&self.input,
+                                                         self.position,
+                                                         &fnstr);
+                self.input = input;
+                self.position = position;
             }
             Cancel => { self.clear(); self.input_cancelled()? }
             Finish => {
@@ -504,26 +589,13 @@ impl Acting for MiniBuffer {
                 self.input_finnished()?
             },
             Complete => self.complete()?,
-            DeleteChar => {
-                if self.position != self.input.len() {
-                    self.input.remove(self.position);
-                }
-            },
-            BackwardDeleteChar => {
-                if self.position != 0 {
-                    self.input.remove(self.position - 1);
-                    self.position -= 1;
-                }
-            }
+            DeleteChar => self.edit(lineedit::delete_char),
+            BackwardDeleteChar => self.edit(lineedit::backward_delete_char),
             CursorLeft => {
-                if self.position != 0 {
-                    self.position -= 1;
-                }
+                self.position = lineedit::prev_boundary(&self.input, self.position);
             },
             CursorRight => {
-                if self.position != self.input.len() {
-                    self.position += 1;
-                }
+                self.position = lineedit::next_boundary(&self.input, self.position);
             },
             HistoryUp => self.history_up()?,
             HistoryDown => self.history_down()?,
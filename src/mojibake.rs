@@ -0,0 +1,95 @@
+use std::ffi::OsStr;
+use std::os::unix::ffi::OsStrExt;
+
+
+// Names from old archives and Windows machines often come out garbled.
+// Either they're still in a legacy encoding and aren't valid UTF-8 at all,
+// or they were UTF-8 that got read as Windows-1252 and encoded again, so
+// "é" turns into "Ã©" and Cyrillic into "Ð¿Ñ€Ð¸". Both can be undone.
+
+// 0x80 to 0xff in Windows-1251, the usual legacy Cyrillic encoding
+const CP1251: [char; 128] = [
+    'Ђ', 'Ѓ', '‚', 'ѓ', '„', '…', '†', '‡', '€', '‰', 'Љ', '‹', 'Њ', 'Ќ', 'Ћ', 'Џ',
+    'ђ', '‘', '’', '“', '”', '•', '–', '—', '\u{98}', '™', 'љ', '›', 'њ', 'ќ', 'ћ', 'џ',
+    '\u{a0}', 'Ў', 'ў', 'Ј', '¤', 'Ґ', '¦', '§', 'Ё', '©', 'Є', '«', '¬', '\u{ad}', '®', 'Ї',
+    '°', '±', 'І', 'і', 'ґ', 'µ', '¶', '·', 'ё', '№', 'є', '»', 'ј', 'Ѕ', 'ѕ', 'ї',
+    'А', 'Б', 'В', 'Г', 'Д', 'Е', 'Ж', 'З', 'И', 'Й', 'К', 'Л', 'М', 'Н', 'О', 'П',
+    'Р', 'С', 'Т', 'У', 'Ф', 'Х', 'Ц', 'Ч', 'Ш', 'Щ', 'Ъ', 'Ы', 'Ь', 'Э', 'Ю', 'Я',
+    'а', 'б', 'в', 'г', 'д', 'е', 'ж', 'з', 'и', 'й', 'к', 'л', 'м', 'н', 'о', 'п',
+    'р', 'с', 'т', 'у', 'ф', 'х', 'ц', 'ч', 'ш', 'щ', 'ъ', 'ы', 'ь', 'э', 'ю', 'я',
+];
+
+// 0x80 to 0x9f in Windows-1252, the rest is the same as Latin-1
+const CP1252: [char; 32] = [
+    '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8d}', 'Ž', '\u{8f}',
+    '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9d}', 'ž', 'Ÿ',
+];
+
+fn decode_cp1251(bytes: &[u8]) -> String {
+    bytes.iter()
+        .map(|&byte| match byte {
+            0..=0x7f => byte as char,
+            _ => CP1251[byte as usize - 0x80]
+        })
+        .collect()
+}
+
+fn decode_cp1252(bytes: &[u8]) -> String {
+    bytes.iter()
+        .map(|&byte| match byte {
+            0x80..=0x9f => CP1252[byte as usize - 0x80],
+            _ => byte as char
+        })
+        .collect()
+}
+
+// None if there's a char Windows-1252 doesn't have, then it wasn't that
+fn encode_cp1252(text: &str) -> Option<Vec<u8>> {
+    text.chars()
+        .map(|c| match c as u32 {
+            0..=0x7f | 0xa0..=0xff => Some(c as u8),
+            _ => CP1252.iter()
+                .position(|&other| other == c)
+                .map(|pos| 0x80 + pos as u8)
+        })
+        .collect()
+}
+
+// Cyrillic is nearly all high bytes, western languages only have the odd
+// accented letter in mostly ASCII
+fn guess_legacy(bytes: &[u8]) -> String {
+    let high = bytes.iter().filter(|&&byte| byte >= 0x80).count();
+    let ascii_letters = bytes.iter().filter(|byte| byte.is_ascii_alphabetic()).count();
+
+    match high > ascii_letters {
+        true => decode_cp1251(bytes),
+        false => decode_cp1252(bytes)
+    }
+}
+
+// The fixed name if it looks garbled, None if it's fine or can't be helped
+pub fn fix(name: &OsStr) -> Option<String> {
+    let bytes = name.as_bytes();
+
+    let text = match std::str::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(_) => return Some(guess_legacy(bytes))
+    };
+
+    if text.is_ascii() { return None; }
+
+    // sometimes it went through twice
+    let mut fixed = text.to_string();
+    for _ in 0..2 {
+        let undone = encode_cp1252(&fixed)
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .filter(|undone| *undone != fixed);
+
+        match undone {
+            Some(undone) => fixed = undone,
+            None => break
+        }
+    }
+
+    Some(fixed).filter(|fixed| fixed != text)
+}
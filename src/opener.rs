@@ -0,0 +1,88 @@
+use crate::files::File;
+
+
+// What Open does with a file depends on the first rule in open_rules that
+// matches it. Rules look like "text/*:edit" or ".pdf:action:Zathura",
+// anything without a rule is opened like Right does it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Opener {
+    // enter directories, xdg-open everything else
+    Default,
+    Edit,
+    Preview,
+    Actions,
+    // a quick action by its title
+    Action(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenRule {
+    pattern: String,
+    opener: Opener,
+}
+
+impl OpenRule {
+    // "dir", ".ext", "*", "type/*" or "type/subtype"
+    fn matches(&self, file: &File) -> bool {
+        let pattern = self.pattern.as_str();
+
+        match pattern {
+            "*" => return true,
+            "dir" => return file.is_dir(),
+            _ if file.is_dir() => return false,
+            _ => {}
+        }
+
+        if pattern.starts_with('.') {
+            return file.path.extension()
+                .map(|ext| ext.to_string_lossy().eq_ignore_ascii_case(&pattern[1..]))
+                .unwrap_or(false);
+        }
+
+        let mime = match file.get_mime() {
+            Ok(mime) => mime,
+            Err(_) => return false
+        };
+
+        match pattern.splitn(2, '/').collect::<Vec<_>>().as_slice() {
+            [kind, "*"] => mime.type_().as_str() == *kind,
+            [kind, subtype] => mime.type_().as_str() == *kind &&
+                mime.subtype().as_str() == *subtype,
+            _ => false
+        }
+    }
+}
+
+// None if any of the rules doesn't make sense
+pub fn parse(setting: &str) -> Option<Vec<OpenRule>> {
+    setting.split(',')
+        .map(|rule| rule.trim())
+        .filter(|rule| !rule.is_empty())
+        .map(|rule| {
+            let mut parts = rule.splitn(2, ':');
+            let pattern = parts.next()?.trim().to_string();
+            let opener = match parts.next()?.trim() {
+                "open" => Opener::Default,
+                "edit" => Opener::Edit,
+                "preview" => Opener::Preview,
+                "actions" => Opener::Actions,
+                opener if opener.starts_with("action:") => {
+                    Opener::Action(opener["action:".len()..].trim().to_string())
+                }
+                _ => return None
+            };
+
+            Some(OpenRule {
+                pattern: pattern,
+                opener: opener
+            })
+        })
+        .collect()
+}
+
+pub fn find(rules: &[OpenRule], file: &File) -> Opener {
+    rules.iter()
+        .find(|rule| rule.matches(file))
+        .map(|rule| rule.opener.clone())
+        .unwrap_or(Opener::Default)
+}
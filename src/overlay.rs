@@ -0,0 +1,91 @@
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::coordinates::Coordinates;
+use crate::fail::HResult;
+use crate::term;
+
+
+// Stuff that's drawn on top of everything else, after the normal
+// drawlists, so it doesn't matter what's below. Higher z goes on top.
+#[derive(Debug, Clone)]
+pub struct Overlay {
+    pub id: usize,
+    pub z: usize,
+    pub coordinates: Coordinates,
+    pub drawlist: String,
+}
+
+impl Overlay {
+    // blank out what's below first, or it would shine through any gaps
+    pub fn clearlist(&self) -> String {
+        let (xpos, ypos) = self.coordinates.u16position();
+        let (xsize, ysize) = self.coordinates.u16size();
+
+        (ypos..ypos + ysize)
+            .map(|y| format!("{}{}{:xsize$}",
+                             term::reset(),
+                             term::goto_xy(xpos, y),
+                             " ",
+                             xsize = xsize as usize))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OverlayLayer {
+    overlays: Arc<Mutex<Vec<Overlay>>>,
+    next_id: Arc<AtomicUsize>,
+}
+
+impl OverlayLayer {
+    pub fn new() -> OverlayLayer {
+        OverlayLayer {
+            overlays: Arc::new(Mutex::new(vec![])),
+            next_id: Arc::new(AtomicUsize::new(0))
+        }
+    }
+
+    pub fn show(&self,
+                z: usize,
+                coordinates: Coordinates,
+                drawlist: String) -> HResult<usize> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let overlay = Overlay { id, z, coordinates, drawlist };
+
+        let mut overlays = self.overlays.lock()?;
+        overlays.push(overlay);
+        // stable, so the newest one wins on the same level
+        overlays.sort_by_key(|overlay| overlay.z);
+
+        Ok(id)
+    }
+
+    pub fn update(&self, id: usize, drawlist: String) -> HResult<()> {
+        let mut overlays = self.overlays.lock()?;
+        let overlay = overlays.iter_mut().find(|overlay| overlay.id == id)?;
+        overlay.drawlist = drawlist;
+        Ok(())
+    }
+
+    pub fn close(&self, id: usize) -> HResult<Overlay> {
+        let mut overlays = self.overlays.lock()?;
+        let pos = overlays.iter().position(|overlay| overlay.id == id)?;
+        Ok(overlays.remove(pos))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.overlays
+            .lock()
+            .map(|overlays| overlays.is_empty())
+            .unwrap_or(true)
+    }
+
+    pub fn drawlist(&self) -> HResult<String> {
+        Ok(self.overlays
+           .lock()?
+           .iter()
+           .map(|overlay| overlay.clearlist() + &overlay.drawlist)
+           .collect())
+    }
+}
@@ -60,12 +60,79 @@ pub fn history_path() -> HResult<PathBuf> {
     Ok(history_path)
 }
 
+pub fn dir_history_path() -> HResult<PathBuf> {
+    let mut dir_history_path = hunter_path()?;
+    dir_history_path.push("dir_history");
+    Ok(dir_history_path)
+}
+
+pub fn projects_path() -> HResult<PathBuf> {
+    let mut projects_path = hunter_path()?;
+    projects_path.push("projects");
+    Ok(projects_path)
+}
+
+pub fn pipelines_path() -> HResult<PathBuf> {
+    let mut pipelines_path = hunter_path()?;
+    pipelines_path.push("pipelines");
+    Ok(pipelines_path)
+}
+
+pub fn trusted_path() -> HResult<PathBuf> {
+    let mut trusted_path = hunter_path()?;
+    trusted_path.push("trusted");
+    Ok(trusted_path)
+}
+
+pub fn theme_path() -> HResult<PathBuf> {
+    let mut theme_path = hunter_path()?;
+    theme_path.push("theme");
+    Ok(theme_path)
+}
+
+pub fn dirsize_cache_path() -> HResult<PathBuf> {
+    let mut dirsize_cache_path = hunter_path()?;
+    dirsize_cache_path.push("dirsize_cache");
+    Ok(dirsize_cache_path)
+}
+
+pub fn thumbs_path() -> HResult<PathBuf> {
+    let mut thumbs_path = dirs_2::cache_dir()?;
+    thumbs_path.push("hunter/thumbs");
+    Ok(thumbs_path)
+}
+
+// Not in our own directory, it's shared with every other file manager
+pub fn trash_path() -> HResult<PathBuf> {
+    let mut trash_path = dirs_2::data_dir()?;
+    trash_path.push("Trash");
+    Ok(trash_path)
+}
+
+pub fn process_logs_path() -> HResult<PathBuf> {
+    let mut process_logs_path = dirs_2::data_dir()?;
+    process_logs_path.push("hunter/logs");
+    Ok(process_logs_path)
+}
+
+pub fn audit_path() -> HResult<PathBuf> {
+    let mut audit_path = dirs_2::data_dir()?;
+    audit_path.push("hunter/audit.log");
+    Ok(audit_path)
+}
+
 pub fn actions_path() -> HResult<PathBuf> {
     let mut actions_path = hunter_path()?;
     actions_path.push("actions");
     Ok(actions_path)
 }
 
+pub fn templates_path() -> HResult<PathBuf> {
+    let mut templates_path = hunter_path()?;
+    templates_path.push("templates");
+    Ok(templates_path)
+}
+
 pub fn previewers_path() -> HResult<PathBuf> {
     let mut previewers_path = hunter_path()?;
     previewers_path.push("previewers");
@@ -0,0 +1,330 @@
+use termion::event::Key;
+
+use std::ffi::CString;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+use crate::coordinates::Coordinates;
+use crate::fail::{HResult, HError, ErrorLog};
+use crate::keybind::{Acting, Bindings, Movement, PermissionAction};
+use crate::term;
+use crate::widget::{Widget, WidgetCore};
+
+
+// The 12 mode bits in the order they're shown, setuid first
+const BITS: [(u32, char); 12] = [(0o4000, 's'), (0o2000, 's'), (0o1000, 't'),
+                                 (0o400, 'r'), (0o200, 'w'), (0o100, 'x'),
+                                 (0o040, 'r'), (0o020, 'w'), (0o010, 'x'),
+                                 (0o004, 'r'), (0o002, 'w'), (0o001, 'x')];
+
+pub struct PermPopup {
+    core: WidgetCore,
+    paths: Vec<PathBuf>,
+    mode: u32,
+    // only the bits in here are touched, each file keeps the rest of its own
+    changed: u32,
+    uid: u32,
+    gid: u32,
+    cursor: usize,
+    recursive: bool,
+    owner_changed: bool,
+}
+
+impl PermPopup {
+    pub fn new(core: &WidgetCore, paths: Vec<PathBuf>) -> HResult<PermPopup> {
+        // everything starts out like the first file
+        let meta = std::fs::metadata(paths.first()?)?;
+
+        let mut popup = PermPopup {
+            core: core.clone(),
+            paths: paths,
+            mode: meta.mode() & 0o7777,
+            changed: 0,
+            uid: meta.uid(),
+            gid: meta.gid(),
+            cursor: 3,
+            recursive: false,
+            owner_changed: false
+        };
+        popup.set_coordinates(&core.coordinates).log();
+        Ok(popup)
+    }
+
+    pub fn edit(&mut self) -> HResult<()> {
+        self.get_core()?.clear()?;
+        match self.popup() {
+            Ok(_) | Err(HError::PopupFinnished) => {},
+            err @ Err(_) => err?,
+        }
+        self.get_core()?.clear()?;
+        Ok(())
+    }
+
+    fn toggle_bit(&mut self) {
+        let (bit, _) = BITS[self.cursor];
+        self.mode ^= bit;
+        self.changed ^= bit;
+    }
+
+    fn set_mode(&mut self) -> HResult<()> {
+        let mode = self.core.minibuffer("mode (octal)")?;
+        match u32::from_str_radix(mode.trim(), 8) {
+            // typed out, that's what all of them get
+            Ok(mode) if mode <= 0o7777 => {
+                self.mode = mode;
+                self.changed = 0o7777;
+            }
+            _ => self.core.show_status(&format!("Not a valid mode: {}", mode))?
+        }
+        Ok(())
+    }
+
+    // user, user:group or :group, names or numbers
+    fn set_owner(&mut self) -> HResult<()> {
+        let owner = self.core.minibuffer("owner (user:group)")?;
+        let mut parts = owner.trim().splitn(2, ':');
+
+        let user = parts.next().filter(|user| !user.is_empty());
+        let group = parts.next().filter(|group| !group.is_empty());
+
+        if let Some(user) = user {
+            self.uid = match user.parse::<u32>() {
+                Ok(uid) => uid,
+                Err(_) => users::get_user_by_name(user)
+                    .map(|user| user.uid())
+                    .ok_or_else(|| HError::Error(format!("No such user: {}", user)))?
+            };
+            self.owner_changed = true;
+        }
+
+        if let Some(group) = group {
+            self.gid = match group.parse::<u32>() {
+                Ok(gid) => gid,
+                Err(_) => users::get_group_by_name(group)
+                    .map(|group| group.gid())
+                    .ok_or_else(|| HError::Error(format!("No such group: {}", group)))?
+            };
+            self.owner_changed = true;
+        }
+
+        Ok(())
+    }
+
+    fn apply(&self) -> HResult<()> {
+        let allowed = match self.recursive {
+            true => crate::protect::guard(&self.core, &self.paths)?,
+            false => crate::protect::guard_shallow(&self.core, &self.paths)?
+        };
+        if !allowed { return HError::popup_finnished(); }
+
+        let (mut changed, mut failed) = (0, 0);
+
+        for path in &self.paths {
+            self.apply_to(path, true, &mut changed, &mut failed);
+        }
+
+        let status = match failed {
+            0 => format!("Changed {} files", changed),
+            _ => format!("Changed {} files, {} failed, check the log", changed, failed)
+        };
+        self.core.show_status(&status).log();
+
+        HError::popup_finnished()
+    }
+
+    // What a file that has mode now ends up with. Files found in a directory
+    // only get x where they already had some, like chmod's X, so turning on
+    // x for a tree doesn't make every file in it executable.
+    fn new_mode(&self, mode: u32, is_dir: bool, picked: bool) -> u32 {
+        let mut changed = self.changed;
+        if !is_dir && !picked && mode & 0o111 == 0 {
+            changed &= !(0o111 & self.mode);
+        }
+
+        (mode & !changed) | (self.mode & changed)
+    }
+
+    fn apply_to(&self, path: &Path, picked: bool, changed: &mut usize, failed: &mut usize) {
+        let result = (|| -> HResult<()> {
+            let meta = std::fs::symlink_metadata(path)?;
+
+            if self.owner_changed {
+                let c_path = CString::new(path.as_os_str().as_bytes())
+                    .map_err(|e| HError::Error(e.to_string()))?;
+                let result = unsafe {
+                    libc::lchown(c_path.as_ptr(), self.uid, self.gid)
+                };
+                if result != 0 {
+                    Err(std::io::Error::last_os_error())?
+                }
+            }
+
+            // before the directory itself, taking away r or x there would
+            // leave the rest unreachable
+            if self.recursive && meta.is_dir() {
+                for entry in std::fs::read_dir(path)? {
+                    self.apply_to(&entry?.path(), false, changed, failed);
+                }
+            }
+
+            // chmod would change the target instead, links have no mode anyway
+            if !meta.file_type().is_symlink() {
+                // chown clears setuid/setgid, the mode from before would
+                // put them back for the new owner
+                let mode = match self.owner_changed {
+                    true => std::fs::symlink_metadata(path)?.mode(),
+                    false => meta.mode()
+                };
+                let mode = self.new_mode(mode & 0o7777, meta.is_dir(), picked);
+                let permissions = std::fs::Permissions::from_mode(mode);
+                std::fs::set_permissions(path, permissions)?;
+            }
+
+            Ok(())
+        })();
+        crate::audit::record("chmod", path, None, &result);
+
+        match result {
+            Ok(()) => *changed += 1,
+            Err(e) => {
+                *failed += 1;
+                HError::log::<()>(&format!("Can't change {}: {}",
+                                           path.to_string_lossy(),
+                                           e)).ok();
+            }
+        }
+    }
+
+    fn render_bits(&self) -> String {
+        BITS.iter()
+            .enumerate()
+            .map(|(i, &(bit, letter))| {
+                let letter = match self.mode & bit {
+                    0 => '-',
+                    _ => letter
+                };
+
+                let cell = match i == self.cursor {
+                    true => format!("{}{}{}", term::invert(), letter, term::reset()),
+                    false => letter.to_string()
+                };
+
+                // gap between the groups
+                match i % 3 {
+                    2 => format!("{}  ", cell),
+                    _ => format!("{} ", cell)
+                }
+            })
+            .collect()
+    }
+
+    fn owner_name(&self) -> String {
+        let user = users::get_user_by_uid(self.uid)
+            .map(|user| user.name().to_string_lossy().to_string())
+            .unwrap_or(self.uid.to_string());
+        let group = users::get_group_by_gid(self.gid)
+            .map(|group| group.name().to_string_lossy().to_string())
+            .unwrap_or(self.gid.to_string());
+        format!("{}:{}", user, group)
+    }
+}
+
+impl Widget for PermPopup {
+    fn get_core(&self) -> HResult<&WidgetCore> {
+        Ok(&self.core)
+    }
+    fn get_core_mut(&mut self) -> HResult<&mut WidgetCore> {
+        Ok(&mut self.core)
+    }
+    fn refresh(&mut self) -> HResult<()> {
+        Ok(())
+    }
+
+    fn set_coordinates(&mut self, _: &Coordinates) -> HResult<()> {
+        let (xsize, ysize) = crate::term::size()?;
+        let ypos = ysize.saturating_sub(4);
+
+        self.core.coordinates.set_size_u(xsize.saturating_sub(1), 4);
+        self.core.coordinates.set_position_u(1, ypos);
+
+        Ok(())
+    }
+
+    fn get_drawlist(&self) -> HResult<String> {
+        let xsize = self.get_coordinates()?.xsize_u();
+        let ypos = self.get_coordinates()?.ypos();
+
+        let title = match self.paths.len() {
+            1 => self.paths[0].to_string_lossy().to_string(),
+            n => format!("{} files", n)
+        };
+        let recursive = match self.recursive {
+            true => "on",
+            false => "off"
+        };
+
+        let lines = vec![
+            format!("{}{}", term::header_color(), title),
+            format!("special user   group  other"),
+            self.render_bits(),
+            format!("mode: {:04o}  owner: {}  recursive: {}",
+                    self.mode,
+                    self.owner_name(),
+                    recursive)
+        ];
+
+        let drawlist = lines.iter()
+            .enumerate()
+            .map(|(i, line)| {
+                format!("{}{}{}",
+                        term::goto_xy(1, ypos + i as u16),
+                        term::reset(),
+                        term::sized_string_u(line, xsize))
+            })
+            .collect();
+
+        Ok(drawlist)
+    }
+
+    fn on_key(&mut self, key: Key) -> HResult<()> {
+        self.do_key(key)
+    }
+}
+
+impl Acting for PermPopup {
+    type Action = PermissionAction;
+
+    fn search_in(&self) -> Bindings<Self::Action> {
+        self.core.config().keybinds.permissions
+    }
+
+    fn movement(&mut self, movement: &Movement) -> HResult<()> {
+        use Movement::*;
+
+        match movement {
+            Left => self.cursor = self.cursor.saturating_sub(1),
+            Right => self.cursor = (self.cursor + 1).min(BITS.len() - 1),
+            Top => self.cursor = 0,
+            Bottom => self.cursor = BITS.len() - 1,
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn do_action(&mut self, action: &Self::Action) -> HResult<()> {
+        use PermissionAction::*;
+
+        match action {
+            Close => self.popup_finnished()?,
+            ToggleBit => self.toggle_bit(),
+            SetMode => self.set_mode()?,
+            SetOwner => self.set_owner()?,
+            ToggleRecursive => self.recursive = !self.recursive,
+            Apply => self.apply()?
+        }
+
+        Ok(())
+    }
+}
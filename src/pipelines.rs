@@ -0,0 +1,279 @@
+use ini::Ini;
+use osstrtools::OsStrTools;
+use termion::event::Key;
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use crate::fail::{HResult, HError, ErrorLog};
+use crate::keybind::{Acting, Bindings, Movement, PipelinePickerAction};
+use crate::listview::{Listable, ListView};
+use crate::proclist::FollowUp;
+use crate::term;
+use crate::widget::Widget;
+
+
+// Commands that get used over and over on different files, saved with a
+// name in ~/.config/hunter/pipelines, one section each:
+//
+// [optimize PNGs]
+// command=optipng -o%{level:5} %f
+// parallel=4
+//
+// %f is each selected file in turn, as many at once as parallel says,
+// that's done by xargs -P. %s is all of them at once and %d the current
+// directory. %{name} or %{name:default} is asked for before it runs.
+// on_success and on_failure say what happens once it's done.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pipeline {
+    pub name: String,
+    pub command: String,
+    pub parallel: usize,
+    pub low_priority: bool,
+    pub follow_up: FollowUp,
+}
+
+pub fn load() -> HResult<Vec<Pipeline>> {
+    let path = crate::paths::pipelines_path()?;
+    let ini = Ini::load_from_file(&path)
+        .map_err(|e| HError::Error(format!("Can't read {}: {}", path.to_string_lossy(), e)))?;
+
+    let pipelines = ini.iter()
+        .filter_map(|(name, section)| {
+            let name = name.as_ref()?.trim().to_string();
+            let command = match section.get("command") {
+                Some(command) => command.trim().to_string(),
+                None => {
+                    HError::log::<()>(&format!("Pipeline {} has no command", name)).ok();
+                    return None;
+                }
+            };
+            let parallel = section.get("parallel")
+                .map(|parallel| parallel.trim().parse::<usize>())
+                .unwrap_or(Ok(1))
+                .map_err(|_| HError::Error(format!("Pipeline {}: parallel isn't a number",
+                                                   name)))
+                .log_and()
+                .unwrap_or(1)
+                .max(1);
+            let low_priority = section.get("low_priority")
+                .map(|low| low.trim() == "on")
+                .unwrap_or(false);
+            let follow_up = FollowUp::parse(section.get("on_success").map(|s| s.as_str()).unwrap_or(""),
+                                            section.get("on_failure").map(|s| s.as_str()).unwrap_or(""))
+                .map_err(|e| HError::Error(format!("Pipeline {}: {}", name, e)))
+                .log_and()
+                .unwrap_or_default();
+
+            Some(Pipeline {
+                name: name,
+                command: command,
+                parallel: parallel,
+                low_priority: low_priority,
+                follow_up: follow_up
+            })
+        })
+        .collect();
+
+    Ok(pipelines)
+}
+
+fn quote(text: OsString) -> OsString {
+    let mut quoted = OsString::from("'");
+    quoted.push(text.escape_single_quote());
+    quoted.push("'");
+    quoted
+}
+
+impl Pipeline {
+    // Names with their defaults, each one once
+    pub fn params(&self) -> Vec<(String, String)> {
+        let mut params: Vec<(String, String)> = vec![];
+        let mut rest = self.command.as_str();
+
+        while let Some(start) = rest.find("%{") {
+            let end = match rest[start..].find('}') {
+                Some(end) => start + end,
+                None => break
+            };
+
+            let mut parts = rest[start + 2..end].splitn(2, ':');
+            let name = parts.next().unwrap_or("").to_string();
+            let default = parts.next().unwrap_or("").to_string();
+
+            if !params.iter().any(|(known, _)| *known == name) {
+                params.push((name, default));
+            }
+
+            rest = &rest[end + 1..];
+        }
+
+        params
+    }
+
+    fn per_file(&self) -> bool {
+        self.command.contains("%f")
+    }
+
+    // What the shell gets, files relative to dir
+    pub fn shell_command(&self,
+                         values: &[(String, String)],
+                         files: &[PathBuf],
+                         dir: &Path) -> OsString {
+        let all_files = files.iter()
+            .map(|file| quote(file.clone().into_os_string()))
+            .collect::<Vec<_>>();
+
+        let mut cmd = OsString::new();
+        let mut chars = self.command.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                cmd.push(c.to_string());
+                continue;
+            }
+
+            match chars.next() {
+                // the one xargs passes in
+                Some('f') => cmd.push("\"$1\""),
+                Some('s') => {
+                    for (i, file) in all_files.iter().enumerate() {
+                        if i > 0 { cmd.push(" "); }
+                        cmd.push(file);
+                    }
+                }
+                Some('d') => cmd.push(quote(dir.as_os_str().to_os_string())),
+                Some('{') => {
+                    let param = chars.by_ref()
+                        .take_while(|&c| c != '}')
+                        .collect::<String>();
+                    let name = param.splitn(2, ':').next().unwrap_or("");
+                    let value = values.iter()
+                        .find(|(known, _)| known == name)
+                        .map(|(_, value)| value.as_str())
+                        .unwrap_or("");
+                    cmd.push(value);
+                }
+                Some('%') => cmd.push("%"),
+                Some(other) => cmd.push(format!("%{}", other)),
+                None => cmd.push("%")
+            }
+        }
+
+        if !self.per_file() { return cmd; }
+
+        // NUL separated, names can have anything else in them
+        let mut pipeline = OsString::from("printf '%s\\0'");
+        for file in &all_files {
+            pipeline.push(" ");
+            pipeline.push(file);
+        }
+        pipeline.push(format!(" | xargs -0 -n 1 -P {} sh -c ", self.parallel));
+        pipeline.push(quote(cmd));
+        pipeline.push(" sh");
+        pipeline
+    }
+}
+
+pub struct Pipelines {
+    entries: Vec<Pipeline>,
+    picked: Option<Pipeline>,
+}
+
+impl Pipelines {
+    pub fn new(entries: Vec<Pipeline>) -> Pipelines {
+        Pipelines {
+            entries: entries,
+            picked: None
+        }
+    }
+}
+
+pub type PipelinePicker = ListView<Pipelines>;
+
+impl PipelinePicker {
+    pub fn picked(&self) -> Option<Pipeline> {
+        self.content.picked.clone()
+    }
+}
+
+impl Listable for ListView<Pipelines> {
+    type Item = ();
+
+    fn len(&self) -> usize {
+        self.content.entries.len()
+    }
+
+    fn render(&self) -> Vec<String> {
+        let name_len = self.content.entries
+            .iter()
+            .map(|pipeline| pipeline.name.chars().count())
+            .max()
+            .unwrap_or(0);
+
+        self.content.entries
+            .iter()
+            .map(|pipeline| {
+                let parallel = match pipeline.per_file() {
+                    true => format!(" {}(x{})", term::color_yellow(), pipeline.parallel),
+                    false => String::new()
+                };
+
+                format!("{}{:width$}{}  {}{}",
+                        term::color_cyan(),
+                        term::sanitize(&pipeline.name),
+                        term::normal_color(),
+                        term::sanitize(&pipeline.command),
+                        parallel,
+                        width = name_len)
+            })
+            .collect()
+    }
+
+    fn render_header(&self) -> HResult<String> {
+        Ok(format!("Pipelines: {}", self.content.entries.len()))
+    }
+
+    fn on_key(&mut self, key: Key) -> HResult<()> {
+        self.do_key(key)
+    }
+}
+
+impl Acting for ListView<Pipelines> {
+    type Action = PipelinePickerAction;
+
+    fn search_in(&self) -> Bindings<Self::Action> {
+        self.core.config().keybinds.pipelinepicker
+    }
+
+    fn movement(&mut self, movement: &Movement) -> HResult<()> {
+        use Movement::*;
+
+        match movement {
+            Up(n) => { for _ in 0..*n { self.move_up(); }; self.refresh()?; }
+            Down(n) => { for _ in 0..*n { self.move_down(); }; self.refresh()?; }
+            PageUp => self.page_up(),
+            PageDown => self.page_down(),
+            Top => self.move_top(),
+            Bottom => self.move_bottom(),
+            Left | Right => {}
+        }
+
+        Ok(())
+    }
+
+    fn do_action(&mut self, action: &Self::Action) -> HResult<()> {
+        use PipelinePickerAction::*;
+
+        match action {
+            Close => self.popup_finnished()?,
+            Pick => {
+                let selection = self.get_selection();
+                self.content.picked = Some(self.content.entries.get(selection)?.clone());
+                self.popup_finnished()?
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -2,17 +2,22 @@ use async_value::{Async, Stale};
 use termion::event::Key;
 
 use std::sync::{Arc, Mutex};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 use crate::files::{File, Files, Kind};
-use crate::fscache::FsCache;
+use crate::fscache::{FsCache, PreviewCache};
 use crate::listview::{ListView, FileSource};
-use crate::textview::TextView;
+use crate::textview::{TextView, read_lines, PREVIEW_MAX_BYTES};
 use crate::widget::{Widget, WidgetCore};
 use crate::coordinates::Coordinates;
 use crate::fail::{HResult, HError, ErrorLog};
 use crate::imgview::ImgView;
+use crate::hexview::{HexView, PREVIEW_LIMIT};
+use crate::graphics::Backend;
+use crate::thumbs::{thumbnail, ThumbKind, THUMB_MIN_IMAGE};
 use crate::mediaview::MediaView;
+use crate::animation::Animation;
 
 
 pub type AsyncWidgetFn<W> = dyn FnOnce(&Stale, WidgetCore)
@@ -210,7 +215,8 @@ enum PreviewWidget {
     FileList(ListView<Files>),
     TextView(TextView),
     ImgView(ImgView),
-    MediaView(MediaView)
+    MediaView(MediaView),
+    HexView(HexView)
 }
 
 enum ExtPreviewer {
@@ -218,9 +224,16 @@ enum ExtPreviewer {
     Graphics(PathBuf)
 }
 
+// Rendered by hunter itself, see markdown.rs
+fn is_markdown(file: &File) -> bool {
+    match file.path.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) => ["md", "markdown", "mkd"].contains(&ext.to_lowercase().as_str()),
+        None => false
+    }
+}
+
 fn find_previewer(file: &File, g_mode: bool) -> HResult<ExtPreviewer> {
     let path = crate::paths::previewers_path()?;
-    let ext = file.path.extension()?;
 
     // Special case to highlight text files that aren't text/plain
     if file.is_text() {
@@ -230,32 +243,58 @@ fn find_previewer(file: &File, g_mode: bool) -> HResult<ExtPreviewer> {
         return Ok(ExtPreviewer::Text(previewer));
     }
 
-    // Try to find a graphical previewer first
-    if g_mode {
-        let g_previewer = path.read_dir()?
+    if let Some(ext) = file.path.extension() {
+        // Try to find a graphical previewer first
+        if g_mode {
+            let g_previewer = path.read_dir()?
+                .find(|previewer| previewer.as_ref()
+                      .and_then(|p| {
+                          Ok(p.path().file_stem() == Some(ext)
+                             && p.path().extension() == Some(&std::ffi::OsStr::new("g")))
+                      })
+                      .unwrap_or(false))
+                .map(|p| p.map(|p| p.path()));
+            match g_previewer {
+                Some(Ok(g_p)) => return Ok(ExtPreviewer::Graphics(g_p)),
+                _ => {}
+            }
+        }
+
+        // Look for previewers matching the file extension
+        let previewer = path.read_dir()?
             .find(|previewer| previewer.as_ref()
-                  .and_then(|p| {
-                      Ok(p.path().file_stem() == Some(ext)
-                         && p.path().extension() == Some(&std::ffi::OsStr::new("g")))
-                  })
-                  .unwrap_or(false))
+                                       .and_then(|p| Ok(p.file_name() == ext ))
+                                       .unwrap_or(false))
             .map(|p| p.map(|p| p.path()));
-        match g_previewer {
-            Some(Ok(g_p)) => return Ok(ExtPreviewer::Graphics(g_p)),
-            _ => {}
+
+        if let Some(Ok(previewer)) = previewer {
+            return Ok(ExtPreviewer::Text(previewer));
         }
     }
 
+    find_mime_previewer(&path, file, g_mode)
+}
 
+// Like quick actions, previewers/<base-type>/<sub-type> for one MIME type,
+// or previewers/<base-type>/any for all of them, with .g for graphical ones
+fn find_mime_previewer(path: &Path, file: &File, g_mode: bool) -> HResult<ExtPreviewer> {
+    let mime = file.get_mime()?;
+    let dir = path.join(mime.type_().as_str());
+    let names = [mime.subtype().as_str(), "any"];
+
+    for name in names.iter() {
+        let g_previewer = dir.join(format!("{}.g", name));
+        if g_mode && g_previewer.is_file() {
+            return Ok(ExtPreviewer::Graphics(g_previewer));
+        }
 
-    // Look for previewers matching the file extension
-    let previewer = path.read_dir()?
-        .find(|previewer| previewer.as_ref()
-                                   .and_then(|p| Ok(p.file_name() == ext ))
-                                   .unwrap_or(false))
-        .map(|p| p.map(|p| p.path()));
+        let previewer = dir.join(name);
+        if previewer.is_file() {
+            return Ok(ExtPreviewer::Text(previewer));
+        }
+    }
 
-    Ok(ExtPreviewer::Text(previewer??))
+    HError::preview_failed(file)
 }
 
 
@@ -302,6 +341,16 @@ impl Previewer {
         self.file.as_ref()
     }
 
+    // Done loading and not moving, so it looks the same when drawn again
+    pub fn is_settled(&self) -> bool {
+        match self.widget.widget() {
+            Ok(PreviewWidget::FileList(file_list)) => !file_list.content.has_changes(),
+            Ok(PreviewWidget::MediaView(_)) => false,
+            Ok(_) => true,
+            Err(_) => false
+        }
+    }
+
     pub fn cancel_animation(&self) -> HResult<()> {
         Ok(self.animator.set_stale()?)
     }
@@ -326,12 +375,17 @@ impl Previewer {
         self.widget.change_to(move |stale, core| {
             let source = crate::listview::FileSource::Files(files);
 
-            let list = ListView::builder(core.clone(), source)
+            let mut builder = ListView::builder(core.clone(), source)
                 // .prerender()
                 .with_cache(cache)
                 .with_stale(stale.clone())
-                .select(selected_file)
-                .build()?;
+                .select(selected_file);
+
+            if core.config().dir_stats {
+                builder = builder.with_stats_header();
+            }
+
+            let list = builder.build()?;
 
             Ok(PreviewWidget::FileList(list))
         }).log();
@@ -381,6 +435,16 @@ impl Previewer {
                     return Ok(preview?);
                 }
 
+                if core.config().render_markdown && is_markdown(&file) {
+                    let preview = Previewer::preview_markdown(&file,
+                                                              &core,
+                                                              &stale,
+                                                              &animator);
+                    if preview.is_ok() {
+                        return Ok(preview?);
+                    }
+                }
+
                 if let Some(mime) = file.get_mime()
                                         .log_and()
                                         .ok()
@@ -390,7 +454,7 @@ impl Previewer {
                     let has_media = core.config().media_available();
 
                     match mime_type {
-                        _ if mime_type == "video" || is_gif && has_media => {
+                        _ if (mime_type == "video" || is_gif) && has_media => {
                             let media_type = crate::mediaview::MediaType::Video;
                             let mediaview = MediaView::new_from_file(core.clone(),
                                                                      &file.path,
@@ -399,9 +463,20 @@ impl Previewer {
                         }
                         "image" if has_media => {
                             let imgview = ImgView::new_from_file(core.clone(),
-                                                                 &file.path())?;
+                                                                 &Previewer::image_or_thumb(&file))?;
                             return Ok(PreviewWidget::ImgView(imgview));
                         }
+                        "image" | "video" => {
+                            let kind = match mime_type {
+                                "video" => ThumbKind::Video,
+                                _ => ThumbKind::Image
+                            };
+
+                            // no luck, maybe an external previewer can do it
+                            if let Ok(preview) = Previewer::preview_inline(&file, kind, &core) {
+                                return Ok(preview);
+                            }
+                        }
                         "audio" if has_media => {
                             let media_type = crate::mediaview::MediaType::Audio;
                             let mediaview = MediaView::new_from_file(core.clone(),
@@ -422,20 +497,52 @@ impl Previewer {
                 let preview = Previewer::preview_external(&file,
                                                           &core,
                                                           &stale,
-                                                          &animator);
+                                                          &animator,
+                                                          &cache.previews);
                 if preview.is_ok() {
                     return Ok(preview?);
                 }
                 else {
+                    // better than nothing, but only for actual files, a fifo
+                    // would never be done reading
+                    let is_file = std::fs::metadata(&file.path)
+                        .map(|meta| meta.is_file() && meta.len() > 0)
+                        .unwrap_or(false);
+                    if is_file && !file.is_text() {
+                        let hexview = HexView::new_from_file(&core,
+                                                             &file.path,
+                                                             PREVIEW_LIMIT);
+                        if let Ok(mut hexview) = hexview {
+                            hexview.set_coordinates(&coordinates).log();
+                            return Ok(PreviewWidget::HexView(hexview));
+                        }
+                    }
+
                     let mut blank = TextView::new_blank(&core);
                     blank.set_coordinates(&coordinates).log();
                     blank.refresh().log();
-                    blank.animate_slide_up(Some(&animator)).log();
+                    blank.animate(Animation::SlideUp, Some(&animator)).log();
                     return Ok(PreviewWidget::TextView(blank))
                 }
             })))
     }
 
+    pub fn toggle_tail(&mut self) -> HResult<()> {
+        let path = self.file.as_ref()?.path.clone();
+
+        match self.widget.widget_mut()? {
+            PreviewWidget::TextView(textview) => {
+                textview.toggle_tail(&path)?;
+                let status = match textview.is_tailing() {
+                    true => format!("Following {}", path.to_string_lossy()),
+                    false => String::from("Stopped following")
+                };
+                self.core.show_status(&status)
+            }
+            _ => self.core.show_status("Can only follow text files!")
+        }
+    }
+
     pub fn reload(&mut self) {
         if let Some(file) = self.file.take() {
             self.set_file(&file).log();
@@ -459,20 +566,52 @@ impl Previewer {
         if stale.is_stale()? { return Previewer::preview_failed(&file) }
         let source = FileSource::Path(file.clone());
 
-        let mut file_list = ListView::builder(core.clone(), source)
+        let mut builder = ListView::builder(core.clone(), source)
                     // .prerender()
                     .with_cache(cache)
-                    .with_stale(stale.clone())
-                    .build()?;
+                    .with_stale(stale.clone());
+
+        if core.config().dir_stats {
+            builder = builder.with_stats_header();
+        }
+
+        let mut file_list = builder.build()?;
 
         if stale.is_stale()? { return Previewer::preview_failed(&file) }
 
-        file_list.animate_slide_up(Some(animator))?;
+        file_list.animate(Animation::SlideUp, Some(animator))?;
         file_list.core.set_clean();
 
         Ok(PreviewWidget::FileList(file_list))
     }
 
+    // Big images take a while to scale down every time they're shown
+    fn image_or_thumb(file: &File) -> PathBuf {
+        let size = std::fs::metadata(&file.path)
+            .map(|meta| meta.len())
+            .unwrap_or(0);
+
+        match size > THUMB_MIN_IMAGE {
+            true => thumbnail(&file.path, ThumbKind::Image)
+                .unwrap_or_else(|_| file.path.clone()),
+            false => file.path.clone()
+        }
+    }
+
+    // Without hunter-media, videos only get a still frame
+    fn preview_inline(file: &File,
+                      kind: ThumbKind,
+                      core: &WidgetCore) -> HResult<PreviewWidget> {
+        let backend = Backend::detect(&core.config())?;
+        let path = match kind {
+            ThumbKind::Video => thumbnail(&file.path, kind)?,
+            ThumbKind::Image => Previewer::image_or_thumb(file)
+        };
+
+        let imgview = ImgView::new_inline(core.clone(), &path, backend)?;
+        Ok(PreviewWidget::ImgView(imgview))
+    }
+
     fn preview_text(file: &File,
                     core: &WidgetCore,
                     stale: &Stale,
@@ -490,16 +629,56 @@ impl Previewer {
 
         if stale.is_stale()? { return Previewer::preview_failed(&file) }
 
-        textview.animate_slide_up(Some(animator))?;
+        textview.animate(Animation::SlideUp, Some(animator))?;
+        Ok(PreviewWidget::TextView(textview))
+    }
+
+    fn preview_markdown(file: &File,
+                        core: &WidgetCore,
+                        stale: &Stale,
+                        animator: &Stale)
+                        -> HResult<PreviewWidget> {
+        let lines = read_lines(&file.path, Some(PREVIEW_MAX_BYTES))?;
+        let lines = crate::markdown::render(&lines, core.coordinates.xsize() as usize);
+        if stale.is_stale()? { return Previewer::preview_failed(&file) }
+
+        let mut textview = TextView {
+            lines: lines,
+            core: core.clone(),
+            follow: false,
+            offset: 0,
+            selection: None,
+            wrap: false,
+            hoffset: 0,
+            tail: None,
+            search: None};
+        textview.set_coordinates(&core.coordinates)?;
+        textview.refresh()?;
+
+        if stale.is_stale()? { return Previewer::preview_failed(&file) }
+
+        textview.animate(Animation::SlideUp, Some(animator))?;
         Ok(PreviewWidget::TextView(textview))
     }
 
-    fn run_external(cmd: PathBuf, file: &File, stale: &Stale) -> HResult<Vec<String>> {
+    // Previewers get the path, the width and the height of the column. They
+    // are killed when the file isn't selected anymore or they take too long,
+    // in the latter case whatever they printed until then is shown anyway.
+    // Also says if it ran to completion.
+    fn run_external(cmd: PathBuf,
+                    file: &File,
+                    stale: &Stale,
+                    core: &WidgetCore) -> HResult<(Vec<String>, bool)> {
         use std::os::unix::process::CommandExt;
+        use std::io::Read;
+
+        let (xsize, ysize) = core.coordinates.u16size();
 
-        let process = unsafe {
+        let mut process = unsafe {
             std::process::Command::new(cmd)
                 .arg(&file.path)
+                .arg(xsize.to_string())
+                .arg(ysize.to_string())
                 .stdin(std::process::Stdio::null())
                 .stdout(std::process::Stdio::piped())
                 .stderr(std::process::Stdio::null())
@@ -518,31 +697,70 @@ impl Previewer {
             *pid_ = Some(pid);
         }
 
-        if stale.is_stale()? { return Previewer::preview_failed(&file) }
-        let output = process.wait_with_output()?;
-        if stale.is_stale()? { return Previewer::preview_failed(&file) }
+        // Read while waiting, or a chatty previewer fills up the pipe and
+        // never exits
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let mut stdout = process.stdout.take()?;
+        let reader_output = output.clone();
+        let reader = std::thread::spawn(move || {
+            let mut buffer = [0u8; 8192];
+            while let Ok(n) = stdout.read(&mut buffer) {
+                if n == 0 { break }
+                reader_output.lock()
+                    .map(|mut output| output.extend_from_slice(&buffer[..n]))
+                    .ok();
+            }
+        });
+
+        let timeout = core.config().preview_timeout;
+        let started = Instant::now();
+        let mut timed_out = false;
+
+        while process.try_wait()?.is_none() {
+            if stale.is_stale()? {
+                kill_proc().log();
+                return Previewer::preview_failed(&file)
+            }
+
+            if timeout > 0 && started.elapsed() > Duration::from_secs(timeout) {
+                kill_proc().log();
+                timed_out = true;
+                break;
+            }
+
+            std::thread::sleep(Duration::from_millis(20));
+        }
+
+        // something it started might still hold on to stdout
+        if !timed_out {
+            reader.join().ok();
+        }
 
         {
             let mut pid_ = SUBPROC.lock()?;
             *pid_ = None;
         }
 
+        let output = output.lock()?;
+        let mut output = String::from_utf8_lossy(&output)
+            .lines()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
 
+        if timed_out {
+            output.push(format!("{}… previewer took longer than {}s",
+                                crate::term::color_yellow(),
+                                timeout));
+        }
 
-        //let status = output.status.code()?;
-
-        let output = std::str::from_utf8(&output.stdout)?
-            .to_string()
-            .lines().map(|s| s.to_string())
-            .collect();
-
-        Ok(output)
+        Ok((output, !timed_out))
     }
 
     fn preview_external(file: &File,
                         core: &WidgetCore,
                         stale: &Stale,
-                        animator: &Stale)
+                        animator: &Stale,
+                        previews: &PreviewCache)
                         -> HResult<PreviewWidget> {
         let previewer = if core.config().graphics.as_str() != "unicode" {
              find_previewer(&file, true)?
@@ -552,23 +770,45 @@ impl Previewer {
 
         match previewer {
             ExtPreviewer::Text(previewer) => {
-                let lines = Previewer::run_external(previewer, file, stale);
-
-                if stale.is_stale()? { return Previewer::preview_failed(&file) }
+                let key = PreviewCache::key(file, core.coordinates.u16size());
+                let cached = key.as_ref().and_then(|key| previews.get(key));
+
+                let lines = match cached {
+                    Some(lines) => lines.as_ref().clone(),
+                    None => {
+                        let (lines, complete) = Previewer::run_external(previewer,
+                                                                        file,
+                                                                        stale,
+                                                                        core)?;
+                        if stale.is_stale()? { return Previewer::preview_failed(&file) }
+
+                        // cut short, maybe it gets further next time
+                        if let (Some(key), true) = (key, complete) {
+                            let limit = core.config().preview_cache_size;
+                            previews.put(key, lines.clone(), limit).log();
+                        }
+                        lines
+                    }
+                };
 
                 let mut textview = TextView {
-                    lines: lines?,
+                    lines: lines,
                     core: core.clone(),
                     follow: false,
-                    offset: 0};
+                    offset: 0,
+                    selection: None,
+                    wrap: false,
+                    hoffset: 0,
+                    tail: None,
+                    search: None};
                 textview.set_coordinates(&core.coordinates).log();
                 textview.refresh().log();
-                textview.animate_slide_up(Some(animator)).log();
+                textview.animate(Animation::SlideUp, Some(animator)).log();
 
                 Ok(PreviewWidget::TextView(textview))
             },
             ExtPreviewer::Graphics(previewer) => {
-                let lines = Previewer::run_external(previewer, file, stale)?;
+                let (lines, _) = Previewer::run_external(previewer, file, stale, core)?;
                 let gfile = lines.first()?;
                 let imgview = ImgView::new_from_file(core.clone(),
                                                      &PathBuf::from(&gfile))?;
@@ -633,7 +873,8 @@ impl Widget for PreviewWidget {
             PreviewWidget::FileList(widget) => widget.get_core(),
             PreviewWidget::TextView(widget) => widget.get_core(),
             PreviewWidget::ImgView(widget) => widget.get_core(),
-            PreviewWidget::MediaView(widget) => widget.get_core()
+            PreviewWidget::MediaView(widget) => widget.get_core(),
+            PreviewWidget::HexView(widget) => widget.get_core()
         }
     }
     fn get_core_mut(&mut self) -> HResult<&mut WidgetCore> {
@@ -641,7 +882,8 @@ impl Widget for PreviewWidget {
             PreviewWidget::FileList(widget) => widget.get_core_mut(),
             PreviewWidget::TextView(widget) => widget.get_core_mut(),
             PreviewWidget::ImgView(widget) => widget.get_core_mut(),
-            PreviewWidget::MediaView(widget) => widget.get_core_mut()
+            PreviewWidget::MediaView(widget) => widget.get_core_mut(),
+            PreviewWidget::HexView(widget) => widget.get_core_mut()
         }
     }
     fn set_coordinates(&mut self, coordinates: &Coordinates) -> HResult<()> {
@@ -650,6 +892,7 @@ impl Widget for PreviewWidget {
             PreviewWidget::TextView(widget) => widget.set_coordinates(coordinates),
             PreviewWidget::ImgView(widget) => widget.set_coordinates(coordinates),
             PreviewWidget::MediaView(widget) => widget.set_coordinates(coordinates),
+            PreviewWidget::HexView(widget) => widget.set_coordinates(coordinates),
         }
     }
     fn refresh(&mut self) -> HResult<()> {
@@ -657,7 +900,8 @@ impl Widget for PreviewWidget {
             PreviewWidget::FileList(widget) => widget.refresh(),
             PreviewWidget::TextView(widget) => widget.refresh(),
             PreviewWidget::ImgView(widget) => widget.refresh(),
-            PreviewWidget::MediaView(widget) => widget.refresh()
+            PreviewWidget::MediaView(widget) => widget.refresh(),
+            PreviewWidget::HexView(widget) => widget.refresh()
         }
     }
     fn get_drawlist(&self) -> HResult<String> {
@@ -665,7 +909,8 @@ impl Widget for PreviewWidget {
             PreviewWidget::FileList(widget) => widget.get_drawlist(),
             PreviewWidget::TextView(widget) => widget.get_drawlist(),
             PreviewWidget::ImgView(widget) => widget.get_drawlist(),
-            PreviewWidget::MediaView(widget) => widget.get_drawlist()
+            PreviewWidget::MediaView(widget) => widget.get_drawlist(),
+            PreviewWidget::HexView(widget) => widget.get_drawlist()
         }
     }
 
@@ -674,7 +919,8 @@ impl Widget for PreviewWidget {
             PreviewWidget::FileList(widget) => widget.on_key(key),
             PreviewWidget::TextView(widget) => widget.on_key(key),
             PreviewWidget::ImgView(widget) => widget.on_key(key),
-            PreviewWidget::MediaView(widget) => widget.on_key(key)
+            PreviewWidget::MediaView(widget) => widget.on_key(key),
+            PreviewWidget::HexView(widget) => widget.on_key(key)
         }
     }
 }
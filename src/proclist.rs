@@ -1,11 +1,16 @@
+use std::collections::VecDeque;
 use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::mpsc::Sender;
 use std::process::{Child, Command};
 use std::os::unix::process::{CommandExt, ExitStatusExt};
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::PathBuf;
 use std::ffi::OsString;
+use std::time::SystemTime;
 use std::os::unix::ffi::OsStrExt;
 
+use lazy_static;
 use termion::event::Key;
 use unicode_width::UnicodeWidthStr;
 use osstrtools::{OsStringTools, OsStrTools, OsStrConcat};
@@ -21,14 +26,173 @@ use crate::hbox::HBox;
 use crate::fail::{HResult, HError, ErrorLog};
 use crate::term::{self, ScreenExt};
 use crate::files::File;
+use crate::animation::Animation;
+use crate::direnv::DirenvExt;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Stream {
+    Stdout,
+    Stderr
+}
+
+// Which of the streams the output view shows
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Streams {
+    Both,
+    Stdout,
+    Stderr
+}
+
+impl Streams {
+    fn next(self) -> Streams {
+        match self {
+            Streams::Both => Streams::Stdout,
+            Streams::Stdout => Streams::Stderr,
+            Streams::Stderr => Streams::Both
+        }
+    }
+
+    fn shows(self, stream: Stream) -> bool {
+        match (self, stream) {
+            (Streams::Both, _) => true,
+            (Streams::Stdout, Stream::Stdout) => true,
+            (Streams::Stderr, Stream::Stderr) => true,
+            _ => false
+        }
+    }
+}
+
+// Lines from stdout and stderr in the order they came in. Unfinished lines
+// wait until the rest arrives, so the two don't get mixed up in one line.
+// Everything also goes to the log file right away, if there is one.
+#[derive(Debug, Default)]
+struct Output {
+    lines: Vec<(Stream, String)>,
+    stdout: String,
+    stderr: String,
+    log: Option<std::fs::File>,
+    log_path: Option<PathBuf>,
+}
+
+impl Output {
+    fn partial(&mut self, stream: Stream) -> &mut String {
+        match stream {
+            Stream::Stdout => &mut self.stdout,
+            Stream::Stderr => &mut self.stderr
+        }
+    }
+
+    fn push(&mut self, stream: Stream, text: &str) {
+        if let Some(log) = self.log.as_mut() {
+            log.write_all(text.as_bytes()).ok();
+        }
+
+        let partial = self.partial(stream);
+        partial.push_str(text);
+
+        let mut lines = vec![];
+        while let Some(pos) = partial.find('\n') {
+            lines.push((stream, partial[..pos].to_string()));
+            partial.drain(..=pos);
+        }
+
+        self.lines.extend(lines);
+    }
+
+    fn open_log(&mut self, cmd: &str) -> HResult<()> {
+        let dir = crate::paths::process_logs_path()?;
+        std::fs::create_dir_all(&dir)?;
+
+        let name = cmd.chars()
+            .map(|c| if c.is_alphanumeric() || c == '-' || c == '.' { c } else { '_' })
+            .take(40)
+            .collect::<String>();
+        let time = chrono::Local::now().format("%Y%m%d-%H%M%S");
+        let path = dir.join(format!("{}-{}.log", time, name));
+
+        let mut log = std::fs::File::create(&path)?;
+        writeln!(log, "$ {}", cmd)?;
+
+        self.log = Some(log);
+        self.log_path = Some(path);
+        Ok(())
+    }
+
+    fn close_log(&mut self, status: i32) {
+        if let Some(mut log) = self.log.take() {
+            let newline = match self.stdout.is_empty() && self.stderr.is_empty() {
+                true => "",
+                false => "\n"
+            };
+            write!(log, "{}[exited with status {}]\n", newline, status).ok();
+        }
+    }
+
+    fn text(&self, streams: Streams) -> String {
+        let partials = [(Stream::Stdout, &self.stdout), (Stream::Stderr, &self.stderr)];
+
+        self.lines
+            .iter()
+            .map(|(stream, line)| (*stream, line))
+            .chain(partials.iter()
+                   .filter(|(_, partial)| !partial.is_empty())
+                   .map(|(stream, partial)| (*stream, *partial)))
+            .filter(|(stream, _)| streams.shows(*stream))
+            .map(|(stream, line)| match stream {
+                Stream::Stdout => line.clone(),
+                Stream::Stderr => format!("{}{}{}",
+                                          term::color_red(),
+                                          line,
+                                          term::normal_color())
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[derive(Debug, Default)]
+struct Timing {
+    started: Option<(chrono::DateTime<chrono::Local>, std::time::Instant)>,
+    took: Option<std::time::Duration>,
+}
+
+impl Timing {
+    // still counting while it runs
+    fn elapsed(&self) -> Option<std::time::Duration> {
+        self.took.or_else(|| self.started.map(|(_, started)| started.elapsed()))
+    }
+}
+
+fn pretty_duration(duration: std::time::Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m{:02}s", secs / 60, secs % 60),
+        _ => format!("{}h{:02}m", secs / 3600, secs % 3600 / 60)
+    }
+}
+
+#[derive(Debug, Clone)]
 struct Process {
     cmd: String,
-    handle: Arc<Mutex<Child>>,
-    output: Arc<Mutex<String>>,
+    // what's needed to run it again
+    command: OsString,
+    args: Vec<OsString>,
+    cwd: File,
+    // set by SIGSTOP and the like, until SIGCONT
+    stopped: bool,
+    keep_log: bool,
+    notify: bool,
+    direnv: bool,
+    // nice level and CPU quota, for the ones started with low priority
+    low_priority: Option<(i32, String)>,
+    follow_up: Option<(FollowUp, Vec<PathBuf>)>,
+    // nothing yet while it's queued
+    handle: Arc<Mutex<Option<Child>>>,
+    output: Arc<Mutex<Output>>,
     status: Arc<Mutex<Option<i32>>>,
     success: Arc<Mutex<Option<bool>>>,
+    timing: Arc<Mutex<Timing>>,
     sender: Sender<Events>
 
 }
@@ -42,6 +206,99 @@ pub struct Cmd {
     pub cwd_files: Option<Vec<File>>,
     pub tab_files: Option<Vec<Vec<File>>>,
     pub tab_paths: Option<Vec<File>>,
+    pub low_priority: bool,
+    // and the files it's run on
+    pub follow_up: Option<(FollowUp, Vec<PathBuf>)>,
+}
+
+// What happens once a job from a pipeline or quick action is done, set
+// with on_success=refresh,select and on_failure=output,mark
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FollowUp {
+    // reload the directory, and go to the file it made
+    pub refresh: bool,
+    pub select_output: bool,
+    // the process list is opened for the job and stays open if it failed,
+    // otherwise it closes again
+    pub show_output: bool,
+    // select the files it failed on
+    pub mark_sources: bool,
+}
+
+impl FollowUp {
+    pub fn parse(on_success: &str, on_failure: &str) -> HResult<FollowUp> {
+        let mut follow_up = FollowUp::default();
+
+        let words = |list: &str| -> Vec<String> {
+            list.split(',')
+                .map(|word| word.trim().to_string())
+                .filter(|word| !word.is_empty())
+                .collect()
+        };
+
+        for word in words(on_success) {
+            match word.as_str() {
+                "refresh" => follow_up.refresh = true,
+                "select" => follow_up.select_output = true,
+                _ => return Err(HError::Error(format!("on_success: can't {}", word)))
+            }
+        }
+
+        for word in words(on_failure) {
+            match word.as_str() {
+                "output" => follow_up.show_output = true,
+                "mark" => follow_up.mark_sources = true,
+                _ => return Err(HError::Error(format!("on_failure: can't {}", word)))
+            }
+        }
+
+        Ok(follow_up)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == FollowUp::default()
+    }
+}
+
+// Handed to the file browser when a job with a follow up is done
+#[derive(Debug, Clone)]
+pub struct FinishedJob {
+    pub follow_up: FollowUp,
+    pub cwd: PathBuf,
+    pub sources: Vec<PathBuf>,
+    pub started: SystemTime,
+    pub success: bool,
+}
+
+impl FinishedJob {
+    // Whatever in its directory changed last since it started, that
+    // isn't one of the files it was run on
+    pub fn output(&self) -> Option<PathBuf> {
+        std::fs::read_dir(&self.cwd).ok()?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| !self.sources.contains(&entry.path()))
+            .filter_map(|entry| {
+                let mtime = entry.metadata().ok()?.modified().ok()?;
+                Some((mtime, entry.path()))
+            })
+            .filter(|(mtime, _)| *mtime >= self.started)
+            .max_by_key(|(mtime, _)| *mtime)
+            .map(|(_, path)| path)
+    }
+}
+
+lazy_static! {
+    static ref FINISHED: Mutex<Vec<FinishedJob>> = Mutex::new(vec![]);
+}
+
+pub fn push_finished(job: FinishedJob) {
+    FINISHED.lock().map(|mut finished| finished.push(job)).ok();
+}
+
+pub fn take_finished() -> Vec<FinishedJob> {
+    FINISHED.lock()
+        .map(|mut finished| std::mem::replace(&mut *finished, vec![]))
+        .unwrap_or_default()
 }
 
 impl Cmd {
@@ -125,49 +382,210 @@ impl PartialEq for Process {
     }
 }
 
+lazy_static! {
+    // Waiting for a running one to finish, when process_limit is set
+    static ref QUEUE: Mutex<VecDeque<Process>> = Mutex::new(VecDeque::new());
+}
+
+static RUNNING: AtomicUsize = AtomicUsize::new(0);
+
+// Nobody needs a notification for what's right in front of them
+static VISIBLE: AtomicBool = AtomicBool::new(false);
+
+pub fn set_visible(visible: bool) {
+    VISIBLE.store(visible, Ordering::Relaxed);
+}
+
+// Fire and forget, it's fine if there's no notify-send
+fn notify(cmd: &str, status: i32, success: bool) {
+    let summary = match success {
+        true => "Process finished",
+        false => "Process failed"
+    };
+    let body = format!("{}\nexited with status {}", cmd, status);
+
+    Command::new("notify-send")
+        .arg("--app-name=hunter")
+        .arg(summary)
+        .arg(body)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .map(|mut child| std::thread::spawn(move || child.wait()))
+        .ok();
+}
+
+// Low priority jobs get nice_level and the idle I/O class, so a batch of
+// transcodes doesn't make the rest of the desktop crawl. With a cpu_quota
+// they're also put into a transient systemd scope limited to that much CPU.
+// systemd-run runs the command itself in there, so it's still the process
+// that gets killed and stopped.
+fn command(process: &Process) -> Command {
+    let quota = match &process.low_priority {
+        Some((_, quota)) if !quota.is_empty() => quota,
+        _ => {
+            let mut command = Command::new(&process.command);
+            command.args(&process.args);
+            return command;
+        }
+    };
+
+    let mut command = Command::new("systemd-run");
+    command.args(&["--user", "--scope", "--quiet", "--collect"])
+        .arg(format!("--property=CPUQuota={}", quota))
+        .arg("--")
+        .arg(&process.command)
+        .args(&process.args);
+    command
+}
+
+// Between fork and exec, so nothing but plain syscalls
+fn lower_priority(nice: i32) {
+    unsafe {
+        libc::setpriority(libc::PRIO_PROCESS as _, 0, nice);
+
+        // IOPRIO_WHO_PROCESS and the idle class
+        #[cfg(target_os = "linux")]
+        libc::syscall(libc::SYS_ioprio_set, 1, 0, 3 << 13);
+    }
+}
+
 impl Process {
-    fn read_proc(&mut self) -> HResult<()> {
+    fn pid(&self) -> Option<u32> {
+        self.handle.lock().ok()?.as_ref().map(|child| child.id())
+    }
+
+    fn start(&self) -> HResult<()> {
+        RUNNING.fetch_add(1, Ordering::SeqCst);
+
+        // stderr is kept apart, the output view can show it on its own.
+        // Its own process group makes signals reach what the shell started.
+        let nice = self.low_priority.as_ref().map(|(nice, _)| *nice);
+        let handle = unsafe {
+            command(self)
+                .direnv(&self.cwd.path, self.direnv)
+                .current_dir(&self.cwd.path)
+                .stdin(std::process::Stdio::null())
+                .stdout(std::process::Stdio::piped())
+                .stderr(std::process::Stdio::piped())
+                .pre_exec(move || {
+                    libc::setpgid(0, 0);
+                    if let Some(nice) = nice { lower_priority(nice); }
+                    Ok(())
+                })
+                .spawn()
+        };
+
+        match handle {
+            Ok(handle) => {
+                if self.keep_log {
+                    self.output.lock()?.open_log(&self.cmd).log();
+                }
+                self.timing.lock()?.started = Some((chrono::Local::now(),
+                                                    std::time::Instant::now()));
+                *self.handle.lock()? = Some(handle);
+                self.read_proc()
+            }
+            Err(e) => {
+                *self.success.lock()? = Some(false);
+                *self.status.lock()? = Some(-1);
+                Process::finished();
+                Err(e)?
+            }
+        }
+    }
+
+    // Makes room for the next one in the queue
+    fn finished() {
+        RUNNING.fetch_sub(1, Ordering::SeqCst);
+
+        let next = QUEUE.lock()
+            .ok()
+            .and_then(|mut queue| queue.pop_front());
+
+        if let Some(next) = next {
+            let status = format!("Running: {}", next.cmd);
+            next.sender.send(Events::Status(status)).ok();
+
+            if let Err(e) = next.start() {
+                let status = format!("Error! Failed to start process: {}", e);
+                next.sender.send(Events::Status(status)).ok();
+            }
+        }
+    }
+
+    // Queued ones are taken out of the queue, they can't be started again
+    fn cancel(&self) -> HResult<()> {
+        if self.pid().is_some() { return Ok(()); }
+
+        QUEUE.lock()?.retain(|queued| !Arc::ptr_eq(&queued.handle, &self.handle));
+        *self.success.lock()? = Some(false);
+        *self.status.lock()? = Some(-1);
+        Ok(())
+    }
+
+    fn read_proc(&self) -> HResult<()> {
         let handle = self.handle.clone();
         let output = self.output.clone();
         let status = self.status.clone();
         let success = self.success.clone();
+        let timing = self.timing.clone();
+        let notify_finished = self.notify;
         let sender = self.sender.clone();
         let cmd = self.cmd.clone();
-        let pid = self.handle.lock()?.id();
+        let pid = self.pid()?;
+        let follow_up = self.follow_up.clone();
+        let cwd = self.cwd.path.clone();
+        let started = SystemTime::now();
 
         std::thread::spawn(move || -> HResult<()> {
-            let stdout = handle.lock()?.stdout.take()?;
-            let mut stdout = BufReader::new(stdout);
-            let mut processor = move |cmd, sender: &Sender<Events>| -> HResult<()> {
-                loop {
-                    let buffer = stdout.fill_buf()?;
-                    let len = buffer.len();
-                    let buffer = String::from_utf8_lossy(buffer);
-
-                    if len == 0 { return Ok(()) }
-
-                    output.lock()?.push_str(&buffer);
-
-                    let status = format!("{}: read {} chars!", cmd, len);
-                    sender.send(Events::Status(status))?;
+            let stdout = handle.lock()?.as_mut()?.stdout.take()?;
+            let stderr = handle.lock()?.as_mut()?.stderr.take()?;
+
+            let stderr = {
+                let output = output.clone();
+                let sender = sender.clone();
+                let cmd = cmd.clone();
+                std::thread::spawn(move || {
+                    read_stream(stderr, Stream::Stderr, &output, &cmd, &sender).log();
+                })
+            };
 
-                    stdout.consume(len);
+            read_stream(stdout, Stream::Stdout, &output, &cmd, &sender).log();
+            stderr.join().ok();
 
-                    // Wait a bit so hunter doesn't explode
-                    std::thread::sleep(std::time::Duration::from_millis(100));
-                }
-            };
-            processor(&cmd, &sender).log();
+            let waited = handle.lock()?.as_mut()?.wait();
+            {
+                let mut timing = timing.lock()?;
+                timing.took = timing.elapsed();
+            }
+            Process::finished();
 
-            if let Ok(proc_status) = handle.lock()?.wait() {
+            if let Ok(proc_status) = waited {
                 let proc_success = proc_status.success();
                 let proc_status = match proc_status.code() {
                     Some(status) => status,
                     None => proc_status.signal().unwrap_or(-1)
                 };
 
+                if let Some((follow_up, sources)) = follow_up {
+                    push_finished(FinishedJob {
+                        follow_up: follow_up,
+                        cwd: cwd,
+                        sources: sources,
+                        started: started,
+                        success: proc_success
+                    });
+                }
+
                 *success.lock()? = Some(proc_success);
                 *status.lock()? = Some(proc_status);
+                output.lock()?.close_log(proc_status);
+
+                if notify_finished && !VISIBLE.load(Ordering::Relaxed) {
+                    notify(&cmd, proc_status, proc_success);
+                }
 
                 let color_success =
                     if proc_success {
@@ -198,6 +616,69 @@ impl Process {
     }
 }
 
+// By name, with or without SIG, or by number
+fn parse_signal(signal: &str) -> Option<libc::c_int> {
+    let signal = signal.trim().to_uppercase();
+    let signal = signal.trim_start_matches("SIG");
+
+    if let Ok(number) = signal.parse::<libc::c_int>() {
+        return Some(number).filter(|&number| number > 0 && number < 65);
+    }
+
+    let signal = match signal {
+        "HUP" => libc::SIGHUP,
+        "INT" => libc::SIGINT,
+        "QUIT" => libc::SIGQUIT,
+        "KILL" => libc::SIGKILL,
+        "USR1" => libc::SIGUSR1,
+        "USR2" => libc::SIGUSR2,
+        "TERM" => libc::SIGTERM,
+        "CONT" => libc::SIGCONT,
+        "STOP" => libc::SIGSTOP,
+        "TSTP" => libc::SIGTSTP,
+        "WINCH" => libc::SIGWINCH,
+        _ => return None
+    };
+
+    Some(signal)
+}
+
+fn shell_quote(arg: &str) -> String {
+    let plain = !arg.is_empty() && arg.chars()
+        .all(|c| c.is_alphanumeric() || "-_./=:,+@%".contains(c));
+
+    match plain {
+        true => arg.to_string(),
+        false => format!("'{}'", arg.replace("'", "'\\''"))
+    }
+}
+
+fn read_stream<R: Read>(stream: R,
+                        kind: Stream,
+                        output: &Mutex<Output>,
+                        cmd: &str,
+                        sender: &Sender<Events>) -> HResult<()> {
+    let mut stream = BufReader::new(stream);
+
+    loop {
+        let buffer = stream.fill_buf()?;
+        let len = buffer.len();
+        let buffer = String::from_utf8_lossy(buffer);
+
+        if len == 0 { return Ok(()) }
+
+        output.lock()?.push(kind, &buffer);
+
+        let status = format!("{}: read {} chars!", cmd, len);
+        sender.send(Events::Status(status))?;
+
+        stream.consume(len);
+
+        // Wait a bit so hunter doesn't explode
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+}
+
 impl Listable for ListView<Vec<Process>> {
     type Item = ();
     fn len(&self) -> usize { self.content.len() }
@@ -262,38 +743,45 @@ impl ListView<Vec<Process>> {
                        .to_string());
         let args = cmd.args.unwrap_or(vec![]);
 
-        self.core.show_status(&format!("Running: {}", &short_cmd)).log();
-
-        // Need pre_exec here to interleave stderr with stdout
-        let handle = unsafe {
-            Command::new(real_cmd)
-                .args(args)
-                .stdin(std::process::Stdio::null())
-                .stdout(std::process::Stdio::piped())
-                // Without this stderr would be separate which is no good for procview
-                .pre_exec(||  { libc::dup2(1, 2); Ok(()) })
-                .spawn()
+        let proc = Process {
+            cmd: short_cmd.clone(),
+            command: real_cmd,
+            args: args,
+            cwd: cmd.cwd,
+            stopped: false,
+            keep_log: self.core.config().process_logs,
+            notify: self.core.config().process_notify,
+            direnv: self.core.config().direnv,
+            low_priority: match cmd.low_priority {
+                true => Some((self.core.config().nice_level,
+                              self.core.config().cpu_quota.clone())),
+                false => None
+            },
+            follow_up: cmd.follow_up,
+            handle: Arc::new(Mutex::new(None)),
+            output: Arc::new(Mutex::new(Output::default())),
+            status: Arc::new(Mutex::new(None)),
+            success: Arc::new(Mutex::new(None)),
+            timing: Arc::new(Mutex::new(Timing::default())),
+            sender: self.get_core()?.get_sender()
         };
 
-        let handle = match handle {
-            Ok(handle) => handle,
-            Err(e) => {
+        let limit = self.core.config().process_limit;
+
+        if limit > 0 && RUNNING.load(Ordering::SeqCst) >= limit {
+            QUEUE.lock()?.push_back(proc.clone());
+            self.core.show_status(&format!("Queued: {}", &short_cmd)).log();
+        } else {
+            self.core.show_status(&format!("Running: {}", &short_cmd)).log();
+
+            if let Err(e) = proc.start() {
                 let msg = format!("Error! Failed to start process: {}",
                                   e);
                 self.core.show_status(&msg)?;
-                return Err(e)?;
+                return Err(e);
             }
-        };
+        }
 
-        let mut proc = Process {
-            cmd: short_cmd,
-            handle: Arc::new(Mutex::new(handle)),
-            output: Arc::new(Mutex::new(String::new())),
-            status: Arc::new(Mutex::new(None)),
-            success: Arc::new(Mutex::new(None)),
-            sender: self.get_core()?.get_sender()
-        };
-        proc.read_proc()?;
         self.content.push(proc);
         Ok(())
     }
@@ -305,6 +793,7 @@ impl ListView<Vec<Process>> {
                                       .to_string_lossy()
                                       .to_string());
         let args = cmd.args.unwrap_or(vec![]);
+        let direnv = self.core.config().direnv;
 
         self.core.show_status(&format!("Running (fg): {}", &short_cmd)).log();
 
@@ -314,6 +803,7 @@ impl ListView<Vec<Process>> {
 
         match Command::new(real_cmd)
             .args(args)
+            .direnv(&cmd.cwd.path, direnv)
             .status() {
                 Ok(status) => {
                     let color_success =
@@ -359,9 +849,100 @@ impl ListView<Vec<Process>> {
         Ok(())
     }
 
+    // Finished ones only, or there would be two of them at once
+    fn rerun_proc(&mut self, edit: bool) -> HResult<()> {
+        let (mut cmd, running) = {
+            let proc = self.selected_proc()?;
+            let running = proc.status.lock()?.is_none();
+            let cmd = Cmd {
+                cmd: proc.command.clone(),
+                args: Some(proc.args.clone()),
+                vars: None,
+                short_cmd: Some(proc.cmd.clone()),
+                cwd: proc.cwd.clone(),
+                cwd_files: None,
+                tab_files: None,
+                tab_paths: None,
+                low_priority: proc.low_priority.is_some(),
+                follow_up: proc.follow_up.clone()
+            };
+            (cmd, running)
+        };
+
+        if running {
+            return self.core.show_status("Still running!");
+        }
+
+        if edit {
+            let args = cmd.args.clone().unwrap_or(vec![]);
+            let cmdline = match args.as_slice() {
+                [flag, cmdline] if flag == "-c" => cmdline.to_string_lossy().to_string(),
+                _ => std::iter::once(&cmd.cmd)
+                    .chain(args.iter())
+                    .map(|arg| shell_quote(&arg.to_string_lossy()))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            };
+
+            let cmdline = self.core.minibuffer_prefilled("rerun", &cmdline)?;
+            let shell = std::env::var("SHELL").unwrap_or("sh".into());
+
+            cmd.cmd = OsString::from(shell);
+            cmd.args = Some(vec![OsString::from("-c"), OsString::from(&cmdline)]);
+            cmd.short_cmd = Some(cmdline);
+        }
+
+        self.run_proc_raw(cmd)?;
+        self.move_bottom();
+        Ok(())
+    }
+
+    fn log_path(&mut self) -> HResult<PathBuf> {
+        let proc = self.selected_proc()?;
+        let path = proc.output.lock()?.log_path.clone();
+        Ok(path?)
+    }
+
     fn kill_proc(&mut self) -> HResult<()> {
         let proc = self.selected_proc()?;
-        proc.handle.lock()?.kill()?;
+        proc.cancel()?;
+        if let Some(child) = proc.handle.lock()?.as_mut() {
+            child.kill()?;
+        }
+        Ok(())
+    }
+
+    fn signal_proc(&mut self) -> HResult<()> {
+        let query = "signal (TERM, STOP, CONT, HUP, INT, KILL, USR1, USR2 or number)";
+        let signal = self.core.minibuffer(query)?;
+        let signal = match parse_signal(&signal) {
+            Some(signal) => signal,
+            None => return self.core.show_status(&format!("No signal {}", signal))
+        };
+
+        let (finished, pid) = {
+            let proc = self.selected_proc()?;
+            let finished = proc.status.lock()?.is_some();
+            (finished, proc.pid())
+        };
+
+        let pid = match (finished, pid) {
+            (true, _) => return self.core.show_status("Already finished!"),
+            (false, None) => return self.core.show_status("Not started yet!"),
+            (false, Some(pid)) => pid as libc::pid_t
+        };
+
+        if unsafe { libc::kill(-pid, signal) } != 0 {
+            return Err(std::io::Error::last_os_error())?;
+        }
+
+        match signal {
+            libc::SIGSTOP | libc::SIGTSTP => self.selected_proc()?.stopped = true,
+            libc::SIGCONT => self.selected_proc()?.stopped = false,
+            _ => {}
+        }
+
+        self.core.set_dirty();
         Ok(())
     }
 
@@ -378,10 +959,15 @@ impl ListView<Vec<Process>> {
     }
 
     pub fn render_proc(&self, proc: &Process) -> HResult<String> {
-        let pid = proc.handle.lock()?.id();
-        let status = match *proc.status.lock()? {
-            Some(status) => format!("{}", status),
-            None => format!("<{}>", pid),
+        let elapsed = proc.timing.lock()?
+            .elapsed()
+            .map(|elapsed| format!("{} ", pretty_duration(elapsed)))
+            .unwrap_or_default();
+        let status = match (*proc.status.lock()?, proc.pid()) {
+            (Some(status), _) => format!("{}{}", elapsed, status),
+            (None, None) => String::from("queued"),
+            (None, Some(pid)) if proc.stopped => format!("{}stopped <{}>", elapsed, pid),
+            (None, Some(pid)) => format!("{}<{}>", elapsed, pid),
         };
 
         let xsize = self.get_coordinates()?.xsize();
@@ -392,6 +978,7 @@ impl ListView<Vec<Process>> {
 
         let color_status = match *proc.success.lock().unwrap() {
             Some(false) => { format!("{}{}", term::color_red(), status) }
+            None if proc.stopped => { format!("{}{}", term::color_yellow(), status) }
             _ => { status }
         };
 
@@ -448,7 +1035,12 @@ pub struct ProcView {
     core: WidgetCore,
     hbox: HBox<ProcViewWidgets>,
     viewing: Option<usize>,
-    animator: Stale
+    streams: Streams,
+    // kept when looking at another process
+    search: Option<String>,
+    animator: Stale,
+    // opened for this one, see show_last
+    watched: Option<Arc<Mutex<Option<bool>>>>
 }
 
 impl HBox<ProcViewWidgets> {
@@ -470,6 +1062,12 @@ impl HBox<ProcViewWidgets> {
             _ => unreachable!()
         }
     }
+    fn get_textview_ref(&self) -> &AsyncWidget<TextView> {
+        match &self.widgets[1] {
+            ProcViewWidgets::TextView(textview) => textview,
+            _ => unreachable!()
+        }
+    }
 }
 
 impl ProcView {
@@ -484,12 +1082,16 @@ impl ProcView {
         hbox.push_widget(ProcViewWidgets::List(listview));
         hbox.push_widget(ProcViewWidgets::TextView(textview));
         hbox.set_ratios(vec![33, 66]);
+        hbox.set_active(0).log();
         hbox.refresh().log();
         ProcView {
             core: core.clone(),
             hbox: hbox,
             viewing: None,
-            animator: Stale::new()
+            streams: Streams::Both,
+            search: None,
+            animator: Stale::new(),
+            watched: None
         }
     }
 
@@ -515,6 +1117,39 @@ impl ProcView {
         Ok(())
     }
 
+    // Opened for the job started last, it closes by itself when that went
+    // fine and stays open with the output when it didn't
+    pub fn show_last(&mut self) -> HResult<()> {
+        let last = self.get_listview().len().checked_sub(1)?;
+        self.get_listview_mut().set_selection(last);
+        self.watched = Some(self.get_listview().content.get(last)?.success.clone());
+
+        let ticker = self.start_ticker();
+        set_visible(true);
+        let result = self.popup();
+        set_visible(false);
+        ticker.set_stale().ok();
+        self.watched = None;
+
+        match result {
+            Ok(_) | Err(HError::PopupFinnished) => Ok(()),
+            err @ Err(_) => err
+        }
+    }
+
+    pub fn running_procs(&self) -> usize {
+        self.get_listview()
+            .content
+            .iter()
+            .filter(|proc| proc.status.lock().unwrap().is_none())
+            .filter(|proc| proc.pid().is_some())
+            .count()
+    }
+
+    pub fn queued_procs(&self) -> usize {
+        QUEUE.lock().map(|queue| queue.len()).unwrap_or(0)
+    }
+
     pub fn remove_proc(&mut self) -> HResult<()> {
         if self.get_listview_mut().content.len() == 0 { return Ok(()) }
         self.get_listview_mut().remove_proc()?;
@@ -528,7 +1163,9 @@ impl ProcView {
         if Some(self.get_listview_mut().get_selection()) == self.viewing {
             return Ok(());
         }
-        let output = self.get_listview_mut().selected_proc()?.output.lock()?.clone();
+        let streams = self.streams;
+        let search = self.search.clone();
+        let output = self.get_listview_mut().selected_proc()?.output.lock()?.text(streams);
 
         let animator = self.animator.clone();
         animator.set_fresh().log();
@@ -536,7 +1173,8 @@ impl ProcView {
         self.get_textview().change_to(move |_, core| {
             let mut textview = TextView::new_blank(&core);
             textview.set_text(&output).log();
-            textview.animate_slide_up(Some(&animator)).log();
+            textview.set_search(search);
+            textview.animate(Animation::SlideUp, Some(&animator)).log();
             Ok(textview)
         }).log();
 
@@ -544,6 +1182,99 @@ impl ProcView {
         Ok(())
     }
 
+    pub fn toggle_streams(&mut self) -> HResult<()> {
+        self.streams = self.streams.next();
+        self.viewing = None;
+
+        let showing = match self.streams {
+            Streams::Both => "stdout and stderr",
+            Streams::Stdout => "stdout only",
+            Streams::Stderr => "stderr only"
+        };
+        self.core.show_status(&format!("Showing {}", showing))
+    }
+
+    // Redraws every second until it's set stale, so the times of running
+    // processes count up even when they're quiet
+    pub fn start_ticker(&self) -> Stale {
+        let ticker = Stale::new();
+        let sender = self.core.get_sender();
+
+        let stop = ticker.clone();
+        std::thread::spawn(move || {
+            while let Ok(false) = stop.is_stale() {
+                std::thread::sleep(std::time::Duration::from_secs(1));
+                if sender.send(Events::WidgetReady).is_err() { break; }
+            }
+        });
+
+        ticker
+    }
+
+    // Empty input stops highlighting
+    pub fn search_output(&mut self) -> HResult<()> {
+        let search = match self.core.minibuffer("search output") {
+            Ok(search) => Some(search),
+            Err(HError::MiniBufferEmptyInput) => None,
+            Err(e) => Err(e)?
+        };
+        self.search = search.clone();
+
+        let textview = self.get_textview().widget_mut()?;
+        textview.set_search(search.clone());
+
+        let search = match search {
+            Some(search) => search,
+            None => return Ok(())
+        };
+
+        match textview.search_here() {
+            Some(_) => {
+                let matches = textview.search_matches();
+                self.core.show_status(&format!("{} lines with \"{}\"", matches, search))
+            }
+            None => self.core.show_status(&format!("No \"{}\" in the output", search))
+        }
+    }
+
+    pub fn search_output_next(&mut self, backwards: bool) -> HResult<()> {
+        if self.search.is_none() {
+            return self.core.show_status("Nothing to search for!");
+        }
+
+        let textview = self.get_textview().widget_mut()?;
+        let found = match backwards {
+            true => textview.search_prev(),
+            false => textview.search_next()
+        };
+
+        match found {
+            Some(line) => self.core.show_status(&format!("Line {}", line + 1)),
+            None => self.core.show_status("No match!")
+        }
+    }
+
+    // Running ones too, the pager just shows what's there so far
+    pub fn show_log(&mut self) -> HResult<()> {
+        let path = match self.get_listview_mut().log_path() {
+            Ok(path) => path,
+            Err(_) => return self.core.show_status("No log for this process!")
+        };
+
+        let mut pager = crate::textview::Pager::new(&self.core, &path)?;
+        match pager.popup() {
+            Ok(_) | Err(HError::PopupFinnished) => {},
+            err @ Err(_) => err?
+        }
+        self.core.clear()
+    }
+
+    pub fn rerun_proc(&mut self, edit: bool) -> HResult<()> {
+        self.get_listview_mut().rerun_proc(edit)?;
+        self.viewing = None;
+        Ok(())
+    }
+
     pub fn toggle_follow(&mut self) -> HResult<()> {
         self.get_textview().widget_mut()?.toggle_follow();
         Ok(())
@@ -578,6 +1309,69 @@ impl ProcView {
         self.get_textview().widget_mut()?.scroll_bottom();
         Ok(())
     }
+
+    fn output_focused(&self) -> bool {
+        self.hbox.focused_index() == Some(1)
+    }
+
+    pub fn toggle_wrap(&mut self) -> HResult<()> {
+        self.get_textview().widget_mut()?.toggle_wrap();
+        Ok(())
+    }
+
+    pub fn scroll_left(&mut self) -> HResult<()> {
+        self.get_textview().widget_mut()?.scroll_left();
+        Ok(())
+    }
+
+    pub fn scroll_right(&mut self) -> HResult<()> {
+        self.get_textview().widget_mut()?.scroll_right();
+        Ok(())
+    }
+
+    pub fn toggle_selection(&mut self) -> HResult<()> {
+        self.get_textview().widget_mut()?.toggle_selection();
+        Ok(())
+    }
+
+    // All of it, as far as it's shown, without colors
+    pub fn save_output(&mut self) -> HResult<()> {
+        const CLIPBOARD_MAX: usize = 100 * 1024;
+
+        let streams = self.streams;
+        let (output, cwd) = {
+            let proc = self.get_listview_mut().selected_proc()?;
+            let output = proc.output.lock()?.text(streams);
+            (term::strip_ansi(&output), proc.cwd.path.clone())
+        };
+        let lines = output.lines().count();
+
+        match self.core.minibuffer("save output to (empty for clipboard)") {
+            Ok(path) => {
+                let home = crate::paths::home_path().ok();
+                let path = crate::lineedit::parse_path(&path,
+                                                       &cwd,
+                                                       home.as_ref().map(|h| h.as_path()));
+                std::fs::write(&path, output + "\n")?;
+                self.core.show_status(&format!("Saved {} lines to {}",
+                                               lines,
+                                               path.to_string_lossy()))
+            }
+            Err(HError::MiniBufferEmptyInput) => {
+                if output.len() > CLIPBOARD_MAX {
+                    return self.core.show_status(&format!("Output is too big to copy, {}K max",
+                                                          CLIPBOARD_MAX / 1024));
+                }
+                self.core.screen()?.set_clipboard(&output)?;
+                self.core.show_status(&format!("Copied {} lines to clipboard", lines))
+            }
+            Err(e) => Err(e)
+        }
+    }
+
+    pub fn copy_selection(&mut self) -> HResult<()> {
+        self.get_textview().widget_mut()?.copy_selection()
+    }
 }
 
 impl Widget for ProcView {
@@ -594,18 +1388,17 @@ impl Widget for ProcView {
     }
 
     fn render_header(&self) -> HResult<String> {
-        let listview = self.get_listview();
-        let procs_num = listview.len();
-        let procs_running = listview
-            .content
-            .iter()
-            .filter(|proc| proc.status.lock().unwrap().is_none())
-            .count();
+        let procs_num = self.get_listview().len();
+        let procs_running = self.running_procs();
 
         let header = format!("Running processes: {} / {}",
                              procs_running,
                              procs_num);
-        Ok(header)
+
+        match self.queued_procs() {
+            0 => Ok(header),
+            queued => Ok(format!("{}, {} queued", header, queued))
+        }
     }
 
     fn render_footer(&self) -> HResult<String> {
@@ -615,7 +1408,9 @@ impl Widget for ProcView {
 
         if let Some(proc) = listview.content.get(selection) {
             let cmd = &proc.cmd;
-            let pid = proc.handle.lock()?.id();
+            let pid = proc.pid()
+                .map(|pid| pid.to_string())
+                .unwrap_or_else(|| String::from("-"));
             let proc_status = proc.status.lock()?;
             let proc_success = proc.success.lock()?;
 
@@ -644,8 +1439,35 @@ impl Widget for ProcView {
                                      term::status_bg(),
                                      color_status);
                 procinfo
+            } else if proc.pid().is_none() {
+                "queued".to_string()
             } else { "still running".to_string() };
 
+            let timing = proc.timing.lock()?;
+            let procinfo = match (timing.started, timing.elapsed()) {
+                (Some((started, _)), Some(elapsed)) => {
+                    let verb = if timing.took.is_some() { "took" } else { "running for" };
+                    format!("{}, started {}, {} {}",
+                            procinfo,
+                            started.format("%T"),
+                            verb,
+                            pretty_duration(elapsed))
+                }
+                _ => procinfo
+            };
+
+            let column = self.hbox
+                .get_textview_ref()
+                .widget()
+                .map(|textview| textview.column_indicator())
+                .unwrap_or_default();
+            let streams = match self.streams {
+                Streams::Both => "",
+                Streams::Stdout => "[stdout] ",
+                Streams::Stderr => "[stderr] "
+            };
+            let procinfo = format!("[{}] {}{}", column, streams, procinfo);
+
             let footer = term::sized_string_u(&procinfo, xsize);
 
             Ok(footer)
@@ -661,6 +1483,18 @@ impl Widget for ProcView {
             self.get_textview().refresh().log();
         }
 
+        let succeeded = self.watched
+            .as_ref()
+            .map(|success| success.lock().map(|s| *s == Some(true)).unwrap_or(false))
+            .unwrap_or(false);
+
+        if succeeded {
+            self.watched = None;
+            self.animator.set_stale().log();
+            self.core.clear().log();
+            return HError::popup_finnished();
+        }
+
         Ok(())
     }
     fn get_drawlist(&self) -> HResult<String> {
@@ -668,7 +1502,11 @@ impl Widget for ProcView {
     }
     fn on_key(&mut self, key: Key) -> HResult<()> {
         self.do_key(key)?;
-        self.refresh().log();
+        match self.refresh() {
+            err @ Err(HError::PopupFinnished) => err?,
+            err @ Err(_) => err.log(),
+            Ok(_) => {}
+        }
         self.draw().log();
 
         Ok(())
@@ -687,7 +1525,15 @@ impl Acting for ProcView {
     }
 
     fn movement(&mut self, movement: &Movement) -> HResult<()> {
-        self.get_listview_mut().movement(movement)
+        if self.output_focused() {
+            return self.get_textview().widget_mut()?.movement(movement);
+        }
+
+        match movement {
+            Movement::Left => self.scroll_left(),
+            Movement::Right => self.scroll_right(),
+            _ => self.get_listview_mut().movement(movement)
+        }
     }
 
     fn do_action(&mut self, action: &Self::Action) -> HResult<()> {
@@ -699,13 +1545,26 @@ impl Acting for ProcView {
                        Err(HError::PopupFinnished)? }
             Remove => self.remove_proc()?,
             Kill => self.get_listview_mut().kill_proc()?,
+            SendSignal => self.get_listview_mut().signal_proc()?,
+            Rerun => self.rerun_proc(false)?,
+            EditRerun => self.rerun_proc(true)?,
             FollowOutput => self.toggle_follow()?,
             ScrollOutputDown => self.scroll_down()?,
             ScrollOutputUp => self.scroll_up()?,
             ScrollOutputPageDown => self.page_down()?,
             ScrollOutputPageUp => self.page_up()?,
             ScrollOutputBottom => self.scroll_bottom()?,
-            ScrollOutputTop => self.scroll_top()?
+            ScrollOutputTop => self.scroll_top()?,
+            SelectOutput => self.toggle_selection()?,
+            CopyOutput => self.copy_selection()?,
+            SaveOutput => self.save_output()?,
+            ToggleWrap => self.toggle_wrap()?,
+            ToggleStreams => self.toggle_streams()?,
+            ShowLog => self.show_log()?,
+            SearchOutput => self.search_output()?,
+            SearchOutputNext => self.search_output_next(false)?,
+            SearchOutputPrev => self.search_output_next(true)?,
+            FocusNext => self.hbox.focus_next()?
         }
 
         Ok(())
@@ -0,0 +1,200 @@
+use lazy_static;
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::fail::HResult;
+
+
+// With --profile, hunter keeps track of how long the interesting things
+// take and writes a summary when it quits. --profile-trace additionally
+// records every single span in Chrome's trace format, which can be looked
+// at in chrome://tracing or Perfetto. When neither is given, a span costs
+// one atomic load.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+// a big directory makes lots of metadata spans, don't eat all memory
+const TRACE_MAX: usize = 1_000_000;
+
+#[derive(Debug, Default)]
+struct Stats {
+    count: u64,
+    total: Duration,
+    max: Duration,
+}
+
+#[derive(Debug)]
+struct TraceEvent {
+    name: &'static str,
+    thread: String,
+    start: Duration,
+    duration: Duration,
+}
+
+#[derive(Debug)]
+struct Profile {
+    started: Instant,
+    report: PathBuf,
+    trace: Option<PathBuf>,
+    stats: HashMap<&'static str, Stats>,
+    events: Vec<TraceEvent>,
+}
+
+lazy_static! {
+    static ref PROFILE: Mutex<Option<Profile>> = Mutex::new(None);
+}
+
+// hunter changes its working directory while browsing
+fn absolute(path: &Path) -> PathBuf {
+    std::env::current_dir()
+        .map(|cwd| cwd.join(path))
+        .unwrap_or_else(|_| path.to_path_buf())
+}
+
+pub fn enable(report: &Path, trace: Option<&Path>) {
+    let profile = Profile {
+        started: Instant::now(),
+        report: absolute(report),
+        trace: trace.map(absolute),
+        stats: HashMap::new(),
+        events: vec![]
+    };
+
+    if let Ok(mut global) = PROFILE.lock() {
+        *global = Some(profile);
+        ENABLED.store(true, Ordering::Relaxed);
+    }
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+// Measures from here until it's dropped
+pub struct Span {
+    name: &'static str,
+    start: Instant,
+}
+
+pub fn span(name: &'static str) -> Option<Span> {
+    if !is_enabled() { return None; }
+
+    Some(Span {
+        name: name,
+        start: Instant::now()
+    })
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        let duration = self.start.elapsed();
+
+        let mut profile = match PROFILE.lock() {
+            Ok(profile) => profile,
+            Err(_) => return
+        };
+        let profile = match profile.as_mut() {
+            Some(profile) => profile,
+            None => return
+        };
+
+        let stats = profile.stats.entry(self.name).or_default();
+        stats.count += 1;
+        stats.total += duration;
+        stats.max = stats.max.max(duration);
+
+        if profile.trace.is_some() && profile.events.len() < TRACE_MAX {
+            let thread = std::thread::current();
+            let start = self.start.saturating_duration_since(profile.started);
+
+            profile.events.push(TraceEvent {
+                name: self.name,
+                thread: thread.name()
+                    .map(String::from)
+                    .unwrap_or_else(|| format!("{:?}", thread.id())),
+                start: start,
+                duration: duration
+            });
+        }
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+// Slowest in total first, that's where the time goes
+fn report(profile: &Profile) -> String {
+    let mut stats = profile.stats.iter().collect::<Vec<_>>();
+    stats.sort_by(|(_, a), (_, b)| b.total.cmp(&a.total));
+
+    let header = format!("hunter profile, {:.1}s\n\n{:20} {:>8} {:>12} {:>10} {:>10}\n",
+                         profile.started.elapsed().as_secs_f64(),
+                         "span",
+                         "count",
+                         "total ms",
+                         "mean ms",
+                         "max ms");
+
+    let lines = stats.iter()
+        .map(|(name, stats)| {
+            let mean = stats.total / stats.count.max(1) as u32;
+            format!("{:20} {:>8} {:>12.2} {:>10.3} {:>10.3}\n",
+                    name,
+                    stats.count,
+                    millis(stats.total),
+                    millis(mean),
+                    millis(stats.max))
+        })
+        .collect::<String>();
+
+    header + &lines
+}
+
+fn write_trace(path: &Path, events: &[TraceEvent]) -> HResult<()> {
+    let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    let mut threads: HashMap<&str, usize> = HashMap::new();
+
+    writeln!(file, "[")?;
+
+    for (i, event) in events.iter().enumerate() {
+        let next_tid = threads.len();
+        let tid = *threads.entry(event.thread.as_str()).or_insert(next_tid);
+        let separator = if i + 1 < events.len() { "," } else { "" };
+
+        writeln!(file,
+                 "{{\"name\":\"{}\",\"ph\":\"X\",\"pid\":1,\"tid\":{},\
+                  \"ts\":{},\"dur\":{}}}{}",
+                 event.name,
+                 tid,
+                 event.start.as_micros(),
+                 event.duration.as_micros(),
+                 separator)?;
+    }
+
+    writeln!(file, "]")?;
+    Ok(())
+}
+
+// Called on the way out
+pub fn finish() -> HResult<()> {
+    if !is_enabled() { return Ok(()); }
+    ENABLED.store(false, Ordering::Relaxed);
+
+    let profile = match PROFILE.lock()?.take() {
+        Some(profile) => profile,
+        None => return Ok(())
+    };
+
+    std::fs::write(&profile.report, report(&profile))?;
+
+    if let Some(trace) = &profile.trace {
+        write_trace(trace, &profile.events)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,196 @@
+use lazy_static;
+use termion::event::Key;
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::fail::HResult;
+use crate::files::Files;
+use crate::keybind::{Acting, Bindings, Movement, ProjectPickerAction};
+use crate::listview::{Listable, ListView};
+use crate::term;
+use crate::widget::Widget;
+
+
+// With project_detection on, every directory hunter goes to is checked for
+// being in a project, the top of which has one of these in it. The ones
+// used last are kept, so it's quick to jump back to them in their own tab.
+const MARKERS: &[&str] = &[".git", "Cargo.toml", "package.json", "go.mod", "pyproject.toml"];
+
+// more than that and it's easier to just go there
+const RECENT_MAX: usize = 30;
+
+lazy_static! {
+    // saves writing the file on every step inside the same project
+    static ref LAST_ROOT: Mutex<Option<PathBuf>> = Mutex::new(None);
+}
+
+// The nearest one up, the home directory doesn't count even if the
+// dotfiles are in git
+pub fn find_root(path: &Path) -> Option<PathBuf> {
+    let home = crate::paths::home_path().ok();
+
+    path.ancestors()
+        .take_while(|dir| home.as_ref().map(|home| *dir != home.as_path()).unwrap_or(true))
+        .find(|dir| MARKERS.iter().any(|marker| dir.join(marker).exists()))
+        .map(|dir| dir.to_path_buf())
+}
+
+// Most recently used first, gone ones are left out
+pub fn recent() -> Vec<PathBuf> {
+    crate::paths::projects_path()
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .unwrap_or_default()
+        .lines()
+        .map(PathBuf::from)
+        .filter(|root| root.is_dir())
+        .collect()
+}
+
+pub fn remember(path: &Path) -> HResult<()> {
+    let root = match find_root(path) {
+        Some(root) => root,
+        None => return Ok(())
+    };
+
+    let mut last_root = LAST_ROOT.lock()?;
+    if last_root.as_ref() == Some(&root) { return Ok(()); }
+
+    let mut roots = recent();
+    roots.retain(|known| *known != root);
+    roots.insert(0, root.clone());
+    roots.truncate(RECENT_MAX);
+
+    let roots = roots.iter()
+        .map(|root| format!("{}\n", root.to_string_lossy()))
+        .collect::<String>();
+    std::fs::write(crate::paths::projects_path()?, roots)?;
+
+    *last_root = Some(root);
+    Ok(())
+}
+
+// Ignored files are treated like hidden ones in project tabs, so build
+// output and the like stay out of the way until hidden files are shown.
+// Every .gitignore from the top of the project down to here counts.
+pub fn hide_ignored(root: &Path, files: &mut Files) {
+    let dir = files.directory.path.clone();
+    if !dir.starts_with(root) { return; }
+
+    let mut dirs = dir.ancestors()
+        .take_while(|dir| dir.starts_with(root))
+        .collect::<Vec<_>>();
+    dirs.reverse();
+
+    let patterns = dirs.iter()
+        .flat_map(|dir| crate::tree::ignore_patterns(dir))
+        .collect::<Vec<_>>();
+    if patterns.is_empty() { return; }
+
+    for file in files.files.iter_mut() {
+        if crate::tree::is_ignored(&patterns, &file.name, file.is_dir()) {
+            file.hidden = true;
+        }
+    }
+
+    files.recalculate_len();
+}
+
+pub fn name(root: &Path) -> String {
+    root.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| root.to_string_lossy().to_string())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Project {
+    pub root: PathBuf,
+    picked: bool,
+}
+
+impl Project {
+    pub fn new(root: PathBuf) -> Project {
+        Project {
+            root: root,
+            picked: false
+        }
+    }
+}
+
+pub type ProjectPicker = ListView<Vec<Project>>;
+
+impl ProjectPicker {
+    pub fn picked(&self) -> Option<PathBuf> {
+        self.content.iter()
+            .find(|project| project.picked)
+            .map(|project| project.root.clone())
+    }
+}
+
+impl Listable for ListView<Vec<Project>> {
+    type Item = ();
+
+    fn len(&self) -> usize {
+        self.content.len()
+    }
+
+    fn render(&self) -> Vec<String> {
+        self.content
+            .iter()
+            .map(|project| format!("{}{} {}{}{}",
+                                   term::normal_color(),
+                                   term::sanitize(&name(&project.root)),
+                                   term::color_cyan(),
+                                   term::sanitize(&project.root.to_string_lossy()),
+                                   term::normal_color()))
+            .collect()
+    }
+
+    fn render_header(&self) -> HResult<String> {
+        Ok(format!("Projects: {} used recently", self.content.len()))
+    }
+
+    fn on_key(&mut self, key: Key) -> HResult<()> {
+        self.do_key(key)
+    }
+}
+
+impl Acting for ListView<Vec<Project>> {
+    type Action = ProjectPickerAction;
+
+    fn search_in(&self) -> Bindings<Self::Action> {
+        self.core.config().keybinds.projectpicker
+    }
+
+    fn movement(&mut self, movement: &Movement) -> HResult<()> {
+        use Movement::*;
+
+        match movement {
+            Up(n) => { for _ in 0..*n { self.move_up(); }; self.refresh()?; }
+            Down(n) => { for _ in 0..*n { self.move_down(); }; self.refresh()?; }
+            PageUp => self.page_up(),
+            PageDown => self.page_down(),
+            Top => self.move_top(),
+            Bottom => self.move_bottom(),
+            Left | Right => {}
+        }
+
+        Ok(())
+    }
+
+    fn do_action(&mut self, action: &Self::Action) -> HResult<()> {
+        use ProjectPickerAction::*;
+
+        match action {
+            Close => self.popup_finnished()?,
+            Pick => {
+                let selection = self.get_selection();
+                self.content.get_mut(selection)?.picked = true;
+                self.popup_finnished()?
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+use std::path::{Path, PathBuf};
+
+use crate::fail::HResult;
+use crate::widget::WidgetCore;
+
+
+// Some places should never go by accident, no matter how fast the fingers
+// are. Anything that deletes, moves, renames or changes files checks with
+// guard() first. A protected path itself and everything it's in can't be
+// touched at all, things inside of it only after typing its name.
+pub fn paths(setting: &str) -> Vec<PathBuf> {
+    let home = crate::paths::home_path().ok();
+
+    setting.split(',')
+        .map(|path| path.trim())
+        .filter(|path| !path.is_empty())
+        .flat_map(|path| match path {
+            "~" => home.iter().cloned().collect::<Vec<_>>(),
+            _ if path.starts_with("~/") => home.iter()
+                .map(|home| home.join(&path[2..]))
+                .collect(),
+            _ => vec![PathBuf::from(path)]
+        })
+        .map(|path| path.canonicalize().unwrap_or(path))
+        .collect()
+}
+
+// Only the directory part is resolved, a link to somewhere protected is
+// still just a link
+fn normalize(path: &Path) -> PathBuf {
+    let parent = path.parent().and_then(|parent| parent.canonicalize().ok());
+
+    match (parent, path.file_name()) {
+        (Some(parent), Some(name)) => parent.join(name),
+        _ => path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+    }
+}
+
+// False if nothing should happen to any of the paths
+pub fn guard(core: &WidgetCore, paths: &[PathBuf]) -> HResult<bool> {
+    check(core, paths, true)
+}
+
+// For changes that don't reach into directories, like setting times
+pub fn guard_shallow(core: &WidgetCore, paths: &[PathBuf]) -> HResult<bool> {
+    check(core, paths, false)
+}
+
+fn check(core: &WidgetCore, paths: &[PathBuf], deep: bool) -> HResult<bool> {
+    let protected = self::paths(&core.config().protected_paths);
+    if protected.is_empty() { return Ok(true); }
+
+    let mut confirm = vec![];
+
+    for path in paths.iter().map(|path| normalize(path)) {
+        let hit = protected.iter().find(|protected| match deep {
+            true => protected.starts_with(&path),
+            false => **protected == path
+        });
+
+        if let Some(hit) = hit {
+            core.show_status(&format!("{} is protected", hit.to_string_lossy()))?;
+            return Ok(false);
+        }
+
+        for dir in protected.iter().filter(|protected| path.starts_with(protected)) {
+            if !confirm.contains(dir) {
+                confirm.push(dir.clone());
+            }
+        }
+    }
+
+    for dir in confirm {
+        let name = dir.file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| dir.to_string_lossy().to_string());
+        let query = format!("{} is protected! Type {} to go ahead",
+                            dir.to_string_lossy(),
+                            name);
+
+        if core.minibuffer(&query)? != name {
+            core.show_status("Nothing changed")?;
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
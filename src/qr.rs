@@ -0,0 +1,141 @@
+use termion::event::Key;
+use unicode_width::UnicodeWidthStr;
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use crate::coordinates::Coordinates;
+use crate::fail::{HResult, HError};
+use crate::term;
+use crate::widget::{Widget, WidgetCore};
+
+
+// Anything bigger doesn't fit on a screen anyway, let alone a phone camera
+pub const QR_MAX: usize = 1024;
+
+// Half blocks with explicit colors, so it scans on dark themes too
+fn encode(text: &str) -> HResult<Vec<String>> {
+    let mut child = Command::new("qrencode")
+        .args(&["-t", "ANSIUTF8", "-m", "2", "-o", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|_| HError::Error(String::from("Can't find qrencode!")))?;
+
+    child.stdin.take()?.write_all(text.as_bytes())?;
+    let output = child.wait_with_output()?;
+
+    if !output.status.success() {
+        return Err(HError::Error(String::from("qrencode failed")));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+       .lines()
+       .map(|line| line.to_string())
+       .collect())
+}
+
+pub struct QrPopup {
+    core: WidgetCore,
+    title: String,
+    lines: Vec<String>,
+}
+
+impl QrPopup {
+    pub fn new(core: &WidgetCore, title: &str, text: &str) -> HResult<QrPopup> {
+        let mut popup = QrPopup {
+            core: core.clone(),
+            title: title.to_string(),
+            lines: encode(text)?
+        };
+        popup.set_coordinates(&core.coordinates)?;
+        Ok(popup)
+    }
+
+    pub fn show(&mut self) -> HResult<()> {
+        match self.popup() {
+            Ok(_) | Err(HError::PopupFinnished) => {},
+            err @ Err(_) => err?,
+        }
+        self.get_core()?.clear()?;
+        Ok(())
+    }
+
+    // the title shouldn't get cut off, it might be the URL itself
+    fn width(&self) -> usize {
+        self.lines.iter()
+            .map(|line| term::strip_ansi(line).width())
+            .chain(std::iter::once(self.title.width()))
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+impl Widget for QrPopup {
+    fn get_core(&self) -> HResult<&WidgetCore> {
+        Ok(&self.core)
+    }
+    fn get_core_mut(&mut self) -> HResult<&mut WidgetCore> {
+        Ok(&mut self.core)
+    }
+    fn refresh(&mut self) -> HResult<()> {
+        Ok(())
+    }
+
+    // centered, with the title on top
+    fn set_coordinates(&mut self, _: &Coordinates) -> HResult<()> {
+        let (xsize, ysize) = term::size()?;
+        let width = self.width();
+        let height = self.lines.len() + 1;
+
+        if width > xsize || height > ysize {
+            return Err(HError::Error(String::from("QR code doesn't fit on the screen")));
+        }
+
+        let xpos = (xsize - width) / 2 + 1;
+        let ypos = (ysize - height) / 2 + 1;
+
+        self.core.coordinates = Coordinates::new_at(width as u16,
+                                                    height as u16,
+                                                    xpos as u16,
+                                                    ypos as u16);
+        Ok(())
+    }
+
+    fn get_drawlist(&self) -> HResult<String> {
+        let (xpos, ypos) = self.get_coordinates()?.u16position();
+        let xsize = self.get_coordinates()?.xsize() as usize;
+
+        let title = format!("{}{}{}{}",
+                            term::goto_xy(xpos, ypos),
+                            term::header_color(),
+                            term::sized_string_u(&self.title, xsize),
+                            term::reset());
+
+        let code = self.lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| format!("{}{}{}",
+                                     term::goto_xy(xpos, ypos + 1 + i as u16),
+                                     line,
+                                     term::reset()))
+            .collect::<String>();
+
+        Ok(title + &code)
+    }
+
+    // nothing to do here but look at it
+    fn on_key(&mut self, _: Key) -> HResult<()> {
+        self.popup_finnished()
+    }
+}
+
+// Internet shortcuts only hold a link, that's what's interesting about them
+pub fn shortcut_url(contents: &str) -> Option<&str> {
+    contents.lines()
+        .map(|line| line.trim())
+        .find(|line| line.starts_with("URL="))
+        .map(|line| line.trim_start_matches("URL="))
+        .filter(|url| !url.is_empty())
+}
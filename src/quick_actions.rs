@@ -3,6 +3,7 @@ use termion::event::Key;
 
 use async_value::Async;
 
+use std::io::{BufRead, BufReader, Read};
 use std::path::PathBuf;
 use std::sync::{
     Arc, Mutex,
@@ -16,12 +17,13 @@ use crate::fail::{HResult, HError, KeyBindError, ErrorLog};
 use crate::widget::{Widget, WidgetCore, Events};
 use crate::foldview::{Foldable, FoldableWidgetExt, ActingExt};
 use crate::listview::ListView;
-use crate::proclist::ProcView;
+use crate::proclist::{FinishedJob, FollowUp, ProcView};
 use crate::files::File;
 use crate::paths;
 use crate::term;
 use crate::term::ScreenExt;
 use crate::keybind::{Bindings, Movement, QuickActionAction};
+use crate::direnv::DirenvExt;
 
 
 pub type QuickActionView = ListView<Vec<QuickActions>>;
@@ -280,6 +282,29 @@ pub fn open(files: Vec<File>,
     action_view.popup()
 }
 
+// Runs the action with that title right away, the one for the exact MIME
+// type if there's more than one
+pub fn run_named(files: Vec<File>,
+                 title: &str,
+                 core: &WidgetCore,
+                 proc_view: Arc<Mutex<ProcView>>) -> HResult<()> {
+    let mime  = files.common_mime()
+        .unwrap_or_else(|| Mime::from_str("*/").unwrap());
+
+    let subpaths = [format!("{}/{}", mime.type_().as_str(), mime.subtype().as_str()),
+                    mime.type_().as_str().to_string(),
+                    String::new()];
+
+    let actions_path = paths::actions_path()?;
+    let action = subpaths.iter()
+        .filter_map(|subpath| std::fs::read_dir(actions_path.join(subpath)).ok())
+        .flat_map(|entries| entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()))
+        .find(|path| !path.is_dir() && path.get_title() == title)
+        .ok_or_else(|| HError::Error(format!("No quick action called {}", title)))?;
+
+    QuickAction::new(action, mime).run(files, core, proc_view)
+}
+
 
 #[derive(Debug)]
 pub struct QuickActions {
@@ -333,7 +358,8 @@ pub struct QuickAction {
     title: String,
     queries: Vec<String>,
     sync: bool,
-    mime: mime::Mime
+    mime: mime::Mime,
+    follow_up: FollowUp
 }
 
 impl QuickAction {
@@ -341,13 +367,15 @@ impl QuickAction {
         let title = path.get_title();
         let queries = path.get_queries();
         let sync = path.get_sync();
+        let follow_up = path.get_follow_up();
 
         QuickAction {
             path,
             title,
             queries,
             sync,
-            mime
+            mime,
+            follow_up
         }
     }
 
@@ -384,18 +412,36 @@ impl QuickAction {
 
         let cwd = files.get(0)?.parent_as_file()?;
 
+        let sources: Vec<PathBuf> = files.iter()
+            .map(|f| f.path.clone())
+            .collect();
         let files: Vec<OsString> = files.iter()
             .map(|f| OsString::from(&f.path))
             .collect();
 
-
+        let follow_up = match self.follow_up.is_empty() {
+            true => None,
+            false => Some((self.follow_up.clone(), sources.clone()))
+        };
 
         if self.sync {
-            std::process::Command::new(&self.path)
+            let started = std::time::SystemTime::now();
+            let status = std::process::Command::new(&self.path)
                 .args(files)
+                .direnv(&cwd.path, core.config().direnv)
                 .envs(answers)
                 .spawn()?
                 .wait()?;
+
+            if !self.follow_up.is_empty() {
+                crate::proclist::push_finished(FinishedJob {
+                    follow_up: self.follow_up.clone(),
+                    cwd: cwd.path.clone(),
+                    sources: sources,
+                    started: started,
+                    success: status.success()
+                });
+            }
             Ok(())
         } else {
             let cmd = crate::proclist::Cmd {
@@ -406,7 +452,9 @@ impl QuickAction {
                 cwd: cwd,
                 cwd_files: None,
                 tab_files: None,
-                tab_paths: None
+                tab_paths: None,
+                low_priority: false,
+                follow_up: follow_up
             };
 
             proc_view
@@ -415,6 +463,10 @@ impl QuickAction {
                     proc_view.run_proc_raw(cmd)
                 })??;
 
+            if self.follow_up.show_output {
+                proc_view.lock()?.show_last()?;
+            }
+
             Ok(())
         }
     }
@@ -504,6 +556,7 @@ pub trait QuickPath {
     fn get_title(&self) -> String;
     fn get_queries(&self) -> Vec<String>;
     fn get_sync(&self) -> bool;
+    fn get_follow_up(&self) -> FollowUp;
 }
 
 impl QuickPath for PathBuf {
@@ -538,4 +591,36 @@ impl QuickPath for PathBuf {
                  .ends_with("!"))
             .unwrap_or(false)
     }
+
+    // From "# on_success=..." and "# on_failure=..." in the comments at
+    // the top of the script, right after the #!
+    fn get_follow_up(&self) -> FollowUp {
+        let file = match std::fs::File::open(self) {
+            Ok(file) => file,
+            Err(_) => return FollowUp::default()
+        };
+
+        let mut on_success = String::new();
+        let mut on_failure = String::new();
+
+        // could be a binary too
+        let comments = BufReader::new(file.take(4096))
+            .lines()
+            .filter_map(|line| line.ok())
+            .take_while(|line| line.starts_with('#'));
+
+        for line in comments {
+            let line = line.trim_start_matches('#').trim();
+            if line.starts_with("on_success=") {
+                on_success = line["on_success=".len()..].to_string();
+            } else if line.starts_with("on_failure=") {
+                on_failure = line["on_failure=".len()..].to_string();
+            }
+        }
+
+        FollowUp::parse(&on_success, &on_failure)
+            .map_err(|e| HError::Error(format!("{}: {}", self.to_string_lossy(), e)))
+            .log_and()
+            .unwrap_or_default()
+    }
 }
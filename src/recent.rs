@@ -0,0 +1,219 @@
+use termion::event::Key;
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::path::{Path, PathBuf};
+
+use crate::fail::HResult;
+use crate::keybind::{Acting, Bindings, Movement, RecentFilesAction};
+use crate::listview::{Listable, ListView};
+use crate::term;
+use crate::widget::Widget;
+
+
+// The files modified last below the roots in recent_roots, newest first.
+// Hidden files and directories and what .gitignore files leave out are
+// skipped, nobody was working on those. The search stops at some point,
+// so a huge home directory doesn't keep hunter busy forever.
+const RECENT_MAX: usize = 50;
+const DEPTH_MAX: usize = 8;
+const VISIT_MAX: usize = 200_000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecentFile {
+    pub path: PathBuf,
+    pub mtime: i64,
+    picked: Option<bool>,
+}
+
+// "~" is the home directory, "projects" the recently used projects
+pub fn roots(setting: &str) -> Vec<PathBuf> {
+    let home = crate::paths::home_path().ok();
+
+    let mut roots = setting.split(',')
+        .map(|root| root.trim())
+        .filter(|root| !root.is_empty())
+        .flat_map(|root| match root {
+            "projects" => crate::projects::recent(),
+            "~" => home.iter().cloned().collect(),
+            _ if root.starts_with("~/") => home.iter()
+                .map(|home| home.join(&root[2..]))
+                .collect(),
+            _ => vec![PathBuf::from(root)]
+        })
+        .filter(|root| root.is_dir())
+        .collect::<Vec<_>>();
+
+    // projects are usually in home already
+    roots.sort();
+    roots.dedup();
+    let nested = roots.iter()
+        .filter(|root| roots.iter().any(|other| other != *root && root.starts_with(other)))
+        .cloned()
+        .collect::<Vec<_>>();
+    roots.retain(|root| !nested.contains(root));
+
+    roots
+}
+
+struct Search {
+    newest: BinaryHeap<Reverse<(i64, PathBuf)>>,
+    visited: usize,
+}
+
+fn walk(dir: &Path, depth: usize, inherited: &[String], search: &mut Search) {
+    use std::os::unix::fs::MetadataExt;
+
+    let mut patterns = inherited.to_vec();
+    patterns.extend(crate::tree::ignore_patterns(dir));
+
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return
+    };
+
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        if search.visited >= VISIT_MAX { return; }
+        search.visited += 1;
+
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with('.') { continue; }
+
+        // symlinks aren't followed, no going around in circles
+        let meta = match std::fs::symlink_metadata(entry.path()) {
+            Ok(meta) => meta,
+            Err(_) => continue
+        };
+        let is_dir = meta.is_dir();
+
+        if crate::tree::is_ignored(&patterns, &name, is_dir) { continue; }
+
+        if is_dir {
+            if depth + 1 < DEPTH_MAX {
+                walk(&entry.path(), depth + 1, &patterns, search);
+            }
+        } else if meta.is_file() {
+            search.newest.push(Reverse((meta.mtime(), entry.path())));
+            if search.newest.len() > RECENT_MAX {
+                search.newest.pop();
+            }
+        }
+    }
+}
+
+pub fn find(roots: &[PathBuf]) -> Vec<RecentFile> {
+    let mut search = Search {
+        newest: BinaryHeap::new(),
+        visited: 0
+    };
+
+    for root in roots {
+        walk(root, 0, &[], &mut search);
+    }
+
+    let mut files = search.newest
+        .into_iter()
+        .map(|Reverse((mtime, path))| RecentFile {
+            path: path,
+            mtime: mtime,
+            picked: None
+        })
+        .collect::<Vec<_>>();
+    files.sort_by_key(|file| Reverse(file.mtime));
+
+    files
+}
+
+pub type RecentFiles = ListView<Vec<RecentFile>>;
+
+impl RecentFiles {
+    // the file and whether it should get its own tab
+    pub fn picked(&self) -> Option<(PathBuf, bool)> {
+        self.content.iter()
+            .find_map(|file| file.picked.map(|in_tab| (file.path.clone(), in_tab)))
+    }
+
+    fn pick(&mut self, in_tab: bool) -> HResult<()> {
+        let selection = self.get_selection();
+        self.content.get_mut(selection)?.picked = Some(in_tab);
+        self.popup_finnished()
+    }
+}
+
+impl Listable for ListView<Vec<RecentFile>> {
+    type Item = ();
+
+    fn len(&self) -> usize {
+        self.content.len()
+    }
+
+    fn render(&self) -> Vec<String> {
+        use chrono::TimeZone;
+
+        let home = crate::paths::home_path().ok();
+
+        self.content
+            .iter()
+            .map(|file| {
+                let time = chrono::Local.timestamp(file.mtime, 0).format("%F %R");
+                let path = match &home {
+                    Some(home) if file.path.starts_with(home) => {
+                        let rest = file.path.strip_prefix(home).unwrap_or(&file.path);
+                        format!("~/{}", rest.to_string_lossy())
+                    }
+                    _ => file.path.to_string_lossy().to_string()
+                };
+
+                format!("{}{} {}{}",
+                        term::color_cyan(),
+                        time,
+                        term::normal_color(),
+                        term::sanitize(&path))
+            })
+            .collect()
+    }
+
+    fn render_header(&self) -> HResult<String> {
+        Ok(format!("Recently modified: {} files", self.content.len()))
+    }
+
+    fn on_key(&mut self, key: Key) -> HResult<()> {
+        self.do_key(key)
+    }
+}
+
+impl Acting for ListView<Vec<RecentFile>> {
+    type Action = RecentFilesAction;
+
+    fn search_in(&self) -> Bindings<Self::Action> {
+        self.core.config().keybinds.recentfiles
+    }
+
+    fn movement(&mut self, movement: &Movement) -> HResult<()> {
+        use Movement::*;
+
+        match movement {
+            Up(n) => { for _ in 0..*n { self.move_up(); }; self.refresh()?; }
+            Down(n) => { for _ in 0..*n { self.move_down(); }; self.refresh()?; }
+            PageUp => self.page_up(),
+            PageDown => self.page_down(),
+            Top => self.move_top(),
+            Bottom => self.move_bottom(),
+            Left | Right => {}
+        }
+
+        Ok(())
+    }
+
+    fn do_action(&mut self, action: &Self::Action) -> HResult<()> {
+        use RecentFilesAction::*;
+
+        match action {
+            Close => self.popup_finnished()?,
+            Pick => self.pick(false)?,
+            PickInTab => self.pick(true)?
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,187 @@
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use crate::fail::{HResult, HError};
+
+
+// URLs typed or pasted into the cd prompt. Plain downloads are fetched with
+// curl, everything else is left to GVFS, which mounts it somewhere below
+// /run/user so it can be browsed like any other directory.
+pub fn is_url(string: &str) -> bool {
+    match string.find("://") {
+        Some(pos) if pos > 0 => string[..pos].chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-'),
+        _ => false
+    }
+}
+
+pub fn scheme(url: &str) -> &str {
+    url.split("://").next().unwrap_or("")
+}
+
+// ftp could be mounted too, but a single file is more likely meant to be
+// downloaded. Directories end with a slash.
+pub fn is_download(url: &str) -> bool {
+    match scheme(url) {
+        "http" | "https" => true,
+        "ftp" => !url.ends_with('/'),
+        _ => false
+    }
+}
+
+// The last part of the path, without query and fragment
+pub fn file_name(url: &str) -> String {
+    let mut parts = url.splitn(4, '/');
+    let host = parts.nth(2).unwrap_or("");
+    let path = parts.next().unwrap_or("");
+
+    let name = path.split(|c| c == '?' || c == '#')
+        .next()
+        .unwrap_or("")
+        .rsplit('/')
+        .next()
+        .map(percent_decode)
+        .filter(|name| !name.is_empty() && name != "." && name != "..")
+        .unwrap_or_else(|| host.to_string());
+
+    // the host can be odd too
+    match name.as_str() {
+        "" | "." | ".." => String::from("download"),
+        _ => name.replace(|c| c == '/' || c == '\0', "_")
+    }
+}
+
+fn percent_decode(string: &str) -> String {
+    let bytes = string.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3)
+            .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+// Asks the server how big it is before downloading, with redirects there
+// can be more than one Content-Length, the last one counts
+pub fn remote_size(url: &str) -> Option<u64> {
+    let output = Command::new("curl")
+        .args(&["-sIL", "--max-time", "10"])
+        .arg(url)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut header = line.splitn(2, ':');
+            let name = header.next()?.trim();
+            let value = header.next()?.trim();
+            match name.eq_ignore_ascii_case("content-length") {
+                true => value.parse::<u64>().ok(),
+                false => None
+            }
+        })
+        .last()
+}
+
+// Mounting might ask for a password, so this needs the terminal
+pub fn mount(url: &str) -> HResult<()> {
+    let status = Command::new("gio")
+        .arg("mount")
+        .arg(url)
+        .status()
+        .map_err(|_| HError::Error(String::from("Can't find gio!")))?;
+
+    // already being mounted fails too, that's fine
+    if !status.success() {
+        return local_path(url).map(|_| ());
+    }
+
+    Ok(())
+}
+
+// Where GVFS's FUSE daemon shows the mounted location
+pub fn local_path(url: &str) -> HResult<PathBuf> {
+    let output = Command::new("gio")
+        .arg("info")
+        .arg(url)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()?;
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find(|line| line.starts_with("local path: "))
+        .map(|line| PathBuf::from(line.trim_start_matches("local path: ")))
+        .ok_or_else(|| HError::Error(format!("Can't open {}, is it mounted?", url)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn urls() {
+        assert!(is_url("https://example.com/a.iso"));
+        assert!(is_url("sftp://host/dir"));
+        assert!(is_url("svn+ssh://host/repo"));
+        assert!(!is_url("://host"));
+        assert!(!is_url("/home/user/dir"));
+        assert!(!is_url("~/weird://name"));
+        assert!(!is_url("dir with space://x"));
+    }
+
+    #[test]
+    fn downloads() {
+        assert!(is_download("https://example.com/"));
+        assert!(is_download("ftp://example.com/file.tar"));
+        assert!(!is_download("ftp://example.com/pub/"));
+        assert!(!is_download("smb://server/share"));
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(file_name("https://example.com/dl/file.tar.gz"), "file.tar.gz");
+        assert_eq!(file_name("https://example.com/get?id=1&name=x#top"), "get");
+        assert_eq!(file_name("https://example.com/my%20file.txt"), "my file.txt");
+        assert_eq!(file_name("https://example.com/dir/"), "example.com");
+        assert_eq!(file_name("https://example.com"), "example.com");
+    }
+
+    #[test]
+    fn names_stay_in_the_directory() {
+        assert_eq!(file_name("https://example.com/a/.."), "example.com");
+        assert_eq!(file_name("https://example.com/%2e%2e"), "example.com");
+        assert_eq!(file_name("https://example.com/..%2F..%2Fetc%2Fpasswd"), ".._.._etc_passwd");
+        assert_eq!(file_name("https://../"), "download");
+        assert_eq!(file_name("https://./."), "download");
+        assert_eq!(file_name("https://example.com/a%00b"), "a_b");
+    }
+
+    #[test]
+    fn broken_escapes_stay() {
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz%4"), "%zz%4");
+        assert_eq!(percent_decode("%+1"), "%+1");
+        assert_eq!(percent_decode("%C3%A9"), "é");
+    }
+}
@@ -0,0 +1,535 @@
+use termion::event::Key;
+use unicode_width::UnicodeWidthStr;
+
+use std::collections::HashSet;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+use crate::coordinates::Coordinates;
+use crate::dirty::Dirtyable;
+use crate::fail::{HResult, HError, KeyBindError};
+use crate::keybind::{Acting, Bindings, Movement, PagerAction};
+use crate::term;
+use crate::widget::{Widget, WidgetCore};
+
+
+// Renames a bunch of files after a pattern like "img_{n:04}_{date}{ext}".
+// What's in braces gets replaced, everything else stays as it is:
+//
+//   {n}, {n:04}  counter starting at 1, optionally padded with zeros
+//   {name}       old name without the extension
+//   {ext}        old extension with the dot, empty if there's none
+//   {date}       modification date, 2020-01-31
+//   {time}       modification time, 13-37-00
+//   {{, }}       literal braces
+pub fn expand(pattern: &str, path: &Path, n: usize) -> HResult<String> {
+    let bad_pattern = |why: &str| HError::Error(format!("{}: {}", why, pattern));
+
+    let name = path.file_name()?.to_string_lossy().to_string();
+    let (stem, ext) = match name.rfind('.') {
+        // dotfiles don't have an extension
+        Some(pos) if pos > 0 => (name[..pos].to_string(), name[pos..].to_string()),
+        _ => (name.clone(), String::new())
+    };
+    let mtime = || -> HResult<chrono::DateTime<chrono::Local>> {
+        use chrono::TimeZone;
+        let meta = std::fs::symlink_metadata(path)?;
+        Ok(chrono::Local.timestamp(meta.mtime(), 0))
+    };
+
+    let mut expanded = String::new();
+    let mut chars = pattern.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => { chars.next(); expanded.push('{'); }
+            '}' if chars.peek() == Some(&'}') => { chars.next(); expanded.push('}'); }
+            '}' => return Err(bad_pattern("Unopened }")),
+            '{' => {
+                let mut field = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => field.push(c),
+                        None => return Err(bad_pattern("Unclosed {"))
+                    }
+                }
+
+                let mut parts = field.splitn(2, ':');
+                let key = parts.next().unwrap_or("");
+                let width = match parts.next() {
+                    Some(width) => width.parse::<usize>()
+                        .map_err(|_| bad_pattern("Bad counter width"))?,
+                    None => 0
+                };
+
+                match key {
+                    "n" => expanded += &format!("{:0width$}", n, width = width),
+                    "name" => expanded += &stem,
+                    "ext" => expanded += &ext,
+                    "date" => expanded += &mtime()?.format("%F").to_string(),
+                    "time" => expanded += &mtime()?.format("%H-%M-%S").to_string(),
+                    _ => return Err(bad_pattern(&format!("Unknown field {{{}}}", key)))
+                }
+            }
+            c => expanded.push(c)
+        }
+    }
+
+    Ok(expanded)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Transform {
+    Lower,
+    Upper,
+    Underscores,
+    Ascii,
+    Safe,
+}
+
+impl Transform {
+    pub fn from_name(name: &str) -> HResult<Transform> {
+        match name {
+            "lower" => Ok(Transform::Lower),
+            "upper" => Ok(Transform::Upper),
+            "underscores" => Ok(Transform::Underscores),
+            "ascii" => Ok(Transform::Ascii),
+            "safe" => Ok(Transform::Safe),
+            _ => Err(HError::Error(format!("Unknown transform: {}", name)))
+        }
+    }
+
+    pub fn apply(self, name: &str) -> String {
+        match self {
+            Transform::Lower => name.to_lowercase(),
+            Transform::Upper => name.to_uppercase(),
+            Transform::Underscores => name.replace(' ', "_"),
+            Transform::Ascii => name.chars()
+                .map(|c| match strip_diacritic(c) {
+                    Some(plain) => plain.to_string(),
+                    None => c.to_string()
+                })
+                .collect(),
+            // nothing that needs quoting, and no - in front to look like an option
+            Transform::Safe => name.chars()
+                .filter_map(|c| match c {
+                    c if c.is_whitespace() => Some('_'),
+                    c if c.is_alphanumeric() || "._-+,@%".contains(c) => Some(c),
+                    _ => None
+                })
+                .collect::<String>()
+                .trim_start_matches('-')
+                .to_string()
+        }
+    }
+}
+
+// Latin letters with accents and the like, the rest stays as it is
+fn strip_diacritic(c: char) -> Option<&'static str> {
+    let plain = match c {
+        'À' | 'Á' | 'Â' | 'Ã' | 'Ä' | 'Å' | 'Ā' | 'Ă' | 'Ą' => "A",
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => "a",
+        'Æ' => "AE",
+        'æ' => "ae",
+        'Ç' | 'Ć' | 'Ĉ' | 'Ċ' | 'Č' => "C",
+        'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => "c",
+        'Ď' | 'Đ' | 'Ð' => "D",
+        'ď' | 'đ' | 'ð' => "d",
+        'È' | 'É' | 'Ê' | 'Ë' | 'Ē' | 'Ĕ' | 'Ė' | 'Ę' | 'Ě' => "E",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => "e",
+        'Ĝ' | 'Ğ' | 'Ġ' | 'Ģ' => "G",
+        'ĝ' | 'ğ' | 'ġ' | 'ģ' => "g",
+        'Ĥ' | 'Ħ' => "H",
+        'ĥ' | 'ħ' => "h",
+        'Ì' | 'Í' | 'Î' | 'Ï' | 'Ĩ' | 'Ī' | 'Ĭ' | 'Į' | 'İ' => "I",
+        'ì' | 'í' | 'î' | 'ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' => "i",
+        'Ĵ' => "J",
+        'ĵ' => "j",
+        'Ķ' => "K",
+        'ķ' => "k",
+        'Ĺ' | 'Ļ' | 'Ľ' | 'Ŀ' | 'Ł' => "L",
+        'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => "l",
+        'Ñ' | 'Ń' | 'Ņ' | 'Ň' => "N",
+        'ñ' | 'ń' | 'ņ' | 'ň' => "n",
+        'Ò' | 'Ó' | 'Ô' | 'Õ' | 'Ö' | 'Ø' | 'Ō' | 'Ŏ' | 'Ő' => "O",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ŏ' | 'ő' => "o",
+        'Œ' => "OE",
+        'œ' => "oe",
+        'Ŕ' | 'Ŗ' | 'Ř' => "R",
+        'ŕ' | 'ŗ' | 'ř' => "r",
+        'Ś' | 'Ŝ' | 'Ş' | 'Š' => "S",
+        'ś' | 'ŝ' | 'ş' | 'š' => "s",
+        'ß' => "ss",
+        'Ţ' | 'Ť' | 'Ŧ' => "T",
+        'ţ' | 'ť' | 'ŧ' => "t",
+        'Þ' => "Th",
+        'þ' => "th",
+        'Ù' | 'Ú' | 'Û' | 'Ü' | 'Ũ' | 'Ū' | 'Ŭ' | 'Ů' | 'Ű' | 'Ų' => "U",
+        'ù' | 'ú' | 'û' | 'ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => "u",
+        'Ŵ' => "W",
+        'ŵ' => "w",
+        'Ý' | 'Ÿ' | 'Ŷ' => "Y",
+        'ý' | 'ÿ' | 'ŷ' => "y",
+        'Ź' | 'Ż' | 'Ž' => "Z",
+        'ź' | 'ż' | 'ž' => "z",
+        _ => return None
+    };
+    Some(plain)
+}
+
+// Old and new paths, only the ones that actually change. Refuses anything
+// that would lose a file.
+pub fn plan(pattern: &str, paths: &[PathBuf]) -> HResult<Vec<(PathBuf, PathBuf)>> {
+    plan_names(paths, |i, path| expand(pattern, path, i + 1))
+}
+
+// One after another, like "ascii safe lower"
+pub fn plan_transforms(transforms: &[Transform],
+                       paths: &[PathBuf]) -> HResult<Vec<(PathBuf, PathBuf)>> {
+    plan_names(paths, |_, path| {
+        let name = path.file_name()?.to_string_lossy().to_string();
+        Ok(transforms.iter().fold(name, |name, transform| transform.apply(&name)))
+    })
+}
+
+// Only the garbled ones, the rest is left alone
+pub fn plan_fixes(paths: &[PathBuf]) -> HResult<Vec<(PathBuf, PathBuf)>> {
+    let garbled = paths.iter()
+        .filter(|path| path.file_name().and_then(crate::mojibake::fix).is_some())
+        .cloned()
+        .collect::<Vec<_>>();
+
+    plan_names(&garbled, |_, path| Ok(crate::mojibake::fix(path.file_name()?)?))
+}
+
+fn plan_names(paths: &[PathBuf],
+              new_name: impl Fn(usize, &Path) -> HResult<String>)
+              -> HResult<Vec<(PathBuf, PathBuf)>> {
+    let mut renames = vec![];
+    let mut targets = HashSet::new();
+    let sources = paths.iter().cloned().collect::<HashSet<_>>();
+
+    for (i, path) in paths.iter().enumerate() {
+        let name = new_name(i, path.as_path())?;
+
+        if name.is_empty() || name == "." || name == ".." || name.contains('/') {
+            return Err(HError::Error(format!("Can't name a file \"{}\"", name)));
+        }
+
+        let target = path.with_file_name(&name);
+        if !targets.insert(target.clone()) {
+            return Err(HError::Error(format!("{} would be there twice", name)));
+        }
+
+        // taken by something that isn't renamed itself, on case-insensitive
+        // filesystems that can be the file itself under its old case
+        if !sources.contains(&target) && std::fs::symlink_metadata(&target).is_ok() &&
+            !same_file(path, &target) {
+            return Err(HError::Error(format!("{} already exists", name)));
+        }
+
+        if &target != path {
+            renames.push((path.clone(), target));
+        }
+    }
+
+    Ok(renames)
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (std::fs::symlink_metadata(a), std::fs::symlink_metadata(b)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false
+    }
+}
+
+// Everything gets a temporary name first, so a and b can swap names
+pub fn apply(renames: &[(PathBuf, PathBuf)]) -> HResult<usize> {
+    let pid = std::process::id();
+    let mut moved = vec![];
+
+    for (i, (from, _)) in renames.iter().enumerate() {
+        let temp = from.with_file_name(format!(".hunter-rename-{}-{}", pid, i));
+        if let Err(e) = std::fs::rename(from, &temp) {
+            // put back what was already moved, nothing's lost this way
+            for (from, temp) in moved {
+                std::fs::rename(temp, from).ok();
+            }
+            return Err(e)?;
+        }
+        moved.push((from.clone(), temp));
+    }
+
+    let mut renamed = 0;
+    for ((from, temp), (_, to)) in moved.iter().zip(renames.iter()) {
+        let result = std::fs::rename(temp, to);
+        crate::audit::record("rename", from, Some(to), &result);
+
+        match result {
+            Ok(_) => renamed += 1,
+            Err(e) => {
+                HError::log::<()>(&format!("Can't rename {} to {}: {}",
+                                           from.to_string_lossy(),
+                                           to.to_string_lossy(),
+                                           e)).ok();
+                std::fs::rename(temp, from).ok();
+            }
+        }
+    }
+
+    Ok(renamed)
+}
+
+// Shows what's going to happen before it does
+pub struct RenamePreview {
+    core: WidgetCore,
+    lines: Vec<String>,
+    offset: usize,
+    accepted: bool,
+}
+
+impl RenamePreview {
+    pub fn new(core: &WidgetCore, renames: &[(PathBuf, PathBuf)]) -> HResult<RenamePreview> {
+        let name = |path: &PathBuf| path.file_name()
+            .map(|name| term::sanitize(&name.to_string_lossy()))
+            .unwrap_or_default();
+
+        let mut preview = RenamePreview {
+            core: core.clone(),
+            lines: renames.iter()
+                .map(|(from, to)| {
+                    // fine on its own, but not everywhere
+                    let collision = crate::fileops::case_collision(to)
+                        .filter(|other| other != from &&
+                                !renames.iter().any(|(from, _)| from == other));
+                    match collision {
+                        Some(other) => format!("{} → {} (only differs in case from {})",
+                                               name(from),
+                                               name(to),
+                                               name(&other)),
+                        None => format!("{} → {}", name(from), name(to))
+                    }
+                })
+                .collect(),
+            offset: 0,
+            accepted: false
+        };
+        preview.set_coordinates(&core.coordinates)?;
+        Ok(preview)
+    }
+
+    // true if it should go ahead
+    pub fn ask(&mut self) -> HResult<bool> {
+        match self.popup() {
+            Ok(_) | Err(HError::PopupFinnished) => {},
+            err @ Err(_) => err?,
+        }
+        self.get_core()?.clear()?;
+        Ok(self.accepted)
+    }
+
+    fn title(&self) -> String {
+        let accept = self.core.config().keybinds.pager
+            .keys_for(&PagerAction::Accept)
+            .iter()
+            .map(|key| key.to_string())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        format!("Rename {} files? {} to go ahead, arrows to scroll",
+                self.lines.len(),
+                accept)
+    }
+
+    fn room(&self) -> usize {
+        self.core.coordinates.ysize_u().saturating_sub(2)
+    }
+}
+
+impl Widget for RenamePreview {
+    fn get_core(&self) -> HResult<&WidgetCore> {
+        Ok(&self.core)
+    }
+    fn get_core_mut(&mut self) -> HResult<&mut WidgetCore> {
+        Ok(&mut self.core)
+    }
+    fn refresh(&mut self) -> HResult<()> {
+        Ok(())
+    }
+
+    fn set_coordinates(&mut self, _: &Coordinates) -> HResult<()> {
+        let (xsize, ysize) = term::size()?;
+        let width = self.lines.iter()
+            .map(|line| line.width())
+            .chain(std::iter::once(self.title().width()))
+            .max()
+            .unwrap_or(0) + 2;
+        let width = width.min(xsize);
+        let height = (self.lines.len() + 2).min(ysize);
+
+        self.core.coordinates = Coordinates::new_at(width as u16,
+                                                    height as u16,
+                                                    ((xsize - width) / 2 + 1) as u16,
+                                                    ((ysize - height) / 2 + 1) as u16);
+        Ok(())
+    }
+
+    fn get_drawlist(&self) -> HResult<String> {
+        let (xpos, ypos) = self.get_coordinates()?.u16position();
+        let xsize = self.get_coordinates()?.xsize_u();
+
+        let title = format!("{}{}{}{}",
+                            term::goto_xy(xpos, ypos),
+                            term::header_color(),
+                            term::sized_string_u(&format!(" {}", self.title()), xsize),
+                            term::reset());
+
+        let lines = self.lines
+            .iter()
+            .skip(self.offset)
+            .take(self.room())
+            .chain(std::iter::once(&String::new()))
+            .enumerate()
+            .map(|(i, line)| format!("{}{}{}",
+                                     term::goto_xy(xpos, ypos + 1 + i as u16),
+                                     term::normal_color(),
+                                     term::sized_string_u(&format!(" {}", line), xsize)))
+            .collect::<String>();
+
+        Ok(title + &lines + &term::reset())
+    }
+
+    // anything that isn't bound cancels too
+    fn on_key(&mut self, key: Key) -> HResult<()> {
+        match self.do_key(key) {
+            Err(HError::WidgetUndefinedKeyError{..}) => self.popup_finnished(),
+            result => result
+        }
+    }
+}
+
+impl Acting for RenamePreview {
+    type Action = PagerAction;
+
+    fn search_in(&self) -> Bindings<Self::Action> {
+        self.core.config().keybinds.pager
+    }
+
+    fn movement(&mut self, movement: &Movement) -> HResult<()> {
+        use Movement::*;
+
+        let last = self.lines.len().saturating_sub(self.room());
+
+        match movement {
+            Up(n) => self.offset = self.offset.saturating_sub(*n),
+            Down(n) => self.offset = (self.offset + n).min(last),
+            PageUp => self.offset = self.offset.saturating_sub(self.room()),
+            PageDown => self.offset = (self.offset + self.room()).min(last),
+            Top => self.offset = 0,
+            Bottom => self.offset = last,
+            _ => Err(KeyBindError::MovementUndefined)?
+        }
+
+        self.core.set_dirty();
+        Ok(())
+    }
+
+    fn do_action(&mut self, action: &Self::Action) -> HResult<()> {
+        match action {
+            PagerAction::Close => self.popup_finnished(),
+            PagerAction::Accept => {
+                self.accepted = true;
+                self.popup_finnished()
+            }
+            // the names are short enough
+            PagerAction::ToggleWrap |
+            PagerAction::ScrollLeft |
+            PagerAction::ScrollRight |
+            PagerAction::SelectLines |
+            PagerAction::CopySelection => Ok(())
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(names: &[&str]) -> Vec<PathBuf> {
+        names.iter()
+            .map(|name| Path::new("/nonexistent/hunter-rename-test").join(name))
+            .collect()
+    }
+
+    fn new_names(renames: &[(PathBuf, PathBuf)]) -> Vec<String> {
+        renames.iter()
+            .map(|(_, new)| new.file_name().unwrap().to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn fields() {
+        let path = Path::new("/tmp/holiday.photo.jpg");
+        assert_eq!(expand("{name}-{n}{ext}", path, 7).unwrap(), "holiday.photo-7.jpg");
+        assert_eq!(expand("img_{n:04}", path, 12).unwrap(), "img_0012");
+        assert_eq!(expand("{n:02}", path, 123).unwrap(), "123");
+        assert_eq!(expand("{{{n}}}", path, 1).unwrap(), "{1}");
+    }
+
+    #[test]
+    fn dotfiles_have_no_extension() {
+        let path = Path::new("/tmp/.bashrc");
+        assert_eq!(expand("{name}|{ext}", path, 1).unwrap(), ".bashrc|");
+    }
+
+    #[test]
+    fn bad_patterns() {
+        let path = Path::new("/tmp/a.txt");
+        assert!(expand("{n", path, 1).is_err());
+        assert!(expand("n}", path, 1).is_err());
+        assert!(expand("{size}", path, 1).is_err());
+        assert!(expand("{n:x}", path, 1).is_err());
+        // doesn't fit a usize
+        assert!(expand("{n:99999999999999999999999}", path, 1).is_err());
+    }
+
+    #[test]
+    fn counter_follows_the_given_order() {
+        let renames = plan("img_{n:02}{ext}", &paths(&["c.jpg", "a.jpg", "b.png"])).unwrap();
+        assert_eq!(new_names(&renames), vec!["img_01.jpg", "img_02.jpg", "img_03.png"]);
+        assert_eq!(renames[0].0.file_name().unwrap(), "c.jpg");
+    }
+
+    #[test]
+    fn unchanged_names_are_left_out() {
+        let renames = plan("{name}{ext}", &paths(&["a.txt", "b.txt"])).unwrap();
+        assert!(renames.is_empty());
+    }
+
+    #[test]
+    fn names_that_cant_be() {
+        let files = paths(&["a", "b"]);
+        assert!(plan("..", &files[..1]).is_err());
+        assert!(plan(".", &files[..1]).is_err());
+        assert!(plan("", &files[..1]).is_err());
+        assert!(plan("../{name}", &files[..1]).is_err());
+        assert!(plan("same", &files).is_err());
+    }
+
+    #[test]
+    fn names_can_be_swapped() {
+        let files = paths(&["a", "b"]);
+        let renames = plan_names(&files, |i, _| Ok(["b", "a"][i].to_string())).unwrap();
+        assert_eq!(new_names(&renames), vec!["b", "a"]);
+    }
+
+    #[test]
+    fn transforms() {
+        assert_eq!(Transform::Ascii.apply("Crème Brûlée Æ"), "Creme Brulee AE");
+        assert_eq!(Transform::Safe.apply("--my file (1)?.txt"), "my_file_1.txt");
+        assert_eq!(Transform::Underscores.apply("a b  c"), "a_b__c");
+        assert!(Transform::from_name("title").is_err());
+    }
+}
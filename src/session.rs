@@ -0,0 +1,204 @@
+use lazy_static;
+use termion::event::Event;
+
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use crate::fail::{HResult, HError, ErrorLog};
+use crate::term;
+
+
+// --record writes every key and mouse event with its timing to a file,
+// along with the terminal size and what the starting directory looked
+// like. --replay feeds that back in place of the keyboard, so a glitch
+// that only shows up with the right keys at the right speed can be seen
+// again. The raw bytes are kept, they're parsed just like the terminal's.
+const HEADER: &str = "hunter-session 1";
+
+#[derive(Debug)]
+struct Recorder {
+    file: std::fs::File,
+    started: Instant,
+}
+
+#[derive(Debug, Clone)]
+pub struct Recorded {
+    pub at: Duration,
+    pub raw: Vec<u8>,
+}
+
+lazy_static! {
+    static ref RECORDER: Mutex<Option<Recorder>> = Mutex::new(None);
+    static ref REPLAY: Mutex<Option<Vec<Recorded>>> = Mutex::new(None);
+}
+
+// Top level only, name last since it can contain spaces
+fn snapshot(dir: &Path) -> HResult<Vec<String>> {
+    let mut files = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.contains('\n') { return None; }
+
+            let meta = entry.path().symlink_metadata().ok()?;
+            let mtime = meta.modified().ok()?
+                .duration_since(UNIX_EPOCH).ok()?
+                .as_secs();
+            Some(format!("file {} {} {}", meta.len(), mtime, name))
+        })
+        .collect::<Vec<_>>();
+
+    files.sort();
+    Ok(files)
+}
+
+fn write_line(recorder: &mut Recorder, line: &str) -> HResult<()> {
+    // flushed right away, a crash is just what the recording is for
+    writeln!(recorder.file, "{}", line)?;
+    recorder.file.flush()?;
+    Ok(())
+}
+
+pub fn record(path: &Path) -> HResult<()> {
+    let cwd = std::env::current_dir()?;
+    let (xsize, ysize) = term::size()?;
+
+    let mut recorder = Recorder {
+        file: std::fs::File::create(path)?,
+        started: Instant::now()
+    };
+
+    write_line(&mut recorder, HEADER)?;
+    write_line(&mut recorder, &format!("size {} {}", xsize, ysize))?;
+    write_line(&mut recorder, &format!("cwd {}", cwd.to_string_lossy()))?;
+    for file in snapshot(&cwd)? {
+        write_line(&mut recorder, &file)?;
+    }
+
+    *RECORDER.lock()? = Some(recorder);
+    Ok(())
+}
+
+fn record_line(line: impl Fn(u128) -> String) {
+    if let Ok(mut recorder) = RECORDER.lock() {
+        if let Some(recorder) = recorder.as_mut() {
+            let ms = recorder.started.elapsed().as_millis();
+            write_line(recorder, &line(ms)).log();
+        }
+    }
+}
+
+pub fn record_input(raw: &[u8]) {
+    record_line(|ms| {
+        let hex = raw.iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<String>();
+        format!("input {} {}", ms, hex)
+    });
+}
+
+pub fn record_resize(xsize: usize, ysize: usize) {
+    record_line(|ms| format!("resize {} {} {}", ms, xsize, ysize));
+}
+
+fn parse_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 { return None; }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+// Goes to where the recording started and complains about everything that
+// doesn't match, replaying in a different place shows different things
+pub fn replay(path: &Path) -> HResult<()> {
+    let file = BufReader::new(std::fs::File::open(path)?);
+    let mut lines = file.lines();
+    let bad_line = |line: &str| HError::Error(format!("Bad session line: {}", line));
+
+    if lines.next().transpose()?.as_ref().map(|l| l.as_str()) != Some(HEADER) {
+        return Err(HError::Error(format!("{} isn't a hunter session",
+                                         path.to_string_lossy())));
+    }
+
+    let mut cwd = None;
+    let mut recorded_files = vec![];
+    let mut events = vec![];
+    let mut mismatches = vec![];
+
+    for line in lines {
+        let line = line?;
+        let mut parts = line.splitn(2, ' ');
+        let kind = parts.next().unwrap_or("");
+        let rest = parts.next().unwrap_or("");
+
+        match kind {
+            "size" => {
+                let size = term::size()?;
+                if rest != format!("{} {}", size.0, size.1) {
+                    mismatches.push(format!("terminal was {}", rest));
+                }
+            }
+            "cwd" => cwd = Some(PathBuf::from(rest)),
+            "file" => recorded_files.push(line.clone()),
+            "input" => {
+                let mut parts = rest.splitn(2, ' ');
+                let at = parts.next()
+                    .and_then(|ms| ms.parse::<u64>().ok())
+                    .ok_or_else(|| bad_line(&line))?;
+                let raw = parts.next()
+                    .and_then(parse_hex)
+                    .filter(|raw| !raw.is_empty())
+                    .ok_or_else(|| bad_line(&line))?;
+                events.push(Recorded {
+                    at: Duration::from_millis(at),
+                    raw: raw
+                });
+            }
+            // the terminal can't be resized from here
+            "resize" => mismatches.push(format!("terminal was resized to {}",
+                                                rest.splitn(2, ' ')
+                                                    .nth(1)
+                                                    .unwrap_or(""))),
+            _ => return Err(bad_line(&line))
+        }
+    }
+
+    let cwd = cwd.ok_or_else(|| HError::Error(String::from("Session has no cwd")))?;
+    std::env::set_current_dir(&cwd)?;
+
+    let files = snapshot(&cwd)?;
+    let changed = files.iter()
+        .filter(|file| !recorded_files.contains(file))
+        .count() +
+        recorded_files.iter()
+        .filter(|file| !files.contains(file))
+        .count();
+    if changed > 0 {
+        mismatches.push(format!("{} files in {} changed",
+                                changed,
+                                cwd.to_string_lossy()));
+    }
+
+    if !mismatches.is_empty() {
+        HError::log::<()>(&format!("Replay differs from recording: {}",
+                                   mismatches.join(", "))).ok();
+    }
+
+    *REPLAY.lock()? = Some(events);
+    Ok(())
+}
+
+// Handed out once, to the input thread
+pub fn take_replay() -> Option<Vec<Recorded>> {
+    REPLAY.lock().ok()?.take()
+}
+
+pub fn parse(raw: &[u8]) -> HResult<Event> {
+    let first = *raw.first()?;
+    let mut rest = raw[1..].iter().map(|&byte| Ok(byte));
+    Ok(termion::event::parse_event(first, &mut rest)?)
+}
@@ -0,0 +1,188 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, TcpListener, TcpStream, UdpSocket};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::fail::{HResult, HError, ErrorLog};
+
+// Enough for a few phones at once, not enough to run out of threads when
+// something on the network keeps connecting. Request lines are short.
+const MAX_CONNECTIONS: usize = 8;
+const MAX_REQUEST_LINE: u64 = 8192;
+
+// A tiny HTTP server for handing a few files to the phone next to you.
+// It only lives as long as the Share does, and the URL has a random part
+// so nobody else on the network can guess it.
+pub struct Share {
+    pub url: String,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Share {
+    pub fn start(paths: Vec<PathBuf>) -> HResult<Share> {
+        let files = paths.into_iter()
+            .filter(|path| path.is_file())
+            .collect::<Vec<_>>();
+
+        if files.is_empty() {
+            return Err(HError::Error(String::from("Only files can be shared")));
+        }
+
+        let listener = TcpListener::bind((lan_ip(), 0))?;
+        listener.set_nonblocking(true)?;
+
+        let token = token()?;
+        let url = format!("http://{}/{}/", listener.local_addr()?, token);
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+
+        let thread = std::thread::spawn(move || {
+            let files = Arc::new(files);
+            let connections = Arc::new(AtomicUsize::new(0));
+
+            while !thread_stop.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    // dropping it closes the connection
+                    Ok(_) if connections.load(Ordering::Relaxed) >= MAX_CONNECTIONS => {}
+                    Ok((stream, _)) => {
+                        let files = files.clone();
+                        let token = token.clone();
+                        let connections = connections.clone();
+                        connections.fetch_add(1, Ordering::Relaxed);
+                        std::thread::spawn(move || {
+                            serve(stream, &token, &files).log();
+                            connections.fetch_sub(1, Ordering::Relaxed);
+                        });
+                    }
+                    Err(_) => std::thread::sleep(Duration::from_millis(50))
+                }
+            }
+        });
+
+        Ok(Share {
+            url: url,
+            stop: stop,
+            thread: Some(thread)
+        })
+    }
+}
+
+impl Drop for Share {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.thread.take().map(|thread| thread.join());
+    }
+}
+
+// The address other machines see. Connecting a UDP socket doesn't send
+// anything, it just picks the interface the default route goes through.
+fn lan_ip() -> IpAddr {
+    let ip = || -> HResult<IpAddr> {
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        socket.connect("192.0.2.1:80")?;
+        Ok(socket.local_addr()?.ip())
+    };
+
+    ip().unwrap_or(IpAddr::V4(Ipv4Addr::LOCALHOST))
+}
+
+fn token() -> HResult<String> {
+    let mut bytes = [0u8; 8];
+    std::fs::File::open("/dev/urandom")?.read_exact(&mut bytes)?;
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+fn serve(mut stream: TcpStream, token: &str, files: &[PathBuf]) -> HResult<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+
+    let mut request = String::new();
+    BufReader::new((&stream).take(MAX_REQUEST_LINE)).read_line(&mut request)?;
+
+    // GET /token/ or GET /token/n HTTP/1.1
+    let path = request.split_whitespace().nth(1).unwrap_or("");
+    let prefix = format!("/{}/", token);
+
+    if !request.starts_with("GET ") || !path.starts_with(&prefix) {
+        return respond(&mut stream, "404 Not Found", "text/plain", b"Not found");
+    }
+
+    match &path[prefix.len()..] {
+        "" => {
+            let index = index(files);
+            respond(&mut stream, "200 OK", "text/html; charset=utf-8", index.as_bytes())
+        }
+        n => match n.parse::<usize>().ok().and_then(|n| files.get(n)) {
+            Some(file) => send_file(&mut stream, file),
+            None => respond(&mut stream, "404 Not Found", "text/plain", b"Not found")
+        }
+    }
+}
+
+fn respond(stream: &mut TcpStream,
+           status: &str,
+           content_type: &str,
+           body: &[u8]) -> HResult<()> {
+    write!(stream,
+           "HTTP/1.0 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\
+            Connection: close\r\n\r\n",
+           status,
+           content_type,
+           body.len())?;
+    stream.write_all(body)?;
+    Ok(())
+}
+
+fn send_file(stream: &mut TcpStream, file: &PathBuf) -> HResult<()> {
+    let mut source = std::fs::File::open(file)?;
+    let size = source.metadata()?.len();
+    let name = file.file_name()?.to_string_lossy();
+
+    write!(stream,
+           "HTTP/1.0 200 OK\r\nContent-Type: application/octet-stream\r\n\
+            Content-Length: {}\r\n\
+            Content-Disposition: attachment; filename*=UTF-8''{}\r\n\
+            Connection: close\r\n\r\n",
+           size,
+           percent_encode(&name))?;
+    std::io::copy(&mut source, stream)?;
+    Ok(())
+}
+
+fn index(files: &[PathBuf]) -> String {
+    let links = files.iter()
+        .enumerate()
+        .map(|(i, file)| {
+            let name = file.file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            format!("<li><a href=\"{}\">{}</a></li>\n", i, html_escape(&name))
+        })
+        .collect::<String>();
+
+    format!("<!DOCTYPE html>\n<html><head><meta name=\"viewport\" \
+             content=\"width=device-width\"><title>hunter</title></head>\n\
+             <body><ul>\n{}</ul></body></html>\n",
+            links)
+}
+
+fn html_escape(string: &str) -> String {
+    string.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn percent_encode(string: &str) -> String {
+    string.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' |
+            b'-' | b'.' | b'_' | b'~' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte)
+        })
+        .collect()
+}
@@ -61,6 +61,7 @@ pub trait FsExt {
     fn get_dev(&self) -> Option<String>;
     fn get_total(&self) -> String;
     fn get_free(&self) -> String;
+    fn is_case_insensitive(&self) -> bool;
 }
 
 impl FsExt for Filesystem {
@@ -83,5 +84,13 @@ impl FsExt for Filesystem {
         self.avail.to_string_as(false)
     }
 
+    fn is_case_insensitive(&self) -> bool {
+        match self.fs_type.as_str() {
+            "vfat" | "msdos" | "exfat" | "cifs" | "smbfs" | "smb3" |
+            "hfs" | "hfsplus" | "apfs" | "msdosfs" => true,
+            _ => false
+        }
+    }
+
 
 }
@@ -1,19 +1,43 @@
-use termion::event::Key;
+use termion::event::{Key, MouseEvent};
 
-use crate::widget::{Widget, WidgetCore};
+use std::cmp::Ordering;
+
+use crate::widget::{Events, Widget, WidgetCore};
 use crate::fail::{HResult, HError, ErrorLog};
 use crate::coordinates::Coordinates;
+use crate::listview::{Listable, ListView};
+use crate::term;
+use crate::footer;
+
+use unicode_width::UnicodeWidthStr;
+
+// Longer names get cut off in the tab bar
+const TAB_NAME_MAX: usize = 16;
+// the tab bar line has more room, names share it down to this
+const TAB_LINE_NAME_MAX: usize = 32;
+const TAB_LINE_NAME_MIN: usize = 8;
 
 pub trait Tabbable {
     type Tab: Widget;
     fn new_tab(&mut self) -> HResult<()>;
+    fn duplicate_tab(&mut self) -> HResult<()>;
     fn close_tab(&mut self) -> HResult<()>;
     fn next_tab(&mut self) -> HResult<()>;
     fn prev_tab(&mut self) -> HResult<()>;
+    fn move_tab(&mut self, right: bool) -> HResult<()>;
     fn goto_tab(&mut self, index: usize) -> HResult<()>;
+    fn last_tab(&mut self) -> HResult<()>;
+    fn toggle_dual_pane(&mut self) -> HResult<()>;
+    fn switch_pane(&mut self) -> HResult<()>;
+    // the tab that was active before, right before switching away from it
+    fn on_tab_leave(&mut self, _prev: usize) -> HResult<()> {
+        Ok(())
+    }
     fn on_tab_switch(&mut self) -> HResult<()> {
         Ok(())
     }
+    // which tab goes where changed, see pane_areas
+    fn on_layout(&mut self) -> HResult<()> { Ok(()) }
     fn get_tab_names(&self) -> Vec<Option<String>>;
     fn active_tab(&self) -> &Self::Tab;
     fn active_tab_mut(&mut self) -> &mut Self::Tab;
@@ -32,7 +56,13 @@ pub trait Tabbable {
 pub struct TabView<T> where T: Widget, TabView<T>: Tabbable {
     pub widgets: Vec<T>,
     pub active: usize,
-    pub core: WidgetCore
+    pub core: WidgetCore,
+    // two tabs side by side, the active one and other_pane
+    pub dual_pane: bool,
+    pub other_pane: Option<usize>,
+    pub active_right: bool,
+    // the one that was active before, for LastTab
+    pub last_active: Option<usize>,
 }
 
 impl<T> TabView<T> where T: Widget, TabView<T>: Tabbable {
@@ -40,24 +70,57 @@ impl<T> TabView<T> where T: Widget, TabView<T>: Tabbable {
         let mut tabview = TabView {
             widgets: vec![],
             active: 0,
-            core: core.clone()
+            core: core.clone(),
+            dual_pane: false,
+            other_pane: None,
+            active_right: false,
+            last_active: None
         };
 
         Tabbable::on_new(&mut tabview).log();
+        start_header_ticker(&tabview.core);
 
         tabview
     }
 
-    pub fn push_widget(&mut self, widget: T) -> HResult<()> {
+    pub fn push_widget(&mut self, mut widget: T) -> HResult<()> {
+        widget.set_coordinates(&self.tab_area()).log();
         self.widgets.push(widget);
         Ok(())
     }
 
+    // What's left for the tabs, the tab bar line goes on top of them
+    pub fn tab_area(&self) -> Coordinates {
+        let mut area = self.core.coordinates.clone();
+
+        if self.core.config().tab_bar {
+            area.set_ypos(area.ypos() + 1);
+            area.set_ysize(area.ysize().saturating_sub(1));
+        }
+
+        area
+    }
+
+    pub fn insert_widget(&mut self, index: usize, mut widget: T) -> HResult<()> {
+        widget.set_coordinates(&self.tab_area()).log();
+        self.widgets.insert(index, widget);
+
+        if let Some(other) = self.other_pane {
+            if other >= index { self.other_pane = Some(other + 1); }
+        }
+        if let Some(last) = self.last_active {
+            if last >= index { self.last_active = Some(last + 1); }
+        }
+        Ok(())
+    }
+
     pub fn pop_widget(&mut self) -> HResult<T> {
         let widget = self.widgets.pop()?;
         if self.widgets.len() <= self.active {
             self.active -= 1;
         }
+        self.fix_other_pane();
+        self.fix_last_active();
         Ok(widget)
     }
 
@@ -68,18 +131,139 @@ impl<T> TabView<T> where T: Widget, TabView<T>: Tabbable {
             if index+1 == len {
                 self.active -= 1;
             }
+
+            let shift = |tab: usize| match tab.cmp(&index) {
+                Ordering::Less => Some(tab),
+                Ordering::Equal => None,
+                Ordering::Greater => Some(tab - 1)
+            };
+            self.other_pane = self.other_pane.and_then(shift);
+            self.last_active = self.last_active.and_then(shift);
+            self.fix_other_pane();
+            self.fix_last_active();
         }
         Ok(())
     }
 
+    // Left and right half of the tab area, with a line in between
+    pub fn pane_areas(&self) -> (Coordinates, Coordinates) {
+        let area = self.tab_area();
+        let left_xsize = area.xsize().saturating_sub(1) / 2;
+
+        let mut left = area.clone();
+        left.set_xsize(left_xsize);
+
+        let mut right = area.clone();
+        right.set_xpos(area.xpos() + left_xsize + 1);
+        right.set_xsize(area.xsize().saturating_sub(left_xsize + 1));
+
+        (left, right)
+    }
+
+    // Where the active tab goes and where the one in the other pane
+    pub fn active_pane_areas(&self) -> (Coordinates, Coordinates) {
+        let (left, right) = self.pane_areas();
+        match self.active_right {
+            true => (right, left),
+            false => (left, right)
+        }
+    }
+
+    pub fn other_pane(&self) -> Option<usize> {
+        match self.dual_pane {
+            true => self.other_pane,
+            false => None
+        }
+    }
+
+    // After tabs came and went, the other pane gets some other tab if its
+    // own is gone or became the active one. With just one tab left there's
+    // nothing to put next to it.
+    fn fix_other_pane(&mut self) {
+        if !self.dual_pane { return; }
+
+        let len = self.widgets.len();
+        let valid = self.other_pane
+            .map(|other| other < len && other != self.active)
+            .unwrap_or(false);
+
+        if !valid {
+            self.other_pane = match len {
+                0 | 1 => None,
+                _ => Some((self.active + 1) % len)
+            };
+        }
+
+        if self.other_pane.is_none() {
+            self.dual_pane = false;
+            self.core.clear().log();
+        }
+    }
+
+    fn fix_last_active(&mut self) {
+        let len = self.widgets.len();
+        self.last_active = self.last_active
+            .filter(|&last| last < len && last != self.active);
+    }
+
+    // Switching to the tab in the other pane puts the one that was active
+    // over there, instead of showing the same tab twice
+    fn switched_from(&mut self, prev: usize) {
+        if prev != self.active {
+            self.on_tab_leave(prev).log();
+            self.last_active = Some(prev);
+        }
+        if self.dual_pane && self.other_pane == Some(self.active) {
+            self.other_pane = Some(prev);
+            self.active_right = !self.active_right;
+        }
+    }
+
+    pub fn toggle_dual_pane_(&mut self) -> HResult<()> {
+        self.dual_pane = !self.dual_pane;
+
+        if self.dual_pane {
+            // it's left where it is, a copy of it goes to the right
+            if self.widgets.len() < 2 {
+                Tabbable::duplicate_tab(self)?;
+                self.other_pane = Some(self.active - 1);
+                self.active_right = true;
+            } else {
+                self.other_pane = Some((self.active + 1) % self.widgets.len());
+                self.active_right = false;
+            }
+        } else {
+            self.other_pane = None;
+        }
+
+        self.core.clear().log();
+        self.on_layout()
+    }
+
+    pub fn switch_pane_(&mut self) -> HResult<()> {
+        match self.other_pane() {
+            Some(other) => self.goto_tab_(other),
+            None => self.core.show_status("Only one pane")
+        }
+    }
+
     pub fn goto_tab_(&mut self, index: usize) -> HResult<()> {
         if index < self.widgets.len() {
+            let prev = self.active;
             self.active = index;
+            self.switched_from(prev);
             self.on_tab_switch().log();
         }
         Ok(())
     }
 
+    pub fn last_tab_(&mut self) -> HResult<()> {
+        match self.last_active {
+            Some(last) => self.goto_tab_(last),
+            None => self.core.show_status("No other tab was open before")
+        }
+    }
+
     pub fn active_tab_(&self) -> &T {
         &self.widgets[self.active]
     }
@@ -94,22 +278,207 @@ impl<T> TabView<T> where T: Widget, TabView<T>: Tabbable {
     }
 
     pub fn next_tab_(&mut self) {
+        let prev = self.active;
         if self.active + 1 == self.widgets.len() {
             self.active = 0;
         } else {
             self.active += 1
         }
+        self.switched_from(prev);
         self.on_tab_switch().log();
     }
 
     pub fn prev_tab_(&mut self) {
+        let prev = self.active;
         if self.active == 0 {
             self.active = self.widgets.len() - 1;
         } else {
             self.active -= 1;
         }
+        self.switched_from(prev);
         self.on_tab_switch().log();
     }
+
+    // Past the last one it goes to the front, and the other way round
+    pub fn move_tab_(&mut self, right: bool) {
+        let len = self.widgets.len();
+        if len < 2 { return; }
+
+        let to = match right {
+            true => (self.active + 1) % len,
+            false => (self.active + len - 1) % len
+        };
+
+        // the other pane's tab shifts along with the rest
+        let active = self.active;
+        let shift = |tab: usize| {
+            let tab = match tab > active {
+                true => tab - 1,
+                false => tab
+            };
+            match tab >= to {
+                true => tab + 1,
+                false => tab
+            }
+        };
+        self.other_pane = self.other_pane.map(shift);
+        self.last_active = self.last_active.map(shift);
+
+        let tab = self.widgets.remove(self.active);
+        self.widgets.insert(to, tab);
+        self.active = to;
+    }
+
+    pub fn pick_tab(&mut self) -> HResult<()> {
+        let entries = self.get_tab_names()
+            .into_iter()
+            .enumerate()
+            .map(|(index, name)| TabEntry {
+                index: index,
+                name: name.unwrap_or_default(),
+                active: index == self.active,
+                picked: false
+            })
+            .collect();
+
+        let mut picker: TabPicker = ListView::new(&self.core, entries);
+        picker.set_selection(self.active);
+
+        match picker.popup() {
+            Ok(_) | Err(HError::PopupFinnished) => {},
+            err @ Err(_) => err?
+        }
+
+        if let Some(entry) = picker.content.iter().find(|entry| entry.picked) {
+            Tabbable::goto_tab(self, entry.index)?;
+        }
+
+        Ok(())
+    }
+
+    // As many tabs around the active one as fit into xsize, with the
+    // number of tabs that didn't fit on either side
+    fn tab_bar(&self, xsize: usize, name_max: usize) -> (String, usize) {
+        let labels = self.get_tab_names()
+            .into_iter()
+            .enumerate()
+            .map(|(num, name)| {
+                let name = term::ellipsize(&name.unwrap_or_default(), name_max);
+                format!("{}:{}", num, name)
+            })
+            .collect::<Vec<_>>();
+        let widths = labels.iter()
+            .map(|label| label.width() + 1)
+            .collect::<Vec<_>>();
+
+        let total: usize = widths.iter().sum();
+        // room for " ‹12" and " 12›"
+        let reserve = match total > xsize {
+            true => 8,
+            false => 0
+        };
+
+        let (mut start, mut end) = (self.active, self.active);
+        let mut used = widths[self.active];
+
+        loop {
+            let mut grown = false;
+
+            if end + 1 < labels.len() && used + widths[end + 1] + reserve <= xsize {
+                end += 1;
+                used += widths[end];
+                grown = true;
+            }
+            if start > 0 && used + widths[start - 1] + reserve <= xsize {
+                start -= 1;
+                used += widths[start];
+                grown = true;
+            }
+
+            if !grown { break; }
+        }
+
+        let left = match start {
+            0 => String::new(),
+            hidden => format!(" ‹{}", hidden)
+        };
+        let right = match labels.len() - 1 - end {
+            0 => String::new(),
+            hidden => format!(" {}›", hidden)
+        };
+
+        let other_pane = self.other_pane();
+        let tabs = (start..=end)
+            .map(|num| match num == self.active {
+                true => format!(" {}{}{}{}",
+                                term::invert(),
+                                labels[num],
+                                term::reset(),
+                                term::header_color()),
+                false if Some(num) == other_pane => format!(" {}{}{}{}",
+                                                            term::underline(),
+                                                            labels[num],
+                                                            term::reset(),
+                                                            term::header_color()),
+                false => format!(" {}", labels[num])
+            })
+            .collect::<String>();
+
+        let width = left.width() + used + right.width();
+        (format!("{}{}{}", left, tabs, right), width)
+    }
+
+    // All the tabs on a line of their own, names get shorter the more tabs
+    // there are
+    fn render_tab_line(&self) -> HResult<String> {
+        let coords = &self.core.coordinates;
+        let xsize = coords.xsize_u();
+        let tabs = self.widgets.len().max(1);
+
+        // "12:" and the space in front
+        let name_max = (xsize / tabs).saturating_sub(4)
+            .max(TAB_LINE_NAME_MIN)
+            .min(TAB_LINE_NAME_MAX);
+        let (bar, _) = self.tab_bar(xsize, name_max);
+
+        Ok(format!("{}{}{:xsize$}{}{}{}",
+                   term::goto_xy(coords.xpos(), coords.ypos()),
+                   term::header_color(),
+                   " ",
+                   term::goto_xy(coords.xpos(), coords.ypos()),
+                   bar,
+                   term::reset(),
+                   xsize = xsize))
+    }
+
+    fn render_pane_divider(&self) -> String {
+        let (_, right) = self.pane_areas();
+        let x = right.xpos().saturating_sub(1);
+
+        (right.ypos()..right.ypos() + right.ysize())
+            .map(|y| format!("{}{}│{}",
+                             term::goto_xy(x, y),
+                             term::normal_color(),
+                             term::reset()))
+            .collect()
+    }
+}
+
+// The clock and battery don't cause any events, so just redraw at the
+// start of each minute while there's something in the header to update
+fn start_header_ticker(core: &WidgetCore) {
+    use chrono::Timelike;
+
+    let core = core.clone();
+
+    std::thread::spawn(move || loop {
+        let second = chrono::Local::now().second() as u64;
+        std::thread::sleep(std::time::Duration::from_secs(60 - second.min(59)));
+
+        if !core.config().header_format.is_empty() {
+            core.get_sender().send(Events::WidgetReady).ok();
+        }
+    });
 }
 
 impl<T> Widget for TabView<T> where T: Widget, TabView<T>: Tabbable {
@@ -121,44 +490,45 @@ impl<T> Widget for TabView<T> where T: Widget, TabView<T>: Tabbable {
     }
 
     fn config_loaded(&mut self) -> HResult<()> {
+        // tab_bar might have changed
+        let coordinates = self.core.coordinates.clone();
+        self.set_coordinates(&coordinates).log();
         self.on_config_loaded()
     }
 
     fn set_coordinates(&mut self, coordinates: &Coordinates) -> HResult<()> {
         self.core.coordinates = coordinates.clone();
+        let area = self.tab_area();
         for widget in &mut self.widgets {
-            widget.set_coordinates(coordinates).log();
+            widget.set_coordinates(&area).log();
         }
-        Ok(())
+        self.on_layout()
     }
 
     fn render_header(&self) -> HResult<String> {
         let xsize = self.get_coordinates()?.xsize();
         let header = self.active_tab_().render_header()?;
-        let tab_names = self.get_tab_names();
-        let mut nums_length = 0;
-        let tabnums = (0..self.widgets.len()).map(|num| {
-            nums_length += format!("{}:{} ",
-                                   num,
-                                   tab_names[num].as_ref().unwrap()).len();
-            if num == self.active {
-                format!(" {}{}:{}{}{}",
-                        crate::term::invert(),
-                        num,
-                        tab_names[num].as_ref().unwrap(),
-                        crate::term::reset(),
-                        crate::term::header_color())
-            } else {
-                format!(" {}:{}", num, tab_names[num].as_ref().unwrap())
-            }
-        }).collect::<String>();
-
 
+        // the path gets at least half of the header, unless the tabs have
+        // a line of their own
+        let (tabnums, nums_length) = match self.core.config().tab_bar {
+            true => (String::new(), 0),
+            false => self.tab_bar(xsize as usize / 2, TAB_NAME_MAX)
+        };
         let nums_pos = xsize.saturating_sub(nums_length as u16);
 
-        Ok(format!("{}{}{}{}",
+        // clock, battery, etc. go right before the tabs
+        let header_format = self.core.config().header_format;
+        let extras = footer::render_inline(&header_format, footer::common_segment);
+        let extras_xsize = term::strip_ansi(&extras).width() as u16;
+        let extras_pos = nums_pos.saturating_sub(extras_xsize + 1);
+
+        Ok(format!("{}{}{}{}{}{}{}",
                 header,
                 crate::term::header_color(),
+                crate::term::goto_xy(extras_pos, 1),
+                extras,
+                crate::term::header_color(),
                 crate::term::goto_xy(nums_pos, 1),
                 tabnums))
     }
@@ -170,11 +540,27 @@ impl<T> Widget for TabView<T> where T: Widget, TabView<T>: Tabbable {
 
     fn refresh(&mut self) -> HResult<()> {
         Tabbable::on_refresh(self).log();
+        // before the active one, that one gets to set the title
+        if let Some(other) = self.other_pane() {
+            self.widgets[other].refresh().log();
+        }
         self.active_tab_mut().refresh()
     }
 
     fn get_drawlist(&self) -> HResult<String> {
-        self.active_tab_().get_drawlist()
+        let tab_line = match self.core.config().tab_bar {
+            true => self.render_tab_line()?,
+            false => String::new()
+        };
+
+        let other_pane = match self.other_pane() {
+            Some(other) => self.widgets[other].get_drawlist()
+                .log_and()
+                .unwrap_or_default() + &self.render_pane_divider(),
+            None => String::new()
+        };
+
+        Ok(tab_line + &other_pane + &self.active_tab_().get_drawlist()?)
     }
 
     fn on_key(&mut self, key: Key) -> HResult<()> {
@@ -185,6 +571,22 @@ impl<T> Widget for TabView<T> where T: Widget, TabView<T>: Tabbable {
 
         Ok(())
     }
+
+    fn on_mouse(&mut self, event: MouseEvent) -> HResult<()> {
+        // clicking into the other pane makes it the active one
+        if let (Some(_), MouseEvent::Press(_, x, _)) = (self.other_pane(), event) {
+            let (_, other_area) = self.active_pane_areas();
+            if x >= other_area.xpos() && x < other_area.xpos() + other_area.xsize() {
+                self.switch_pane_()?;
+            }
+        }
+
+        self.active_tab_mut().on_mouse(event)
+    }
+
+    fn on_wtf(&mut self, event: Vec<u8>) -> HResult<()> {
+        self.active_tab_mut().on_wtf(event)
+    }
 }
 
 use crate::keybind::*;
@@ -201,10 +603,100 @@ impl<T: Widget> Acting for TabView<T> where TabView<T>: Tabbable {
 
         match action {
             GotoTab(n) => self.goto_tab(*n)?,
+            LastTab => self.last_tab()?,
             NewTab => self.new_tab()?,
+            DuplicateTab => self.duplicate_tab()?,
             CloseTab => self.close_tab()?,
             NextTab => self.next_tab()?,
             PrevTab => self.prev_tab()?,
+            MoveTabLeft => self.move_tab(false)?,
+            MoveTabRight => self.move_tab(true)?,
+            PickTab => self.pick_tab()?,
+            ToggleDualPane => self.toggle_dual_pane()?,
+            SwitchPane => self.switch_pane()?,
+        }
+
+        Ok(())
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TabEntry {
+    index: usize,
+    name: String,
+    active: bool,
+    picked: bool,
+}
+
+pub type TabPicker = ListView<Vec<TabEntry>>;
+
+impl Listable for ListView<Vec<TabEntry>> {
+    type Item = ();
+
+    fn len(&self) -> usize {
+        self.content.len()
+    }
+
+    fn render(&self) -> Vec<String> {
+        self.content
+            .iter()
+            .map(|entry| {
+                let marker = match entry.active {
+                    true => "*",
+                    false => " "
+                };
+                format!("{}{}{:>3}: {}",
+                        term::normal_color(),
+                        marker,
+                        entry.index,
+                        entry.name)
+            })
+            .collect()
+    }
+
+    fn render_header(&self) -> HResult<String> {
+        Ok(format!("Tabs: {}", self.content.len()))
+    }
+
+    fn on_key(&mut self, key: Key) -> HResult<()> {
+        self.do_key(key)
+    }
+}
+
+impl Acting for ListView<Vec<TabEntry>> {
+    type Action = TabPickerAction;
+
+    fn search_in(&self) -> Bindings<Self::Action> {
+        self.core.config().keybinds.tabpicker
+    }
+
+    fn movement(&mut self, movement: &Movement) -> HResult<()> {
+        use Movement::*;
+
+        match movement {
+            Up(n) => { for _ in 0..*n { self.move_up(); }; self.refresh()?; }
+            Down(n) => { for _ in 0..*n { self.move_down(); }; self.refresh()?; }
+            PageUp => self.page_up(),
+            PageDown => self.page_down(),
+            Top => self.move_top(),
+            Bottom => self.move_bottom(),
+            Left | Right => {}
+        }
+
+        Ok(())
+    }
+
+    fn do_action(&mut self, action: &Self::Action) -> HResult<()> {
+        use TabPickerAction::*;
+
+        match action {
+            Close => self.popup_finnished()?,
+            Pick => {
+                let selection = self.get_selection();
+                self.content.get_mut(selection)?.picked = true;
+                self.popup_finnished()?
+            }
         }
 
         Ok(())
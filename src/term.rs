@@ -1,5 +1,6 @@
 use std::io::{Stdout, Write, BufWriter, BufRead};
 use std::sync::{Arc, Mutex, RwLock};
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use termion;
 use termion::screen::AlternateScreen;
@@ -17,7 +18,8 @@ pub type TermMode = AlternateScreen<RawTerminal<BufWriter<Stdout>>>;
 pub struct Screen {
     screen: Arc<Mutex<TermMode>>,
     size: Arc<RwLock<Option<(usize, usize)>>>,
-    terminal: String
+    terminal: String,
+    mouse: Arc<AtomicBool>
 }
 
 impl Screen {
@@ -30,7 +32,8 @@ impl Screen {
         Ok(Screen {
             screen: Arc::new(Mutex::new(screen)),
             size: Arc::new(RwLock::new(None)),
-            terminal: terminal
+            terminal: terminal,
+            mouse: Arc::new(AtomicBool::new(false))
         })
     }
 
@@ -54,19 +57,74 @@ impl Screen {
         Ok(self.size.write()?.take()?)
     }
 
+    // any-event tracking (1003) reports motion without buttons, SGR (1006)
+    // encoding has no limit on the coordinates
+    pub fn set_mouse(&mut self, enabled: bool) -> HResult<()> {
+        self.mouse.store(enabled, Ordering::Relaxed);
+        self.write_mouse_mode(enabled)
+    }
+
+    fn write_mouse_mode(&mut self, enabled: bool) -> HResult<()> {
+        match enabled {
+            true => write!(self, "\x1b[?1003h\x1b[?1006h")?,
+            false => write!(self, "\x1b[?1003l\x1b[?1006l")?
+        }
+        self.flush()?;
+        Ok(())
+    }
+
+    // OSC 52 works over ssh and without xclip/wl-copy, most terminals
+    // support it (tmux needs set-clipboard on). For those that don't it
+    // also goes through wl-copy/xclip if there's a display.
+    pub fn set_clipboard(&mut self, text: &str) -> HResult<()> {
+        write!(self, "\x1b]52;c;{}\x07", base64::encode(text))?;
+        self.flush()?;
+        clipboard_tool(text).ok();
+        Ok(())
+    }
+
     pub fn set_title(&mut self, title: &str) -> HResult<()> {
         if self.terminal.starts_with("xterm") ||
+            self.terminal.starts_with("rxvt") ||
+            self.terminal.starts_with("alacritty") ||
             self.terminal.starts_with("screen") ||
             self.terminal.starts_with("tmux"){
-             write!(self, "\x1b]2;hunter: {}\x1b\\", title)?;
+             // set both icon name and window title
+             write!(self, "\x1b]0;{}\x1b\\", title)?;
         }
         if self.terminal.starts_with("tmux") {
-            write!(self, "\x1bkhunter: {}\x1b\\", title)?;
+            write!(self, "\x1bk{}\x1b\\", title)?;
         }
         Ok(())
     }
 }
 
+fn clipboard_tool(text: &str) -> HResult<()> {
+    use std::process::{Command, Stdio};
+
+    let mut command = if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        Command::new("wl-copy")
+    } else if std::env::var_os("DISPLAY").is_some() {
+        let mut xclip = Command::new("xclip");
+        xclip.args(&["-selection", "clipboard"]);
+        xclip
+    } else {
+        return Ok(());
+    };
+
+    let mut child = command.stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    child.stdin.take()?.write_all(text.as_bytes())?;
+
+    // they hang around until something else is copied
+    std::thread::spawn(move || child.wait());
+
+    Ok(())
+}
+
 impl Write for Screen {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
         self.screen
@@ -153,6 +211,27 @@ pub trait ScreenExt: Write {
 }
 
 impl ScreenExt for Screen {
+    // don't leave mouse reporting on for the shell or a program running
+    // in the foreground
+    fn suspend(&mut self) -> HResult<()> {
+        if self.mouse.load(Ordering::Relaxed) {
+            self.write_mouse_mode(false).log();
+        }
+        self.cursor_show().log();
+        self.suspend_raw_mode().log();
+        self.to_main_screen()
+    }
+
+    fn activate(&mut self) -> HResult<()> {
+        self.cursor_hide().log();
+        self.activate_raw_mode().log();
+        self.to_alternate_screen()?;
+        if self.mouse.load(Ordering::Relaxed) {
+            self.write_mouse_mode(true).log();
+        }
+        Ok(())
+    }
+
     fn suspend_raw_mode(&mut self) -> HResult<()> {
         self.screen
             .lock()?
@@ -220,6 +299,25 @@ pub fn cell_ratio() -> HResult<f32> {
     Ok(ratio)
 }
 
+// Control characters could mess up the terminal, so they're shown as
+// symbols instead, like ␛ for escape. File names can have them too.
+pub fn sanitize(line: &str) -> String {
+    line.chars()
+        .map(|chr| match chr {
+            '\t' => String::from("    "),
+            '\u{7f}' => String::from("␡"),
+            _ if (chr as u32) < 0x20 => {
+                std::char::from_u32(0x2400 + chr as u32)
+                    .unwrap_or('?')
+                    .to_string()
+            }
+            // C1 controls, some terminals act on these too
+            '\u{80}'..='\u{9f}' => String::from("�"),
+            _ => chr.to_string()
+        })
+        .collect()
+}
+
 pub fn sized_string(string: &str, xsize: u16) -> &str {
     let len = string.chars()
                     .map(|ch| {
@@ -290,6 +388,115 @@ pub fn string_len(string: &str) -> usize {
     })
 }
 
+pub fn strip_ansi(string: &str) -> String {
+    get_tokens(&string).iter()
+        .filter_map(|token| match token {
+            Token::Text(text) => Some(*text),
+            _ => None
+        })
+        .collect()
+}
+
+// Where needle is in the text, lowercase needles match any case. Only
+// ASCII is folded, so the positions stay the same.
+pub fn find_all(text: &str, needle: &str) -> Vec<usize> {
+    if needle.is_empty() { return vec![]; }
+
+    let ignore_case = !needle.chars().any(|c| c.is_uppercase());
+    let folded = match ignore_case {
+        true => text.to_ascii_lowercase(),
+        false => text.to_string()
+    };
+
+    let mut found = vec![];
+    let mut start = 0;
+    while let Some(pos) = folded[start..].find(needle) {
+        found.push(start + pos);
+        start += pos + needle.len();
+    }
+    found
+}
+
+pub fn contains(text: &str, needle: &str) -> bool {
+    !find_all(&strip_ansi(text), needle).is_empty()
+}
+
+// Inverts every match, color codes are left alone
+pub fn highlight(string: &str, needle: &str) -> String {
+    get_tokens(&string).iter()
+        .map(|token| match token {
+            Token::Ansi(ansi) => ansi.to_string(),
+            Token::Text(text) => {
+                let mut highlighted = String::with_capacity(text.len());
+                let mut last = 0;
+                for pos in find_all(text, needle) {
+                    highlighted += &text[last..pos];
+                    highlighted += &format!("{}{}{}",
+                                            termion::style::Invert,
+                                            &text[pos..pos + needle.len()],
+                                            termion::style::NoInvert);
+                    last = pos + needle.len();
+                }
+                highlighted += &text[last..];
+                highlighted
+            }
+        })
+        .collect()
+}
+
+// Drops the first columns of text, color codes are kept so the rest of
+// the line still looks the same
+pub fn skip_columns(string: &str, columns: usize) -> String {
+    let mut skipped = 0;
+
+    get_tokens(&string).iter()
+        .map(|token| match token {
+            Token::Ansi(ansi) => ansi.to_string(),
+            Token::Text(text) => {
+                text.chars()
+                    .filter(|chr| {
+                        if skipped >= columns { return true }
+                        skipped += chr.width().unwrap_or(0);
+                        false
+                    })
+                    .collect()
+            }
+        })
+        .collect()
+}
+
+// Splits into lines of at most xsize columns. Color codes from earlier
+// parts are repeated, because every line starts with a reset.
+pub fn wrap_string(string: &str, xsize: usize) -> Vec<String> {
+    let mut lines = vec![];
+    let mut line = String::new();
+    let mut codes = String::new();
+    let mut width = 0;
+
+    for token in get_tokens(&string) {
+        match token {
+            Token::Ansi(ansi) => {
+                codes.push_str(ansi);
+                line.push_str(ansi);
+            }
+            Token::Text(text) => {
+                for chr in text.chars() {
+                    let chr_width = chr.width().unwrap_or(0);
+                    if width + chr_width > xsize && width > 0 {
+                        lines.push(std::mem::replace(&mut line, codes.clone()));
+                        width = 0;
+                    }
+                    line.push(chr);
+                    width += chr_width;
+                }
+            }
+        }
+    }
+
+    lines.push(line);
+    lines
+}
+
 
 pub fn sized_string_u(string: &str, xsize: usize) -> String {
     let tokens = get_tokens(&string);
@@ -349,6 +556,69 @@ pub fn sized_string_u(string: &str, xsize: usize) -> String {
 }
 
 
+// Cuts off the end with a "…" if it doesn't fit, no padding
+pub fn ellipsize(string: &str, xsize: usize) -> String {
+    if string.width() <= xsize {
+        return string.to_string();
+    }
+
+    let mut width = 0;
+    let mut shortened = string.chars()
+        .take_while(|chr| {
+            width += chr.width().unwrap_or(0);
+            width < xsize
+        })
+        .collect::<String>();
+    shortened.push('…');
+    shortened
+}
+
+// Cuts segments out of the middle of a path until it fits into xsize,
+// e.g. "/home/user/some/deep/dir" -> "/home/…/deep/dir". keep is the
+// number of leading and trailing segments that are never removed.
+pub fn sized_path(path: &str, xsize: usize, keep: (usize, usize)) -> String {
+    let path = &sanitize(path);
+
+    if path.width() <= xsize {
+        return path.to_string();
+    }
+
+    let (keep_head, keep_tail) = keep;
+    let absolute = path.starts_with("/");
+    let segments = path.split("/")
+        .filter(|seg| seg.len() > 0)
+        .collect::<Vec<&str>>();
+
+    // can't remove anything, just cut off the end
+    if segments.len() <= keep_head + keep_tail {
+        return sized_string_u(path, xsize).trim_end().to_string();
+    }
+
+    let head = &segments[..keep_head];
+    let mut tail_len = segments.len() - keep_head - 1;
+
+    loop {
+        let tail = &segments[segments.len() - tail_len..];
+        let mut shortened = head.iter()
+            .chain(["…"].iter())
+            .chain(tail.iter())
+            .cloned()
+            .collect::<Vec<&str>>()
+            .join("/");
+        if absolute { shortened.insert(0, '/'); }
+
+        if shortened.width() <= xsize || tail_len <= keep_tail {
+            if shortened.width() > xsize {
+                return sized_string_u(&shortened, xsize).trim_end().to_string();
+            }
+            return shortened;
+        }
+
+        tail_len -= 1;
+    }
+}
+
+
 // Do these as constants
 
 
@@ -458,6 +728,10 @@ pub fn invert() -> String {
     format!("{}", termion::style::Invert)
 }
 
+pub fn underline() -> String {
+    format!("{}", termion::style::Underline)
+}
+
 pub fn cursor_save() -> String {
     format!("{}", termion::cursor::Save)
 }
@@ -1,17 +1,190 @@
-use std::io::{BufRead, BufReader};
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::time::Duration;
 
+use async_value::Stale;
+use notify::{RecommendedWatcher, Watcher, DebouncedEvent, RecursiveMode};
+
+use crate::coordinates::Coordinates;
 use crate::files::File;
-use crate::term::sized_string_u;
-use crate::widget::{Widget, WidgetCore};
-use crate::fail::HResult;
+use crate::term::{sanitize, sized_string_u};
+use crate::widget::{Events, Widget, WidgetCore};
+use crate::fail::{HResult, HError, ErrorLog};
+use crate::keybind::{Acting, Bindings, Movement, PagerAction};
 use crate::dirty::Dirtyable;
 
+// Only this much of a tailed file is kept around
+const TAIL_MAX_LINES: usize = 10_000;
+// Enough for a preview, without reading huge files
+pub const PREVIEW_MAX_BYTES: u64 = 256 * 1024;
+
+
+// Makes text out of whatever bytes a file contains. BOMs are honored, then
+// it's UTF-16 if every other byte is zero, UTF-8 if valid and Latin-1 if
+// nothing else fits, since every byte is valid Latin-1.
+pub fn decode(bytes: &[u8]) -> String {
+    let utf16 = |bytes: &[u8], le: bool| {
+        let units = bytes.chunks(2)
+            .filter(|pair| pair.len() == 2)
+            .map(|pair| match le {
+                true => u16::from_le_bytes([pair[0], pair[1]]),
+                false => u16::from_be_bytes([pair[0], pair[1]])
+            })
+            .collect::<Vec<u16>>();
+        String::from_utf16_lossy(&units)
+    };
+
+    match bytes {
+        [0xEF, 0xBB, 0xBF, rest @ ..] => return String::from_utf8_lossy(rest).to_string(),
+        [0xFF, 0xFE, rest @ ..] => return utf16(rest, true),
+        [0xFE, 0xFF, rest @ ..] => return utf16(rest, false),
+        _ => {}
+    }
+
+    let sample = &bytes[..bytes.len().min(512)];
+    let zeros_at = |odd: usize| sample.iter()
+                                      .skip(odd)
+                                      .step_by(2)
+                                      .filter(|&&byte| byte == 0)
+                                      .count();
+    let half = sample.len() / 2;
+
+    if half > 0 && zeros_at(1) * 10 > half * 9 && zeros_at(0) == 0 {
+        return utf16(bytes, true);
+    }
+    if half > 0 && zeros_at(0) * 10 > half * 9 && zeros_at(1) == 0 {
+        return utf16(bytes, false);
+    }
+
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        // cut off in the middle of a character, happens with limited reads
+        Err(e) if e.error_len().is_none() => {
+            String::from_utf8_lossy(&bytes[..e.valid_up_to()]).to_string()
+        }
+        Err(_) => bytes.iter().map(|&byte| byte as char).collect()
+    }
+}
+
+pub fn read_lines(path: &Path, max_bytes: Option<u64>) -> HResult<Vec<String>> {
+    let file = std::fs::File::open(path)?;
+    let mut bytes = vec![];
+
+    match max_bytes {
+        Some(max_bytes) => file.take(max_bytes).read_to_end(&mut bytes)?,
+        None => BufReader::new(file).read_to_end(&mut bytes)?
+    };
+
+    let lines = decode(&bytes)
+        .lines()
+        .map(|line| sanitize(line))
+        .collect();
+
+    Ok(lines)
+}
+
+// Reads what gets appended to a file, like tail -f. The watcher thread
+// stops when this is dropped.
+#[derive(Debug)]
+pub struct Tail {
+    path: PathBuf,
+    new_lines: Arc<Mutex<Vec<String>>>,
+    stop: Stale,
+}
+
+impl PartialEq for Tail {
+    fn eq(&self, other: &Tail) -> bool {
+        self.path == other.path
+    }
+}
+
+impl Drop for Tail {
+    fn drop(&mut self) {
+        self.stop.set_stale().ok();
+    }
+}
+
+impl Tail {
+    fn start(path: &Path, pos: u64, sender: Sender<Events>) -> HResult<Tail> {
+        let (tx_event, rx_event) = channel();
+        let mut watcher = RecommendedWatcher::new(tx_event,
+                                                  Duration::from_millis(250))?;
+        watcher.watch(path, RecursiveMode::NonRecursive)?;
+
+        let new_lines = Arc::new(Mutex::new(vec![]));
+        let stop = Stale::new();
+
+        let tail = Tail {
+            path: path.to_path_buf(),
+            new_lines: new_lines.clone(),
+            stop: stop.clone()
+        };
+
+        let path = path.to_path_buf();
+
+        std::thread::spawn(move || -> HResult<()> {
+            // keeps watching as long as it's alive
+            let _watcher = watcher;
+            let mut pos = pos;
+            let mut partial = String::new();
+
+            while !stop.is_stale()? {
+                match rx_event.recv_timeout(Duration::from_millis(500)) {
+                    Ok(DebouncedEvent::Write(_)) => {}
+                    Ok(_) => continue,
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    Err(RecvTimeoutError::Disconnected) => break
+                }
+
+                let mut file = std::fs::File::open(&path)?;
+                let len = file.metadata()?.len();
+
+                // truncated, probably rotated with copytruncate
+                if len < pos {
+                    pos = 0;
+                    partial.clear();
+                }
+
+                let mut appended = vec![];
+                file.seek(SeekFrom::Start(pos))?;
+                file.take(len - pos).read_to_end(&mut appended)?;
+                pos = len;
+
+                partial += &String::from_utf8_lossy(&appended);
+
+                // the last line might not be finished yet
+                let complete = match partial.rfind('\n') {
+                    Some(end) => partial.drain(..=end).collect::<String>(),
+                    None => continue
+                };
+
+                new_lines.lock()?.extend(complete.lines().map(sanitize));
+                sender.send(Events::WidgetReady)?;
+            }
+
+            Ok(())
+        });
+
+        Ok(tail)
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct TextView {
     pub lines: Vec<String>,
     pub core: WidgetCore,
     pub follow: bool,
     pub offset: usize,
+    // anchor and cursor line
+    pub selection: Option<(usize, usize)>,
+    pub wrap: bool,
+    // first visible column when not wrapping
+    pub hoffset: usize,
+    pub tail: Option<Tail>,
+    // highlighted and jumped to with search_next/prev
+    pub search: Option<String>,
 }
 
 impl TextView {
@@ -21,52 +194,114 @@ impl TextView {
             core: core.clone(),
             follow: false,
             offset: 0,
+            selection: None,
+            wrap: false,
+            hoffset: 0,
+            tail: None,
+            search: None,
         }
     }
     pub fn new_from_file(core: &WidgetCore, file: &File) -> HResult<TextView> {
-        let file = std::fs::File::open(&file.path)?;
-        let file = std::io::BufReader::new(file);
-        let lines = file.lines().map(|line|
-                                     Ok(line?
-                                        .replace("\t", "    ")))
-            .filter_map(|l: HResult<String>| l.ok())
-            .collect();
+        let lines = read_lines(&file.path, None)?;
 
         Ok(TextView {
             lines: lines,
             core: core.clone(),
             follow: false,
             offset: 0,
+            selection: None,
+            wrap: false,
+            hoffset: 0,
+            tail: None,
+            search: None,
         })
     }
     pub fn new_from_file_limit_lines(core: &WidgetCore,
                                      file: &File,
                                      num: usize) -> HResult<TextView> {
-        let file = std::fs::File::open(&file.path)?;
-        let file = BufReader::new(file);
-        let lines = file.lines()
-                        .take(num)
-                        .map(|line|
-                             Ok(line?
-                                .replace("\t", "    ")))
-            .filter_map(|l: HResult<String>| l.ok())
-            .collect();
+        let mut lines = read_lines(&file.path, Some(PREVIEW_MAX_BYTES))?;
+        lines.truncate(num);
 
         Ok(TextView {
             lines: lines,
             core: core.clone(),
             follow: false,
             offset: 0,
+            selection: None,
+            wrap: false,
+            hoffset: 0,
+            tail: None,
+            search: None,
         })
     }
 
     pub fn set_text(&mut self, text: &str) -> HResult<()> {
         let lines = text.lines().map(|l| l.to_string()).collect();
         self.lines = lines;
+        self.selection = None;
         self.core.set_dirty();
         self.refresh()
     }
 
+    // Shows the end of the file and keeps following it. Only the last part
+    // is read, log files can be huge.
+    pub fn toggle_tail(&mut self, path: &Path) -> HResult<()> {
+        if self.tail.take().is_some() {
+            self.follow = false;
+            return Ok(());
+        }
+
+        let mut file = std::fs::File::open(path)?;
+        let len = file.metadata()?.len();
+        let start = len.saturating_sub(256 * 1024);
+
+        let mut content = vec![];
+        file.seek(SeekFrom::Start(start))?;
+        file.take(len - start).read_to_end(&mut content)?;
+
+        let content = decode(&content);
+        let mut lines = content.lines()
+                               .map(sanitize)
+                               .collect::<Vec<_>>();
+
+        // started in the middle of a line
+        if start > 0 && lines.len() > 1 {
+            lines.remove(0);
+        }
+
+        self.lines = lines;
+        self.selection = None;
+        self.tail = Some(Tail::start(path, len, self.core.get_sender())?);
+        self.follow = true;
+        self.core.set_dirty();
+        Ok(())
+    }
+
+    fn pull_tail(&mut self) -> HResult<()> {
+        let new_lines = match &self.tail {
+            Some(tail) => std::mem::take(&mut *tail.new_lines.lock()?),
+            None => return Ok(())
+        };
+
+        if new_lines.is_empty() { return Ok(()) }
+
+        self.lines.extend(new_lines);
+
+        if self.lines.len() > TAIL_MAX_LINES {
+            let excess = self.lines.len() - TAIL_MAX_LINES;
+            self.lines.drain(..excess);
+            self.offset = self.offset.saturating_sub(excess);
+            self.selection = None;
+        }
+
+        self.core.set_dirty();
+        Ok(())
+    }
+
+    pub fn is_tailing(&self) -> bool {
+        self.tail.is_some()
+    }
+
     pub fn toggle_follow(&mut self) {
         self.follow = !self.follow
     }
@@ -99,21 +334,194 @@ impl TextView {
     }
 
     pub fn scroll_up(&mut self) {
-        self.scroll(-1);
+        match self.selection {
+            Some(_) => self.move_selection(-1),
+            None => self.scroll(-1)
+        }
     }
 
     pub fn scroll_down(&mut self) {
-        self.scroll(1);
+        match self.selection {
+            Some(_) => self.move_selection(1),
+            None => self.scroll(1)
+        }
     }
 
     pub fn page_up(&mut self) {
         let ysize = self.get_coordinates().unwrap().ysize() as isize;
-        self.scroll(0 - ysize + 1);
+        match self.selection {
+            Some(_) => self.move_selection(0 - ysize + 1),
+            None => self.scroll(0 - ysize + 1)
+        }
     }
 
     pub fn page_down(&mut self) {
         let ysize = self.get_coordinates().unwrap().ysize() as isize;
-        self.scroll(ysize - 1);
+        match self.selection {
+            Some(_) => self.move_selection(ysize - 1),
+            None => self.scroll(ysize - 1)
+        }
+    }
+
+    pub fn toggle_wrap(&mut self) {
+        self.wrap = !self.wrap;
+        self.hoffset = 0;
+        self.core.set_dirty();
+    }
+
+    pub fn scroll_horizontal(&mut self, amount: isize) {
+        if self.wrap { return }
+
+        let xsize = self.get_coordinates().unwrap().xsize() as usize;
+        let max_width = self.lines
+            .iter()
+            .map(|line| crate::term::string_len(line))
+            .max()
+            .unwrap_or(0);
+        let max_offset = max_width.saturating_sub(xsize - 1) as isize;
+
+        let hoffset = (self.hoffset as isize + amount).max(0).min(max_offset) as usize;
+
+        if hoffset != self.hoffset {
+            self.hoffset = hoffset;
+            self.core.set_dirty();
+        }
+    }
+
+    pub fn scroll_left(&mut self) {
+        self.scroll_horizontal(-8);
+    }
+
+    pub fn scroll_right(&mut self) {
+        self.scroll_horizontal(8);
+    }
+
+    pub fn column_indicator(&self) -> String {
+        match self.wrap {
+            true => String::from("wrap"),
+            false => format!("col {}", self.hoffset + 1)
+        }
+    }
+
+    pub fn toggle_selection(&mut self) {
+        self.selection = match self.selection {
+            Some(_) => None,
+            None => {
+                // following would move the lines away under the selection
+                self.follow = false;
+                Some((self.offset, self.offset))
+            }
+        };
+        self.core.set_dirty();
+    }
+
+    pub fn move_selection(&mut self, amount: isize) {
+        let (anchor, cursor) = match self.selection {
+            Some(selection) => selection,
+            None => return
+        };
+
+        let last = self.lines.len().saturating_sub(1) as isize;
+        let cursor = (cursor as isize + amount).max(0).min(last) as usize;
+        self.selection = Some((anchor, cursor));
+
+        // keep the cursor on screen
+        let ysize = self.get_coordinates().unwrap().ysize() as usize;
+        if cursor < self.offset {
+            self.offset = cursor;
+        } else if cursor >= self.offset + ysize {
+            self.offset = cursor + 1 - ysize;
+        }
+
+        self.core.set_dirty();
+    }
+
+    fn is_selected(&self, line: usize) -> bool {
+        match self.selection {
+            Some((anchor, cursor)) => {
+                line >= anchor.min(cursor) && line <= anchor.max(cursor)
+            }
+            None => false
+        }
+    }
+
+    // Without any color codes, nobody wants those in the clipboard
+    pub fn selected_text(&self) -> Option<String> {
+        let (anchor, cursor) = self.selection?;
+        let start = anchor.min(cursor);
+        let end = anchor.max(cursor);
+
+        let text = self.lines
+            .iter()
+            .skip(start)
+            .take(end + 1 - start)
+            .map(|line| crate::term::strip_ansi(line))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        Some(text)
+    }
+
+    pub fn copy_selection(&mut self) -> HResult<()> {
+        let text = match self.selected_text() {
+            Some(text) => text,
+            None => return self.core.show_status("Nothing selected!")
+        };
+        self.toggle_selection();
+
+        let lines = text.lines().count();
+        self.core.screen()?.set_clipboard(&text)?;
+        self.core.show_status(&format!("Copied {} lines to clipboard", lines))
+    }
+
+    pub fn set_search(&mut self, search: Option<String>) {
+        self.search = search.filter(|search| !search.is_empty());
+        self.core.set_dirty();
+    }
+
+    // Wraps around and puts the matching line at the top, returns which
+    // line that was
+    pub fn search_next(&mut self) -> Option<usize> {
+        self.search_from(self.offset + 1, false)
+    }
+
+    pub fn search_prev(&mut self) -> Option<usize> {
+        self.search_from(self.offset, true)
+    }
+
+    // Includes the top line, for a new search
+    pub fn search_here(&mut self) -> Option<usize> {
+        self.search_from(self.offset, false)
+    }
+
+    fn search_from(&mut self, start: usize, backwards: bool) -> Option<usize> {
+        let search = self.search.clone()?;
+        let len = self.lines.len();
+        let start = start.min(len);
+
+        let lines = match backwards {
+            true => (0..start).rev().chain((start..len).rev()).collect::<Vec<_>>(),
+            false => (start..len).chain(0..start).collect::<Vec<_>>()
+        };
+
+        let found = lines.into_iter()
+            .find(|&n| crate::term::contains(&self.lines[n], &search))?;
+
+        // following would just jump back to the end
+        self.follow = false;
+        self.offset = found;
+        self.core.set_dirty();
+        Some(found)
+    }
+
+    pub fn search_matches(&self) -> usize {
+        match &self.search {
+            Some(search) => self.lines
+                .iter()
+                .filter(|line| crate::term::contains(line, search))
+                .count(),
+            None => 0
+        }
     }
 
     pub fn scroll_top(&mut self) {
@@ -138,6 +546,8 @@ impl Widget for TextView {
         // let (xpos, ypos) = self.get_coordinates()?.position().position();
         // let len = self.lines.len();
 
+        self.pull_tail().log();
+
         if self.follow {
             self.scroll_bottom();
         }
@@ -152,21 +562,218 @@ impl Widget for TextView {
         let (xsize, ysize) = self.get_coordinates()?.size().size();
         let (xpos, ypos) = self.get_coordinates()?.position().position();
 
+        let xsize = (xsize - 1) as usize;
+        let wrap = self.wrap;
+        let hoffset = self.hoffset;
+
+        // offset still counts whole lines, wrapped parts are only for drawing
         let output = self.core.get_clearlist()? +
             &self
             .lines
             .iter()
+            .enumerate()
             .skip(self.offset)
+            .flat_map(|(n, line)| {
+                let rows = match wrap {
+                    true => crate::term::wrap_string(line, xsize),
+                    false => vec![crate::term::skip_columns(line, hoffset)]
+                };
+                let rows = match &self.search {
+                    Some(search) => rows.iter()
+                        .map(|row| crate::term::highlight(row, search))
+                        .collect(),
+                    None => rows
+                };
+                rows.into_iter().map(move |row| (n, row))
+            })
             .take(ysize as usize)
             .enumerate()
-            .map(|(i, line)| {
+            .map(|(i, (n, row))| {
+                let selected = match self.is_selected(n) {
+                    true => crate::term::invert(),
+                    false => String::new()
+                };
+
                 format!(
-                    "{}{}{}",
+                    "{}{}{}{}",
                     crate::term::goto_xy(xpos, i as u16 + ypos),
                     crate::term::reset(),
-                    sized_string_u(&line, (xsize-1) as usize))
+                    selected,
+                    sized_string_u(&row, xsize))
             })
             .collect::<String>();
         Ok(output)
     }
+
+    fn on_key(&mut self, key: termion::event::Key) -> HResult<()> {
+        match self.do_key(key) {
+            // closing is up to whoever has it
+            Err(HError::PopupFinnished) => HError::undefined_key(key),
+            result => result
+        }
+    }
+}
+
+impl Acting for TextView {
+    type Action = PagerAction;
+
+    fn search_in(&self) -> Bindings<Self::Action> {
+        self.core.config().keybinds.pager
+    }
+
+    fn movement(&mut self, movement: &Movement) -> HResult<()> {
+        use Movement::*;
+
+        match movement {
+            Up(n) => for _ in 0..*n { self.scroll_up() },
+            Down(n) => for _ in 0..*n { self.scroll_down() },
+            Left => self.scroll_left(),
+            Right => self.scroll_right(),
+            Top => self.scroll_top(),
+            Bottom => self.scroll_bottom(),
+            PageUp => self.page_up(),
+            PageDown => self.page_down()
+        }
+
+        Ok(())
+    }
+
+    fn do_action(&mut self, action: &Self::Action) -> HResult<()> {
+        use PagerAction::*;
+
+        match action {
+            // there's nothing to agree to, so that closes too
+            Close | Accept => self.popup_finnished()?,
+            ToggleWrap => self.toggle_wrap(),
+            ScrollLeft => self.scroll_left(),
+            ScrollRight => self.scroll_right(),
+            SelectLines => self.toggle_selection(),
+            CopySelection => self.copy_selection()?
+        }
+
+        Ok(())
+    }
+}
+
+// A whole file in a popup of its own, with its name on top
+pub struct Pager {
+    title: String,
+    textview: TextView,
+}
+
+impl Pager {
+    pub fn new(core: &WidgetCore, path: &Path) -> HResult<Pager> {
+        let mut textview = TextView::new_blank(core);
+        textview.lines = read_lines(path, None)?;
+
+        let mut pager = Pager {
+            title: sanitize(&path.to_string_lossy()),
+            textview: textview
+        };
+        pager.set_coordinates(&core.coordinates)?;
+        Ok(pager)
+    }
+}
+
+impl Widget for Pager {
+    fn get_core(&self) -> HResult<&WidgetCore> {
+        self.textview.get_core()
+    }
+    fn get_core_mut(&mut self) -> HResult<&mut WidgetCore> {
+        self.textview.get_core_mut()
+    }
+    fn refresh(&mut self) -> HResult<()> {
+        self.textview.refresh()
+    }
+
+    // everything but the title and the status line
+    fn set_coordinates(&mut self, _: &Coordinates) -> HResult<()> {
+        let (xsize, ysize) = crate::term::size()?;
+        let coordinates = Coordinates::new_at(xsize as u16,
+                                              ysize.saturating_sub(2) as u16,
+                                              1,
+                                              2);
+        self.textview.core.coordinates = coordinates;
+        self.textview.core.set_dirty();
+        Ok(())
+    }
+
+    fn get_drawlist(&self) -> HResult<String> {
+        let xsize = self.get_coordinates()?.xsize_u();
+        let header = format!("{} ({})", self.title, self.textview.column_indicator());
+
+        Ok(format!("{}{}{}{}{}",
+                   crate::term::goto_xy(1, 1),
+                   crate::term::header_color(),
+                   sized_string_u(&format!(" {}", header), xsize),
+                   crate::term::reset(),
+                   self.textview.get_drawlist()?))
+    }
+
+    fn on_key(&mut self, key: termion::event::Key) -> HResult<()> {
+        match self.textview.do_key(key) {
+            Err(HError::WidgetUndefinedKeyError{..}) => Ok(()),
+            result => result
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn boms() {
+        assert_eq!(decode(b"\xEF\xBB\xBFhi"), "hi");
+        assert_eq!(decode(b"\xFF\xFEh\x00i\x00"), "hi");
+        assert_eq!(decode(b"\xFE\xFF\x00h\x00i"), "hi");
+        // odd byte left over at the end
+        assert_eq!(decode(b"\xFF\xFEh\x00i\x00!"), "hi");
+    }
+
+    #[test]
+    fn utf16_without_bom() {
+        let le = "hello world".encode_utf16()
+            .flat_map(|unit| unit.to_le_bytes().to_vec())
+            .collect::<Vec<u8>>();
+        let be = "hello world".encode_utf16()
+            .flat_map(|unit| unit.to_be_bytes().to_vec())
+            .collect::<Vec<u8>>();
+        assert_eq!(decode(&le), "hello world");
+        assert_eq!(decode(&be), "hello world");
+    }
+
+    #[test]
+    fn utf8_and_latin1() {
+        assert_eq!(decode(b""), "");
+        assert_eq!(decode("grüße 日本".as_bytes()), "grüße 日本");
+        assert_eq!(decode(b"gr\xFC\xDFe"), "grüße");
+        // a limited read can end in the middle of a character
+        assert_eq!(decode(b"caf\xC3"), "caf");
+        assert_eq!(decode(b"\xE6\x97"), "");
+    }
+
+    #[test]
+    fn control_characters_are_shown_not_run() {
+        assert_eq!(sanitize("a\tb"), "a    b");
+        assert_eq!(sanitize("\x1b[2J"), "␛[2J");
+        assert_eq!(sanitize("\x00\x07\x7f"), "␀␇␡");
+        assert_eq!(sanitize("\u{9b}2J"), "�2J");
+        assert_eq!(sanitize("é日🦀"), "é日🦀");
+    }
+
+    #[test]
+    fn read_lines_cut_off() {
+        let path = std::env::temp_dir()
+            .join(format!("hunter-textview-test-{}", std::process::id()));
+        std::fs::write(&path, "one\r\ntwo\x1b\nthree é").unwrap();
+
+        let all = read_lines(&path, None).unwrap();
+        let cut = read_lines(&path, Some(17)).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(all, vec!["one", "two␛", "three é"]);
+        assert_eq!(cut, vec!["one", "two␛", "three "]);
+    }
 }
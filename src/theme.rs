@@ -0,0 +1,286 @@
+use ini::Ini;
+
+use crate::fail::{HResult, HError, ErrorLog};
+
+
+// Escape codes to turn a style on and off again. Only attributes are
+// reset when turning it off, so the inverted selection stays intact.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Style {
+    pub on: String,
+    pub off: String,
+}
+
+impl Style {
+    fn parse(style: &str) -> HResult<Style> {
+        let mut on = vec![];
+        let mut off = vec![];
+
+        for part in style.split(|c: char| c.is_whitespace() || c == ',')
+                         .filter(|part| part.len() > 0) {
+            let (code, reset) = match part {
+                "bold" => ("1".to_string(), "22"),
+                "dim" => ("2".to_string(), "22"),
+                "italic" => ("3".to_string(), "23"),
+                "underline" => ("4".to_string(), "24"),
+                "blink" => ("5".to_string(), "25"),
+                "strike" => ("9".to_string(), "29"),
+                "black" => ("30".to_string(), "39"),
+                "red" => ("31".to_string(), "39"),
+                "green" => ("32".to_string(), "39"),
+                "yellow" => ("33".to_string(), "39"),
+                "blue" => ("34".to_string(), "39"),
+                "magenta" => ("35".to_string(), "39"),
+                "cyan" => ("36".to_string(), "39"),
+                "white" => ("37".to_string(), "39"),
+                // 256 colors like color208
+                _ if part.starts_with("color") => {
+                    let color = part.trim_start_matches("color")
+                                    .parse::<u8>()
+                                    .map_err(|_| HError::ThemeError(part.to_string()))?;
+                    (format!("38;5;{}", color), "39")
+                }
+                // true color like #ff8000
+                _ if part.starts_with("#") && part.len() == 7 => {
+                    let channel = |i| u8::from_str_radix(&part[i..i+2], 16)
+                        .map_err(|_| HError::ThemeError(part.to_string()));
+                    (format!("38;2;{};{};{}", channel(1)?, channel(3)?, channel(5)?),
+                     "39")
+                }
+                _ => HError::theme_error(part)?
+            };
+
+            on.push(code);
+            off.push(reset);
+        }
+
+        if on.is_empty() { HError::theme_error(style)? }
+
+        Ok(Style {
+            on: format!("\x1b[{}m", on.join(";")),
+            off: format!("\x1b[{}m", off.join(";"))
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    // glob, style
+    pub files: Vec<(String, Style)>,
+    // lower bound in bytes, style. None means normal color
+    pub sizes: Vec<(u64, Option<Style>)>,
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme {
+            files: vec![],
+            sizes: vec![(0, Style::parse("dim").ok()),
+                        (1024 * 1024, None),
+                        (1024 * 1024 * 1024, Style::parse("bold yellow").ok())],
+        }
+    }
+}
+
+// Sizes like 512, 10K, 1M, 2G or 1T
+pub fn parse_size(size: &str) -> HResult<u64> {
+    let size = size.trim();
+    let (num, factor) = match size.chars().last() {
+        Some('K') | Some('k') => (&size[..size.len()-1], 1024),
+        Some('M') | Some('m') => (&size[..size.len()-1], 1024 * 1024),
+        Some('G') | Some('g') => (&size[..size.len()-1], 1024 * 1024 * 1024),
+        Some('T') | Some('t') => (&size[..size.len()-1], 1024 * 1024 * 1024 * 1024),
+        _ => (size, 1)
+    };
+
+    num.parse::<u64>()
+       .ok()
+       .and_then(|num| num.checked_mul(factor))
+       .ok_or_else(|| HError::ThemeError(size.to_string()))
+}
+
+impl Theme {
+    pub fn load() -> HResult<Theme> {
+        let theme_path = crate::paths::theme_path()?;
+        if !theme_path.exists() { return Ok(Theme::default()) }
+
+        let ini = Ini::load_from_file_noescape(&theme_path)
+            .map_err(|e| HError::Error(e.to_string()))?;
+        let mut theme = Theme::default();
+
+        // in the order they're written, the later ones win
+        if ini.section(Some("files")).is_some() {
+            let text = std::fs::read_to_string(&theme_path)?;
+            theme.files = crate::config::ini_section(&text, "files")
+                .iter()
+                .filter_map(|(glob, style)| {
+                    let style = Style::parse(style).log_and().ok()?;
+                    Some((glob.to_string(), style))
+                })
+                .collect();
+        }
+
+        if let Some(section) = ini.section(Some("sizes")) {
+            let mut sizes = section.iter()
+                .filter_map(|(size, style)| {
+                    let size = parse_size(size).log_and().ok()?;
+                    let style = match style.trim() {
+                        "normal" | "" => None,
+                        style => Some(Style::parse(style).log_and().ok()?)
+                    };
+                    Some((size, style))
+                })
+                .collect::<Vec<_>>();
+            sizes.sort_by_key(|(size, _)| *size);
+            theme.sizes = sizes;
+        }
+
+        Ok(theme)
+    }
+
+    pub fn size_style(&self, size: u64) -> Option<&Style> {
+        self.sizes
+            .iter()
+            .rev()
+            .find(|(threshold, _)| size >= *threshold)
+            .and_then(|(_, style)| style.as_ref())
+    }
+
+    // Later entries override earlier ones, like in LS_COLORS
+    pub fn file_style(&self, name: &str) -> Option<&Style> {
+        self.files
+            .iter()
+            .rev()
+            .find(|(glob, _)| glob_match(glob, name))
+            .map(|(_, style)| style)
+    }
+}
+
+// Only * and ? are supported, that's enough for file names
+fn glob_match(glob: &str, name: &str) -> bool {
+    let glob = glob.chars().collect::<Vec<char>>();
+    let name = name.chars().collect::<Vec<char>>();
+
+    let (mut g, mut n) = (0, 0);
+    let mut star = None;
+
+    while n < name.len() {
+        match glob.get(g) {
+            Some('*') => {
+                star = Some((g, n));
+                g += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                g += 1;
+                n += 1;
+            }
+            _ => match star {
+                // let the last * eat one more char and try again
+                Some((star_g, star_n)) => {
+                    g = star_g + 1;
+                    n = star_n + 1;
+                    star = Some((star_g, star_n + 1));
+                }
+                None => return false
+            }
+        }
+    }
+
+    glob[g..].iter().all(|&c| c == '*')
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn theme(files: &[(&str, &str)]) -> Theme {
+        Theme {
+            files: files.iter()
+                .map(|(glob, style)| (glob.to_string(), Style::parse(style).unwrap()))
+                .collect(),
+            ..Theme::default()
+        }
+    }
+
+    #[test]
+    fn globs() {
+        assert!(glob_match("*.rs", "main.rs"));
+        assert!(glob_match("*.rs", ".rs"));
+        assert!(!glob_match("*.rs", "main.rs.orig"));
+        assert!(glob_match("?.txt", "a.txt"));
+        assert!(!glob_match("?.txt", "ab.txt"));
+        assert!(glob_match("Makefile", "Makefile"));
+        assert!(!glob_match("Makefile", "makefile"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("**", "anything"));
+    }
+
+    #[test]
+    fn globs_backtrack() {
+        assert!(glob_match("*a*b", "xaxxab"));
+        assert!(glob_match("*.tar.*", "backup.tar.tar.gz"));
+        assert!(!glob_match("*a*b", "xaxxa"));
+        assert!(glob_match("日*?", "日本語"));
+    }
+
+    #[test]
+    fn later_files_entries_win() {
+        let theme = theme(&[("*", "dim"), ("*.rs", "red"), ("main.*", "bold")]);
+        assert_eq!(theme.file_style("main.rs").unwrap().on, "\x1b[1m");
+        assert_eq!(theme.file_style("lib.rs").unwrap().on, "\x1b[31m");
+        assert_eq!(theme.file_style("README").unwrap().on, "\x1b[2m");
+    }
+
+    #[test]
+    fn files_section_keeps_its_order() {
+        let text = "[sizes]\n1M = red\n[files]\n# comment\n*.rs = red\n*.md=bold\nmain.rs = blue\n";
+        let globs = crate::config::ini_section(text, "files")
+            .into_iter()
+            .map(|(glob, _)| glob)
+            .collect::<Vec<_>>();
+        assert_eq!(globs, vec!["*.rs", "*.md", "main.rs"]);
+    }
+
+    #[test]
+    fn styles() {
+        assert_eq!(Style::parse("bold red").unwrap().on, "\x1b[1;31m");
+        assert_eq!(Style::parse("bold red").unwrap().off, "\x1b[22;39m");
+        assert_eq!(Style::parse("color208").unwrap().on, "\x1b[38;5;208m");
+        assert_eq!(Style::parse("#ff8000").unwrap().on, "\x1b[38;2;255;128;0m");
+        assert!(Style::parse("color256").is_err());
+        assert!(Style::parse("#ff80zz").is_err());
+        assert!(Style::parse("sparkly").is_err());
+        assert!(Style::parse(" , ").is_err());
+    }
+
+    #[test]
+    fn sizes() {
+        assert_eq!(parse_size("512").unwrap(), 512);
+        assert_eq!(parse_size(" 10K ").unwrap(), 10 * 1024);
+        assert_eq!(parse_size("1m").unwrap(), 1024 * 1024);
+        assert_eq!(parse_size("2G").unwrap(), 2 * 1024 * 1024 * 1024);
+        assert_eq!(parse_size("1T").unwrap(), 1024 * 1024 * 1024 * 1024);
+        assert!(parse_size("").is_err());
+        assert!(parse_size("K").is_err());
+        assert!(parse_size("-1K").is_err());
+        assert!(parse_size("1.5M").is_err());
+        assert!(parse_size("10Q").is_err());
+    }
+
+    #[test]
+    fn sizes_that_dont_fit() {
+        assert_eq!(parse_size("16777215T").unwrap(), 16777215 * (1 << 40));
+        assert!(parse_size("16777216T").is_err());
+        assert!(parse_size("18446744073709551616").is_err());
+    }
+
+    #[test]
+    fn size_thresholds() {
+        let theme = Theme::default();
+        assert_eq!(theme.size_style(0).unwrap().on, "\x1b[2m");
+        assert_eq!(theme.size_style(1024 * 1024 - 1).unwrap().on, "\x1b[2m");
+        assert!(theme.size_style(1024 * 1024).is_none());
+        assert_eq!(theme.size_style(std::u64::MAX).unwrap().on, "\x1b[1;33m");
+    }
+}
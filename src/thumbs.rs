@@ -0,0 +1,96 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::fail::{HResult, HError};
+use crate::graphics::in_path;
+
+
+// Big enough for any preview column
+const THUMB_SIZE: usize = 1024;
+
+// Smaller images are quicker to just show as they are
+pub const THUMB_MIN_IMAGE: u64 = 2 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ThumbKind {
+    Video,
+    Image,
+}
+
+fn thumb_path(file: &Path) -> HResult<PathBuf> {
+    let mut hasher = DefaultHasher::new();
+    file.hash(&mut hasher);
+
+    let mut thumb = crate::paths::thumbs_path()?;
+    thumb.push(format!("{:016x}.png", hasher.finish()));
+    Ok(thumb)
+}
+
+// A thumbnail is only good as long as the file hasn't changed since
+fn is_fresh(file: &Path, thumb: &Path) -> bool {
+    let mtime = |path: &Path| std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok();
+
+    match (mtime(file), mtime(thumb)) {
+        (Some(file_mtime), Some(thumb_mtime)) => thumb_mtime >= file_mtime,
+        _ => false
+    }
+}
+
+// Path to a PNG thumbnail of file, made now if there's no fresh one yet
+pub fn thumbnail(file: &Path, kind: ThumbKind) -> HResult<PathBuf> {
+    let thumb = thumb_path(file)?;
+
+    if is_fresh(file, &thumb) {
+        return Ok(thumb);
+    }
+
+    std::fs::create_dir_all(thumb.parent()?)?;
+
+    // written somewhere else first, so a preview that's cancelled
+    // halfway never leaves a broken thumbnail behind
+    let part = thumb.with_extension("part.png");
+    let size = THUMB_SIZE.to_string();
+
+    let mut command = match kind {
+        ThumbKind::Video => {
+            let mut command = Command::new("ffmpegthumbnailer");
+            command.arg("-i").arg(file)
+                .arg("-o").arg(&part)
+                .arg("-s").arg(&size);
+            command
+        }
+        ThumbKind::Image => {
+            let convert = match in_path("magick") {
+                true => "magick",
+                false => "convert"
+            };
+            let mut first_frame = file.as_os_str().to_os_string();
+            first_frame.push("[0]");
+
+            let mut command = Command::new(convert);
+            command.arg(first_frame)
+                .arg("-auto-orient")
+                .arg("-thumbnail")
+                .arg(format!("{}x{}>", size, size))
+                .arg(&part);
+            command
+        }
+    };
+
+    let status = command.stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()?;
+
+    if !status.success() {
+        std::fs::remove_file(&part).ok();
+        return Err(HError::Error(format!("Couldn't make a thumbnail of {}",
+                                         file.to_string_lossy())));
+    }
+
+    std::fs::rename(&part, &thumb)?;
+    Ok(thumb)
+}
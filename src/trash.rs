@@ -0,0 +1,567 @@
+use chrono::{Local, NaiveDateTime};
+use termion::event::Key;
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::ffi::OsString;
+
+use crate::fail::{HResult, HError, ErrorLog};
+use crate::unicode_width::UnicodeWidthStr;
+use crate::listview::{Listable, ListView};
+use crate::widget::{Events, Widget, WidgetCore};
+use crate::term;
+use crate::dirty::Dirtyable;
+use crate::keybind::{Acting, Bindings, Movement, TrashAction};
+
+
+// Trash as described in the freedesktop.org trash specification. Files
+// from the home filesystem go to $XDG_DATA_HOME/Trash, everything else
+// to $topdir/.Trash-$uid on the filesystem of the file.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrashEntry {
+    pub name: OsString,
+    pub original: PathBuf,
+    pub deleted: Option<NaiveDateTime>,
+    trash_dir: PathBuf,
+}
+
+impl TrashEntry {
+    fn trashed_path(&self) -> PathBuf {
+        self.trash_dir.join("files").join(&self.name)
+    }
+
+    fn info_path(&self) -> PathBuf {
+        let mut info = self.name.clone();
+        info.push(".trashinfo");
+        self.trash_dir.join("info").join(info)
+    }
+
+    pub fn restore(&self) -> HResult<()> {
+        if std::fs::symlink_metadata(&self.original).is_ok() {
+            return Err(HError::Error(format!("{} already exists",
+                                             self.original.to_string_lossy())));
+        }
+
+        if let Some(parent) = self.original.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let result = std::fs::rename(self.trashed_path(), &self.original);
+        crate::audit::record("restore", &self.trashed_path(), Some(&self.original), &result);
+        result?;
+        std::fs::remove_file(self.info_path())?;
+        Ok(())
+    }
+
+    pub fn delete(&self) -> HResult<()> {
+        let result = remove(&self.trashed_path());
+        crate::audit::record("delete", &self.trashed_path(), None, &result);
+        result?;
+        std::fs::remove_file(self.info_path())?;
+        Ok(())
+    }
+}
+
+fn remove(path: &Path) -> HResult<()> {
+    match std::fs::symlink_metadata(path)?.is_dir() {
+        true => std::fs::remove_dir_all(path)?,
+        false => std::fs::remove_file(path)?
+    }
+    Ok(())
+}
+
+// Everything except unreserved characters and / gets escaped
+fn encode_path(path: &Path) -> String {
+    path.as_os_str()
+        .as_bytes()
+        .iter()
+        .map(|&byte| match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' |
+            b'-' | b'_' | b'.' | b'~' | b'/' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte)
+        })
+        .collect()
+}
+
+fn decode_path(path: &str) -> PathBuf {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let escaped = match bytes[i] {
+            b'%' if i + 2 < bytes.len() => {
+                std::str::from_utf8(&bytes[i+1..i+3])
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            }
+            _ => None
+        };
+
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    PathBuf::from(OsString::from_vec(decoded))
+}
+
+fn home_trash() -> HResult<PathBuf> {
+    crate::paths::trash_path()
+}
+
+// The highest directory that's still on the same filesystem
+fn topdir(path: &Path) -> HResult<PathBuf> {
+    let dev = std::fs::symlink_metadata(path)?.dev();
+    let mut topdir = path.to_path_buf();
+
+    for ancestor in path.ancestors().skip(1) {
+        match std::fs::metadata(ancestor) {
+            Ok(meta) if meta.dev() == dev => topdir = ancestor.to_path_buf(),
+            _ => break
+        }
+    }
+
+    Ok(topdir)
+}
+
+fn trash_dir_for(path: &Path) -> HResult<(PathBuf, Option<PathBuf>)> {
+    let home_trash = home_trash()?;
+    std::fs::create_dir_all(&home_trash).map_err(HError::from).log();
+
+    let dev = std::fs::symlink_metadata(path)?.dev();
+    let home_dev = std::fs::metadata(&home_trash)
+        .map(|meta| meta.dev())
+        .ok();
+
+    if Some(dev) == home_dev {
+        return Ok((home_trash, None));
+    }
+
+    let topdir = topdir(path)?;
+    let uid = users::get_current_uid();
+    let trash_dir = topdir.join(format!(".Trash-{}", uid));
+
+    Ok((trash_dir, Some(topdir)))
+}
+
+pub fn trash(path: &Path) -> HResult<()> {
+    let (trash_dir, topdir) = trash_dir_for(path)?;
+    let files_dir = trash_dir.join("files");
+    let info_dir = trash_dir.join("info");
+    std::fs::create_dir_all(&files_dir)?;
+    std::fs::create_dir_all(&info_dir)?;
+
+    // Paths in other trash directories are relative to the topdir
+    let original = match &topdir {
+        Some(topdir) => path.strip_prefix(topdir).unwrap_or(path),
+        None => path
+    };
+
+    let base_name = path.file_name()?.to_os_string();
+    let mut name = base_name.clone();
+    let mut n = 1;
+
+    // Creating the info file first reserves the name
+    let (mut info, info_path) = loop {
+        let mut info_name = name.clone();
+        info_name.push(".trashinfo");
+        let info_path = info_dir.join(info_name);
+
+        let info = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&info_path);
+
+        match info {
+            Ok(info) => {
+                if !files_dir.join(&name).exists() { break (info, info_path) }
+                // some stray file without info, leave it alone
+                std::fs::remove_file(&info_path).map_err(HError::from).log();
+            }
+            Err(ref e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+            Err(e) => Err(e)?
+        }
+
+        if n >= 1000 {
+            Err(HError::Error("Too many files with that name in trash".into()))?
+        }
+
+        name = base_name.clone();
+        name.push(format!(".{}", n));
+        n += 1;
+    };
+
+    let date = Local::now().format("%Y-%m-%dT%H:%M:%S");
+    let trashed = files_dir.join(&name);
+    let result = write!(info, "[Trash Info]\nPath={}\nDeletionDate={}\n",
+                        encode_path(original),
+                        date)
+        .and_then(|_| std::fs::rename(path, &trashed));
+    crate::audit::record("trash", path, Some(&trashed), &result);
+
+    // otherwise it would show up in the trash without anything there
+    if result.is_err() {
+        std::fs::remove_file(&info_path).map_err(HError::from).log();
+    }
+
+    result?;
+    Ok(())
+}
+
+fn read_info(trash_dir: &Path,
+             topdir: Option<&Path>,
+             info_path: &Path) -> HResult<TrashEntry> {
+    let content = std::fs::read_to_string(info_path)?;
+    let name = info_path.file_stem()?.to_os_string();
+
+    let mut original = None;
+    let mut deleted = None;
+
+    for line in content.lines() {
+        if line.starts_with("Path=") {
+            let path = decode_path(&line["Path=".len()..]);
+            original = match topdir {
+                Some(topdir) if path.is_relative() => Some(topdir.join(path)),
+                _ => Some(path)
+            };
+        } else if line.starts_with("DeletionDate=") {
+            deleted = NaiveDateTime::parse_from_str(&line["DeletionDate=".len()..],
+                                                   "%Y-%m-%dT%H:%M:%S").ok();
+        }
+    }
+
+    Ok(TrashEntry {
+        name: name,
+        original: original?,
+        deleted: deleted,
+        trash_dir: trash_dir.to_path_buf()
+    })
+}
+
+// Mount points from /proc/mounts, for trash directories on other filesystems
+fn mount_points() -> Vec<PathBuf> {
+    std::fs::read_to_string("/proc/mounts")
+        .map(|mounts| {
+            mounts.lines()
+                  .filter_map(|line| line.split_whitespace().nth(1))
+                  .map(|mount| decode_octal(mount))
+                  .collect()
+        })
+        .unwrap_or_default()
+}
+
+// Spaces and such are escaped like \040 in /proc/mounts
+fn decode_octal(path: &str) -> PathBuf {
+    let mut decoded = vec![];
+    let bytes = path.as_bytes();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let escaped = match bytes[i] {
+            b'\\' if i + 4 <= bytes.len() => {
+                std::str::from_utf8(&bytes[i+1..i+4])
+                    .ok()
+                    .and_then(|oct| u8::from_str_radix(oct, 8).ok())
+            }
+            _ => None
+        };
+
+        match escaped {
+            Some(byte) => { decoded.push(byte); i += 4; }
+            None => { decoded.push(bytes[i]); i += 1; }
+        }
+    }
+
+    PathBuf::from(OsString::from_vec(decoded))
+}
+
+fn trash_dirs() -> Vec<(PathBuf, Option<PathBuf>)> {
+    let uid = users::get_current_uid();
+    let mut dirs = vec![];
+
+    if let Ok(home_trash) = home_trash() {
+        dirs.push((home_trash, None));
+    }
+
+    for mount in mount_points() {
+        let trash_dir = mount.join(format!(".Trash-{}", uid));
+        if trash_dir.exists() {
+            dirs.push((trash_dir, Some(mount)));
+        }
+    }
+
+    dirs
+}
+
+const CLEAN_UP_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+fn size(path: &Path) -> u64 {
+    let meta = match std::fs::symlink_metadata(path) {
+        Ok(meta) => meta,
+        Err(_) => return 0
+    };
+
+    match meta.is_dir() {
+        true => std::fs::read_dir(path)
+            .map(|entries| {
+                entries.filter_map(|entry| entry.ok())
+                    .map(|entry| size(&entry.path()))
+                    .sum()
+            })
+            .unwrap_or(0),
+        false => meta.len()
+    }
+}
+
+// Things older than max_age days go, and as long as everything together is
+// bigger than max_size the oldest go too. 0 turns either off. Returns how
+// many were deleted and how much space that freed.
+pub fn clean_up(max_age: u64, max_size: u64) -> (usize, u64) {
+    let now = Local::now().naive_local();
+
+    // only add it all up when it matters, the trash can be huge
+    let mut entries = list()
+        .into_iter()
+        .map(|entry| {
+            let size = match max_size {
+                0 => None,
+                _ => Some(size(&entry.trashed_path()))
+            };
+            (entry, size)
+        })
+        .collect::<Vec<_>>();
+    entries.reverse();
+
+    let mut total = entries.iter().filter_map(|(_, size)| *size).sum::<u64>();
+    let (mut deleted, mut freed) = (0, 0);
+
+    for (entry, size) in entries {
+        let too_old = max_age > 0 && entry.deleted
+            .map(|deleted| (now - deleted).num_days() >= max_age as i64)
+            .unwrap_or(false);
+        let too_big = max_size > 0 && total > max_size;
+        if !too_old && !too_big { continue; }
+
+        let size = size.unwrap_or_else(|| self::size(&entry.trashed_path()));
+        match entry.delete() {
+            Ok(()) => {
+                deleted += 1;
+                freed += size;
+                total = total.saturating_sub(size);
+            }
+            Err(e) => {
+                HError::log::<()>(&format!("Can't empty {} from the trash: {}",
+                                           entry.original.to_string_lossy(),
+                                           e)).ok();
+            }
+        }
+    }
+
+    (deleted, freed)
+}
+
+// Once right away and then every hour, as long as hunter runs
+pub fn clean_up_regularly(core: &WidgetCore) {
+    let config = core.config();
+    let (max_age, max_size) = (config.trash_max_age, config.trash_max_size);
+    if max_age == 0 && max_size == 0 { return; }
+
+    let sender = core.get_sender();
+    std::thread::spawn(move || loop {
+        let (deleted, freed) = clean_up(max_age, max_size);
+        if deleted > 0 {
+            let status = format!("Emptied {} things ({}) from the trash",
+                                 deleted,
+                                 crate::files::pretty_size(freed));
+            sender.send(Events::Status(status)).ok();
+        }
+
+        std::thread::sleep(CLEAN_UP_INTERVAL);
+    });
+}
+
+pub fn list() -> Vec<TrashEntry> {
+    let mut entries = trash_dirs()
+        .iter()
+        .filter_map(|(trash_dir, topdir)| {
+            let infos = std::fs::read_dir(trash_dir.join("info")).ok()?;
+            let entries = infos
+                .filter_map(|info| info.ok())
+                .filter(|info| info.path().extension() == Some("trashinfo".as_ref()))
+                .filter_map(|info| {
+                    read_info(trash_dir, topdir.as_ref().map(|t| t.as_path()), &info.path())
+                        .log_and()
+                        .ok()
+                })
+                .collect::<Vec<_>>();
+            Some(entries)
+        })
+        .flatten()
+        .collect::<Vec<_>>();
+
+    // newest first
+    entries.sort_by(|a, b| b.deleted.cmp(&a.deleted));
+    entries
+}
+
+
+
+pub type TrashView = ListView<Vec<TrashEntry>>;
+
+impl Listable for ListView<Vec<TrashEntry>> {
+    type Item = ();
+
+    fn len(&self) -> usize {
+        self.content.len()
+    }
+
+    fn render(&self) -> Vec<String> {
+        let xsize = self.core.coordinates.xsize_u();
+
+        self.content
+            .iter()
+            .map(|entry| {
+                let date = entry.deleted
+                    .map(|date| date.format("%F %R").to_string())
+                    .unwrap_or_default();
+                let path = entry.original.to_string_lossy();
+                let path_xsize = xsize.saturating_sub(date.len() + 1);
+                let path = term::sized_path(&path, path_xsize, (1, 2));
+                let padding = xsize.saturating_sub(path.width() + date.len());
+
+                format!("{}{}{:padding$}{}",
+                        term::normal_color(),
+                        path,
+                        "",
+                        date,
+                        padding = padding)
+            })
+            .collect()
+    }
+
+    fn render_header(&self) -> HResult<String> {
+        Ok(format!("Trash: {} files", self.content.len()))
+    }
+
+    fn render_footer(&self) -> HResult<String> {
+        let entry = match self.content.get(self.get_selection()) {
+            Some(entry) => entry,
+            None => return Ok("Trash is empty".to_string())
+        };
+
+        let size = std::fs::symlink_metadata(entry.trashed_path())
+            .map(|meta| crate::files::pretty_size(meta.len()))
+            .unwrap_or_default();
+
+        Ok(format!("{}  {}", entry.name.to_string_lossy(), size))
+    }
+
+    fn on_refresh(&mut self) -> HResult<()> {
+        self.core.set_dirty();
+        Ok(())
+    }
+
+    fn on_key(&mut self, key: Key) -> HResult<()> {
+        self.do_key(key)
+    }
+}
+
+impl ListView<Vec<TrashEntry>> {
+    fn selected_entry(&self) -> HResult<TrashEntry> {
+        Ok(self.content.get(self.get_selection())?.clone())
+    }
+
+    fn remove_selected(&mut self) {
+        let selection = self.get_selection();
+        self.content.remove(selection);
+        self.refresh().log();
+        self.core.clear().log();
+    }
+
+    fn restore(&mut self) -> HResult<()> {
+        let entry = self.selected_entry()?;
+        entry.restore()?;
+        self.remove_selected();
+        self.core.show_status(&format!("Restored {}",
+                                       entry.original.to_string_lossy()))
+    }
+
+    fn delete(&mut self) -> HResult<()> {
+        let entry = self.selected_entry()?;
+        let query = format!("Delete {} for good? (y/n)", entry.name.to_string_lossy());
+        match self.core.minibuffer(&query)?.as_str() {
+            "y" | "yes" => {},
+            _ => return Ok(())
+        }
+
+        entry.delete()?;
+        self.remove_selected();
+        Ok(())
+    }
+
+    fn empty(&mut self) -> HResult<()> {
+        let query = format!("Delete all {} files in trash for good? (y/n)",
+                            self.content.len());
+        match self.core.minibuffer(&query)?.as_str() {
+            "y" | "yes" => {},
+            _ => return Ok(())
+        }
+
+        for entry in self.content.drain(..) {
+            entry.delete().log();
+        }
+
+        self.refresh().log();
+        self.core.clear().log();
+        self.core.show_status("Emptied trash")
+    }
+}
+
+impl Acting for ListView<Vec<TrashEntry>> {
+    type Action = TrashAction;
+
+    fn search_in(&self) -> Bindings<Self::Action> {
+        self.core.config().keybinds.trash
+    }
+
+    fn movement(&mut self, movement: &Movement) -> HResult<()> {
+        use Movement::*;
+
+        match movement {
+            Up(n) => { for _ in 0..*n { self.move_up(); }; self.refresh()?; }
+            Down(n) => { for _ in 0..*n { self.move_down(); }; self.refresh()?; }
+            PageUp => self.page_up(),
+            PageDown => self.page_down(),
+            Top => self.move_top(),
+            Bottom => self.move_bottom(),
+            Left | Right => {}
+        }
+
+        Ok(())
+    }
+
+    fn do_action(&mut self, action: &Self::Action) -> HResult<()> {
+        use TrashAction::*;
+
+        match action {
+            Close => self.popup_finnished()?,
+            Restore => self.restore()?,
+            DeleteForever => self.delete()?,
+            Empty => self.empty()?
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,139 @@
+use std::path::Path;
+
+use crate::fail::HResult;
+
+
+// Like the tree command. Symlinks to directories aren't followed, so this
+// can't go around in circles. Things listed in a .gitignore are left out,
+// as far as simple patterns go.
+pub struct Options {
+    pub depth: usize,
+    pub show_hidden: bool,
+    // only applies to the top directory, like the filter in the listing
+    pub filter: Option<String>,
+}
+
+// a huge tree is useless as text anyway
+const TREE_MAX: usize = 100_000;
+
+struct Tree {
+    lines: Vec<String>,
+    dirs: usize,
+    files: usize,
+}
+
+// *, ? and literal text, a trailing / only matches directories
+fn glob_match(pattern: &[char], name: &[char]) -> bool {
+    match (pattern.first(), name.first()) {
+        (None, None) => true,
+        (Some('*'), _) => glob_match(&pattern[1..], name) ||
+            (!name.is_empty() && glob_match(pattern, &name[1..])),
+        (Some('?'), Some(_)) => glob_match(&pattern[1..], &name[1..]),
+        (Some(p), Some(n)) if p == n => glob_match(&pattern[1..], &name[1..]),
+        _ => false
+    }
+}
+
+pub fn ignore_patterns(dir: &Path) -> Vec<String> {
+    std::fs::read_to_string(dir.join(".gitignore"))
+        .map(|ignore| {
+            ignore.lines()
+                .map(|line| line.trim())
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                // negations and paths are more than this is meant for
+                .filter(|line| !line.starts_with('!'))
+                .filter(|line| !line.trim_end_matches('/').contains('/'))
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub fn is_ignored(patterns: &[String], name: &str, is_dir: bool) -> bool {
+    let name = name.chars().collect::<Vec<_>>();
+
+    patterns.iter().any(|pattern| {
+        let dir_only = pattern.ends_with('/');
+        let pattern = pattern.trim_end_matches('/').chars().collect::<Vec<_>>();
+        (is_dir || !dir_only) && glob_match(&pattern, &name)
+    })
+}
+
+fn walk(dir: &Path,
+        prefix: &str,
+        depth: usize,
+        options: &Options,
+        inherited: &[String],
+        tree: &mut Tree) -> HResult<()> {
+    let mut patterns = inherited.to_vec();
+    patterns.extend(ignore_patterns(dir));
+
+    let mut entries = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let is_link = entry.file_type().map(|t| t.is_symlink()).unwrap_or(false);
+            let is_dir = !is_link && entry.path().is_dir();
+            (name, is_dir, is_link, entry.path())
+        })
+        .filter(|(name, _, _, _)| options.show_hidden || !name.starts_with('.'))
+        .filter(|(name, _, _, _)| name != ".git")
+        .filter(|(name, is_dir, _, _)| !is_ignored(&patterns, name, *is_dir))
+        .filter(|(name, _, _, _)| {
+            depth > 0 || options.filter.as_ref()
+                .map(|filter| name.contains(filter.as_str()))
+                .unwrap_or(true)
+        })
+        .collect::<Vec<_>>();
+
+    entries.sort_by(|(a, a_dir, _, _), (b, b_dir, _, _)| {
+        b_dir.cmp(a_dir).then_with(|| alphanumeric_sort::compare_str(a, b))
+    });
+
+    let count = entries.len();
+    for (i, (name, is_dir, is_link, path)) in entries.into_iter().enumerate() {
+        if tree.lines.len() >= TREE_MAX {
+            tree.lines.push(format!("{}...", prefix));
+            return Ok(());
+        }
+
+        let last = i + 1 == count;
+        let branch = if last { "└── " } else { "├── " };
+        let target = match is_link {
+            true => path.read_link()
+                .map(|target| format!(" -> {}", target.to_string_lossy()))
+                .unwrap_or_default(),
+            false => String::new()
+        };
+
+        tree.lines.push(format!("{}{}{}{}", prefix, branch, name, target));
+
+        if is_dir {
+            tree.dirs += 1;
+            if depth + 1 < options.depth {
+                let prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+                // unreadable directories just stay empty
+                walk(&path, &prefix, depth + 1, options, &patterns, tree).ok();
+            }
+        } else {
+            tree.files += 1;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn render(dir: &Path, options: &Options) -> HResult<String> {
+    let mut tree = Tree {
+        lines: vec![dir.to_string_lossy().to_string()],
+        dirs: 0,
+        files: 0
+    };
+
+    walk(dir, "", 0, options, &[], &mut tree)?;
+
+    Ok(format!("{}\n\n{} directories, {} files\n",
+               tree.lines.join("\n"),
+               tree.dirs,
+               tree.files))
+}
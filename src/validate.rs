@@ -0,0 +1,428 @@
+use termion::event::Key;
+use unicode_width::UnicodeWidthStr;
+
+use crate::coordinates::Coordinates;
+use crate::fail::{HResult, HError, ErrorLog};
+use crate::keybind::AnyKey;
+use crate::term;
+use crate::widget::{Widget, WidgetCore};
+
+
+// The config and keys files are checked on startup, anything hunter
+// doesn't understand is shown right away. Loading still skips over bad
+// lines and uses the defaults for them, this just makes sure it's noticed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+    pub file: &'static str,
+    pub line: usize,
+    pub key: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for Problem {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "{}:{}: {}: {}", self.file, self.line, self.key, self.message)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Value {
+    Switch,
+    Number,
+    Size,
+    Text,
+    Choice(&'static [&'static str]),
+    Ratios,
+    PathKeep,
+}
+
+// Needs to be kept in line with Config::load
+const CONFIG: &[(&str, Value)] = &[
+    ("animation", Value::Switch),
+    ("animation_auto_off", Value::Switch),
+    ("animation_refresh_frequency", Value::Number),
+    ("show_hidden", Value::Switch),
+    ("icons", Value::Switch),
+    ("select_cmd", Value::Text),
+    ("cd_cmd", Value::Text),
+    ("media_autoplay", Value::Switch),
+    ("media_autostart", Value::Switch),
+    ("media_mute", Value::Switch),
+    ("media_previewer", Value::Text),
+    ("ratios", Value::Ratios),
+    ("title_update", Value::Switch),
+    ("title_format", Value::Text),
+    ("footer_format", Value::Text),
+    ("header_format", Value::Text),
+    ("one_filesystem", Value::Switch),
+    ("transfer_limit", Value::Size),
+    ("mouse", Value::Switch),
+    ("tab_bar", Value::Switch),
+    ("dual_pane", Value::Switch),
+    ("layout_translation", Value::Switch),
+    ("dir_stats", Value::Switch),
+    ("render_markdown", Value::Switch),
+    ("preview_cache_size", Value::Size),
+    ("preview_timeout", Value::Number),
+    ("process_limit", Value::Number),
+    ("process_logs", Value::Switch),
+    ("process_notify", Value::Switch),
+    ("direnv", Value::Switch),
+    ("nice_level", Value::Number),
+    ("cpu_quota", Value::Text),
+    ("project_detection", Value::Switch),
+    ("recent_roots", Value::Text),
+    ("cleanup_threshold", Value::Size),
+    ("key_hints", Value::Switch),
+    ("open_rules", Value::Text),
+    ("protected_paths", Value::Text),
+    ("trash_max_age", Value::Number),
+    ("trash_max_size", Value::Size),
+    ("path_truncation", Value::PathKeep),
+    ("graphics", Value::Choice(GRAPHICS)),
+];
+
+#[cfg(feature = "sixel")]
+const GRAPHICS: &[&str] = &["auto", "kitty", "sixel", "unicode"];
+#[cfg(not(feature = "sixel"))]
+const GRAPHICS: &[&str] = &["auto", "kitty", "unicode"];
+
+fn numbers(value: &str) -> Option<Vec<usize>> {
+    value.split(|c| c == ',' || c == ':')
+        .map(|n| n.trim().parse::<usize>().ok())
+        .collect()
+}
+
+// None if it's fine, otherwise what was expected
+fn check_value(kind: Value, value: &str) -> Option<String> {
+    let fine = match kind {
+        Value::Switch => value == "on" || value == "off",
+        Value::Number => value.parse::<u64>().is_ok(),
+        Value::Size => crate::theme::parse_size(value).is_ok(),
+        Value::Text => true,
+        Value::Choice(choices) => choices.contains(&value),
+        Value::Ratios => numbers(value)
+            .map(|ratios| ratios.len() == 3 &&
+                 ratios.iter().sum::<usize>() > 0 &&
+                 ratios.iter().all(|&r| r <= u16::max_value() as usize))
+            .unwrap_or(false),
+        Value::PathKeep => numbers(value)
+            .map(|keep| keep.len() == 2)
+            .unwrap_or(false)
+    };
+
+    if fine { return None; }
+
+    let expected = match kind {
+        Value::Switch => String::from("on or off"),
+        Value::Number => String::from("a number"),
+        Value::Size => String::from("a size like 512K or 10M"),
+        Value::Text => String::new(),
+        Value::Choice(choices) => format!("one of {}", choices.join(", ")),
+        Value::Ratios => String::from("three column widths like 20,30,49"),
+        Value::PathKeep => String::from("two numbers like 1,2")
+    };
+
+    Some(format!("expected {}, got \"{}\"", expected, value))
+}
+
+fn distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let old = row[j + 1];
+            row[j + 1] = match ca == *cb {
+                true => prev,
+                false => 1 + prev.min(row[j]).min(row[j + 1])
+            };
+            prev = old;
+        }
+    }
+
+    row[b.len()]
+}
+
+fn unknown<'a>(what: &str, name: &str, known: impl Iterator<Item=&'a str>) -> String {
+    let closest = known
+        .map(|known| (distance(name, known), known))
+        .min_by_key(|(distance, _)| *distance)
+        .filter(|(distance, _)| *distance <= 3);
+
+    match closest {
+        Some((_, closest)) => format!("unknown {}, did you mean {}?", what, closest),
+        None => format!("unknown {}", what)
+    }
+}
+
+fn skip(line: &str) -> bool {
+    let line = line.trim();
+    line.is_empty() || line.starts_with('#') || line.starts_with(';')
+}
+
+pub fn check_config(text: &str) -> Vec<Problem> {
+    let mut problems = vec![];
+
+    for (i, line) in text.lines().enumerate() {
+        if skip(line) { continue; }
+
+        let problem = |key: &str, message: String| Problem {
+            file: "config",
+            line: i + 1,
+            key: key.to_string(),
+            message: message
+        };
+
+        let setting = line.split('=').collect::<Vec<_>>();
+        if setting.len() != 2 {
+            problems.push(problem(line, String::from("expected option=value, \
+                                                      values can't contain =")));
+            continue;
+        }
+
+        let (key, value) = (setting[0], setting[1]);
+
+        match CONFIG.iter().find(|(name, _)| *name == key) {
+            Some((_, kind)) => {
+                if let Some(message) = check_value(*kind, value) {
+                    problems.push(problem(key, message));
+                }
+            }
+            None => {
+                let known = CONFIG.iter().map(|(name, _)| *name);
+                problems.push(problem(key, unknown("option", key, known)));
+            }
+        }
+    }
+
+    problems
+}
+
+pub fn check_keys(text: &str) -> Vec<Problem> {
+    let sections = crate::keybind::sections();
+    let mut problems = vec![];
+    let mut section: Option<&(&str, Vec<String>)> = None;
+    let mut in_commands = false;
+    let mut in_palette = false;
+
+    for (i, line) in text.lines().enumerate() {
+        if skip(line) { continue; }
+        let line = line.trim();
+
+        let problem = |key: &str, message: String| Problem {
+            file: "keys",
+            line: i + 1,
+            key: key.to_string(),
+            message: message
+        };
+
+        if line.starts_with('[') && line.ends_with(']') {
+            let name = &line[1..line.len() - 1];
+            section = sections.iter().find(|(section, _)| *section == name);
+            in_commands = name == "commands";
+            in_palette = name == "palette";
+
+            if section.is_none() && !in_commands && !in_palette {
+                let known = sections.iter().map(|(section, _)| *section);
+                problems.push(problem(line, unknown("section", name, known)));
+            }
+            continue;
+        }
+
+        // key=command, only the key can be wrong
+        if in_commands {
+            let key = line.split('=').next().unwrap_or(line).trim();
+            if !line.contains('=') {
+                problems.push(problem(line, String::from("expected key=command")));
+            } else if key.parse::<AnyKey>().is_err() {
+                problems.push(problem(key,
+                                      format!("can't make sense of key \"{}\", \
+                                               expected something like x, C-x, M-x or F1",
+                                              key)));
+            }
+            continue;
+        }
+
+        // name=!command or name=Action, with the action from the file
+        // browser or the file list
+        if in_palette {
+            let target = line.splitn(2, '=').nth(1).map(|target| target.trim());
+            let known = sections.iter()
+                .filter(|(section, _)| *section == "filebrowser" || *section == "filelist")
+                .flat_map(|(_, actions)| actions.iter().map(|action| action.as_str()));
+
+            match target {
+                None => problems.push(problem(line,
+                                              String::from("expected name=Action or name=!command"))),
+                Some(target) if target.starts_with('!') => {}
+                Some(target) => {
+                    let name = target.split('(').next().unwrap_or(target);
+                    if !known.clone().any(|known| known == name) {
+                        problems.push(problem(target, unknown("action", name, known)));
+                    }
+                }
+            }
+            continue;
+        }
+
+        // actions in an unknown section were already complained about
+        let actions = match section {
+            Some((_, actions)) => actions,
+            None => continue
+        };
+
+        let pos = match line.find('=') {
+            Some(pos) => pos,
+            None => {
+                problems.push(problem(line, String::from("expected Action=keys")));
+                continue;
+            }
+        };
+
+        let action = line[..pos].trim();
+        let keys = &line[pos + 1..];
+        let name = action.split('(').next().unwrap_or(action);
+
+        if !actions.iter().any(|known| known == name) {
+            let known = actions.iter().map(|known| known.as_str());
+            problems.push(problem(action, unknown("action", name, known)));
+            continue;
+        }
+
+        for key in keys.split(',').map(|key| key.trim()) {
+            if key.parse::<AnyKey>().is_err() {
+                problems.push(problem(action,
+                                      format!("can't make sense of key \"{}\", \
+                                               expected something like x, C-x, M-x or F1",
+                                              key)));
+            }
+        }
+    }
+
+    problems
+}
+
+// Both files, everything that's wrong also goes to the log
+pub fn check() -> Vec<Problem> {
+    let read = |path: HResult<std::path::PathBuf>| {
+        path.ok()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .unwrap_or_default()
+    };
+
+    let mut problems = check_config(&read(crate::paths::config_path()));
+    problems.extend(check_keys(&read(crate::paths::bindings_path())));
+
+    for problem in &problems {
+        HError::log::<()>(&problem.to_string()).ok();
+    }
+
+    problems
+}
+
+pub struct ProblemsPopup {
+    core: WidgetCore,
+    title: String,
+    lines: Vec<String>,
+}
+
+impl ProblemsPopup {
+    pub fn new(core: &WidgetCore, problems: &[Problem]) -> HResult<ProblemsPopup> {
+        let dir = crate::paths::hunter_path()?;
+        let mut popup = ProblemsPopup {
+            core: core.clone(),
+            title: format!("Problems in {}, any key to go on",
+                           dir.to_string_lossy()),
+            lines: problems.iter().map(|problem| problem.to_string()).collect()
+        };
+        popup.set_coordinates(&core.coordinates)?;
+        Ok(popup)
+    }
+
+    pub fn show(&mut self) -> HResult<()> {
+        match self.popup() {
+            Ok(_) | Err(HError::PopupFinnished) => {},
+            err @ Err(_) => err?,
+        }
+        self.get_core()?.clear()?;
+        Ok(())
+    }
+}
+
+impl Widget for ProblemsPopup {
+    fn get_core(&self) -> HResult<&WidgetCore> {
+        Ok(&self.core)
+    }
+    fn get_core_mut(&mut self) -> HResult<&mut WidgetCore> {
+        Ok(&mut self.core)
+    }
+    fn refresh(&mut self) -> HResult<()> {
+        Ok(())
+    }
+
+    fn set_coordinates(&mut self, _: &Coordinates) -> HResult<()> {
+        let (xsize, ysize) = term::size()?;
+        let width = self.lines.iter()
+            .map(|line| line.width())
+            .chain(std::iter::once(self.title.width()))
+            .max()
+            .unwrap_or(0) + 2;
+        let width = width.min(xsize);
+        let height = (self.lines.len() + 2).min(ysize);
+
+        self.core.coordinates = Coordinates::new_at(width as u16,
+                                                    height as u16,
+                                                    ((xsize - width) / 2 + 1) as u16,
+                                                    ((ysize - height) / 2 + 1) as u16);
+        Ok(())
+    }
+
+    fn get_drawlist(&self) -> HResult<String> {
+        let (xpos, ypos) = self.get_coordinates()?.u16position();
+        let (xsize, ysize) = self.get_coordinates()?.u16size();
+        let (xsize, ysize) = (xsize as usize, ysize as usize);
+
+        let title = format!("{}{}{}{}",
+                            term::goto_xy(xpos, ypos),
+                            term::header_color(),
+                            term::sized_string_u(&format!(" {}", self.title), xsize),
+                            term::reset());
+
+        // the rest is in the log
+        let room = ysize.saturating_sub(2);
+        let mut lines = self.lines.iter().take(room).cloned().collect::<Vec<_>>();
+        if self.lines.len() > room && room > 0 {
+            lines.pop();
+            lines.push(format!("...and {} more, see the log",
+                               self.lines.len() - lines.len()));
+        }
+        lines.push(String::new());
+
+        let lines = lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| format!("{}{}{}{}",
+                                     term::goto_xy(xpos, ypos + 1 + i as u16),
+                                     term::color_red(),
+                                     term::sized_string_u(&format!(" {}", line), xsize),
+                                     term::reset()))
+            .collect::<String>();
+
+        Ok(title + &lines)
+    }
+
+    fn on_key(&mut self, _: Key) -> HResult<()> {
+        self.popup_finnished()
+    }
+}
+
+pub fn show_problems(core: &WidgetCore) -> HResult<()> {
+    let problems = check();
+    if problems.is_empty() { return Ok(()); }
+
+    ProblemsPopup::new(core, &problems)?.show().log();
+    Ok(())
+}
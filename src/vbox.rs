@@ -0,0 +1,278 @@
+use termion::event::Event;
+
+use crate::widget::{Widget, WidgetCore};
+use crate::coordinates::{Coordinates, Size, Position};
+use crate::fail::{HResult, HError, ErrorLog};
+use crate::term;
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Constraint {
+    // this many lines, no matter what
+    Fixed(u16),
+    // a share of whatever the fixed ones leave over
+    Ratio(usize),
+}
+
+// Stacks areas on top of each other, fixed ones first get what they want,
+// the rest is split by ratio. The last ratio area gets any rounding leftovers.
+pub fn layout(area: &Coordinates, constraints: &[Constraint]) -> Vec<Coordinates> {
+    let (xpos, ypos) = area.u16position();
+    let (xsize, ysize) = area.u16size();
+
+    let fixed: u16 = constraints.iter()
+        .map(|constraint| match constraint {
+            Constraint::Fixed(lines) => *lines,
+            Constraint::Ratio(_) => 0
+        })
+        .sum();
+    let ratios_sum: usize = constraints.iter()
+        .map(|constraint| match constraint {
+            Constraint::Ratio(ratio) => *ratio,
+            Constraint::Fixed(_) => 0
+        })
+        .sum();
+    let last_ratio = constraints.iter()
+        .rposition(|constraint| match constraint {
+            Constraint::Ratio(_) => true,
+            Constraint::Fixed(_) => false
+        });
+
+    let flexible = ysize.saturating_sub(fixed) as usize;
+    let mut handed_out = 0;
+    let mut top = ypos;
+
+    constraints.iter()
+        .enumerate()
+        .map(|(i, constraint)| {
+            let lines = match constraint {
+                Constraint::Fixed(lines) => *lines,
+                Constraint::Ratio(_) if Some(i) == last_ratio => {
+                    (flexible - handed_out) as u16
+                }
+                Constraint::Ratio(ratio) => {
+                    let lines = flexible * ratio / ratios_sum.max(1);
+                    handed_out += lines;
+                    lines as u16
+                }
+            };
+
+            let coords = Coordinates {
+                size: Size((xsize, lines)),
+                position: Position((xpos, top))
+            };
+            top += lines;
+            coords
+        })
+        .collect()
+}
+
+// The header line, the space for widgets and the footer line
+pub fn main_area(xsize: u16, ysize: u16) -> Coordinates {
+    use Constraint::*;
+
+    let screen = Coordinates::new_at(xsize, ysize, 1, 1);
+    layout(&screen, &[Fixed(1), Ratio(1), Fixed(1)]).remove(1)
+}
+
+
+
+// A row of the screen. The header and footer lines belong to whichever
+// widget is active, a terminal under the columns would just be another
+// widget row.
+#[derive(Debug, PartialEq)]
+pub enum Row<T> {
+    Header,
+    Widget(T),
+    Footer
+}
+
+#[derive(Debug, PartialEq)]
+pub struct VBox<T: Widget> {
+    pub core: WidgetCore,
+    pub rows: Vec<Row<T>>,
+    pub constraints: Vec<Constraint>,
+    pub active: Option<usize>,
+}
+
+
+impl<T> VBox<T> where T: Widget {
+    // Takes up the whole screen, header and footer included
+    pub fn new(core: &WidgetCore) -> VBox<T> {
+        let mut core = core.clone();
+        core.coordinates = Coordinates::new_at(term::xsize(), term::ysize(), 1, 1);
+
+        VBox { core: core,
+               rows: vec![],
+               constraints: vec![],
+               active: None
+        }
+    }
+
+    pub fn resize_children(&mut self) -> HResult<()> {
+        let coords = layout(&self.core.coordinates, &self.constraints);
+
+        for (row, coord) in self.rows.iter_mut().zip(coords.iter()) {
+            if let Row::Widget(widget) = row {
+                widget.set_coordinates(coord).log();
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn push_header(&mut self) {
+        self.rows.push(Row::Header);
+        self.constraints.push(Constraint::Fixed(1));
+        self.resize_children().log();
+    }
+
+    pub fn push_footer(&mut self) {
+        self.rows.push(Row::Footer);
+        self.constraints.push(Constraint::Fixed(1));
+        self.resize_children().log();
+    }
+
+    // The first widget becomes the active one
+    pub fn push_widget(&mut self, widget: T, constraint: Constraint) -> HResult<()> {
+        self.rows.push(Row::Widget(widget));
+        self.constraints.push(constraint);
+
+        if self.active.is_none() {
+            self.set_active(self.rows.len() - 1)?;
+        }
+        self.resize_children()
+    }
+
+    pub fn set_constraint(&mut self, index: usize, constraint: Constraint) -> HResult<()> {
+        *self.constraints.get_mut(index)? = constraint;
+        self.core.clear().log();
+        self.resize_children()
+    }
+
+    pub fn set_active(&mut self, i: usize) -> HResult<()> {
+        match self.rows.get(i) {
+            Some(Row::Widget(_)) => {},
+            _ => HError::no_widget()?
+        }
+        self.active = Some(i);
+
+        for (n, row) in self.rows.iter_mut().enumerate() {
+            if let Row::Widget(widget) = row {
+                widget.set_focus(n == i).log();
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn active_widget(&self) -> Option<&T> {
+        match self.rows.get(self.active?)? {
+            Row::Widget(widget) => Some(widget),
+            _ => None
+        }
+    }
+
+    pub fn active_widget_mut(&mut self) -> Option<&mut T> {
+        match self.rows.get_mut(self.active?)? {
+            Row::Widget(widget) => Some(widget),
+            _ => None
+        }
+    }
+
+    fn widgets_mut(&mut self) -> impl Iterator<Item=&mut T> {
+        self.rows.iter_mut().filter_map(|row| match row {
+            Row::Widget(widget) => Some(widget),
+            _ => None
+        })
+    }
+
+    fn render_line(&self, coords: &Coordinates, line: String) -> String {
+        let (xpos, ypos) = coords.u16position();
+
+        format!("{}{}{:xsize$}{}{}",
+                term::goto_xy(xpos, ypos),
+                term::header_color(),
+                " ",
+                term::goto_xy(xpos, ypos),
+                line,
+                xsize = coords.xsize() as usize)
+    }
+}
+
+
+impl<T> Widget for VBox<T> where T: Widget {
+    fn get_core(&self) -> HResult<&WidgetCore> {
+        Ok(&self.core)
+    }
+    fn get_core_mut(&mut self) -> HResult<&mut WidgetCore> {
+        Ok(&mut self.core)
+    }
+
+    fn set_coordinates(&mut self, coordinates: &Coordinates) -> HResult<()> {
+        self.core.coordinates = coordinates.clone();
+        self.resize_children()
+    }
+
+    fn config_loaded(&mut self) -> HResult<()> {
+        for widget in self.widgets_mut() {
+            widget.config_loaded().log();
+        }
+        Ok(())
+    }
+
+    fn render_header(&self) -> HResult<String> {
+        self.active_widget()?.render_header()
+    }
+
+    fn render_footer(&self) -> HResult<String> {
+        self.active_widget()?.render_footer()
+    }
+
+    fn refresh(&mut self) -> HResult<()> {
+        for widget in self.widgets_mut() {
+            widget.refresh().log();
+        }
+        Ok(())
+    }
+
+    fn get_drawlist(&self) -> HResult<String> {
+        let coords = layout(&self.core.coordinates, &self.constraints);
+
+        Ok(self.rows.iter().zip(coords.iter()).map(|(row, coords)| {
+            match row {
+                Row::Header => self.render_header()
+                    .map(|header| self.render_line(coords, header))
+                    .unwrap_or_default(),
+                Row::Widget(widget) => widget.get_drawlist()
+                    .log_and()
+                    .unwrap_or_default(),
+                Row::Footer => self.render_footer()
+                    .map(|footer| self.render_line(coords, footer))
+                    .unwrap_or_default()
+            }
+        }).collect())
+    }
+
+    // the header and footer are rows of their own
+    fn get_header_drawlist(&mut self) -> HResult<String> {
+        Ok(String::new())
+    }
+
+    fn get_footer_drawlist(&mut self) -> HResult<String> {
+        Ok(String::new())
+    }
+
+    fn on_event(&mut self, event: Event) -> HResult<()> {
+        self.active_widget_mut()?.on_event(event)
+    }
+
+    fn resize(&mut self) -> HResult<()> {
+        if let Ok(true) = self.core.screen()?.is_resized() {
+            let (xsize, ysize) = self.core.screen()?.get_size()?;
+            let screen = Coordinates::new_at(xsize as u16 + 1, ysize as u16 + 1, 1, 1);
+            self.set_coordinates(&screen)?;
+        }
+        Ok(())
+    }
+}
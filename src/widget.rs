@@ -1,9 +1,10 @@
 use std::sync::{Arc, Mutex, RwLock};
 use std::sync::mpsc::{Sender, Receiver, channel};
 use std::io::{Write, stdin};
+use std::path::Path;
 
 use termion::event::{Event, Key, MouseEvent};
-use termion::input::TermRead;
+use termion::input::TermReadEventsAndRaw;
 use async_value::{Async, Stale};
 
 
@@ -15,6 +16,8 @@ use crate::term::{Screen, ScreenExt};
 use crate::dirty::{Dirtyable, DirtyBit};
 use crate::signal_notify::{notify, Signal};
 use crate::config::Config;
+use crate::overlay::OverlayLayer;
+use crate::animation::{Animation, Tween};
 
 
 
@@ -29,6 +32,7 @@ pub enum Events {
     RequestInput,
     Status(String),
     ConfigLoaded,
+    KeysChanged,
 }
 
 impl PartialEq for WidgetCore {
@@ -61,6 +65,9 @@ pub struct WidgetCore {
     pub status_bar_content: Arc<Mutex<Option<String>>>,
     term_size: (usize, usize),
     dirty: DirtyBit,
+    // not shared, every clone decides for itself
+    focused: bool,
+    pub overlays: OverlayLayer,
     pub config: Arc<RwLock<Async<Config>>>
 }
 
@@ -68,10 +75,7 @@ impl WidgetCore {
     pub fn new() -> HResult<WidgetCore> {
         let screen = Screen::new()?;
         let (xsize, ysize) = screen.size()?;
-        let coords = Coordinates::new_at(term::xsize(),
-                                         term::ysize() - 2,
-                                         1,
-                                         2);
+        let coords = crate::vbox::main_area(term::xsize(), term::ysize());
         let (sender, receiver) = channel();
         let status_bar_content = Arc::new(Mutex::new(None));
 
@@ -92,6 +96,8 @@ impl WidgetCore {
             status_bar_content: status_bar_content,
             term_size: (xsize, ysize),
             dirty: DirtyBit::new(),
+            focused: true,
+            overlays: OverlayLayer::new(),
             config: Arc::new(RwLock::new(config)) };
 
         let minibuffer = MiniBuffer::new(&core);
@@ -109,7 +115,7 @@ impl WidgetCore {
             Some(status) => status.to_string(),
             None => "".to_string(),
         };
-        let sized_status = term::sized_string_u(&status, xsize);
+        let sized_status = self.sized_status(&status, xsize);
 
         self.write_to_screen(
             &format!(
@@ -122,6 +128,23 @@ impl WidgetCore {
         Ok(())
     }
 
+    pub fn sized_status(&self, status: &str, xsize: usize) -> String {
+        // shorten any paths in there before cutting off the end
+        if term::string_len(status) > xsize {
+            let keep = self.config().path_keep;
+            let status = status.split(" ")
+                .map(|word| match word.contains("/") {
+                    true => term::sized_path(word, xsize / 2, keep),
+                    false => word.to_string()
+                })
+                .collect::<Vec<String>>()
+                .join(" ");
+            term::sized_string_u(&status, xsize)
+        } else {
+            term::sized_string_u(status, xsize)
+        }
+    }
+
     pub fn show_status(&self, status: &str) -> HResult<()> {
         HError::log::<()>(status).ok();
         {
@@ -149,6 +172,38 @@ impl WidgetCore {
         answer
     }
 
+    pub fn minibuffer_prefilled(&self, query: &str, text: &str) -> HResult<String> {
+        let answer = self.minibuffer
+            .lock()?
+            .as_mut()?
+            .query_prefilled(query, text);
+        let mut screen = self.screen()?;
+        screen.cursor_hide().log();
+        answer
+    }
+
+    // Tab completes from choices only
+    pub fn minibuffer_choices(&self, query: &str, choices: &[String]) -> HResult<String> {
+        let answer = self.minibuffer
+            .lock()?
+            .as_mut()?
+            .query_choices(query, choices);
+        let mut screen = self.screen()?;
+        screen.cursor_hide().log();
+        answer
+    }
+
+    // History of commands run in dir first
+    pub fn minibuffer_in_dir(&self, query: &str, dir: &Path) -> HResult<String> {
+        let answer = self.minibuffer
+            .lock()?
+            .as_mut()?
+            .query_in_dir(query, dir);
+        let mut screen = self.screen()?;
+        screen.cursor_hide().log();
+        answer
+    }
+
     pub fn minibuffer_continuous(&self, query: &str) -> HResult<String> {
         let answer = self.minibuffer
             .lock()?
@@ -191,6 +246,51 @@ impl WidgetCore {
         screen.write_str(s)
     }
 
+    pub fn show_overlay(&self,
+                        z: usize,
+                        coordinates: Coordinates,
+                        drawlist: String) -> HResult<usize> {
+        let id = self.overlays.show(z, coordinates, drawlist)?;
+        self.get_sender().send(Events::WidgetReady)?;
+        Ok(id)
+    }
+
+    // Blanks the covered area and has everything redrawn on top of it
+    pub fn close_overlay(&self, id: usize) -> HResult<()> {
+        let overlay = self.overlays.close(id)?;
+        self.write_to_screen(&overlay.clearlist())?;
+        self.get_sender().send(Events::WidgetReady)?;
+        Ok(())
+    }
+
+    pub fn has_focus(&self) -> bool {
+        self.focused
+    }
+
+    pub fn set_focus(&mut self, focused: bool) {
+        if self.focused != focused {
+            self.focused = focused;
+            self.set_dirty();
+        }
+    }
+
+    // Everything shares the same config, so that's where the new keys go
+    pub fn reload_keys(&self) -> HResult<()> {
+        let keybinds = crate::keybind::KeyBinds::load()?;
+        self.config.write()?.get_mut()?.keybinds = keybinds;
+
+        let text = std::fs::read_to_string(crate::paths::bindings_path()?)?;
+        let problems = crate::validate::check_keys(&text);
+        for problem in &problems {
+            HError::log::<()>(&problem.to_string()).ok();
+        }
+
+        match problems.len() {
+            0 => self.show_status("Reloaded keys"),
+            n => self.show_status(&format!("Reloaded keys, {} bad lines skipped, see the log", n))
+        }
+    }
+
     pub fn config(&self) -> Config {
         self.get_conf()
             .unwrap_or_else(|_| Config::new())
@@ -247,6 +347,17 @@ pub trait Widget {
     fn after_draw(&self) -> HResult<()> { Ok(()) }
     fn config_loaded(&mut self) -> HResult<()> { Ok(()) }
 
+    fn has_focus(&self) -> bool {
+        self.get_core()
+            .map(|core| core.has_focus())
+            .unwrap_or(false)
+    }
+
+    fn set_focus(&mut self, focused: bool) -> HResult<()> {
+        self.get_core_mut()?.set_focus(focused);
+        Ok(())
+    }
+
 
 
     fn on_event(&mut self, event: Event) -> HResult<()> {
@@ -337,6 +448,7 @@ pub trait Widget {
             let ypos = self.get_coordinates()?.ypos();
             print!("\x1b_Ga=d,d=y,y={}\x1b\\", ypos+1);
         }
+        crate::graphics::clear_ueberzug();
         let result = self.run_widget();
         self.get_core()?.clear().log();
         self.get_core()?.get_sender().send(Events::ExclusiveEvent(None))?;
@@ -373,7 +485,7 @@ pub trait Widget {
                     self.get_core()?.get_sender().send(Events::RequestInput)?;
                 }
                 Events::WidgetReady => {
-                    self.refresh().log();
+                    self.refresh_popup()?;
                     self.draw().log();
                 }
                 Events::Status(status) => {
@@ -392,19 +504,46 @@ pub trait Widget {
                 Events::ConfigLoaded => {
                     self.get_core_mut()?.config.write()?.pull_async()?;
                 }
+                Events::KeysChanged => {
+                    self.get_core()?.reload_keys().log();
+                }
                 _ => {}
             }
-            self.refresh().log();
+            self.refresh_popup()?;
             self.draw().log();
             self.after_draw().log();
         }
         Ok(())
     }
 
+    // A popup can close itself when it's refreshed, like the process list
+    // once the job it was opened for is done
+    fn refresh_popup(&mut self) -> HResult<()> {
+        match self.refresh() {
+            err @ Err(HError::PopupFinnished) => err,
+            err @ Err(_) => Ok(err.log()),
+            Ok(_) => Ok(())
+        }
+    }
+
 
-    fn animate_slide_up(&mut self, animator: Option<&Stale>) -> HResult<()> {
-        if !self.get_core()?.config().animate() { return Ok(()); }
+    // All motion goes through here, so turning it off turns it off everywhere
+    fn animate(&mut self,
+               animation: Animation,
+               animator: Option<&Stale>) -> HResult<()> {
+        let tween = match Tween::new(&self.get_core()?.config(), animation) {
+            Some(tween) => tween,
+            None => return Ok(())
+        };
+
+        match animation {
+            Animation::SlideUp => self.animate_slide_up(tween, animator)
+        }
+    }
 
+    fn animate_slide_up(&mut self,
+                        tween: Tween,
+                        animator: Option<&Stale>) -> HResult<()> {
         let coords = self.get_coordinates()?.clone();
         let xpos = coords.position().x();
         let ypos = coords.position().y();
@@ -412,12 +551,6 @@ pub trait Widget {
         let ysize = coords.ysize();
         let clear = self.get_core()?.get_clearlist()?;
 
-        let animation_hz = self.get_core()?.config().animation_refresh_frequency as u64;
-        let pause_millis = 1000/animation_hz;
-        const ANIMATION_DURATION_MILLIS: u64 = 64;
-        let number_of_frames= (ANIMATION_DURATION_MILLIS/pause_millis) as u16;
-        let pause = std::time::Duration::from_millis(pause_millis);
-
         if let Some(ref animator) = animator {
             if animator.is_stale()? {
                 return Ok(())
@@ -426,7 +559,7 @@ pub trait Widget {
 
         self.get_core()?.write_to_screen(&clear).log();
 
-        for i in (0..number_of_frames).rev() {
+        for i in tween.frames() {
             if let Some(ref animator) = animator {
                 if animator.is_stale()? {
                     self.set_coordinates(&coords).log();
@@ -445,17 +578,19 @@ pub trait Widget {
                 self.get_core()?.write_to_screen(&buffer).log();
             }
 
-            std::thread::sleep(pause);
+            tween.wait();
         }
 
         Ok(())
     }
 
     fn draw(&mut self) -> HResult<()> {
+        let _span = crate::profile::span("draw");
         let output =
             self.get_drawlist().unwrap_or("".to_string()) +
             &self.get_header_drawlist().unwrap_or("".to_string()) +
-            &self.get_footer_drawlist().unwrap_or("".to_string());
+            &self.get_footer_drawlist().unwrap_or("".to_string()) +
+            &self.get_core()?.overlays.drawlist().unwrap_or_default();
         self.get_core()?.write_to_screen(&output).log();
         self.get_core()?.screen()?.flush().ok();
         Ok(())
@@ -470,6 +605,7 @@ pub trait Widget {
         for event in rx_internal_event.iter() {
             match event {
                 Events::InputEvent(event) => {
+                    let _span = crate::profile::span("event");
                     match self.on_event(event) {
                         Err(HError::Quit) => { HError::quit()?; },
                         _ => {}
@@ -486,13 +622,19 @@ pub trait Widget {
                     self.get_core_mut()?.config.write()?.pull_async().ok();
                     self.config_loaded().log();
                 }
+                Events::KeysChanged => {
+                    self.get_core()?.reload_keys().log();
+                }
                 _ => {}
             }
             self.resize().log();
             if self.get_core()?.screen()?.is_resized()? {
                 self.get_core()?.screen()?.take_size().ok();
             }
-            self.refresh().ok();
+            {
+                let _span = crate::profile::span("refresh");
+                self.refresh().ok();
+            }
             self.draw().ok();
         }
         Ok(())
@@ -501,8 +643,9 @@ pub trait Widget {
     fn resize(&mut self) -> HResult<()> {
         if let Ok(true) = self.get_core()?.screen()?.is_resized() {
             let (xsize, ysize) = self.get_core()?.screen()?.get_size()?;
+            let area = crate::vbox::main_area(xsize as u16 + 1, ysize as u16 + 1);
             let mut coords = self.get_core()?.coordinates.clone();
-            coords.set_size_u(xsize, ysize-2);
+            coords.set_size(area.xsize(), area.ysize());
             self.set_coordinates(&coords)?;
         }
         Ok(())
@@ -568,20 +711,66 @@ fn event_thread(rx_global: Receiver<Events>,
 
 fn input_thread(tx: Sender<Events>, rx_input_request: Receiver<()>) {
     std::thread::spawn(move || {
-        for input in stdin().events() {
-            input.map(|input| {
-                tx.send(Events::InputEvent(input)).unwrap();
-                rx_input_request.recv().unwrap();
-                term::flush_stdin();
+        // a recorded session goes first, with the same pauses as back then
+        if let Some(replay) = crate::session::take_replay() {
+            let started = std::time::Instant::now();
+
+            for recorded in replay {
+                let elapsed = started.elapsed();
+                if recorded.at > elapsed {
+                    std::thread::sleep(recorded.at - elapsed);
+                }
+
+                crate::session::parse(&recorded.raw).map(|input| {
+                    tx.send(Events::InputEvent(input)).unwrap();
+                    rx_input_request.recv().unwrap();
+                    term::flush_stdin();
+                }).log();
+            }
+
+            HError::log::<()>("Replay finished").ok();
+        }
+
+        for input in stdin().events_and_raw() {
+            input.map(|(input, raw)| {
+                crate::session::record_input(&raw);
+                send_input(&tx, &rx_input_request, input);
             }).map_err(|e| HError::from(e)).log();
         }
     });
 }
 
+// Waits until each key is dealt with before the next one goes out, so
+// keys from a macro end up in the popup the key before opened
+fn send_input(tx: &Sender<Events>, rx_input_request: &Receiver<()>, input: Event) {
+    let (forward, status) = crate::macros::intercept(&input);
+
+    if let Some(status) = status {
+        tx.send(Events::Status(status)).unwrap();
+    }
+
+    if forward {
+        tx.send(Events::InputEvent(input)).unwrap();
+        rx_input_request.recv().unwrap();
+        term::flush_stdin();
+    }
+
+    if let Some(events) = crate::macros::take_playback() {
+        crate::macros::enter_playback();
+        for event in events {
+            send_input(tx, rx_input_request, event);
+        }
+        crate::macros::leave_playback();
+    }
+}
+
 fn signal_thread(tx: Sender<Events>) {
     std::thread::spawn(move || {
         let rx = notify(&[Signal::WINCH]);
         for _ in rx.iter() {
+            if let Ok((xsize, ysize)) = term::size() {
+                crate::session::record_resize(xsize, ysize);
+            }
             tx.send(Events::TerminalResized).unwrap();
         }
     });